@@ -1,4 +1,6 @@
-use std::ops::Index;
+use std::ops::{Index, IndexMut};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
 
 /// A fixed size array.
 #[derive(Debug)]
@@ -94,8 +96,31 @@ impl<T, const N: usize> Index<usize> for Array<T, N> {
     }
 }
 
+impl<T, const N: usize> IndexMut<usize> for Array<T, N> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.inner[index].as_mut().unwrap()
+    }
+}
+
 impl<T: Eq, const N: usize> Eq for Array<T, N> {}
 
+impl<T: Serialize, const N: usize> Serialize for Array<T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de>, const N: usize> Deserialize<'de> for Array<T, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let vec = Vec::<T>::deserialize(deserializer)?;
+        if vec.len() > N {
+            let expected = format!("at most {N} elements");
+            return Err(D::Error::invalid_length(vec.len(), &expected.as_str()));
+        }
+        Ok(vec.into_iter().collect())
+    }
+}
+
 impl<T, const N: usize> Default for Array<T, N> {
     fn default() -> Self {
         Self::new()
@@ -291,4 +316,13 @@ mod tests {
         assert_eq!(slice.len(), 4);
         assert_eq!(slice, expected.as_slice());
     }
+
+    #[test]
+    fn serde() {
+        let array = Array::<u32, 3>::from_iter([1, 2]);
+        let json = serde_json::to_string(&array).unwrap();
+        assert_eq!(json, "[1,2]");
+        assert_eq!(serde_json::from_str::<Array<u32, 3>>(&json).unwrap(), array);
+        assert!(serde_json::from_str::<Array<u32, 3>>("[1,2,3,4]").is_err());
+    }
 }
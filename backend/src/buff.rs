@@ -1,13 +1,19 @@
 use std::{
     mem,
     ops::{Index, IndexMut},
+    sync::Arc,
 };
 
-use anyhow::Result;
-use strum::EnumIter;
+use anyhow::{Result, bail};
+use opencv::{
+    core::{Mat, MatTraitConst, Vector},
+    imgcodecs::{IMREAD_COLOR, imdecode},
+};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
 
 use crate::{
-    Configuration, Settings,
+    Configuration, CustomBuff, Settings,
     context::{Context, Contextual, ControlFlow},
     player::Player,
     task::{Task, Update, update_detection_task},
@@ -17,8 +23,10 @@ const BUFF_FAIL_MAX_COUNT: u32 = 5;
 
 #[derive(Debug)]
 pub struct BuffState {
-    /// The kind of buff
-    kind: BuffKind,
+    /// The id of buff
+    id: BuffId,
+    /// The decoded template and threshold for detecting [`BuffId::Custom`] buff
+    custom_template: Option<(Arc<Mat>, f64)>,
     /// Task for detecting buff
     task: Option<Task<Result<bool>>>,
     /// The count [`Buff::HasBuff`] has failed to detect
@@ -33,7 +41,8 @@ pub struct BuffState {
 impl BuffState {
     pub fn new(kind: BuffKind) -> Self {
         Self {
-            kind,
+            id: BuffId::Kind(kind),
+            custom_template: None,
             task: None,
             fail_count: 0,
             max_fail_count: match kind {
@@ -55,9 +64,37 @@ impl BuffState {
         }
     }
 
+    /// Creates a state for the user-defined `buff`
+    ///
+    /// Returns `Err` if the template of `buff` cannot be decoded.
+    pub fn new_custom(buff: &CustomBuff) -> Result<Self> {
+        let template = imdecode(&Vector::<u8>::from_slice(&buff.template), IMREAD_COLOR)?;
+        if template.empty() {
+            bail!("custom buff template is empty");
+        }
+        Ok(Self {
+            id: BuffId::Custom(buff.id),
+            custom_template: Some((Arc::new(template), buff.threshold)),
+            task: None,
+            fail_count: 0,
+            max_fail_count: BUFF_FAIL_MAX_COUNT,
+            enabled: buff.key.enabled,
+        })
+    }
+
+    #[inline]
+    pub fn id(&self) -> BuffId {
+        self.id
+    }
+
     /// Update the enabled state of buff to only detect if enabled
+    ///
+    /// The enabled state of [`BuffId::Custom`] buff is only determined when it is created.
     pub fn update_enabled_state(&mut self, config: &Configuration, settings: &Settings) {
-        self.enabled = match self.kind {
+        let BuffId::Kind(kind) = self.id else {
+            return;
+        };
+        self.enabled = match kind {
             BuffKind::Rune => settings.enable_rune_solving,
             BuffKind::SayramElixir => config.sayram_elixir_key.enabled,
             BuffKind::AureliaElixir => config.aurelia_elixir_key.enabled,
@@ -79,14 +116,21 @@ impl BuffState {
     }
 }
 
+/// Identifies a buff tracked by [`BuffState`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BuffId {
+    Kind(BuffKind),
+    /// A user-defined buff with the [`CustomBuff::id`]
+    Custom(u32),
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum Buff {
     NoBuff,
     HasBuff,
 }
 
-#[derive(Clone, Copy, Debug, EnumIter)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Clone, Copy, PartialEq, Debug, EnumIter, Display, Serialize, Deserialize)]
 #[repr(usize)]
 pub enum BuffKind {
     /// NOTE: Upon failing to solving rune, there is a cooldown
@@ -131,7 +175,12 @@ impl Contextual for Buff {
         if !state.enabled {
             return ControlFlow::Next(Buff::NoBuff);
         }
-        let next = if matches!(context.player, Player::CashShopThenExit(_, _)) {
+        let next = if matches!(
+            context.player,
+            Player::CashShopThenExit(_, _)
+                | Player::ReadingMesos(_)
+                | Player::ChangingChannel(_, _)
+        ) {
             self
         } else {
             update_context(self, context, state)
@@ -142,10 +191,17 @@ impl Contextual for Buff {
 
 #[inline]
 fn update_context(contextual: Buff, context: &Context, state: &mut BuffState) -> Buff {
-    let kind = state.kind;
+    let id = state.id;
+    let custom_template = state.custom_template.clone();
     let Update::Ok(has_buff) =
         update_detection_task(context, 5000, &mut state.task, move |detector| {
-            Ok(detector.detect_player_buff(kind))
+            Ok(match (id, custom_template) {
+                (BuffId::Custom(_), Some((template, threshold))) => {
+                    detector.detect_player_custom_buff(&template, threshold)
+                }
+                (BuffId::Kind(kind), _) => detector.detect_player_buff(kind),
+                (BuffId::Custom(_), None) => unreachable!(),
+            })
         })
     else {
         return contextual;
@@ -172,12 +228,16 @@ fn update_context(contextual: Buff, context: &Context, state: &mut BuffState) ->
 mod tests {
     use std::{assert_matches::assert_matches, time::Duration};
 
-    use mockall::predicate::eq;
+    use mockall::predicate::{always, eq};
+    use opencv::{
+        core::{CV_8UC3, Scalar, VectorToVec},
+        imgcodecs::imencode_def,
+    };
     use strum::IntoEnumIterator;
     use tokio::time::advance;
 
     use super::*;
-    use crate::detect::MockDetector;
+    use crate::{KeyBindingConfiguration, detect::MockDetector};
 
     fn detector_with_kind(kind: BuffKind, result: bool) -> MockDetector {
         let mut detector = MockDetector::new();
@@ -191,6 +251,33 @@ mod tests {
         detector
     }
 
+    fn detector_with_custom(result: bool) -> MockDetector {
+        let mut detector = MockDetector::new();
+        detector
+            .expect_detect_player_custom_buff()
+            .with(always(), eq(0.75))
+            .return_const(result);
+        detector
+            .expect_clone()
+            .returning(move || detector_with_custom(result));
+        detector
+    }
+
+    fn custom_buff() -> CustomBuff {
+        let template = Mat::new_rows_cols_with_default(4, 4, CV_8UC3, Scalar::default()).unwrap();
+        let mut bytes = Vector::new();
+        imencode_def(".png", &template, &mut bytes).unwrap();
+        CustomBuff {
+            id: 1,
+            template: bytes.to_vec(),
+            key: KeyBindingConfiguration {
+                enabled: true,
+                ..KeyBindingConfiguration::default()
+            },
+            ..CustomBuff::default()
+        }
+    }
+
     async fn advance_task(contextual: Buff, context: &Context, state: &mut BuffState) -> Buff {
         let mut buff = update_context(contextual, context, state);
         while !state.task.as_ref().unwrap().completed() {
@@ -228,4 +315,24 @@ mod tests {
             assert_matches!(buff, Buff::NoBuff);
         }
     }
+
+    #[test]
+    fn buff_custom_invalid_template() {
+        let buff = CustomBuff {
+            template: vec![1, 2, 3],
+            ..custom_buff()
+        };
+        assert!(BuffState::new_custom(&buff).is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn buff_custom_no_buff_to_has_buff() {
+        let context = Context::new(None, Some(detector_with_custom(true)));
+        let mut state = BuffState::new_custom(&custom_buff()).unwrap();
+        assert_eq!(state.id(), BuffId::Custom(1));
+
+        let buff = advance_task(Buff::NoBuff, &context, &mut state).await;
+        let buff = update_context(buff, &context, &mut state);
+        assert_matches!(buff, Buff::HasBuff);
+    }
 }
@@ -0,0 +1,121 @@
+use std::{
+    cell::Cell,
+    fmt::Debug,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::context::MS_PER_TICK;
+
+/// A source of time owned by [`crate::context::Context`]
+///
+/// All contextual states and the [`crate::rotator::Rotator`] should read time through this
+/// instead of calling [`Instant::now`] directly. This allows tests and offline simulations to
+/// control time deterministically using [`ManualClock`].
+///
+/// The exception is [`crate::player::timeout::Timeout`] and the player velocity, which
+/// intentionally count state updates rather than time. [`crate::context::Context::tick`] is the
+/// single place where the two are reconciled.
+pub trait Clock: Debug {
+    /// Returns the current [`Instant`]
+    fn now(&self) -> Instant;
+
+    /// Returns the number of milliseconds passed since `instant`
+    #[inline]
+    fn millis_since(&self, instant: Instant) -> u128 {
+        self.now().saturating_duration_since(instant).as_millis()
+    }
+
+    /// Returns the number of whole ticks passed since `instant`
+    ///
+    /// This is where wall-clock time is converted to tick time. When ticks run slower than
+    /// [`MS_PER_TICK`], the returned value still reflects the wall-clock time passed.
+    #[inline]
+    fn ticks_since(&self, instant: Instant) -> u64 {
+        (self.millis_since(instant) / MS_PER_TICK as u128) as u64
+    }
+}
+
+/// A [`Clock`] backed by the system monotonic clock
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A [`Clock`] that only moves forward when advanced manually
+///
+/// Cloning shares the same underlying time so that a clone kept outside of
+/// [`crate::context::Context`] can still advance it.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Rc<Cell<Instant>>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self {
+            now: Rc::new(Cell::new(Instant::now())),
+        }
+    }
+}
+
+impl ManualClock {
+    /// Advances the clock by `duration`
+    #[inline]
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+
+    /// Advances the clock by `ticks` number of ticks
+    #[inline]
+    pub fn advance_ticks(&self, ticks: u64) {
+        self.advance(Duration::from_millis(ticks * MS_PER_TICK));
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+/// Converts `millis` to the number of ticks
+#[inline]
+pub fn millis_to_ticks(millis: u64) -> u32 {
+    (millis / MS_PER_TICK) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Clock, ManualClock, millis_to_ticks};
+    use crate::context::MS_PER_TICK;
+
+    #[test]
+    fn manual_clock_advance_shared() {
+        let clock = ManualClock::default();
+        let cloned = clock.clone();
+        let start = clock.now();
+
+        cloned.advance(Duration::from_millis(1500));
+        assert_eq!(clock.now(), start + Duration::from_millis(1500));
+        assert_eq!(clock.millis_since(start), 1500);
+
+        cloned.advance_ticks(3);
+        assert_eq!(clock.ticks_since(start), 1500 / MS_PER_TICK + 3);
+    }
+
+    #[test]
+    fn millis_to_ticks_truncate() {
+        assert_eq!(millis_to_ticks(0), 0);
+        assert_eq!(millis_to_ticks(MS_PER_TICK - 1), 0);
+        assert_eq!(millis_to_ticks(MS_PER_TICK * 10 + 1), 10);
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     cell::RefCell,
+    collections::HashMap,
     env,
     fs::File,
     io::Write,
@@ -11,7 +12,7 @@ use std::{
 
 use dyn_clone::clone_box;
 use opencv::{
-    core::{Vector, VectorToVec},
+    core::{Rect, Vector, VectorToVec},
     imgcodecs::imencode_def,
 };
 use platforms::windows::{self, Handle, KeyInputKind, KeyReceiver};
@@ -21,20 +22,26 @@ use tokio::sync::broadcast;
 use crate::{
     Action, RequestHandler,
     bridge::{DefaultKeySender, ImageCapture, ImageCaptureKind, KeySender, KeySenderMethod},
-    buff::{Buff, BuffKind, BuffState},
+    buff::{Buff, BuffId, BuffKind, BuffState},
+    clock::{Clock, SystemClock},
     database::{CaptureMode, InputMethod, KeyBinding},
     detect::{CachedDetector, Detector},
     mat::OwnedMat,
     minimap::{Minimap, MinimapState},
     network::{DiscordNotification, NotificationKind},
+    pathing::PlatformWithNeighbors,
     player::{Player, PlayerState},
+    playlist::PlaylistState,
+    progress::ProgressState,
     query_configs, query_settings,
-    request_handler::{DefaultRequestHandler, config_buffs},
+    request_handler::{
+        DefaultRequestHandler, config_buffs, config_custom_buff_states, config_custom_skill_states,
+    },
     rotator::Rotator,
-    skill::{Skill, SkillKind, SkillState},
+    skill::{Skill, SkillId, SkillKind, SkillState},
 };
 #[cfg(test)]
-use crate::{Settings, bridge::MockKeySender, detect::MockDetector};
+use crate::{Settings, bridge::MockKeySender, clock::ManualClock, detect::MockDetector};
 
 const FPS: u32 = 30;
 pub const MS_PER_TICK: u64 = 1000 / FPS as u64;
@@ -70,14 +77,33 @@ pub struct Context {
     /// The `MapleStory` class game handle.
     pub handle: Handle,
     pub keys: Box<dyn KeySender>,
+    /// The source of time for contextual states and the rotator.
+    pub clock: Box<dyn Clock>,
     pub notification: DiscordNotification,
     pub detector: Option<Box<dyn Detector>>,
     pub minimap: Minimap,
+    /// The platforms graph of the current minimap data.
+    ///
+    /// It is stored outside of [`Minimap`] so that the number of platforms is not limited while
+    /// the contextual states stay [`Copy`].
+    pub platforms: Vec<PlatformWithNeighbors>,
+    /// The portals detected on the current minimap.
+    ///
+    /// Copied from [`MinimapState`] after each update for the same reason as [`Self::platforms`].
+    pub portals: Vec<Rect>,
     pub player: Player,
     pub skills: [Skill; SkillKind::COUNT],
+    /// The states of user-registered skills keyed by [`SkillId::Custom`] id
+    pub custom_skills: HashMap<u32, Skill>,
     pub buffs: [Buff; BuffKind::COUNT],
+    /// The states of user-defined buffs keyed by [`BuffId::Custom`] id
+    pub custom_buffs: HashMap<u32, Buff>,
     pub halting: bool,
     /// The game current tick.
+    ///
+    /// This is derived from [`Self::clock`] so that it keeps up with wall-clock time even when
+    /// a tick takes longer than [`MS_PER_TICK`]. Player timeouts and velocity do not use this and
+    /// count updates instead.
     pub tick: u64,
 }
 
@@ -87,17 +113,48 @@ impl Context {
         Context {
             handle: Handle::new(""),
             keys: Box::new(keys.unwrap_or_default()),
+            clock: Box::new(ManualClock::default()),
             notification: DiscordNotification::new(Rc::new(RefCell::new(Settings::default()))),
             detector: detector.map(|detector| Box::new(detector) as Box<dyn Detector>),
             minimap: Minimap::Detecting,
+            platforms: Vec::new(),
+            portals: Vec::new(),
             player: Player::Detecting,
             skills: [Skill::Detecting; SkillKind::COUNT],
+            custom_skills: HashMap::new(),
             buffs: [Buff::NoBuff; BuffKind::COUNT],
+            custom_buffs: HashMap::new(),
             halting: false,
             tick: 0,
         }
     }
 
+    /// Returns the current [`Skill`] of `id`
+    ///
+    /// A [`SkillId::Custom`] skill that has not been detected yet is [`Skill::Detecting`].
+    #[inline]
+    pub fn skill(&self, id: SkillId) -> Skill {
+        match id {
+            SkillId::Kind(kind) => self.skills[kind],
+            SkillId::Custom(id) => self
+                .custom_skills
+                .get(&id)
+                .copied()
+                .unwrap_or(Skill::Detecting),
+        }
+    }
+
+    /// Returns the current [`Buff`] of `id`
+    ///
+    /// A [`BuffId::Custom`] buff that has not been detected yet is [`Buff::NoBuff`].
+    #[inline]
+    pub fn buff(&self, id: BuffId) -> Buff {
+        match id {
+            BuffId::Kind(kind) => self.buffs[kind],
+            BuffId::Custom(id) => self.custom_buffs.get(&id).copied().unwrap_or(Buff::NoBuff),
+        }
+    }
+
     #[inline]
     pub fn detector_unwrap(&self) -> &dyn Detector {
         self.detector
@@ -185,17 +242,25 @@ fn update_loop() {
     let mut context = Context {
         handle,
         keys: Box::new(keys),
+        clock: Box::new(SystemClock),
         notification: DiscordNotification::new(settings.clone()),
         detector: None,
         minimap: Minimap::Detecting,
+        platforms: Vec::new(),
+        portals: Vec::new(),
         player: Player::Idle,
         skills: [Skill::Detecting],
+        custom_skills: HashMap::new(),
         buffs: [Buff::NoBuff; BuffKind::COUNT],
+        custom_buffs: HashMap::new(),
         halting: true,
         tick: 0,
     };
     let mut player_state = PlayerState::default();
+    player_state.config.revive_on_death = settings.borrow().revive_on_death;
     let mut minimap_state = MinimapState::default();
+    let mut playlist_state = PlaylistState::default();
+    let mut progress_state = ProgressState::default();
     let mut skill_states = SkillKind::iter()
         .map(SkillState::new)
         .collect::<Vec<SkillState>>();
@@ -205,22 +270,28 @@ fn update_loop() {
     buff_states.iter_mut().for_each(|state| {
         state.update_enabled_state(&config, &settings.borrow());
     });
+    let mut custom_skill_states = config_custom_skill_states(&config);
+    let mut custom_buff_states = config_custom_buff_states(&config);
 
     #[cfg(debug_assertions)]
     let mut recording_images_id = None;
     #[cfg(debug_assertions)]
     let mut infering_rune = None;
+    let started = context.clock.now();
 
     loop_with_fps(FPS, || {
         let mat = image_capture.grab().map(OwnedMat::new);
         let was_player_alive = !player_state.is_dead;
+        let was_player_health_critical = player_state.is_health_critical;
         let was_minimap_idle = matches!(context.minimap, Minimap::Idle(_));
         let detector = mat.map(CachedDetector::new);
 
-        context.tick += 1;
+        context.tick = context.clock.ticks_since(started).max(context.tick + 1);
         if let Some(detector) = detector {
             context.detector = Some(Box::new(detector));
             context.minimap = fold_context(&context, context.minimap, &mut minimap_state);
+            context.portals.clear();
+            context.portals.extend_from_slice(minimap_state.portals());
             context.player = fold_context(&context, context.player, &mut player_state);
             for (i, state) in skill_states
                 .iter_mut()
@@ -229,9 +300,23 @@ fn update_loop() {
             {
                 context.skills[i] = fold_context(&context, context.skills[i], state);
             }
+            for state in custom_skill_states.iter_mut() {
+                let SkillId::Custom(id) = state.id() else {
+                    unreachable!()
+                };
+                let skill = fold_context(&context, context.skill(state.id()), state);
+                context.custom_skills.insert(id, skill);
+            }
             for (i, state) in buff_states.iter_mut().enumerate().take(context.buffs.len()) {
                 context.buffs[i] = fold_context(&context, context.buffs[i], state);
             }
+            for state in custom_buff_states.iter_mut() {
+                let BuffId::Custom(id) = state.id() else {
+                    unreachable!()
+                };
+                let buff = fold_context(&context, context.buff(state.id()), state);
+                context.custom_buffs.insert(id, buff);
+            }
             // Rotating action must always be done last
             rotator.rotate_action(&context, &mut player_state);
         }
@@ -245,10 +330,14 @@ fn update_loop() {
             settings: &mut settings_borrow_mut,
             buffs: &mut buffs,
             buff_states: &mut buff_states,
+            custom_skill_states: &mut custom_skill_states,
+            custom_buff_states: &mut custom_buff_states,
             actions: &mut actions,
             rotator: &mut rotator,
             player: &mut player_state,
             minimap: &mut minimap_state,
+            playlist: &mut playlist_state,
+            progress: &mut progress_state,
             key_sender: &key_sender,
             key_receiver: &mut key_receiver,
             image_capture: &mut image_capture,
@@ -261,6 +350,7 @@ fn update_loop() {
         };
         handler.poll_request();
         handler.poll_key();
+        handler.poll_playlist();
         #[cfg(debug_assertions)]
         handler.poll_debug();
         handler.context.notification.update_scheduled_frames(|| {
@@ -274,22 +364,53 @@ fn update_loop() {
         });
 
         // Upon accidental or white roomed causing map to change,
-        // abort actions and send notification
-        let minimap_changed =
-            was_minimap_idle && matches!(handler.context.minimap, Minimap::Detecting);
-        let player_died = was_player_alive && handler.player.is_dead;
+        // abort actions and send notification. Except when reviving or changing channel as they
+        // handle appearing on a different map by themselves. Dying also aborts actions unless
+        // reviving is enabled.
+        let minimap_changed = was_minimap_idle
+            && matches!(handler.context.minimap, Minimap::Detecting)
+            && !matches!(
+                handler.context.player,
+                Player::Reviving(_, _) | Player::ChangingChannel(_, _)
+            );
+        let player_died =
+            was_player_alive && handler.player.is_dead && !handler.settings.revive_on_death;
+        let player_health_critical =
+            !was_player_health_critical && handler.player.is_health_critical;
+        let mut notifications = vec![];
         if handler.minimap.data().is_some() && !handler.context.halting {
             if (minimap_changed || player_died) && handler.settings.stop_on_fail_or_change_map {
                 handler.on_rotate_actions(true);
             }
+            // Always halts since it is opted in by the potion rule
+            if player_health_critical {
+                handler.on_rotate_actions(true);
+            }
 
             if minimap_changed {
-                drop(settings_borrow_mut); // For notification to borrow immutably
-                let _ = context
-                    .notification
-                    .schedule_notification(NotificationKind::FailOrMapChange);
+                notifications.push(NotificationKind::FailOrMapChange);
+            }
+            if player_health_critical {
+                notifications.push(NotificationKind::PlayerHealthCritical);
             }
         }
+        let revive_halted = handler.poll_revive();
+        let change_channel_halted = handler.poll_change_channel();
+        let progress_report = handler.poll_progress();
+
+        drop(settings_borrow_mut); // For notification to borrow immutably
+        for kind in notifications {
+            let _ = context.notification.schedule_notification(kind);
+        }
+        if let Some(reason) = revive_halted {
+            let _ = context.notification.schedule_revive_halted(reason);
+        }
+        if let Some(reason) = change_channel_halted {
+            let _ = context.notification.schedule_change_channel_halted(reason);
+        }
+        if let Some(report) = progress_report {
+            let _ = context.notification.schedule_exp_report(report);
+        }
     });
 }
 
@@ -12,7 +12,10 @@ use rusqlite::{Connection, Params, Statement, types::Null};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use strum::{Display, EnumIter, EnumString};
 
-use crate::pathing;
+use crate::{array::Array, buff::BuffKind, pathing};
+
+/// The maximum number of steps in an [`ActionSequence`]
+pub const MAX_SEQUENCE_STEPS_COUNT: usize = 16;
 
 static CONNECTION: LazyLock<Mutex<Connection>> = LazyLock::new(|| {
     let path = env::current_exe()
@@ -36,6 +39,10 @@ static CONNECTION: LazyLock<Mutex<Connection>> = LazyLock::new(|| {
             id INTEGER PRIMARY KEY,
             data TEXT NOT NULL
         );
+        CREATE TABLE IF NOT EXISTS sessions (
+            id INTEGER PRIMARY KEY,
+            data TEXT NOT NULL
+        );
         "#,
     )
     .unwrap();
@@ -68,6 +75,9 @@ pub struct Notifications {
     pub notify_on_player_guildie_appear: bool,
     pub notify_on_player_stranger_appear: bool,
     pub notify_on_player_friend_appear: bool,
+    /// Milliseconds between EXP reports with [`None`] indicating no report
+    #[serde(default)]
+    pub exp_report_millis: Option<u64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -80,6 +90,22 @@ pub struct Settings {
     pub enable_rune_solving: bool,
     #[serde(default)]
     pub stop_on_fail_or_change_map: bool,
+    /// Whether to accept the revive dialog and resume actions when the player dies
+    #[serde(default)]
+    pub revive_on_death: bool,
+    /// The maximum number of deaths within an hour before halting with [`None`] indicating
+    /// no limit
+    #[serde(default)]
+    pub max_deaths_per_hour: Option<u32>,
+    /// Whether to change channel when a stranger appears on the minimap
+    #[serde(default)]
+    pub change_channel_on_stranger: bool,
+    /// The interval in milliseconds to change channel with [`None`] indicating no schedule
+    #[serde(default)]
+    pub change_channel_millis: Option<u64>,
+    /// Whether to change to a random channel instead of the next one
+    #[serde(default)]
+    pub change_channel_random: bool,
     #[serde(default)]
     pub input_method: InputMethod,
     #[serde(default)]
@@ -105,6 +131,11 @@ impl Default for Settings {
             input_method: InputMethod::default(),
             input_method_rpc_server_url: String::default(),
             stop_on_fail_or_change_map: false,
+            revive_on_death: false,
+            max_deaths_per_hour: None,
+            change_channel_on_stranger: false,
+            change_channel_millis: None,
+            change_channel_random: false,
             notifications: Notifications::default(),
             toggle_actions_key: toggle_actions_key_default(),
             platform_start_key: platform_start_key_default(),
@@ -185,6 +216,20 @@ pub struct Configuration {
     pub potion_key: KeyBindingConfiguration,
     pub potion_mode: PotionMode,
     pub health_update_millis: u64,
+    #[serde(default)]
+    pub mana_potion_key: KeyBindingConfiguration,
+    #[serde(default = "mana_potion_mode_default")]
+    pub mana_potion_mode: PotionMode,
+    #[serde(default = "mana_update_millis_default")]
+    pub mana_update_millis: u64,
+    /// The key to open and close the inventory for reading mesos
+    #[serde(default)]
+    pub inventory_key: KeyBindingConfiguration,
+    /// The region of the game frame containing the mesos amount while the inventory is opened
+    #[serde(default)]
+    pub mesos_region: Option<Bound>,
+    #[serde(default = "mesos_update_millis_default")]
+    pub mesos_update_millis: u64,
     pub sayram_elixir_key: KeyBindingConfiguration,
     pub aurelia_elixir_key: KeyBindingConfiguration,
     pub exp_x3_key: KeyBindingConfiguration,
@@ -205,8 +250,43 @@ pub struct Configuration {
     pub extreme_gold_potion_key: KeyBindingConfiguration,
     #[serde(default)]
     pub class: Class,
+    /// Overrides the movement profile preset of [`Self::class`] when set
+    #[serde(default)]
+    pub custom_movement_profile: Option<MovementProfile>,
     #[serde(default)]
     pub actions: Vec<ActionConfiguration>,
+    /// Named lists of actions that can be referenced by [`Action::Macro`] in any preset
+    #[serde(default)]
+    pub macros: Vec<ActionMacro>,
+    /// The known durations of buffs
+    ///
+    /// A buff without a known duration relies only on detecting its icon.
+    #[serde(default)]
+    pub buff_durations: Vec<BuffDuration>,
+    /// User-defined buffs detected from a cropped template of their icons
+    #[serde(default)]
+    pub custom_buffs: Vec<CustomBuff>,
+    /// User-registered skills whose cooldowns can be used by [`ActionCondition::SkillOffCooldown`]
+    #[serde(default)]
+    pub custom_skills: Vec<CustomSkill>,
+    /// Skills that place a summon on the map and are placed again when it is out of range
+    #[serde(default)]
+    pub placeable_skills: Vec<PlaceableSkill>,
+    /// Health potions used depending on how low the health is
+    #[serde(default)]
+    pub potion_rules: Vec<PotionRule>,
+}
+
+fn mana_potion_mode_default() -> PotionMode {
+    PotionMode::EveryMillis(180000)
+}
+
+fn mana_update_millis_default() -> u64 {
+    1000
+}
+
+fn mesos_update_millis_default() -> u64 {
+    600000
 }
 
 fn jump_key_default() -> KeyBindingConfiguration {
@@ -232,6 +312,12 @@ impl Default for Configuration {
             potion_key: KeyBindingConfiguration::default(),
             potion_mode: PotionMode::EveryMillis(180000),
             health_update_millis: 1000,
+            mana_potion_key: KeyBindingConfiguration::default(),
+            mana_potion_mode: mana_potion_mode_default(),
+            mana_update_millis: mana_update_millis_default(),
+            inventory_key: KeyBindingConfiguration::default(),
+            mesos_region: None,
+            mesos_update_millis: mesos_update_millis_default(),
             sayram_elixir_key: KeyBindingConfiguration::default(),
             aurelia_elixir_key: KeyBindingConfiguration::default(),
             exp_x3_key: KeyBindingConfiguration::default(),
@@ -245,7 +331,155 @@ impl Default for Configuration {
             extreme_green_potion_key: KeyBindingConfiguration::default(),
             extreme_gold_potion_key: KeyBindingConfiguration::default(),
             class: Class::default(),
+            custom_movement_profile: None,
             actions: vec![],
+            macros: vec![],
+            buff_durations: vec![],
+            custom_buffs: vec![],
+            custom_skills: vec![],
+            placeable_skills: vec![],
+            potion_rules: vec![],
+        }
+    }
+}
+
+/// The known duration of a [`BuffKind`] after being used
+///
+/// Used to predict the buff expiry and cross-check against the detected buff icon.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct BuffDuration {
+    pub kind: BuffKind,
+    pub millis: u64,
+}
+
+/// A user-defined buff that is not one of the built-in [`BuffKind`]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CustomBuff {
+    pub id: u32,
+    pub name: String,
+    /// The PNG encoded template of the buff icon cropped from the buffs region
+    pub template: Vec<u8>,
+    /// The template matching threshold from `0.0` to `1.0`
+    #[serde(default = "custom_buff_threshold_default")]
+    pub threshold: f64,
+    pub key: KeyBindingConfiguration,
+    /// The known duration of the buff after being used
+    #[serde(default)]
+    pub duration_millis: Option<u64>,
+}
+
+fn custom_buff_threshold_default() -> f64 {
+    0.75
+}
+
+impl Default for CustomBuff {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            name: String::new(),
+            template: vec![],
+            threshold: custom_buff_threshold_default(),
+            key: KeyBindingConfiguration::default(),
+            duration_millis: None,
+        }
+    }
+}
+
+/// A user-registered skill whose cooldown is tracked from its icon
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CustomSkill {
+    pub id: u32,
+    pub name: String,
+    pub detection: CustomSkillDetection,
+    /// The template matching threshold from `0.0` to `1.0` for
+    /// [`CustomSkillDetection::Template`]
+    #[serde(default = "custom_skill_threshold_default")]
+    pub threshold: f64,
+}
+
+fn custom_skill_threshold_default() -> f64 {
+    0.8
+}
+
+impl Default for CustomSkill {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            name: String::new(),
+            detection: CustomSkillDetection::default(),
+            threshold: custom_skill_threshold_default(),
+        }
+    }
+}
+
+/// How the icon of a [`CustomSkill`] is located
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, EnumIter, Display, EnumString)]
+pub enum CustomSkillDetection {
+    /// The PNG encoded template of the skill icon captured while off cooldown
+    Template(Vec<u8>),
+    /// A fixed screen region of the skill icon and the BGRA pixel at its center captured
+    /// while off cooldown
+    Region(Bound, [u8; 4]),
+}
+
+impl Default for CustomSkillDetection {
+    fn default() -> Self {
+        Self::Template(vec![])
+    }
+}
+
+/// A skill that places a summon or an installation on the map (e.g. Erda Fountain)
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PlaceableSkill {
+    pub id: u32,
+    pub name: String,
+    pub key: KeyBindingConfiguration,
+    /// How long the summon lasts after being placed
+    pub duration_millis: u64,
+    /// The minimap distance the player can move away from the summon before placing again
+    pub max_distance: u32,
+}
+
+impl Default for PlaceableSkill {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            name: String::new(),
+            key: KeyBindingConfiguration::default(),
+            duration_millis: 60_000,
+            max_distance: 30,
+        }
+    }
+}
+
+/// A health potion used when the player health is at or below a percentage
+///
+/// When multiple rules are reached, only the rule with the lowest percentage is used.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PotionRule {
+    pub id: u32,
+    pub name: String,
+    pub key: KeyBindingConfiguration,
+    pub below_percent: f32,
+    /// The minimum milliseconds between two uses of this rule
+    pub cooldown_millis: u64,
+    /// Halts and notifies when health stays at or below for this many milliseconds
+    pub halt_after_millis: Option<u64>,
+    /// Stops rotating actions from the preset while health is at or below
+    #[serde(default)]
+    pub stop_attacking: bool,
+}
+
+impl Default for PotionRule {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            name: String::new(),
+            key: KeyBindingConfiguration::default(),
+            below_percent: 50.0,
+            cooldown_millis: 2000,
+            halt_after_millis: None,
+            stop_attacking: false,
         }
     }
 }
@@ -305,6 +539,10 @@ impl From<ActionConfiguration> for Action {
             wait_before_use_millis_random_range: 0,
             wait_after_use_millis: value.wait_after_use_millis,
             wait_after_use_millis_random_range: 0,
+            hold_millis: 0,
+            hold_millis_random_range: 0,
+            hold_repeat_millis: None,
+            weight: weight_default(),
         })
     }
 }
@@ -384,6 +622,48 @@ impl Default for AutoMobbing {
     }
 }
 
+/// The behavior used while the player is inside a [`PatrolZone`]
+#[derive(
+    Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum PatrolBehavior {
+    #[default]
+    PingPong,
+    AutoMobbing,
+}
+
+/// A zone visited in order by [`RotationMode::Patrol`]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PatrolZone {
+    pub bound: Bound,
+    pub behavior: PatrolBehavior,
+    pub key: KeyBinding,
+    #[serde(default = "key_count_default")]
+    pub key_count: u32,
+    pub key_wait_before_millis: u64,
+    pub key_wait_after_millis: u64,
+    /// The number of milliseconds to stay in this zone before moving to the next one
+    pub dwell_millis: u64,
+    /// The initial direction when entering this zone with [`PatrolBehavior::PingPong`]
+    #[serde(default)]
+    pub direction: ActionKeyDirection,
+}
+
+impl Default for PatrolZone {
+    fn default() -> Self {
+        Self {
+            bound: Bound::default(),
+            behavior: PatrolBehavior::default(),
+            key: KeyBinding::default(),
+            key_count: key_count_default(),
+            key_wait_before_millis: 0,
+            key_wait_after_millis: 0,
+            dwell_millis: 60000,
+            direction: ActionKeyDirection::default(),
+        }
+    }
+}
+
 fn key_count_default() -> u32 {
     1
 }
@@ -395,8 +675,23 @@ pub enum RotationMode {
     StartToEnd,
     #[default]
     StartToEndThenReverse,
+    Random,
+    Weighted,
+    ShuffleEachCycle,
     AutoMobbing(AutoMobbing),
     PingPong(PingPong),
+    /// Visits each of [`Minimap::patrol_zones`] in order
+    Patrol,
+}
+
+impl Configuration {
+    /// The movement profile in use
+    ///
+    /// This is [`Self::custom_movement_profile`] if set or the preset of [`Self::class`] otherwise.
+    pub fn movement_profile(&self) -> MovementProfile {
+        self.custom_movement_profile
+            .unwrap_or_else(|| self.class.movement_profile())
+    }
 }
 
 impl Identifiable for Configuration {
@@ -409,6 +704,53 @@ impl Identifiable for Configuration {
     }
 }
 
+/// A record of actions running on a map without halting, used to compare EXP and mesos efficiency
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionRecord {
+    #[serde(skip_serializing)]
+    pub id: Option<i64>,
+    /// The name of the map the session ran on
+    pub map: String,
+    /// The UNIX timestamp in seconds when the session started
+    pub started_at_secs: u64,
+    /// The number of milliseconds the session has been running
+    pub elapsed_millis: u64,
+    /// The EXP percentage gained with each level counted as 100%
+    pub exp_gained_percent: f32,
+    pub levels_gained: u32,
+    /// The mesos gained from reading the inventory periodically
+    pub mesos_gained: u64,
+}
+
+impl SessionRecord {
+    /// The EXP percentage gained per hour
+    pub fn exp_per_hour_percent(&self) -> f32 {
+        if self.elapsed_millis == 0 {
+            return 0.0;
+        }
+        self.exp_gained_percent * 3_600_000.0 / self.elapsed_millis as f32
+    }
+
+    /// The mesos gained per hour
+    pub fn mesos_per_hour(&self) -> u64 {
+        if self.elapsed_millis == 0 {
+            return 0;
+        }
+        (self.mesos_gained as u128 * 3_600_000 / self.elapsed_millis as u128) as u64
+    }
+}
+
+impl Identifiable for SessionRecord {
+    fn id(&self) -> Option<i64> {
+        self.id
+    }
+
+    fn set_id(&mut self, id: i64) {
+        self.id = Some(id)
+    }
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[cfg_attr(test, derive(PartialEq))]
 #[serde(default)]
@@ -418,8 +760,17 @@ pub struct Minimap {
     pub name: String,
     pub width: i32,
     pub height: i32,
+    /// The fingerprint of the minimap image when it was created
+    ///
+    /// Is [`None`] for minimaps created before fingerprinting, which are compared by size only.
+    pub fingerprint: Option<u64>,
     pub rotation_mode: RotationMode,
     pub platforms: Vec<Platform>,
+    /// The ropes and ladders that can be climbed between [`Self::platforms`]
+    pub ropes: Vec<Rope>,
+    /// The portals paired with their destinations on [`Self::platforms`]
+    pub portals: Vec<Portal>,
+    pub patrol_zones: Vec<PatrolZone>,
     pub rune_platforms_pathing: bool,
     pub rune_platforms_pathing_up_jump_only: bool,
     pub auto_mob_platforms_pathing: bool,
@@ -427,6 +778,9 @@ pub struct Minimap {
     pub auto_mob_platforms_bound: bool,
     pub actions_any_reset_on_erda_condition: bool,
     pub actions: HashMap<String, Vec<Action>>,
+    /// Whether to rotate through [`Self::playlist`] instead of the selected preset
+    pub playlist_enabled: bool,
+    pub playlist: Vec<PlaylistEntry>,
 }
 
 impl Identifiable for Minimap {
@@ -439,6 +793,42 @@ impl Identifiable for Minimap {
     }
 }
 
+/// A preset in [`Minimap::playlist`] and how long it runs for
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    pub preset: String,
+    pub limit: PlaylistLimit,
+}
+
+/// When to switch to the next [`PlaylistEntry`]
+///
+/// The switch only happens when there is no linked action executing.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize, EnumIter, Display, EnumString)]
+pub enum PlaylistLimit {
+    Millis(u64),
+    Cycles(u32),
+}
+
+impl Default for PlaylistLimit {
+    fn default() -> Self {
+        Self::Millis(1_800_000)
+    }
+}
+
+impl PlaylistLimit {
+    /// Replaces a zero limit with a non-zero one
+    ///
+    /// A zero limit would switch to the next entry immediately. [`Self::Millis`] falls back to
+    /// [`Self::default`] and [`Self::Cycles`] to one cycle.
+    pub fn non_zero(self) -> Self {
+        match self {
+            PlaylistLimit::Millis(0) => PlaylistLimit::default(),
+            PlaylistLimit::Cycles(0) => PlaylistLimit::Cycles(1),
+            limit => limit,
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
 pub struct Platform {
     pub x_start: i32,
@@ -452,6 +842,35 @@ impl From<Platform> for pathing::Platform {
     }
 }
 
+/// A rope or ladder that can be climbed from [`Self::y_bottom`] to [`Self::y_top`]
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Rope {
+    pub x: i32,
+    pub y_bottom: i32,
+    pub y_top: i32,
+}
+
+impl From<Rope> for pathing::Rope {
+    fn from(value: Rope) -> Self {
+        Self::new(value.x, value.y_bottom, value.y_top)
+    }
+}
+
+/// A portal at ([`Self::x`], [`Self::y`]) that teleports to ([`Self::to_x`], [`Self::to_y`])
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Portal {
+    pub x: i32,
+    pub y: i32,
+    pub to_x: i32,
+    pub to_y: i32,
+}
+
+impl From<Portal> for pathing::Portal {
+    fn from(value: Portal) -> Self {
+        Self::new(value.x, value.y, value.to_x, value.to_y)
+    }
+}
+
 #[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Position {
     pub x: i32,
@@ -461,11 +880,26 @@ pub struct Position {
     pub allow_adjusting: bool,
 }
 
-#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub struct ActionMove {
     pub position: Position,
     pub condition: ActionCondition,
     pub wait_after_move_millis: u64,
+    /// The weight used by [`RotationMode::Weighted`] when the condition is
+    /// [`ActionCondition::Any`]
+    #[serde(default = "weight_default")]
+    pub weight: u32,
+}
+
+impl Default for ActionMove {
+    fn default() -> Self {
+        Self {
+            position: Position::default(),
+            condition: ActionCondition::default(),
+            wait_after_move_millis: 0,
+            weight: weight_default(),
+        }
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
@@ -485,7 +919,21 @@ pub struct ActionKey {
     pub wait_after_use_millis: u64,
     #[serde(default)]
     pub wait_after_use_millis_random_range: u64,
+    /// The number of milliseconds to hold the key down before releasing it
+    ///
+    /// The key is tapped when this is `0`.
+    #[serde(default)]
+    pub hold_millis: u64,
+    #[serde(default)]
+    pub hold_millis_random_range: u64,
+    /// The interval in milliseconds to press the key again while it is held down
+    #[serde(default)]
+    pub hold_repeat_millis: Option<u64>,
     pub queue_to_front: Option<bool>,
+    /// The weight used by [`RotationMode::Weighted`] when the condition is
+    /// [`ActionCondition::Any`]
+    #[serde(default = "weight_default")]
+    pub weight: u32,
 }
 
 impl Default for ActionKey {
@@ -502,7 +950,11 @@ impl Default for ActionKey {
             wait_before_use_millis_random_range: 0,
             wait_after_use_millis: 0,
             wait_after_use_millis_random_range: 0,
+            hold_millis: 0,
+            hold_millis_random_range: 0,
+            hold_repeat_millis: None,
             queue_to_front: None,
+            weight: weight_default(),
         }
     }
 }
@@ -541,10 +993,145 @@ impl Default for LinkKeyBinding {
     }
 }
 
+/// A named list of actions stored in [`Configuration::macros`]
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct ActionMacro {
+    pub id: u32,
+    pub name: String,
+    pub actions: Vec<Action>,
+}
+
+/// A reference to the [`ActionMacro`] with the same [`ActionMacro::id`]
+///
+/// The macro is expanded into a single linked chain where the first action uses the condition
+/// of this reference. The conditions of the actions inside the macro are ignored.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ActionMacroRef {
+    pub id: u32,
+    pub condition: ActionCondition,
+    pub queue_to_front: Option<bool>,
+    /// The weight used by [`RotationMode::Weighted`] when the condition is
+    /// [`ActionCondition::Any`]
+    #[serde(default = "weight_default")]
+    pub weight: u32,
+}
+
+impl Default for ActionMacroRef {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            condition: ActionCondition::default(),
+            queue_to_front: None,
+            weight: weight_default(),
+        }
+    }
+}
+
+/// A combo of keys executed as a single action
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ActionSequence {
+    pub position: Option<Position>,
+    pub condition: ActionCondition,
+    pub steps: Array<ActionSequenceStep, MAX_SEQUENCE_STEPS_COUNT>,
+    pub queue_to_front: Option<bool>,
+    /// The weight used by [`RotationMode::Weighted`] when the condition is
+    /// [`ActionCondition::Any`]
+    #[serde(default = "weight_default")]
+    pub weight: u32,
+}
+
+impl Default for ActionSequence {
+    fn default() -> Self {
+        Self {
+            position: None,
+            condition: ActionCondition::default(),
+            steps: Array::new(),
+            queue_to_front: None,
+            weight: weight_default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ActionSequenceStep {
+    pub key: KeyBinding,
+    pub kind: ActionSequenceStepKind,
+    /// The direction key to tap right before this step
+    pub direction: ActionKeyDirection,
+    /// The number of milliseconds to wait after this step
+    pub delay_millis: u64,
+    #[serde(default)]
+    pub delay_millis_random_range: u64,
+}
+
+#[derive(
+    Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum ActionSequenceStepKind {
+    /// Presses and releases the key
+    #[default]
+    Tap,
+    /// Presses the key without releasing
+    Down,
+    /// Releases the key
+    Up,
+}
+
+/// A branch inside a chain of linked actions
+///
+/// The [`Self::then_count`] actions following this branch are only executed when
+/// [`Self::predicate`] is true. The [`Self::else_count`] actions after those are only executed
+/// when it is false.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ActionBranch {
+    pub condition: ActionCondition,
+    pub predicate: ActionBranchPredicate,
+    pub then_count: u32,
+    pub else_count: u32,
+    /// Whether to skip the rest of the chain after the actions of the true branch
+    #[serde(default)]
+    pub then_stop: bool,
+    pub queue_to_front: Option<bool>,
+    /// The weight used by [`RotationMode::Weighted`] when the condition is
+    /// [`ActionCondition::Any`]
+    #[serde(default = "weight_default")]
+    pub weight: u32,
+}
+
+impl Default for ActionBranch {
+    fn default() -> Self {
+        Self {
+            condition: ActionCondition::default(),
+            predicate: ActionBranchPredicate::default(),
+            then_count: 1,
+            else_count: 0,
+            then_stop: false,
+            queue_to_front: None,
+            weight: weight_default(),
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
+pub enum ActionBranchPredicate {
+    #[default]
+    FacingLeft,
+    FacingRight,
+    EliteBossPresent,
+    EliteBossAbsent,
+    ErdaShowerOffCooldown,
+}
+
 fn count_default() -> u32 {
     1
 }
 
+fn weight_default() -> u32 {
+    1
+}
+
 #[derive(
     Clone, Copy, Display, Default, EnumString, EnumIter, PartialEq, Debug, Serialize, Deserialize,
 )]
@@ -556,10 +1143,86 @@ pub enum Class {
     Generic,
 }
 
+impl Class {
+    /// The movement profile preset of this class
+    pub fn movement_profile(self) -> MovementProfile {
+        let generic = MovementProfile::default();
+        match self {
+            Class::Cadena => MovementProfile {
+                link_key_timeout: 4,
+                ..generic
+            },
+            Class::Blaster => MovementProfile {
+                link_key_timeout: 8,
+                jump_after_link_key: true,
+                ..generic
+            },
+            Class::Ark => MovementProfile {
+                link_key_timeout: 10,
+                ..generic
+            },
+            Class::Generic => generic,
+        }
+    }
+}
+
+/// The distances and timeouts used by the player movement and platforms pathing
+///
+/// Distances are in minimap pixels and timeouts are in ticks.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MovementProfile {
+    /// Minimum x distance from the destination required to perform a double jump
+    pub double_jump_threshold: i32,
+    /// Minimum y distance from the destination required to perform a jump
+    pub jump_threshold: i32,
+    /// Minimum y distance from the destination required to perform an up jump
+    pub up_jump_threshold: i32,
+    /// Minimum y distance from the destination required to perform a grappling hook
+    pub grappling_threshold: i32,
+    /// Maximum y distance from the destination allowed to perform a grappling hook
+    pub grappling_max_threshold: i32,
+    /// Maximum y distance from the destination allowed to teleport instead of falling
+    pub teleport_fall_threshold: i32,
+    /// Maximum y distance from the destination allowed to teleport instead of up jumping
+    pub teleport_up_jump_threshold: i32,
+    /// Maximum number of ticks a change in x or y direction must be detected
+    ///
+    /// The timeouts of other movement states are derived from this value.
+    pub move_timeout: u32,
+    /// Number of recent player positions used to approximate the player velocity
+    pub velocity_samples: u32,
+    /// Number of ticks to wait between the keys of a link key
+    pub link_key_timeout: u32,
+    /// Whether to jump after using a [`LinkKeyBinding::After`] link key
+    pub jump_after_link_key: bool,
+}
+
+impl Default for MovementProfile {
+    fn default() -> Self {
+        Self {
+            double_jump_threshold: 25,
+            jump_threshold: 7,
+            up_jump_threshold: 10,
+            grappling_threshold: 24,
+            grappling_max_threshold: 41,
+            teleport_fall_threshold: 15,
+            teleport_up_jump_threshold: 14,
+            move_timeout: 5,
+            velocity_samples: 5,
+            link_key_timeout: 5,
+            jump_after_link_key: false,
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize, EnumIter, Display, EnumString)]
 pub enum Action {
     Move(ActionMove),
     Key(ActionKey),
+    Sequence(ActionSequence),
+    Macro(ActionMacroRef),
+    Branch(ActionBranch),
 }
 
 #[derive(
@@ -570,6 +1233,8 @@ pub enum ActionCondition {
     Any,
     EveryMillis(u64),
     ErdaShowerOffCooldown,
+    /// Queues when the [`CustomSkill`] with the id is off cooldown
+    SkillOffCooldown(u32),
     Linked,
 }
 
@@ -875,6 +1540,14 @@ pub fn delete_map(map: &Minimap) -> Result<()> {
     delete_from_table("maps", map)
 }
 
+pub fn query_sessions() -> Result<Vec<SessionRecord>> {
+    query_from_table("sessions")
+}
+
+pub fn upsert_session(session: &mut SessionRecord) -> Result<()> {
+    upsert_to_table("sessions", session)
+}
+
 fn map_data<T>(mut stmt: Statement<'_>, params: impl Params) -> Result<Vec<T>>
 where
     T: DeserializeOwned + Identifiable + Default,
@@ -3,10 +3,7 @@ use std::{
     collections::HashMap,
     env,
     fmt::Debug,
-    sync::{
-        Arc, LazyLock, Mutex,
-        atomic::{AtomicBool, Ordering},
-    },
+    sync::{Arc, LazyLock, Mutex},
 };
 
 use anyhow::{Result, anyhow, bail};
@@ -112,6 +109,12 @@ pub trait Detector: 'static + Send + DynClone + Debug {
     /// Detects whether to press ESC for unstucking.
     fn detect_esc_settings(&self) -> bool;
 
+    /// Detects whether the ESC menu is opened.
+    fn detect_esc_menu(&self) -> bool;
+
+    /// Detects whether the change channel dialog is opened.
+    fn detect_change_channel_dialog(&self) -> bool;
+
     /// Detects whether there is an elite boss bar.
     fn detect_elite_boss_bar(&self) -> bool;
 
@@ -142,6 +145,9 @@ pub trait Detector: 'static + Send + DynClone + Debug {
     /// Detects whether the player is dead.
     fn detect_player_is_dead(&self) -> bool;
 
+    /// Detects whether the revive dialog is shown after the player died.
+    fn detect_player_revive_dialog(&self) -> bool;
+
     /// Detects whether the player is in cash shop.
     fn detect_player_in_cash_shop(&self) -> bool;
 
@@ -154,9 +160,27 @@ pub trait Detector: 'static + Send + DynClone + Debug {
     /// Detects the player current health and max health.
     fn detect_player_health(&self, current_bar: Rect, max_bar: Rect) -> Result<(u32, u32)>;
 
+    /// Detects the player mana bar from the player `health_bar`.
+    fn detect_player_mana_bar(&self, health_bar: Rect) -> Result<Rect>;
+
+    /// Detects the player current and max mana bars.
+    fn detect_player_current_max_mana_bars(&self, mana_bar: Rect) -> Result<(Rect, Rect)>;
+
+    /// Detects the player current mana and max mana.
+    fn detect_player_mana(&self, current_bar: Rect, max_bar: Rect) -> Result<(u32, u32)>;
+
+    /// Detects the player EXP percentage from the EXP bar at the bottom.
+    fn detect_player_exp(&self) -> Result<f32>;
+
+    /// Detects the mesos amount inside `region` of the opened inventory.
+    fn detect_mesos(&self, region: Rect) -> Result<u64>;
+
     /// Detects whether the player has a buff specified by `kind`.
     fn detect_player_buff(&self, kind: BuffKind) -> bool;
 
+    /// Detects whether the player has a user-defined buff from the given BGR `template`.
+    fn detect_player_custom_buff(&self, template: &Mat, threshold: f64) -> bool;
+
     /// Detects arrows from the given RGBA `Mat` image.
     ///
     /// `calibrating` represents the previous calibrating state returned by
@@ -165,6 +189,9 @@ pub trait Detector: 'static + Send + DynClone + Debug {
 
     /// Detects the Erda Shower skill from the given BGRA `Mat` image.
     fn detect_erda_shower(&self) -> Result<Rect>;
+
+    /// Detects a user-registered skill from the given BGR `template`.
+    fn detect_custom_skill(&self, template: &Mat, threshold: f64) -> Result<Rect>;
 }
 
 #[cfg(test)]
@@ -175,6 +202,8 @@ mock! {
         fn mat(&self) -> &OwnedMat;
         fn detect_mobs(&self, minimap: Rect, bound: Rect, player: Point) -> Result<Vec<Point>>;
         fn detect_esc_settings(&self) -> bool;
+        fn detect_esc_menu(&self) -> bool;
+        fn detect_change_channel_dialog(&self) -> bool;
         fn detect_elite_boss_bar(&self) -> bool;
         fn detect_minimap(&self, border_threshold: u8) -> Result<Rect>;
         fn detect_minimap_portals(&self, minimap: Rect) -> Result<Vec<Rect>>;
@@ -182,16 +211,24 @@ mock! {
         fn detect_player(&self, minimap: Rect) -> Result<Rect>;
         fn detect_player_kind(&self, minimap: Rect, kind: OtherPlayerKind) -> bool;
         fn detect_player_is_dead(&self) -> bool;
+        fn detect_player_revive_dialog(&self) -> bool;
         fn detect_player_in_cash_shop(&self) -> bool;
         fn detect_player_health_bar(&self) -> Result<Rect>;
         fn detect_player_current_max_health_bars(&self, health_bar: Rect) -> Result<(Rect, Rect)>;
         fn detect_player_health(&self, current_bar: Rect, max_bar: Rect) -> Result<(u32, u32)>;
+        fn detect_player_mana_bar(&self, health_bar: Rect) -> Result<Rect>;
+        fn detect_player_current_max_mana_bars(&self, mana_bar: Rect) -> Result<(Rect, Rect)>;
+        fn detect_player_mana(&self, current_bar: Rect, max_bar: Rect) -> Result<(u32, u32)>;
+        fn detect_player_exp(&self) -> Result<f32>;
+        fn detect_mesos(&self, region: Rect) -> Result<u64>;
         fn detect_player_buff(&self, kind: BuffKind) -> bool;
+        fn detect_player_custom_buff(&self, template: &Mat, threshold: f64) -> bool;
         fn detect_rune_arrows<'a>(
             &self,
             calibrating: ArrowsCalibrating,
         ) -> Result<ArrowsState>;
         fn detect_erda_shower(&self) -> Result<Rect>;
+        fn detect_custom_skill(&self, template: &Mat, threshold: f64) -> Result<Rect>;
     }
 
     impl Debug for Detector {
@@ -250,6 +287,14 @@ impl Detector for CachedDetector {
         detect_esc_settings(&**self.grayscale)
     }
 
+    fn detect_esc_menu(&self) -> bool {
+        detect_esc_menu(&**self.grayscale)
+    }
+
+    fn detect_change_channel_dialog(&self) -> bool {
+        detect_change_channel_dialog(&**self.grayscale)
+    }
+
     fn detect_elite_boss_bar(&self) -> bool {
         detect_elite_boss_bar(&**self.grayscale)
     }
@@ -282,6 +327,10 @@ impl Detector for CachedDetector {
         detect_player_is_dead(&**self.grayscale)
     }
 
+    fn detect_player_revive_dialog(&self) -> bool {
+        detect_player_revive_dialog(&**self.grayscale)
+    }
+
     fn detect_player_in_cash_shop(&self) -> bool {
         detect_player_in_cash_shop(&**self.grayscale)
     }
@@ -291,11 +340,31 @@ impl Detector for CachedDetector {
     }
 
     fn detect_player_current_max_health_bars(&self, health_bar: Rect) -> Result<(Rect, Rect)> {
-        detect_player_current_max_health_bars(&*self.mat, &**self.grayscale, health_bar)
+        detect_player_current_max_bars(&*self.mat, &**self.grayscale, health_bar)
     }
 
     fn detect_player_health(&self, current_bar: Rect, max_bar: Rect) -> Result<(u32, u32)> {
-        detect_player_health(&*self.mat, current_bar, max_bar)
+        detect_player_current_max_values(&*self.mat, current_bar, max_bar)
+    }
+
+    fn detect_player_mana_bar(&self, health_bar: Rect) -> Result<Rect> {
+        detect_player_mana_bar(&**self.grayscale, health_bar)
+    }
+
+    fn detect_player_current_max_mana_bars(&self, mana_bar: Rect) -> Result<(Rect, Rect)> {
+        detect_player_current_max_bars(&*self.mat, &**self.grayscale, mana_bar)
+    }
+
+    fn detect_player_mana(&self, current_bar: Rect, max_bar: Rect) -> Result<(u32, u32)> {
+        detect_player_current_max_values(&*self.mat, current_bar, max_bar)
+    }
+
+    fn detect_player_exp(&self) -> Result<f32> {
+        detect_player_exp(&*self.mat)
+    }
+
+    fn detect_mesos(&self, region: Rect) -> Result<u64> {
+        detect_mesos(&*self.mat, region)
     }
 
     fn detect_player_buff(&self, kind: BuffKind) -> bool {
@@ -317,6 +386,11 @@ impl Detector for CachedDetector {
         detect_player_buff(mat, kind)
     }
 
+    fn detect_player_custom_buff(&self, template: &Mat, threshold: f64) -> bool {
+        let mat = to_bgr(&crop_to_buffs_region(&*self.mat));
+        detect_template(&mat, template, Point::default(), threshold).is_ok()
+    }
+
     fn detect_rune_arrows(&self, calibrating: ArrowsCalibrating) -> Result<ArrowsState> {
         detect_rune_arrows(&*self.mat, calibrating)
     }
@@ -324,9 +398,13 @@ impl Detector for CachedDetector {
     fn detect_erda_shower(&self) -> Result<Rect> {
         detect_erda_shower(&**self.grayscale)
     }
+
+    fn detect_custom_skill(&self, template: &Mat, threshold: f64) -> Result<Rect> {
+        detect_template(&to_bgr(&*self.mat), template, Point::default(), threshold)
+    }
 }
 
-fn crop_to_buffs_region(mat: &impl MatTraitConst) -> BoxedRef<Mat> {
+pub fn crop_to_buffs_region(mat: &impl MatTraitConst) -> BoxedRef<Mat> {
     let size = mat.size().unwrap();
     // crop to top right of the image for buffs region
     let crop_x = size.width / 3;
@@ -471,6 +549,44 @@ fn detect_esc_settings(mat: &impl ToInputArray) -> bool {
     false
 }
 
+/// Detects the ESC menu from its `MENU` section header
+fn detect_esc_menu(mat: &impl ToInputArray) -> bool {
+    /// TODO: Support default ratio
+    static TEMPLATE: LazyLock<Mat> = LazyLock::new(|| {
+        imgcodecs::imdecode(include_bytes!(env!("ESC_MENU_TEMPLATE")), IMREAD_GRAYSCALE).unwrap()
+    });
+
+    detect_template(mat, &*TEMPLATE, Point::default(), 0.85).is_ok()
+}
+
+/// Detects the change channel dialog from its OK and Cancel buttons
+///
+/// Unlike [`detect_esc_settings`], both buttons must be found on the same row with the OK button
+/// on the left so that the ESC menu or a dialog with a single button is not mistaken for it.
+fn detect_change_channel_dialog(mat: &impl ToInputArray) -> bool {
+    /// TODO: Support default ratio
+    static OK: LazyLock<Mat> = LazyLock::new(|| {
+        imgcodecs::imdecode(include_bytes!(env!("ESC_OK_TEMPLATE")), IMREAD_GRAYSCALE).unwrap()
+    });
+    static CANCEL: LazyLock<Mat> = LazyLock::new(|| {
+        imgcodecs::imdecode(
+            include_bytes!(env!("ESC_CANCEL_TEMPLATE")),
+            IMREAD_GRAYSCALE,
+        )
+        .unwrap()
+    });
+    /// The maximum vertical distance between the two buttons
+    const ROW_TOLERANCE: i32 = 5;
+
+    let Ok(ok) = detect_template(mat, &*OK, Point::default(), 0.85) else {
+        return false;
+    };
+    let Ok(cancel) = detect_template(mat, &*CANCEL, Point::default(), 0.85) else {
+        return false;
+    };
+    (ok.y - cancel.y).abs() <= ROW_TOLERANCE && ok.x + ok.width <= cancel.x
+}
+
 fn detect_elite_boss_bar(mat: &impl MatTraitConst) -> bool {
     /// TODO: Support default ratio
     static TEMPLATE_1: LazyLock<Mat> = LazyLock::new(|| {
@@ -746,6 +862,15 @@ fn detect_player_is_dead(mat: &impl ToInputArray) -> bool {
     detect_template(mat, &*TEMPLATE, Point::default(), 0.8).is_ok()
 }
 
+fn detect_player_revive_dialog(mat: &impl ToInputArray) -> bool {
+    /// TODO: Support default ratio
+    static TEMPLATE: LazyLock<Mat> = LazyLock::new(|| {
+        imgcodecs::imdecode(include_bytes!(env!("ESC_OK_TEMPLATE")), IMREAD_GRAYSCALE).unwrap()
+    });
+
+    detect_template(mat, &*TEMPLATE, Point::default(), 0.85).is_ok()
+}
+
 fn detect_player_in_cash_shop(mat: &impl ToInputArray) -> bool {
     /// TODO: Support default ratio
     static CASH_SHOP: LazyLock<Mat> = LazyLock::new(|| {
@@ -775,11 +900,38 @@ fn detect_player_health_bar(mat: &impl ToInputArray) -> Result<Rect> {
     ))
 }
 
-fn detect_player_current_max_health_bars(
-    mat: &impl MatTraitConst,
-    grayscale: &impl MatTraitConst,
-    hp_bar: Rect,
-) -> Result<(Rect, Rect)> {
+/// Detects the mana bar below `hp_bar`
+///
+/// The mana bar has the same layout and width as the health bar but its vertical offset can vary
+/// between UI layouts. The bar is located by its value separator inside the region below
+/// `hp_bar` and is centered vertically on the separator.
+fn detect_player_mana_bar(grayscale: &impl MatTraitConst, hp_bar: Rect) -> Result<Rect> {
+    let size = grayscale.size().unwrap();
+    let y = hp_bar.y + hp_bar.height;
+    let height = (hp_bar.height * 2).min(size.height - y);
+    if height < hp_bar.height {
+        bail!("mana bar is out of bound");
+    }
+    let region = Rect::new(hp_bar.x, y, hp_bar.width, height);
+    let separator = detect_player_bar_separator(&grayscale.roi(region).unwrap(), region.tl())
+        .map_err(|_| anyhow!("mana bar not found"))?;
+    let mp_bar = Rect::new(
+        hp_bar.x,
+        (separator.y + separator.height / 2 - hp_bar.height / 2).max(y),
+        hp_bar.width,
+        hp_bar.height,
+    );
+    if mp_bar.y + mp_bar.height > size.height {
+        bail!("mana bar is out of bound");
+    }
+    Ok(mp_bar)
+}
+
+/// Detects the separator between the current and max values of a health or mana bar
+///
+/// Both separator types are tried in order so that the detection has no state shared between
+/// bars.
+fn detect_player_bar_separator(grayscale: &impl ToInputArray, offset: Point) -> Result<Rect> {
     /// TODO: Support default ratio
     static HP_SEPARATOR_1: LazyLock<Mat> = LazyLock::new(|| {
         imgcodecs::imdecode(
@@ -795,26 +947,25 @@ fn detect_player_current_max_health_bars(
         )
         .unwrap()
     });
+
+    detect_template(grayscale, &*HP_SEPARATOR_1, offset, 0.7)
+        .or_else(|_| detect_template(grayscale, &*HP_SEPARATOR_2, offset, 0.7))
+}
+
+/// Detects the current and max value bars of `hp_bar`
+///
+/// This is also used for the mana bar since both bars have the same layout.
+fn detect_player_current_max_bars(
+    mat: &impl MatTraitConst,
+    grayscale: &impl MatTraitConst,
+    hp_bar: Rect,
+) -> Result<(Rect, Rect)> {
+    /// TODO: Support default ratio
     static HP_SHIELD: LazyLock<Mat> = LazyLock::new(|| {
         imgcodecs::imdecode(include_bytes!(env!("HP_SHIELD_TEMPLATE")), IMREAD_GRAYSCALE).unwrap()
     });
-    static HP_SEPARATOR_TYPE_1: AtomicBool = AtomicBool::new(true);
 
-    let hp_separator_type_1 = HP_SEPARATOR_TYPE_1.load(Ordering::Relaxed);
-    let hp_separator_template = if hp_separator_type_1 {
-        &*HP_SEPARATOR_1
-    } else {
-        &*HP_SEPARATOR_2
-    };
-    let hp_separator = detect_template(
-        &grayscale.roi(hp_bar).unwrap(),
-        hp_separator_template,
-        hp_bar.tl(),
-        0.7,
-    )
-    .inspect_err(|_| {
-        HP_SEPARATOR_TYPE_1.store(!hp_separator_type_1, Ordering::Release);
-    })?;
+    let hp_separator = detect_player_bar_separator(&grayscale.roi(hp_bar).unwrap(), hp_bar.tl())?;
     let hp_shield = detect_template(
         &grayscale.roi(hp_bar).unwrap(),
         &*HP_SHIELD,
@@ -866,22 +1017,22 @@ fn detect_player_current_max_health_bars(
     Ok((left_bbox, right_bbox))
 }
 
-fn detect_player_health(
+fn detect_player_current_max_values(
     mat: &impl MatTraitConst,
     current_bar: Rect,
     max_bar: Rect,
 ) -> Result<(u32, u32)> {
-    let current_health = extract_texts(mat, &[current_bar]);
-    let current_health = current_health
+    let current = extract_texts(mat, &[current_bar]);
+    let current = current
         .first()
         .and_then(|value| value.parse::<u32>().ok())
-        .ok_or(anyhow!("cannot detect current health"))?;
-    let max_health = extract_texts(mat, &[max_bar]);
-    let max_health = max_health
+        .ok_or(anyhow!("cannot detect current value"))?;
+    let max = extract_texts(mat, &[max_bar]);
+    let max = max
         .first()
         .and_then(|value| value.parse::<u32>().ok())
-        .ok_or(anyhow!("cannot detect max health"))?;
-    Ok((current_health.min(max_health), max_health))
+        .ok_or(anyhow!("cannot detect max value"))?;
+    Ok((current.min(max), max))
 }
 
 fn detect_player_buff<T: MatTraitConst + ToInputArray>(mat: &T, kind: BuffKind) -> bool {
@@ -1557,6 +1708,9 @@ fn detect_template<T: ToInputArray + MatTraitConst>(
 }
 
 /// Detects a single match with `mask` from `template` with the given BGR image `Mat`.
+///
+/// Returns an error instead of panicking when the template cannot be matched (e.g. a
+/// user-cropped template larger than `mat`).
 #[inline]
 fn detect_template_single<T: ToInputArray + MatTraitConst>(
     mat: &impl ToInputArray,
@@ -1568,7 +1722,7 @@ fn detect_template_single<T: ToInputArray + MatTraitConst>(
     detect_template_multiple(mat, template, mask, offset, 1, threshold)
         .into_iter()
         .next()
-        .unwrap()
+        .unwrap_or_else(|| Err(anyhow!("template detection failed")))
 }
 
 /// Detects multiple matches from `template` with the given BGR image `Mat`.
@@ -1671,6 +1825,70 @@ fn detect_template_multiple<T: ToInputArray + MatTraitConst>(
     filter
 }
 
+/// Detects the EXP percentage text (e.g. `123456789[12.34%]`) in the bottom center of the game.
+fn detect_player_exp(mat: &impl MatTraitConst) -> Result<f32> {
+    /// The height of the bottom region containing the EXP bar text
+    const EXP_BAR_HEIGHT: i32 = 16;
+
+    let size = mat.size().unwrap();
+    if size.height < EXP_BAR_HEIGHT {
+        bail!("EXP bar is out of bound");
+    }
+    let exp_bar = Rect::new(
+        size.width / 4,
+        size.height - EXP_BAR_HEIGHT,
+        size.width / 2,
+        EXP_BAR_HEIGHT,
+    );
+    let exp = mat.roi(exp_bar).unwrap();
+    let (exp_in, exp_w_ratio, exp_h_ratio) = preprocess_for_text_bboxes(&exp);
+    let bboxes = extract_text_bboxes(&exp_in, exp_w_ratio, exp_h_ratio, exp_bar.x, exp_bar.y);
+    extract_texts(mat, &bboxes)
+        .iter()
+        .find_map(|text| parse_exp_percent(text))
+        .ok_or(anyhow!("cannot detect EXP percentage"))
+}
+
+/// Parses the percentage out of the EXP bar text.
+fn parse_exp_percent(text: &str) -> Option<f32> {
+    let end = text.find('%')?;
+    let start = text[..end].rfind('[').map(|i| i + 1).unwrap_or(0);
+    text[start..end]
+        .parse::<f32>()
+        .ok()
+        .filter(|percent| (0.0..=100.0).contains(percent))
+}
+
+fn detect_mesos(mat: &impl MatTraitConst, region: Rect) -> Result<u64> {
+    let size = mat.size().unwrap();
+    if region.x < 0
+        || region.y < 0
+        || region.width <= 0
+        || region.height <= 0
+        || region.x + region.width > size.width
+        || region.y + region.height > size.height
+    {
+        bail!("mesos region is out of bound");
+    }
+    let mesos = mat.roi(region).unwrap();
+    let (mesos_in, mesos_w_ratio, mesos_h_ratio) = preprocess_for_text_bboxes(&mesos);
+    let bboxes = extract_text_bboxes(&mesos_in, mesos_w_ratio, mesos_h_ratio, region.x, region.y);
+    extract_texts(mat, &bboxes)
+        .iter()
+        .filter_map(|text| parse_mesos(text))
+        .max()
+        .ok_or(anyhow!("cannot detect mesos"))
+}
+
+/// Parses the mesos amount out of a text with digit grouping separators.
+fn parse_mesos(text: &str) -> Option<u64> {
+    let digits = text
+        .chars()
+        .filter(|c| !matches!(c, ',' | '.' | ' '))
+        .collect::<String>();
+    digits.parse::<u64>().ok()
+}
+
 /// Extracts texts from the non-preprocessed `Mat` and detected text bounding boxes.
 fn extract_texts(mat: &impl MatTraitConst, bboxes: &[Rect]) -> Vec<String> {
     static TEXT_RECOGNITION_MODEL: LazyLock<Mutex<TextRecognitionModel>> = LazyLock::new(|| {
@@ -2079,3 +2297,55 @@ fn norm_rgb_to_input_value(mat: &impl MatTraitConst) -> SessionInputValue {
     let tensor = Tensor::from_array(input).unwrap();
     SessionInputValue::Owned(tensor.into_dyn())
 }
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::CV_8UC3;
+
+    use super::*;
+
+    #[test]
+    fn parse_exp_percent_texts() {
+        let cases = [
+            ("12.345%", Some(12.345)),
+            ("1234567[12.345%]", Some(12.345)),
+            ("0%", Some(0.0)),
+            ("12.345", None),
+            ("150.000%", None),
+            ("1a.3b5%", None),
+            ("%", None),
+            ("", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_exp_percent(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn parse_mesos_texts() {
+        let cases = [
+            ("1,234,567", Some(1_234_567)),
+            ("1.234.567", Some(1_234_567)),
+            ("1 234 567", Some(1_234_567)),
+            ("0", Some(0)),
+            ("1,234,5a7", None),
+            ("meso 1,234", None),
+            ("-1,234", None),
+            ("18,446,744,073,709,551,615", Some(u64::MAX)),
+            ("18,446,744,073,709,551,616", None),
+            (",.", None),
+            ("", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_mesos(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn detect_template_larger_than_mat() {
+        let mat = Mat::new_rows_cols_with_default(4, 4, CV_8UC3, Scalar::default()).unwrap();
+        let template = Mat::new_rows_cols_with_default(8, 8, CV_8UC3, Scalar::default()).unwrap();
+
+        assert!(detect_template(&mat, &template, Point::default(), 0.8).is_err());
+    }
+}
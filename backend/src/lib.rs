@@ -6,18 +6,28 @@
 #![feature(iter_array_chunks)]
 #![feature(associated_type_defaults)]
 #![feature(assert_matches)]
+#![cfg_attr(test, feature(test))]
 
-use std::sync::{LazyLock, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
 
 use anyhow::{Result, anyhow};
-use tokio::sync::{
-    broadcast, mpsc,
-    oneshot::{self, Sender},
+use tokio::{
+    sync::{
+        broadcast, mpsc,
+        oneshot::{self, Sender},
+    },
+    task::spawn_blocking,
 };
 
+use crate::simulator::SimulationInput;
+
 mod array;
 mod bridge;
 mod buff;
+mod clock;
 mod context;
 mod database;
 #[cfg(debug_assertions)]
@@ -28,23 +38,36 @@ mod minimap;
 mod network;
 mod pathing;
 mod player;
+mod playlist;
+mod progress;
 mod request_handler;
 mod rotator;
 mod rpc;
+mod simulator;
 mod skill;
 mod task;
 
 pub use {
+    array::Array,
+    buff::BuffKind,
     context::init,
     database::{
-        Action, ActionCondition, ActionConfiguration, ActionKey, ActionKeyDirection, ActionKeyWith,
-        ActionMove, AutoMobbing, Bound, CaptureMode, Class, Configuration, InputMethod, KeyBinding,
-        KeyBindingConfiguration, LinkKeyBinding, Minimap, Notifications, PingPong, Platform,
-        Position, PotionMode, RotationMode, Settings, delete_map, query_configs, query_maps,
-        query_settings, upsert_config, upsert_map, upsert_settings,
+        Action, ActionBranch, ActionBranchPredicate, ActionCondition, ActionConfiguration,
+        ActionKey, ActionKeyDirection, ActionKeyWith, ActionMacro, ActionMacroRef, ActionMove,
+        ActionSequence, ActionSequenceStep, ActionSequenceStepKind, AutoMobbing, Bound,
+        BuffDuration, CaptureMode, Class, Configuration, CustomBuff, CustomSkill,
+        CustomSkillDetection, InputMethod, KeyBinding, KeyBindingConfiguration, LinkKeyBinding,
+        MAX_SEQUENCE_STEPS_COUNT, Minimap, MovementProfile, Notifications, PatrolBehavior,
+        PatrolZone, PingPong, PlaceableSkill, Platform, PlaylistEntry, PlaylistLimit, Portal,
+        Position, PotionMode, PotionRule, Rope, RotationMode, SessionRecord, Settings, delete_map,
+        query_configs, query_maps, query_sessions, query_settings, upsert_config, upsert_map,
+        upsert_session, upsert_settings,
+    },
+    rotator::{ActionStatistics, RotatorMode},
+    simulator::{
+        MAX_SIMULATION_DURATION_MILLIS, SimulatedAction, SimulatedActionKind, SimulationOptions,
+        SimulationReport, SimulationWarning,
     },
-    pathing::MAX_PLATFORMS_COUNT,
-    rotator::RotatorMode,
     strum::{EnumMessage, IntoEnumIterator, ParseError},
 };
 
@@ -81,14 +104,23 @@ macro_rules! expect_value_variant {
 enum Request {
     RotateActions(bool),
     RotateActionsHalting,
+    ResetTimers,
+    ChangeChannel,
     CreateMinimap(String),
     UpdateMinimap(Option<String>, Minimap),
     UpdateConfiguration(Configuration),
     UpdateSettings(Settings),
     RedetectMinimap,
     GameState,
+    ActionStatistics,
+    SimulateRotation,
     MinimapFrame,
     MinimapPlatformsBound,
+    BuffsRegionFrame,
+    CaptureCustomBuffTemplate(Bound),
+    GameFrame,
+    CaptureCustomSkillTemplate(Bound),
+    CaptureCustomSkillAnchor(Bound),
     KeyReceiver,
     QueryCaptureHandles,
     SelectCaptureHandle(Option<usize>),
@@ -112,14 +144,23 @@ enum Request {
 enum Response {
     RotateActions,
     RotateActionsHalting(bool),
+    ResetTimers,
+    ChangeChannel,
     CreateMinimap(Option<Minimap>),
     UpdateMinimap,
     UpdateConfiguration,
     UpdateSettings,
     RedetectMinimap,
     GameState(GameState),
+    ActionStatistics(HashMap<usize, ActionStatistics>),
+    SimulateRotation(Option<SimulationInput>),
     MinimapFrame(Option<(Vec<u8>, usize, usize)>),
     MinimapPlatformsBound(Option<Bound>),
+    BuffsRegionFrame(Option<(Vec<u8>, usize, usize)>),
+    CaptureCustomBuffTemplate(Option<Vec<u8>>),
+    GameFrame(Option<(Vec<u8>, usize, usize)>),
+    CaptureCustomSkillTemplate(Option<Vec<u8>>),
+    CaptureCustomSkillAnchor(Option<[u8; 4]>),
     KeyReceiver(broadcast::Receiver<KeyBinding>),
     QueryCaptureHandles((Vec<String>, Option<usize>)),
     SelectCaptureHandle,
@@ -140,6 +181,10 @@ pub(crate) trait RequestHandler {
 
     fn on_rotate_actions_halting(&self) -> bool;
 
+    fn on_reset_timers(&mut self);
+
+    fn on_change_channel(&mut self);
+
     fn on_create_minimap(&self, name: String) -> Option<Minimap>;
 
     fn on_update_minimap(&mut self, preset: Option<String>, minimap: Minimap);
@@ -152,10 +197,24 @@ pub(crate) trait RequestHandler {
 
     fn on_game_state(&self) -> GameState;
 
+    fn on_action_statistics(&self) -> HashMap<usize, ActionStatistics>;
+
+    fn on_simulate_rotation(&self) -> Option<SimulationInput>;
+
     fn on_minimap_frame(&self) -> Option<(Vec<u8>, usize, usize)>;
 
     fn on_minimap_platforms_bound(&self) -> Option<Bound>;
 
+    fn on_buffs_region_frame(&self) -> Option<(Vec<u8>, usize, usize)>;
+
+    fn on_capture_custom_buff_template(&self, bound: Bound) -> Option<Vec<u8>>;
+
+    fn on_game_frame(&self) -> Option<(Vec<u8>, usize, usize)>;
+
+    fn on_capture_custom_skill_template(&self, bound: Bound) -> Option<Vec<u8>>;
+
+    fn on_capture_custom_skill_anchor(&self, bound: Bound) -> Option<[u8; 4]>;
+
     fn on_key_receiver(&self) -> broadcast::Receiver<KeyBinding>;
 
     fn on_query_capture_handles(&mut self) -> (Vec<String>, Option<usize>);
@@ -182,11 +241,24 @@ pub(crate) trait RequestHandler {
 pub struct GameState {
     pub position: Option<(i32, i32)>,
     pub health: Option<(u32, u32)>,
+    pub mana: Option<(u32, u32)>,
+    /// The current EXP percentage
+    pub exp: Option<f32>,
+    /// The last read mesos amount
+    pub mesos: Option<u64>,
+    /// The EXP progress of actions running without halting
+    pub exp_session: Option<SessionRecord>,
     pub state: String,
     pub normal_action: Option<String>,
     pub priority_action: Option<String>,
     pub erda_shower_state: String,
+    /// The names and states of user-registered skills
+    pub custom_skill_states: Vec<(String, String)>,
     pub destinations: Vec<(i32, i32)>,
+    /// The index and preset name of the currently running playlist entry
+    pub playlist: Option<(usize, String)>,
+    /// The buffs whose detected icon disagrees with their known duration timer
+    pub buff_mismatches: Vec<String>,
 }
 
 pub async fn rotate_actions(halting: bool) {
@@ -203,6 +275,19 @@ pub async fn rotate_actions_halting() -> bool {
     )
 }
 
+/// Resets the timers of all priority actions (e.g. every milliseconds actions and buffs)
+pub async fn reset_timers() {
+    expect_unit_variant!(request(Request::ResetTimers).await, Response::ResetTimers)
+}
+
+/// Requests the player to change channel once the current state can be overridden
+pub async fn change_channel() {
+    expect_unit_variant!(
+        request(Request::ChangeChannel).await,
+        Response::ChangeChannel
+    )
+}
+
 pub async fn create_minimap(name: String) -> Option<Minimap> {
     expect_value_variant!(
         request(Request::CreateMinimap(name)).await,
@@ -242,6 +327,27 @@ pub async fn player_state() -> GameState {
     expect_value_variant!(request(Request::GameState).await, Response::GameState)
 }
 
+/// Queries the [`ActionStatistics`] of the running actions keyed by the index in the preset
+pub async fn action_statistics() -> HashMap<usize, ActionStatistics> {
+    expect_value_variant!(
+        request(Request::ActionStatistics).await,
+        Response::ActionStatistics
+    )
+}
+
+/// Simulates the actions of the current minimap preset offline
+///
+/// The indices in [`SimulationReport`] are the indices in the preset. Returns [`None`] if
+/// there is no minimap. The preset is only captured by the update loop and the simulation runs
+/// in a blocking task so that it does not stall the bot.
+pub async fn simulate_rotation(options: SimulationOptions) -> Option<SimulationReport> {
+    let input = expect_value_variant!(
+        request(Request::SimulateRotation).await,
+        Response::SimulateRotation
+    )?;
+    spawn_blocking(move || input.simulate(options)).await.ok()
+}
+
 pub async fn minimap_frame() -> Result<(Vec<u8>, usize, usize)> {
     expect_value_variant!(request(Request::MinimapFrame).await, Response::MinimapFrame)
         .ok_or(anyhow!("minimap frame not found"))
@@ -254,6 +360,46 @@ pub async fn minimap_platforms_bound() -> Option<Bound> {
     )
 }
 
+/// Queries the RGBA frame of the region where buffs are detected with its width and height
+pub async fn buffs_region_frame() -> Option<(Vec<u8>, usize, usize)> {
+    expect_value_variant!(
+        request(Request::BuffsRegionFrame).await,
+        Response::BuffsRegionFrame
+    )
+}
+
+/// Crops `bound` relative to [`buffs_region_frame`] from the current frame as a PNG template
+/// for [`CustomBuff::template`]
+pub async fn capture_custom_buff_template(bound: Bound) -> Option<Vec<u8>> {
+    expect_value_variant!(
+        request(Request::CaptureCustomBuffTemplate(bound)).await,
+        Response::CaptureCustomBuffTemplate
+    )
+}
+
+/// Queries the whole RGBA game frame with its width and height
+pub async fn game_frame() -> Option<(Vec<u8>, usize, usize)> {
+    expect_value_variant!(request(Request::GameFrame).await, Response::GameFrame)
+}
+
+/// Crops `bound` relative to [`game_frame`] from the current frame as a PNG template for
+/// [`CustomSkillDetection::Template`]
+pub async fn capture_custom_skill_template(bound: Bound) -> Option<Vec<u8>> {
+    expect_value_variant!(
+        request(Request::CaptureCustomSkillTemplate(bound)).await,
+        Response::CaptureCustomSkillTemplate
+    )
+}
+
+/// Captures the BGRA pixel at the center of `bound` relative to [`game_frame`] for
+/// [`CustomSkillDetection::Region`]
+pub async fn capture_custom_skill_anchor(bound: Bound) -> Option<[u8; 4]> {
+    expect_value_variant!(
+        request(Request::CaptureCustomSkillAnchor(bound)).await,
+        Response::CaptureCustomSkillAnchor
+    )
+}
+
 pub async fn key_receiver() -> broadcast::Receiver<KeyBinding> {
     expect_value_variant!(request(Request::KeyReceiver).await, Response::KeyReceiver)
 }
@@ -313,6 +459,14 @@ pub(crate) fn poll_request(handler: &mut dyn RequestHandler) {
             Request::RotateActionsHalting => {
                 Response::RotateActionsHalting(handler.on_rotate_actions_halting())
             }
+            Request::ResetTimers => {
+                handler.on_reset_timers();
+                Response::ResetTimers
+            }
+            Request::ChangeChannel => {
+                handler.on_change_channel();
+                Response::ChangeChannel
+            }
             Request::CreateMinimap(name) => {
                 Response::CreateMinimap(handler.on_create_minimap(name))
             }
@@ -333,10 +487,25 @@ pub(crate) fn poll_request(handler: &mut dyn RequestHandler) {
                 Response::RedetectMinimap
             }
             Request::GameState => Response::GameState(handler.on_game_state()),
+            Request::ActionStatistics => Response::ActionStatistics(handler.on_action_statistics()),
+            Request::SimulateRotation => Response::SimulateRotation(handler.on_simulate_rotation()),
             Request::MinimapFrame => Response::MinimapFrame(handler.on_minimap_frame()),
             Request::MinimapPlatformsBound => {
                 Response::MinimapPlatformsBound(handler.on_minimap_platforms_bound())
             }
+            Request::BuffsRegionFrame => {
+                Response::BuffsRegionFrame(handler.on_buffs_region_frame())
+            }
+            Request::CaptureCustomBuffTemplate(bound) => {
+                Response::CaptureCustomBuffTemplate(handler.on_capture_custom_buff_template(bound))
+            }
+            Request::GameFrame => Response::GameFrame(handler.on_game_frame()),
+            Request::CaptureCustomSkillTemplate(bound) => Response::CaptureCustomSkillTemplate(
+                handler.on_capture_custom_skill_template(bound),
+            ),
+            Request::CaptureCustomSkillAnchor(bound) => {
+                Response::CaptureCustomSkillAnchor(handler.on_capture_custom_skill_anchor(bound))
+            }
             Request::KeyReceiver => Response::KeyReceiver(handler.on_key_receiver()),
             Request::QueryCaptureHandles => {
                 Response::QueryCaptureHandles(handler.on_query_capture_handles())
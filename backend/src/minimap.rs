@@ -2,29 +2,45 @@ use std::fmt;
 
 use anyhow::{Result, anyhow};
 use log::debug;
-use opencv::core::{MatTraitConst, Point, Rect, Vec4b};
+use opencv::{
+    core::{Mat, MatTraitConst, Point, Rect, Size, Vec4b},
+    imgproc::{INTER_AREA, resize},
+};
 
 use crate::{
-    array::Array,
+    MovementProfile,
     context::{Context, Contextual, ControlFlow},
     database::Minimap as MinimapData,
     detect::{Detector, OtherPlayerKind},
     network::NotificationKind,
     pathing::{
-        MAX_PLATFORMS_COUNT, Platform, PlatformWithNeighbors, find_neighbors, find_platforms_bound,
+        Platform, PlatformWithNeighbors, Portal, Rope, find_neighbors, find_platforms_bound,
     },
-    player::{DOUBLE_JUMP_THRESHOLD, GRAPPLING_MAX_THRESHOLD, JUMP_THRESHOLD, Player},
+    player::Player,
     task::{Task, Update, update_detection_task},
 };
 
 const MINIMAP_BORDER_WHITENESS_THRESHOLD: u8 = 160;
 
+/// The number of cells per side used to compute [`MinimapIdle::fingerprint`]
+const MINIMAP_FINGERPRINT_CELLS: i32 = 8;
+
+/// The maximum number of differing bits for two fingerprints to be considered the same map
+///
+/// Leaves room for the player, other players and rune dots moving around on the minimap.
+const MINIMAP_FINGERPRINT_MAX_DISTANCE: u32 = 10;
+
 #[derive(Debug, Default)]
 pub struct MinimapState {
     data: Option<MinimapData>,
-    minimap_task: Option<Task<Result<(Anchors, Rect)>>>,
+    minimap_task: Option<Task<Result<(Anchors, Rect, u64)>>>,
     rune_task: Option<Task<Result<Point>>>,
     portals_task: Option<Task<Result<Vec<Rect>>>>,
+    /// The portal positions of the current minimap
+    ///
+    /// Stored here instead of [`MinimapIdle`] so that the number of portals is not limited while
+    /// [`Minimap`] stays [`Copy`].
+    portals: Vec<Rect>,
     has_elite_boss_task: Option<Task<Result<bool>>>,
     has_guildie_player_task: Option<Task<Result<bool>>>,
     has_stranger_player_task: Option<Task<Result<bool>>>,
@@ -41,6 +57,11 @@ impl MinimapState {
         self.data = Some(data);
         self.update_platforms = true;
     }
+
+    /// The portal positions detected on the current minimap
+    pub fn portals(&self) -> &[Rect] {
+        &self.portals
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -77,6 +98,10 @@ pub struct MinimapIdle {
     anchors: Anchors,
     /// The bounding box of the minimap.
     pub bbox: Rect,
+    /// The average hash of the minimap image when it was detected
+    ///
+    /// Used to tell whether two minimaps of the same size are the same map.
+    pub fingerprint: u64,
     /// Whether the UI is being partially overlapped
     ///
     /// It is partially overlapped by other UIs if one of the anchor mismatches.
@@ -97,17 +122,20 @@ pub struct MinimapIdle {
     has_stranger_player: Threshold<bool>,
     /// Whether there is a friend
     has_friend_player: Threshold<bool>,
-    /// The portal positions
+    /// The largest rectangle containing all the user provided platforms
     ///
-    /// Praying each night that there won't be more than 16 portals...
-    /// Initially, it is only 8 until it crashes at Henesys with 10 portals smh
-    pub portals: Array<Rect, 16>,
-    /// The user provided platforms
-    pub platforms: Array<PlatformWithNeighbors, MAX_PLATFORMS_COUNT>,
-    /// The largest rectangle containing all the platforms
+    /// The platforms graph itself is stored in [`Context::platforms`].
     pub platforms_bound: Option<Rect>,
 }
 
+impl MinimapIdle {
+    /// Whether there is a stranger
+    #[inline]
+    pub fn has_stranger_player(&self) -> bool {
+        self.has_stranger_player.value.unwrap_or_default()
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 #[allow(clippy::large_enum_variant)] // There is only ever a single instance of Minimap
 pub enum Minimap {
@@ -134,32 +162,34 @@ fn update_context(contextual: Minimap, context: &Context, state: &mut MinimapSta
 }
 
 fn update_detecting_context(context: &Context, state: &mut MinimapState) -> Minimap {
-    let Update::Ok((anchors, bbox)) =
+    let Update::Ok((anchors, bbox, fingerprint)) =
         update_detection_task(context, 2000, &mut state.minimap_task, move |detector| {
             let bbox = detector.detect_minimap(MINIMAP_BORDER_WHITENESS_THRESHOLD)?;
             let size = bbox.width.min(bbox.height) as usize;
             let tl = anchor_at(detector.mat(), bbox.tl(), size, 1)?;
             let br = anchor_at(detector.mat(), bbox.br(), size, -1)?;
             let anchors = Anchors { tl, br };
+            let fingerprint = fingerprint_at(detector.mat(), bbox)?;
             debug!(target: "minimap", "anchor points: {:?}", anchors);
-            Ok((anchors, bbox))
+            Ok((anchors, bbox, fingerprint))
         })
     else {
         return Minimap::Detecting;
     };
 
-    let (platforms, platforms_bound) = state
+    let platforms_bound = state
         .data
         .as_ref()
-        .map(|data| platforms_from_data(bbox, data))
-        .unwrap_or_default();
+        .and_then(|data| platforms_bound_from_data(bbox, data));
     state.update_platforms = false;
     state.rune_task = None;
     state.has_elite_boss_task = None;
+    state.portals.clear();
 
     Minimap::Idle(MinimapIdle {
         anchors,
         bbox,
+        fingerprint,
         partially_overlapping: false,
         rune: None,
         rune_fail_count: 0,
@@ -167,8 +197,6 @@ fn update_detecting_context(context: &Context, state: &mut MinimapState) -> Mini
         has_guildie_player: Threshold::new(2),
         has_stranger_player: Threshold::new(2),
         has_friend_player: Threshold::new(2),
-        portals: Array::new(),
-        platforms,
         platforms_bound,
     })
 }
@@ -178,7 +206,10 @@ fn update_idle_context(
     state: &mut MinimapState,
     idle: MinimapIdle,
 ) -> Option<Minimap> {
-    if matches!(context.player, Player::CashShopThenExit(_, _)) {
+    if matches!(
+        context.player,
+        Player::CashShopThenExit(_, _) | Player::ReadingMesos(_)
+    ) {
         return Some(Minimap::Idle(idle));
     }
 
@@ -191,8 +222,6 @@ fn update_idle_context(
         has_guildie_player,
         has_stranger_player,
         has_friend_player,
-        portals,
-        mut platforms,
         mut platforms_bound,
         ..
     } = idle;
@@ -236,15 +265,12 @@ fn update_idle_context(
         has_friend_player,
         OtherPlayerKind::Friend,
     );
-    let portals = update_portals_task(context, &mut state.portals_task, portals, bbox);
+    update_portals_task(context, &mut state.portals_task, &mut state.portals, bbox);
 
     // TODO: any better way to read persistent state in other contextual?
     if state.update_platforms {
-        let (updated_platforms, updated_bound) =
-            platforms_from_data(bbox, state.data.as_mut().unwrap());
         state.update_platforms = false;
-        platforms = updated_platforms;
-        platforms_bound = updated_bound
+        platforms_bound = platforms_bound_from_data(bbox, state.data.as_ref().unwrap());
     }
 
     Some(Minimap::Idle(MinimapIdle {
@@ -255,8 +281,6 @@ fn update_idle_context(
         has_guildie_player,
         has_stranger_player,
         has_friend_player,
-        portals,
-        platforms,
         platforms_bound,
         ..idle
     }))
@@ -367,44 +391,64 @@ fn update_other_player_task(
 fn update_portals_task(
     context: &Context,
     task: &mut Option<Task<Result<Vec<Rect>>>>,
-    portals: Array<Rect, 16>,
+    portals: &mut Vec<Rect>,
     minimap: Rect,
-) -> Array<Rect, 16> {
+) {
     let update = update_detection_task(context, 5000, task, move |detector| {
         detector.detect_minimap_portals(minimap)
     });
-    match update {
-        Update::Ok(vec) if portals.len() < vec.len() => {
-            Array::from_iter(vec.into_iter().map(|portal| {
-                Rect::new(
-                    portal.x,
-                    minimap.height - portal.y,
-                    portal.width,
-                    portal.height,
-                )
-            }))
-        }
-        Update::Ok(_) | Update::Err(_) | Update::Pending => portals,
+    if let Update::Ok(vec) = update
+        && portals.len() < vec.len()
+    {
+        portals.clear();
+        portals.extend(vec.into_iter().map(|portal| {
+            Rect::new(
+                portal.x,
+                minimap.height - portal.y,
+                portal.width,
+                portal.height,
+            )
+        }));
     }
 }
 
-fn platforms_from_data(
-    bbox: Rect,
+/// Builds the platforms graph from the platforms, ropes and portals of `minimap` using the
+/// thresholds of `profile`
+pub fn platforms_from_data(
     minimap: &MinimapData,
-) -> (Array<PlatformWithNeighbors, 24>, Option<Rect>) {
-    let platforms = Array::from_iter(find_neighbors(
+    profile: MovementProfile,
+) -> Vec<PlatformWithNeighbors> {
+    find_neighbors(
         &minimap
             .platforms
             .iter()
             .copied()
             .map(Platform::from)
             .collect::<Vec<_>>(),
-        DOUBLE_JUMP_THRESHOLD,
-        JUMP_THRESHOLD,
-        GRAPPLING_MAX_THRESHOLD,
-    ));
-    let bound = find_platforms_bound(bbox, &platforms);
-    (platforms, bound)
+        &minimap
+            .ropes
+            .iter()
+            .copied()
+            .map(Rope::from)
+            .collect::<Vec<_>>(),
+        &minimap
+            .portals
+            .iter()
+            .copied()
+            .map(Portal::from)
+            .collect::<Vec<_>>(),
+        profile,
+    )
+}
+
+fn platforms_bound_from_data(bbox: Rect, minimap: &MinimapData) -> Option<Rect> {
+    let platforms = minimap
+        .platforms
+        .iter()
+        .copied()
+        .map(Platform::from)
+        .collect::<Vec<_>>();
+    find_platforms_bound(bbox, &platforms)
 }
 
 #[inline]
@@ -484,6 +528,41 @@ fn anchor_at(
         .ok_or(anyhow!("anchor not found"))
 }
 
+/// Computes the average hash of the minimap at `bbox`
+///
+/// The minimap is downscaled to a grid of [`MINIMAP_FINGERPRINT_CELLS`] cells and each bit is set
+/// when the cell is brighter than the average of all cells.
+fn fingerprint_at(mat: &impl MatTraitConst, bbox: Rect) -> Result<u64> {
+    let mut cells = Mat::default();
+    resize(
+        &mat.roi(bbox)?,
+        &mut cells,
+        Size::new(MINIMAP_FINGERPRINT_CELLS, MINIMAP_FINGERPRINT_CELLS),
+        0.0,
+        0.0,
+        INTER_AREA,
+    )?;
+    let values = (0..MINIMAP_FINGERPRINT_CELLS * MINIMAP_FINGERPRINT_CELLS)
+        .map(|i| {
+            let pixel = cells
+                .at_2d::<Vec4b>(i / MINIMAP_FINGERPRINT_CELLS, i % MINIMAP_FINGERPRINT_CELLS)?;
+            Ok(pixel.iter().take(3).map(|v| *v as u32).sum::<u32>())
+        })
+        .collect::<Result<Vec<u32>>>()?;
+    let mean = values.iter().sum::<u32>() / values.len() as u32;
+    Ok(values
+        .into_iter()
+        .enumerate()
+        .filter(|(_, value)| *value > mean)
+        .fold(0, |hash, (i, _)| hash | (1 << i)))
+}
+
+/// Whether the two minimap fingerprints are close enough to be the same map
+#[inline]
+pub fn is_same_fingerprint(a: u64, b: u64) -> bool {
+    (a ^ b).count_ones() <= MINIMAP_FINGERPRINT_MAX_DISTANCE
+}
+
 #[cfg(test)]
 mod tests {
     use std::{assert_matches::assert_matches, time::Duration};
@@ -583,6 +662,7 @@ mod tests {
         let idle = MinimapIdle {
             anchors,
             bbox,
+            fingerprint: 0,
             partially_overlapping: false,
             rune: None,
             rune_fail_count: 0,
@@ -590,8 +670,6 @@ mod tests {
             has_guildie_player: Threshold::default(),
             has_stranger_player: Threshold::default(),
             has_friend_player: Threshold::default(),
-            portals: Array::new(),
-            platforms: Array::new(),
             platforms_bound: None,
         };
 
@@ -604,4 +682,22 @@ mod tests {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn minimap_fingerprint() {
+        let mut mat = Mat::zeros(80, 80, opencv::core::CV_8UC4)
+            .unwrap()
+            .to_mat()
+            .unwrap();
+        for y in 0..80 {
+            for x in 0..40 {
+                *mat.at_2d_mut::<Vec4b>(y, x).unwrap() = Vec4b::all(255);
+            }
+        }
+
+        let fingerprint = fingerprint_at(&mat, Rect::new(0, 0, 80, 80)).unwrap();
+        assert_eq!(fingerprint, 0x0F0F_0F0F_0F0F_0F0F);
+        assert!(is_same_fingerprint(fingerprint, fingerprint ^ 0b111));
+        assert!(!is_same_fingerprint(fingerprint, !fingerprint));
+    }
 }
@@ -35,6 +35,10 @@ pub enum NotificationKind {
     PlayerStrangerAppear,
     PlayerFriendAppear,
     PlayerIsDead,
+    PlayerHealthCritical,
+    PlayerReviveHalted,
+    ChangeChannelHalted,
+    ExpReport,
 }
 
 impl From<NotificationKind> for usize {
@@ -96,6 +100,25 @@ impl DiscordNotification {
     }
 
     pub fn schedule_notification(&self, kind: NotificationKind) -> Result<(), Error> {
+        self.schedule(kind, None)
+    }
+
+    /// Schedules a [`NotificationKind::PlayerReviveHalted`] with `reason` as the details
+    pub fn schedule_revive_halted(&self, reason: String) -> Result<(), Error> {
+        self.schedule(NotificationKind::PlayerReviveHalted, Some(reason))
+    }
+
+    /// Schedules a [`NotificationKind::ChangeChannelHalted`] with `reason` as the details
+    pub fn schedule_change_channel_halted(&self, reason: String) -> Result<(), Error> {
+        self.schedule(NotificationKind::ChangeChannelHalted, Some(reason))
+    }
+
+    /// Schedules a [`NotificationKind::ExpReport`] with `report` as the content
+    pub fn schedule_exp_report(&self, report: String) -> Result<(), Error> {
+        self.schedule(NotificationKind::ExpReport, Some(report))
+    }
+
+    fn schedule(&self, kind: NotificationKind, details: Option<String>) -> Result<(), Error> {
         let settings = self.settings.borrow();
         let is_enabled = match kind {
             NotificationKind::FailOrMapChange => {
@@ -104,6 +127,11 @@ impl DiscordNotification {
             NotificationKind::RuneAppear => settings.notifications.notify_on_rune_appear,
             NotificationKind::EliteBossAppear => settings.notifications.notify_on_elite_boss_appear,
             NotificationKind::PlayerIsDead => settings.notifications.notify_on_player_die,
+            // Opted in by the potion rule itself
+            NotificationKind::PlayerHealthCritical => true,
+            // Always notifies since the bot stops
+            NotificationKind::PlayerReviveHalted | NotificationKind::ChangeChannelHalted => true,
+            NotificationKind::ExpReport => settings.notifications.exp_report_millis.is_some(),
             NotificationKind::PlayerGuildieAppear => {
                 settings.notifications.notify_on_player_guildie_appear
             }
@@ -157,6 +185,23 @@ impl DiscordNotification {
             NotificationKind::PlayerIsDead => {
                 format!("{user_id}The player is dead")
             }
+            NotificationKind::PlayerHealthCritical => {
+                format!("{user_id}Bot stopped because the player health stayed critical")
+            }
+            NotificationKind::PlayerReviveHalted => {
+                format!(
+                    "{user_id}Bot stopped after reviving because {}",
+                    details.unwrap_or_default()
+                )
+            }
+            NotificationKind::ChangeChannelHalted => {
+                format!(
+                    "{user_id}Bot stopped after changing channel because {}",
+                    details.unwrap_or_default()
+                )
+            }
+            // Periodic so it does not ping the user
+            NotificationKind::ExpReport => details.unwrap_or_default(),
             NotificationKind::PlayerGuildieAppear => {
                 format!("{user_id}Bot has detected guildie player(s)")
             }
@@ -176,19 +221,27 @@ impl DiscordNotification {
             NotificationKind::FailOrMapChange => vec![(None, 2), (None, 4)],
             NotificationKind::EliteBossAppear
             | NotificationKind::PlayerIsDead
+            | NotificationKind::PlayerHealthCritical
+            | NotificationKind::PlayerReviveHalted
+            | NotificationKind::ChangeChannelHalted
             | NotificationKind::PlayerGuildieAppear
             | NotificationKind::PlayerStrangerAppear
             | NotificationKind::PlayerFriendAppear
-            | NotificationKind::RuneAppear => vec![(None, 2)],
+            | NotificationKind::RuneAppear
+            | NotificationKind::ExpReport => vec![(None, 2)],
         };
         let delay = match kind {
             NotificationKind::FailOrMapChange => 5,
             NotificationKind::EliteBossAppear
             | NotificationKind::PlayerIsDead
+            | NotificationKind::PlayerHealthCritical
+            | NotificationKind::PlayerReviveHalted
+            | NotificationKind::ChangeChannelHalted
             | NotificationKind::PlayerGuildieAppear
             | NotificationKind::PlayerStrangerAppear
             | NotificationKind::PlayerFriendAppear
-            | NotificationKind::RuneAppear => 3,
+            | NotificationKind::RuneAppear
+            | NotificationKind::ExpReport => 3,
         };
 
         let mut scheduled = self.scheduled.lock().unwrap();
@@ -1,14 +1,17 @@
 use core::range::Range;
 use std::{
     cmp::{Reverse, max, min},
-    collections::{BinaryHeap, HashMap},
+    collections::BinaryHeap,
 };
 
 use opencv::core::{Point, Rect};
 
-use crate::array::Array;
+use crate::MovementProfile;
 
-pub const MAX_PLATFORMS_COUNT: usize = 24;
+/// The weight of moving through a portal
+///
+/// Portal teleports regardless of distance but still takes time to walk onto and enter.
+const PORTAL_WEIGHT: u32 = 10;
 
 /// The kind of movement the player should perform
 #[derive(Debug, Clone, Copy)]
@@ -18,6 +21,10 @@ pub enum MovementHint {
     Infer,
     /// Performs a walk and then jump
     WalkAndJump,
+    /// Climbs the rope or ladder at the current point up to the next point
+    ClimbRope,
+    /// Enters the portal at the current point to teleport to the next point
+    UsePortal,
 }
 
 /// A platform where player can stand on
@@ -33,11 +40,63 @@ impl Platform {
     }
 }
 
-/// A platform along with its reachable neighbor platforms
+/// A rope or ladder where player can climb from bottom to top
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Rope {
+    x: i32,
+    y_bottom: i32,
+    y_top: i32,
+}
+
+impl Rope {
+    pub fn new(x: i32, y_bottom: i32, y_top: i32) -> Self {
+        Self {
+            x,
+            y_bottom: min(y_bottom, y_top),
+            y_top: max(y_bottom, y_top),
+        }
+    }
+}
+
+/// A portal where player can enter to teleport to the destination
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct Portal {
+    x: i32,
+    y: i32,
+    to_x: i32,
+    to_y: i32,
+}
+
+impl Portal {
+    pub fn new(x: i32, y: i32, to_x: i32, to_y: i32) -> Self {
+        Self { x, y, to_x, to_y }
+    }
+}
+
+/// The way to move from a platform to its neighbor other than walking or jumping
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Connection {
+    Rope(Rope),
+    Portal(Portal),
+}
+
+/// A platform reachable from another platform
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Neighbor {
+    /// The index of the neighbor platform in the platforms graph
+    index: usize,
+    /// The rope or portal to use to reach the neighbor platform
+    connection: Option<Connection>,
+}
+
+/// A platform along with its reachable neighbor platforms
+///
+/// A list of this struct forms the platforms graph in which each neighbor is referred to by its
+/// index in the list.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PlatformWithNeighbors {
     inner: Platform,
-    neighbors: Array<Platform, MAX_PLATFORMS_COUNT>,
+    neighbors: Vec<Neighbor>,
 }
 
 impl PlatformWithNeighbors {
@@ -56,7 +115,7 @@ impl PlatformWithNeighbors {
 #[derive(PartialEq, Eq)]
 struct VisitingPlatform {
     score: u32,
-    platform: Platform,
+    index: usize,
 }
 
 impl PartialOrd for VisitingPlatform {
@@ -74,17 +133,14 @@ impl Ord for VisitingPlatform {
 /// Finds the smallest bounding rectangle that contains all given platforms.
 ///
 /// Returns [`None`] if the list of platforms is empty
-pub fn find_platforms_bound(
-    minimap: Rect,
-    platforms: &Array<PlatformWithNeighbors, MAX_PLATFORMS_COUNT>,
-) -> Option<Rect> {
+pub fn find_platforms_bound(minimap: Rect, platforms: &[Platform]) -> Option<Rect> {
     platforms
         .iter()
         .map(|platform| {
             Rect::new(
-                platform.inner.xs.start,
-                minimap.height - platform.inner.y,
-                platform.inner.xs.end - platform.inner.xs.start,
+                platform.xs.start,
+                minimap.height - platform.y,
+                platform.xs.end - platform.xs.start,
                 1,
             )
         })
@@ -96,31 +152,50 @@ pub fn find_platforms_bound(
 }
 
 /// Builds a list of `PlatformWithNeighbors` from  `&[Platforms]` by determining which platforms
-/// are reachable from each other.
+/// are reachable from each other. Each platform neighbors refer to the index of the neighbor
+/// platform in the returned list.
 ///
-/// The following thresholds are used to determine reachability:
+/// The following thresholds of `profile` are used to determine reachability:
 /// - `double_jump_threshold`: minimum x distance required for a double jump
 /// - `jump_threshold`: minimum y distance required for a regular jump
-/// - `grappling_threshold`: maximum allowed y vertical distance to grapple upward
+/// - `grappling_max_threshold`: maximum allowed y vertical distance to grapple upward
+///
+/// A platform is also reachable from another platform if there is a rope in `ropes` connecting
+/// the two upward or a portal in `portals` connecting the two regardless of the thresholds above.
+/// A portal connection is added as a separate neighbor so that path finding can choose the
+/// cheaper one.
 pub fn find_neighbors(
     platforms: &[Platform],
-    double_jump_threshold: i32,
-    jump_threshold: i32,
-    grappling_threshold: i32,
+    ropes: &[Rope],
+    portals: &[Portal],
+    profile: MovementProfile,
 ) -> Vec<PlatformWithNeighbors> {
+    let jump_threshold = profile.jump_threshold;
     let mut vec = Vec::with_capacity(platforms.len());
     for i in 0..platforms.len() {
         let current = platforms[i];
-        let mut neighbors = Array::new();
+        let mut neighbors = Vec::new();
         for j in (0..i).chain(i + 1..platforms.len()) {
-            if platforms_reachable(
-                current,
-                platforms[j],
-                double_jump_threshold,
-                jump_threshold,
-                grappling_threshold,
-            ) {
-                neighbors.push(platforms[j]);
+            let rope = find_rope(ropes, current, platforms[j], jump_threshold);
+            if rope.is_some()
+                || platforms_reachable(
+                    current,
+                    platforms[j],
+                    profile.double_jump_threshold,
+                    jump_threshold,
+                    profile.grappling_max_threshold,
+                )
+            {
+                neighbors.push(Neighbor {
+                    index: j,
+                    connection: rope.map(Connection::Rope),
+                });
+            }
+            for portal in find_portals(portals, current, platforms[j], jump_threshold) {
+                neighbors.push(Neighbor {
+                    index: j,
+                    connection: Some(Connection::Portal(portal)),
+                });
             }
         }
         vec.push(PlatformWithNeighbors {
@@ -132,13 +207,17 @@ pub fn find_neighbors(
 }
 
 /// Finds a sequence of points representing a path from `from` to `to`, using the given
-/// platform map.
+/// platforms graph built from [`find_neighbors`].
 ///
 /// `vertical_threshold` represents maximum y distance between two connected platforms to perform
 /// a grappling. This is used as weight score to help prioritize vertical movement over
-/// horizontal movement. If `enable_hint` is true, provides movement hints like `WalkAndJump`.
+/// horizontal movement. Platforms connected by a rope or portal are not limited by this
+/// threshold. If `enable_hint` is true, provides movement hints like `WalkAndJump`. The
+/// `ClimbRope` and `UsePortal` hints are always provided as they are the only way to move through
+/// a rope or portal.
+#[inline]
 pub fn find_points_with(
-    platforms: &Array<PlatformWithNeighbors, MAX_PLATFORMS_COUNT>,
+    platforms: &[PlatformWithNeighbors],
     from: Point,
     to: Point,
     enable_hint: bool,
@@ -146,58 +225,84 @@ pub fn find_points_with(
     jump_threshold: i32,
     vertical_threshold: i32,
 ) -> Option<Vec<(Point, MovementHint)>> {
-    let platforms = platforms
-        .iter()
-        .map(|platform| (platform.inner, *platform))
-        .collect::<HashMap<_, _>>();
-    let from_platform = find_platform(&platforms, from, None)?; // Clamp `from` to nearest platform
-    let to_platform = find_platform(&platforms, to, Some(jump_threshold))?;
-    let mut came_from = HashMap::<Platform, Platform>::new();
+    find_points_excluding_portals(
+        platforms,
+        from,
+        to,
+        enable_hint,
+        double_jump_threshold,
+        jump_threshold,
+        vertical_threshold,
+        &[],
+    )
+}
+
+/// Same as [`find_points_with`] but does not move through the portals in `excluded_portals`.
+///
+/// A portal is identified by its `UsePortal` point, the portal x on the platform it is entered
+/// from, as provided in the found points.
+#[allow(clippy::too_many_arguments)]
+pub fn find_points_excluding_portals(
+    platforms: &[PlatformWithNeighbors],
+    from: Point,
+    to: Point,
+    enable_hint: bool,
+    double_jump_threshold: i32,
+    jump_threshold: i32,
+    vertical_threshold: i32,
+    excluded_portals: &[Point],
+) -> Option<Vec<(Point, MovementHint)>> {
+    let from_index = find_platform(platforms, from, None)?; // Clamp `from` to nearest platform
+    let to_index = find_platform(platforms, to, Some(jump_threshold))?;
+    let mut came_from = vec![None; platforms.len()];
     let mut visiting = BinaryHeap::new();
-    let mut score = HashMap::<Platform, u32>::new();
+    let mut score = vec![u32::MAX; platforms.len()];
 
     visiting.push(Reverse(VisitingPlatform {
         score: 0,
-        platform: from_platform,
+        index: from_index,
     }));
-    score.insert(from_platform, 0);
+    score[from_index] = 0;
 
-    while !visiting.is_empty() {
-        let current = visiting.pop().unwrap().0;
-        let current_score = score.get(&current.platform).copied().unwrap_or(u32::MAX);
-        if current.platform == to_platform {
+    while let Some(Reverse(current)) = visiting.pop() {
+        if current.index == to_index {
             return points_from(
+                platforms,
                 &came_from,
                 from,
-                from_platform,
-                to_platform,
+                from_index,
+                to_index,
                 to,
                 enable_hint,
                 double_jump_threshold,
                 jump_threshold,
             );
         }
+        // Skip stale entry as a better score has been found after it was pushed
+        if current.score > score[current.index] {
+            continue;
+        }
 
-        let neighbors = platforms[&current.platform].neighbors;
-        for neighbor in neighbors {
-            let tentative_score = current_score.saturating_add(weight_score(
-                current.platform,
-                neighbor,
+        let current_platform = platforms[current.index].inner;
+        for &Neighbor { index, connection } in &platforms[current.index].neighbors {
+            if let Some(Connection::Portal(portal)) = connection
+                && excluded_portals.contains(&Point::new(portal.x, current_platform.y))
+            {
+                continue;
+            }
+            let tentative_score = current.score.saturating_add(weight_score(
+                current_platform,
+                platforms[index].inner,
+                connection,
                 vertical_threshold,
             ));
-            let neighbor_score = score.get(&neighbor).copied().unwrap_or(u32::MAX);
-            if tentative_score < neighbor_score {
-                came_from.insert(neighbor, current.platform);
-                score.insert(neighbor, tentative_score);
-                if !visiting
-                    .iter()
-                    .any(|platform| platform.0.platform == neighbor)
-                {
-                    visiting.push(Reverse(VisitingPlatform {
-                        score: tentative_score,
-                        platform: neighbor,
-                    }));
-                }
+            if tentative_score < score[index] {
+                came_from[index] = Some((current.index, connection));
+                score[index] = tentative_score;
+                visiting.push(Reverse(VisitingPlatform {
+                    score: tentative_score,
+                    index,
+                }));
             }
         }
     }
@@ -210,10 +315,11 @@ pub fn find_points_with(
 /// Adds offsets to handle jump and landing safety margins.
 #[allow(clippy::too_many_arguments)]
 fn points_from(
-    came_from: &HashMap<Platform, Platform>,
+    platforms: &[PlatformWithNeighbors],
+    came_from: &[Option<(usize, Option<Connection>)>],
     from: Point,
-    from_platform: Platform,
-    to_platform: Platform,
+    from_index: usize,
+    to_index: usize,
     to: Point,
     enable_hint: bool,
     double_jump_threshold: i32,
@@ -228,26 +334,34 @@ fn points_from(
 
     const WALK_AND_JUMP_THRESHOLD: i32 = 13;
 
-    let mut current = to_platform;
-    let mut went_to = HashMap::new();
-    while came_from.contains_key(&current) {
-        let next = came_from[&current];
-        went_to.insert(next, current);
-        current = next;
+    let mut current_index = to_index;
+    let mut went_to = vec![None; platforms.len()];
+    while let Some((next_index, connection)) = came_from[current_index] {
+        went_to[next_index] = Some((current_index, connection));
+        current_index = next_index;
     }
-    current = from_platform;
+    current_index = from_index;
 
     // TODO: Likely messed up some numbers
     let mut points = vec![];
-    let mut last_point = Point::new(from.x, current.y);
+    let mut last_point = Point::new(from.x, platforms[current_index].inner.y);
     let double_jump_offset = double_jump_threshold / 2 + DOUBLE_JUMP_EXTRA_OFFSET;
-    while went_to.contains_key(&current) {
-        let next = went_to[&current];
+    while let Some((next_index, connection)) = went_to[current_index] {
+        let current = platforms[current_index].inner;
+        let next = platforms[next_index].inner;
         let start_max = max(next.xs.start, current.xs.start);
         let end_min = min(next.xs.end, current.xs.end);
 
-        // Check if the current platform overlap with the next platform
-        if ranges_overlap(next.xs, current.xs) {
+        if let Some(Connection::Rope(rope)) = connection {
+            // Add 2 points to move to the rope and then climb up to the next platform
+            points.push((Point::new(rope.x, current.y), MovementHint::ClimbRope));
+            points.push((Point::new(rope.x, next.y), MovementHint::Infer));
+        } else if let Some(Connection::Portal(portal)) = connection {
+            // Add 2 points to move onto the portal and then teleport to the destination
+            points.push((Point::new(portal.x, current.y), MovementHint::UsePortal));
+            points.push((Point::new(portal.to_x, next.y), MovementHint::Infer));
+        } else if ranges_overlap(next.xs, current.xs) {
+            // The current platform overlaps with the next platform
             if (start_max..end_min).contains(&last_point.x) {
                 // Already inside intersection range, add a point to move up or down
                 points.push((Point::new(last_point.x, next.y), MovementHint::Infer));
@@ -289,43 +403,103 @@ fn points_from(
         }
 
         last_point = points.last().copied().unwrap().0;
-        current = next;
+        current_index = next_index;
     }
 
-    points.push((Point::new(to.x, to_platform.y), MovementHint::Infer));
+    points.push((
+        Point::new(to.x, platforms[to_index].inner.y),
+        MovementHint::Infer,
+    ));
 
     Some(points)
 }
 
-/// Finds the closest platform underneath or near a given `point`.
+/// Finds the index of the closest platform underneath or near a given `point`.
 ///
 /// If `jump_threshold` is provided, it limits how far vertically the point can be from a platform.
 #[inline]
 fn find_platform(
-    platforms: &HashMap<Platform, PlatformWithNeighbors>,
+    platforms: &[PlatformWithNeighbors],
     point: Point,
     jump_threshold: Option<i32>,
-) -> Option<Platform> {
+) -> Option<usize> {
     platforms
-        .keys()
-        .filter(|platform| platform.xs.contains(&point.x))
-        .min_by_key(|platform| (platform.y - point.y).abs())
-        .filter(|platform| {
+        .iter()
+        .enumerate()
+        .map(|(i, platform)| (i, platform.inner))
+        .filter(|(_, platform)| platform.xs.contains(&point.x))
+        .min_by_key(|(_, platform)| (platform.y - point.y).abs())
+        .filter(|(_, platform)| {
             jump_threshold.is_none() || (platform.y - point.y).abs() < jump_threshold.unwrap()
         })
-        .copied()
+        .map(|(i, _)| i)
 }
 
 #[inline]
-fn weight_score(current: Platform, neighbor: Platform, vertical_threshold: i32) -> u32 {
+fn weight_score(
+    current: Platform,
+    neighbor: Platform,
+    connection: Option<Connection>,
+    vertical_threshold: i32,
+) -> u32 {
     let y_distance = (current.y - neighbor.y).abs();
-    if y_distance < vertical_threshold {
-        y_distance as u32
-    } else {
-        u32::MAX
+    match connection {
+        Some(Connection::Portal(_)) => PORTAL_WEIGHT,
+        Some(Connection::Rope(_)) => y_distance as u32,
+        None if y_distance < vertical_threshold => y_distance as u32,
+        None => u32::MAX,
     }
 }
 
+/// Finds a rope in `ropes` that can be climbed from platform `from` up to platform `to`.
+///
+/// A rope connects the two platforms if:
+/// - `to` is above `from` by at least `jump_threshold`
+/// - Both platforms [`Platform::xs`] contain the rope x
+/// - The rope can be grabbed from `from` by pressing up or jumping
+/// - The rope top is within `jump_threshold` from `to`
+#[inline]
+fn find_rope(ropes: &[Rope], from: Platform, to: Platform, jump_threshold: i32) -> Option<Rope> {
+    if to.y - from.y < jump_threshold {
+        return None;
+    }
+    ropes
+        .iter()
+        .find(|rope| {
+            from.xs.contains(&rope.x)
+                && to.xs.contains(&rope.x)
+                && from.y <= rope.y_top
+                && rope.y_bottom - from.y < jump_threshold
+                && (rope.y_top - to.y).abs() < jump_threshold
+        })
+        .copied()
+}
+
+/// Finds the portals in `portals` that can be entered from platform `from` to teleport to
+/// platform `to`.
+///
+/// A portal connects the two platforms if the portal position is on `from` and its destination is
+/// on `to`. A position is on a platform if the platform [`Platform::xs`] contains the position x
+/// and the position y is within `jump_threshold` from the platform y. All connecting portals are
+/// returned so that path finding can fall back to another one if a portal does not teleport.
+#[inline]
+fn find_portals(
+    portals: &[Portal],
+    from: Platform,
+    to: Platform,
+    jump_threshold: i32,
+) -> impl Iterator<Item = Portal> {
+    portals
+        .iter()
+        .filter(move |portal| {
+            from.xs.contains(&portal.x)
+                && (portal.y - from.y).abs() < jump_threshold
+                && to.xs.contains(&portal.to_x)
+                && (portal.to_y - to.y).abs() < jump_threshold
+        })
+        .copied()
+}
+
 /// Determines whether the two platforms are reachable from one another.
 ///
 /// One platform is reachable to another platform if:
@@ -366,25 +540,42 @@ fn ranges_overlap<R: Into<Range<i32>>>(first: R, second: R) -> bool {
 
 #[cfg(test)]
 mod tests {
+    extern crate test;
+
     use opencv::core::Point;
+    use test::{Bencher, black_box};
 
     use super::{
-        MAX_PLATFORMS_COUNT, MovementHint, Platform, PlatformWithNeighbors, find_neighbors,
+        Connection, MovementHint, Platform, PlatformWithNeighbors, Portal, Rope, find_neighbors,
     };
     use crate::{
-        array::Array,
-        pathing::{find_points_with, ranges_overlap},
+        MovementProfile,
+        pathing::{find_points_excluding_portals, find_points_with, ranges_overlap},
     };
 
-    fn make_platforms_with_neighbors(
+    fn make_platforms_with_neighbors(platforms: &[Platform]) -> Vec<PlatformWithNeighbors> {
+        make_platforms_with_neighbors_and_connections(platforms, &[], &[])
+    }
+
+    fn make_platforms_with_neighbors_and_connections(
         platforms: &[Platform],
-    ) -> Array<PlatformWithNeighbors, MAX_PLATFORMS_COUNT> {
-        let connected = find_neighbors(platforms, 25, 7, 41);
-        let mut array = Array::new();
-        for p in connected {
-            array.push(p);
-        }
-        array
+        ropes: &[Rope],
+        portals: &[Portal],
+    ) -> Vec<PlatformWithNeighbors> {
+        find_neighbors(platforms, ropes, portals, MovementProfile::default())
+    }
+
+    /// Makes a tower of `levels` levels with `per_level` platforms on each level
+    fn make_tower_platforms(levels: i32, per_level: i32) -> Vec<Platform> {
+        (0..levels)
+            .flat_map(|level| {
+                (0..per_level).map(move |i| {
+                    // Staggers odd levels so that platforms are connected diagonally
+                    let x = i * 60 + (level % 2) * 30;
+                    Platform::new(x..x + 50, 10 + level * 25)
+                })
+            })
+            .collect()
     }
 
     #[test]
@@ -501,4 +692,162 @@ mod tests {
         assert_eq!(points.first().unwrap().0.y, 50);
         assert_eq!(points.last().unwrap().0.y, 52);
     }
+
+    #[test]
+    fn find_points_with_climb_rope() {
+        let platforms = [
+            Platform::new(0..100, 10),
+            Platform::new(20..60, 70), // Too high to grapple
+        ];
+        let from = Point::new(80, 10);
+        let to = Point::new(50, 70);
+
+        let platforms_without_rope = make_platforms_with_neighbors(&platforms);
+        assert!(find_points_with(&platforms_without_rope, from, to, true, 25, 7, 41).is_none());
+
+        let ropes = [Rope::new(30, 12, 68)];
+        let platforms = make_platforms_with_neighbors_and_connections(&platforms, &ropes, &[]);
+        let points = find_points_with(&platforms, from, to, false, 25, 7, 41).unwrap();
+
+        let expected = vec![
+            (Point::new(30, 10), MovementHint::ClimbRope),
+            (Point::new(30, 70), MovementHint::Infer),
+            (Point::new(50, 70), MovementHint::Infer),
+        ];
+        assert_eq!(points, expected);
+    }
+
+    #[test]
+    fn find_neighbors_rope_only_upward() {
+        let platforms = [Platform::new(0..100, 10), Platform::new(20..60, 70)];
+        let ropes = [Rope::new(30, 12, 68)];
+        let platforms = find_neighbors(&platforms, &ropes, &[], MovementProfile::default());
+
+        assert_eq!(
+            platforms[0].neighbors[0].connection,
+            Some(Connection::Rope(ropes[0]))
+        );
+        assert_eq!(platforms[1].neighbors[0].connection, None);
+    }
+
+    #[test]
+    fn find_points_with_use_portal() {
+        let platforms = [
+            Platform::new(0..50, 10),
+            Platform::new(150..200, 100), // Too far to reach without portal
+        ];
+        let from = Point::new(10, 10);
+        let to = Point::new(190, 100);
+
+        let platforms_without_portal = make_platforms_with_neighbors(&platforms);
+        assert!(find_points_with(&platforms_without_portal, from, to, true, 25, 7, 41).is_none());
+
+        let portals = [Portal::new(40, 10, 160, 100)];
+        let platforms = make_platforms_with_neighbors_and_connections(&platforms, &[], &portals);
+        let points = find_points_with(&platforms, from, to, true, 25, 7, 41).unwrap();
+
+        let expected = vec![
+            (Point::new(40, 10), MovementHint::UsePortal),
+            (Point::new(160, 100), MovementHint::Infer),
+            (Point::new(190, 100), MovementHint::Infer),
+        ];
+        assert_eq!(points, expected);
+    }
+
+    #[test]
+    fn find_points_excluding_portals_uses_other_portal() {
+        let platforms = [Platform::new(0..50, 10), Platform::new(150..200, 100)];
+        let portals = [Portal::new(40, 10, 160, 100), Portal::new(20, 10, 180, 100)];
+        let platforms = make_platforms_with_neighbors_and_connections(&platforms, &[], &portals);
+        let from = Point::new(10, 10);
+        let to = Point::new(190, 100);
+
+        let points = find_points_with(&platforms, from, to, true, 25, 7, 41).unwrap();
+        let (excluded, _) = points[0];
+        let expected = if excluded == Point::new(40, 10) {
+            Point::new(20, 10)
+        } else {
+            Point::new(40, 10)
+        };
+
+        let points =
+            find_points_excluding_portals(&platforms, from, to, true, 25, 7, 41, &[excluded])
+                .unwrap();
+        assert_eq!(points[0], (expected, MovementHint::UsePortal));
+        assert!(
+            find_points_excluding_portals(
+                &platforms,
+                from,
+                to,
+                true,
+                25,
+                7,
+                41,
+                &[excluded, expected]
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn find_points_with_portal_only_when_cheaper() {
+        let platforms = [
+            Platform::new(0..100, 50),
+            Platform::new(0..100, 55), // Directly above and cheaper than portal
+        ];
+        let portals = [Portal::new(40, 50, 60, 55)];
+        let platforms = make_platforms_with_neighbors_and_connections(&platforms, &[], &portals);
+        let from = Point::new(10, 50);
+        let to = Point::new(20, 55);
+
+        let points = find_points_with(&platforms, from, to, true, 25, 7, 41).unwrap();
+
+        assert!(
+            points
+                .iter()
+                .all(|(_, hint)| *hint != MovementHint::UsePortal),
+            "Expected no UsePortal movement hint when direct path is cheaper, got: {points:?}",
+        );
+    }
+
+    #[test]
+    fn find_points_with_more_than_24_platforms() {
+        let platforms = make_tower_platforms(16, 4);
+        let platforms = make_platforms_with_neighbors(&platforms);
+        let from = Point::new(10, 10);
+        let to = Point::new(220, 385);
+
+        let points = find_points_with(&platforms, from, to, true, 25, 7, 41).unwrap();
+
+        assert!(points.len() > 16, "Expected a long path, got: {points:?}");
+        assert_eq!(points.last().unwrap().0, to);
+    }
+
+    #[bench]
+    fn bench_find_neighbors_64_platforms(bencher: &mut Bencher) {
+        let platforms = make_tower_platforms(16, 4);
+
+        bencher
+            .iter(|| find_neighbors(black_box(&platforms), &[], &[], MovementProfile::default()));
+    }
+
+    #[bench]
+    fn bench_find_points_with_24_platforms(bencher: &mut Bencher) {
+        let platforms = make_platforms_with_neighbors(&make_tower_platforms(6, 4));
+        let from = Point::new(10, 10);
+        let to = Point::new(220, 135);
+
+        bencher
+            .iter(|| find_points_with(black_box(&platforms), from, to, true, 25, 7, 41).unwrap());
+    }
+
+    #[bench]
+    fn bench_find_points_with_256_platforms(bencher: &mut Bencher) {
+        let platforms = make_platforms_with_neighbors(&make_tower_platforms(32, 8));
+        let from = Point::new(10, 10);
+        let to = Point::new(460, 785);
+
+        bencher
+            .iter(|| find_points_with(black_box(&platforms), from, to, true, 25, 7, 41).unwrap());
+    }
 }
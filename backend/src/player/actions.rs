@@ -2,11 +2,11 @@ use opencv::core::{Point, Rect};
 use platforms::windows::KeyKind;
 use strum::Display;
 
-use super::{DOUBLE_JUMP_THRESHOLD, Player, PlayerState, use_key::UseKey};
+use super::{Player, PlayerState, use_key::UseKey};
 use crate::{
-    Action, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMove, KeyBinding, Position,
-    context::{Context, MS_PER_TICK},
-    database::LinkKeyBinding,
+    Action, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMove, ActionSequence,
+    ActionSequenceStep, ActionSequenceStepKind, KeyBinding, MAX_SEQUENCE_STEPS_COUNT, Position,
+    array::Array, clock::millis_to_ticks, context::Context, database::LinkKeyBinding,
     minimap::Minimap,
 };
 
@@ -31,6 +31,9 @@ pub struct PlayerActionKey {
     pub wait_before_use_ticks_random_range: u32,
     pub wait_after_use_ticks: u32,
     pub wait_after_use_ticks_random_range: u32,
+    pub hold_ticks: u32,
+    pub hold_ticks_random_range: u32,
+    pub hold_repeat_ticks: Option<u32>,
 }
 
 impl From<ActionKey> for PlayerActionKey {
@@ -46,6 +49,9 @@ impl From<ActionKey> for PlayerActionKey {
             wait_before_use_millis_random_range,
             wait_after_use_millis,
             wait_after_use_millis_random_range,
+            hold_millis,
+            hold_millis_random_range,
+            hold_repeat_millis,
             ..
         }: ActionKey,
     ) -> Self {
@@ -56,12 +62,17 @@ impl From<ActionKey> for PlayerActionKey {
             position,
             direction,
             with,
-            wait_before_use_ticks: (wait_before_use_millis / MS_PER_TICK) as u32,
-            wait_before_use_ticks_random_range: (wait_before_use_millis_random_range / MS_PER_TICK)
-                as u32,
-            wait_after_use_ticks: (wait_after_use_millis / MS_PER_TICK) as u32,
-            wait_after_use_ticks_random_range: (wait_after_use_millis_random_range / MS_PER_TICK)
-                as u32,
+            wait_before_use_ticks: millis_to_ticks(wait_before_use_millis),
+            wait_before_use_ticks_random_range: millis_to_ticks(
+                wait_before_use_millis_random_range,
+            ),
+            wait_after_use_ticks: millis_to_ticks(wait_after_use_millis),
+            wait_after_use_ticks_random_range: millis_to_ticks(wait_after_use_millis_random_range),
+            hold_ticks: millis_to_ticks(hold_millis),
+            hold_ticks_random_range: millis_to_ticks(hold_millis_random_range),
+            hold_repeat_ticks: hold_repeat_millis
+                .map(millis_to_ticks)
+                .filter(|ticks| *ticks > 0),
         }
     }
 }
@@ -73,6 +84,9 @@ impl From<ActionKey> for PlayerActionKey {
 pub struct PlayerActionMove {
     pub position: Position,
     pub wait_after_move_ticks: u32,
+    /// Whether to move through [`crate::context::Context::platforms`] when auto mob platforms
+    /// pathing is enabled.
+    pub platforms_pathing: bool,
 }
 
 impl From<ActionMove> for PlayerActionMove {
@@ -85,7 +99,62 @@ impl From<ActionMove> for PlayerActionMove {
     ) -> Self {
         Self {
             position,
-            wait_after_move_ticks: (wait_after_move_millis / MS_PER_TICK) as u32,
+            wait_after_move_ticks: millis_to_ticks(wait_after_move_millis),
+            platforms_pathing: false,
+        }
+    }
+}
+
+/// Represents the key sequence action.
+///
+/// Converted from [`ActionSequence`] without fields used by [`Rotator`].
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerActionSequence {
+    pub position: Option<Position>,
+    pub steps: Array<PlayerActionSequenceStep, MAX_SEQUENCE_STEPS_COUNT>,
+}
+
+impl From<ActionSequence> for PlayerActionSequence {
+    fn from(
+        ActionSequence {
+            position, steps, ..
+        }: ActionSequence,
+    ) -> Self {
+        Self {
+            position,
+            steps: steps
+                .into_iter()
+                .map(PlayerActionSequenceStep::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PlayerActionSequenceStep {
+    pub key: KeyBinding,
+    pub kind: ActionSequenceStepKind,
+    pub direction: ActionKeyDirection,
+    pub delay_ticks: u32,
+    pub delay_ticks_random_range: u32,
+}
+
+impl From<ActionSequenceStep> for PlayerActionSequenceStep {
+    fn from(
+        ActionSequenceStep {
+            key,
+            kind,
+            direction,
+            delay_millis,
+            delay_millis_random_range,
+        }: ActionSequenceStep,
+    ) -> Self {
+        Self {
+            key,
+            kind,
+            direction,
+            delay_ticks: millis_to_ticks(delay_millis),
+            delay_ticks_random_range: millis_to_ticks(delay_millis_random_range),
         }
     }
 }
@@ -147,8 +216,12 @@ pub enum PlayerAction {
     Key(PlayerActionKey),
     /// Fixed move action provided by the user.
     Move(PlayerActionMove),
+    /// Key sequence action provided by the user.
+    Sequence(PlayerActionSequence),
     /// Solve rune action.
     SolveRune,
+    /// Read mesos action provided by [`Rotator`].
+    ReadMesos,
     /// Auto-mobbing action provided by [`Rotator`].
     #[strum(to_string = "AutoMob({0})")]
     AutoMob(PlayerActionAutoMob),
@@ -161,6 +234,11 @@ impl From<Action> for PlayerAction {
         match action {
             Action::Move(action) => PlayerAction::Move(action.into()),
             Action::Key(action) => PlayerAction::Key(action.into()),
+            Action::Sequence(action) => PlayerAction::Sequence(action.into()),
+            // Expanded by `Rotator` before conversion
+            Action::Macro(_) => unreachable!(),
+            // Evaluated by `Rotator` and never passed to the player
+            Action::Branch(_) => unreachable!(),
         }
     }
 }
@@ -171,11 +249,12 @@ pub fn on_ping_pong_double_jump_action(
     cur_pos: Point,
     bound: Rect,
     direction: PingPongDirection,
+    double_jump_threshold: i32,
 ) -> (Player, bool) {
     let hit_x_bound_edge = match direction {
-        PingPongDirection::Left => (cur_pos.x - bound.x).abs() <= DOUBLE_JUMP_THRESHOLD,
+        PingPongDirection::Left => (cur_pos.x - bound.x).abs() <= double_jump_threshold,
         PingPongDirection::Right => {
-            (cur_pos.x - bound.x - bound.width).abs() <= DOUBLE_JUMP_THRESHOLD
+            (cur_pos.x - bound.x - bound.width).abs() <= double_jump_threshold
         }
     };
     if hit_x_bound_edge {
@@ -284,12 +363,18 @@ pub fn on_action_state_mut(
                 | PlayerAction::Key(PlayerActionKey {
                     position: Some(Position { .. }),
                     ..
+                })
+                | PlayerAction::Sequence(PlayerActionSequence {
+                    position: Some(Position { .. }),
+                    ..
                 }) => {
                     state.clear_unstucking(false);
                 }
                 // Should not clear unstucking for auto-mobbing as it is pretty error prone...
                 PlayerAction::AutoMob(_)
-                | PlayerAction::Key(PlayerActionKey { position: None, .. }) => (),
+                | PlayerAction::ReadMesos
+                | PlayerAction::Key(PlayerActionKey { position: None, .. })
+                | PlayerAction::Sequence(PlayerActionSequence { position: None, .. }) => (),
             }
             // FIXME: clear only when has position?
             state.clear_action_completed();
@@ -10,7 +10,6 @@ use crate::{
         Player,
         actions::{on_action_state, on_auto_mob_use_key_action},
         double_jump::DoubleJumping,
-        moving::MOVE_TIMEOUT,
         state::LastMovement,
         timeout::{ChangeAxis, Timeout, update_moving_axis_context},
     },
@@ -60,10 +59,11 @@ pub fn update_adjusting_context(
         state.last_movement = Some(LastMovement::Adjusting);
     }
 
+    let move_timeout = state.config.movement.move_timeout;
     update_moving_axis_context(
         moving,
         cur_pos,
-        MOVE_TIMEOUT,
+        move_timeout,
         Player::Adjusting,
         Some(|| {
             let _ = context.keys.send_up(KeyKind::Right);
@@ -114,7 +114,7 @@ pub fn update_adjusting_context(
                     if !moving.completed {
                         Player::Adjusting(moving)
                     } else {
-                        Player::Adjusting(moving.timeout_current(MOVE_TIMEOUT))
+                        Player::Adjusting(moving.timeout_current(move_timeout))
                     }
                 },
             )
@@ -178,6 +178,8 @@ fn on_player_action(
         })
         | PlayerAction::PingPong(_)
         | PlayerAction::SolveRune
-        | PlayerAction::Move(_) => None,
+        | PlayerAction::ReadMesos
+        | PlayerAction::Move(_)
+        | PlayerAction::Sequence(_) => None,
     }
 }
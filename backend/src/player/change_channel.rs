@@ -0,0 +1,321 @@
+use log::debug;
+use platforms::windows::KeyKind;
+
+use super::{
+    Player, PlayerState,
+    timeout::{Timeout, update_with_timeout},
+};
+use crate::{
+    context::Context,
+    minimap::Minimap,
+    task::{Update, update_detection_task},
+};
+
+/// The maximum number of ticks to open the ESC menu
+const OPENING_MENU_TIMEOUT: u32 = 305;
+
+/// The maximum number of ticks to open the change channel dialog from the ESC menu
+const OPENING_DIALOG_TIMEOUT: u32 = 150;
+
+/// The number of milliseconds between each detection of the ESC menu or the change channel
+/// dialog
+const OPENING_DETECT_INTERVAL_MILLIS: u64 = 1000;
+
+/// The number of ticks between each key press when selecting a channel
+const SELECTING_INTERVAL: u32 = 5;
+
+/// The maximum number of channels to move past when selecting a random channel
+const SELECTING_RANDOM_MAX: u32 = 10;
+
+/// The maximum number of ticks to wait for the player to appear in the new channel
+const CHANGING_TIMEOUT: u32 = 605;
+
+#[derive(Clone, Copy, Debug)]
+pub enum ChangingChannel {
+    /// Presses the ESC key until the ESC menu is detected
+    ///
+    /// The boolean indicates whether to select a random channel instead of the next one.
+    OpeningMenu(bool),
+    /// Selects the channel item of the ESC menu and waits for the change channel dialog
+    ///
+    /// The boolean indicates whether to select a random channel instead of the next one.
+    OpeningDialog(bool),
+    /// Moves the channel selection by the remaining count then confirms
+    Selecting(u32),
+    /// Waits for the minimap to be re-detected and the player to appear in the new channel
+    ///
+    /// The boolean indicates whether the minimap has been re-detecting.
+    Changing(bool),
+}
+
+/// Updates the [`Player::ChangingChannel`] contextual state
+///
+/// This state is transitioned to when [`PlayerState::change_channel`] is set and the current
+/// state can be overridden. This state does:
+/// - Presses the ESC key until the ESC menu is detected
+/// - Presses the enter key on the channel item, which is the item selected when the ESC menu
+///   opens, and waits for the change channel dialog to be detected
+/// - Presses the right arrow key once for the next channel or a random number of times for a
+///   random channel and confirms with the enter key
+/// - Waits for the minimap to be re-detected and the player to appear again
+/// - On completed or timed out, tracks the channel change and transitions to `Player::Idle`
+///
+/// Whether the minimap is still the same map after changing is verified outside of this state.
+pub fn update_changing_channel_context(
+    context: &Context,
+    state: &mut PlayerState,
+    timeout: Timeout,
+    changing_channel: ChangingChannel,
+    failed_to_detect_player: bool,
+) -> Player {
+    if !timeout.started {
+        state.change_channel_task = None;
+    }
+    let next = match changing_channel {
+        ChangingChannel::OpeningMenu(random) => update_with_timeout(
+            timeout,
+            OPENING_MENU_TIMEOUT,
+            |timeout| {
+                let _ = context.keys.send(KeyKind::Esc);
+                Player::ChangingChannel(timeout, changing_channel)
+            },
+            || {
+                debug!(target: "player", "ESC menu not detected, aborting channel change");
+                Player::Idle
+            },
+            |timeout| match update_detection_task(
+                context,
+                OPENING_DETECT_INTERVAL_MILLIS,
+                &mut state.change_channel_task,
+                |detector| Ok(detector.detect_esc_menu()),
+            ) {
+                Update::Ok(true) => Player::ChangingChannel(
+                    Timeout::default(),
+                    ChangingChannel::OpeningDialog(random),
+                ),
+                Update::Ok(false) => {
+                    let _ = context.keys.send(KeyKind::Esc);
+                    Player::ChangingChannel(timeout, changing_channel)
+                }
+                Update::Err(_) | Update::Pending => {
+                    Player::ChangingChannel(timeout, changing_channel)
+                }
+            },
+        ),
+        ChangingChannel::OpeningDialog(random) => update_with_timeout(
+            timeout,
+            OPENING_DIALOG_TIMEOUT,
+            |timeout| {
+                let _ = context.keys.send(KeyKind::Enter);
+                Player::ChangingChannel(timeout, changing_channel)
+            },
+            || {
+                debug!(target: "player", "change channel dialog not detected, aborting");
+                let _ = context.keys.send(KeyKind::Esc);
+                Player::Idle
+            },
+            |timeout| match update_detection_task(
+                context,
+                OPENING_DETECT_INTERVAL_MILLIS,
+                &mut state.change_channel_task,
+                |detector| Ok(detector.detect_change_channel_dialog()),
+            ) {
+                Update::Ok(true) => {
+                    let count = if random {
+                        rand::random_range(1..=SELECTING_RANDOM_MAX)
+                    } else {
+                        1
+                    };
+                    Player::ChangingChannel(Timeout::default(), ChangingChannel::Selecting(count))
+                }
+                Update::Ok(false) | Update::Err(_) | Update::Pending => {
+                    Player::ChangingChannel(timeout, changing_channel)
+                }
+            },
+        ),
+        ChangingChannel::Selecting(count) => update_with_timeout(
+            timeout,
+            SELECTING_INTERVAL,
+            |timeout| Player::ChangingChannel(timeout, changing_channel),
+            || {
+                if count == 0 {
+                    let _ = context.keys.send(KeyKind::Enter);
+                    Player::ChangingChannel(Timeout::default(), ChangingChannel::Changing(false))
+                } else {
+                    let _ = context.keys.send(KeyKind::Right);
+                    Player::ChangingChannel(
+                        Timeout::default(),
+                        ChangingChannel::Selecting(count - 1),
+                    )
+                }
+            },
+            |timeout| Player::ChangingChannel(timeout, changing_channel),
+        ),
+        ChangingChannel::Changing(redetecting) => update_with_timeout(
+            timeout,
+            CHANGING_TIMEOUT,
+            |timeout| Player::ChangingChannel(timeout, changing_channel),
+            || Player::Idle,
+            |timeout| {
+                let redetecting = redetecting || matches!(context.minimap, Minimap::Detecting);
+                if !redetecting
+                    || failed_to_detect_player
+                    || !matches!(context.minimap, Minimap::Idle(_))
+                {
+                    Player::ChangingChannel(timeout, ChangingChannel::Changing(redetecting))
+                } else {
+                    Player::Idle
+                }
+            },
+        ),
+    };
+    if matches!(next, Player::Idle) {
+        state.track_channel_changed(context.clock.now());
+    }
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{assert_matches::assert_matches, time::Duration};
+
+    use platforms::windows::KeyKind;
+    use tokio::time::advance;
+
+    use super::{ChangingChannel, OPENING_DIALOG_TIMEOUT, update_changing_channel_context};
+    use crate::{
+        bridge::MockKeySender,
+        context::Context,
+        detect::MockDetector,
+        player::{Player, PlayerState, Timeout},
+    };
+
+    fn detector_with(has_menu: bool, has_dialog: bool) -> MockDetector {
+        let mut detector = MockDetector::new();
+        detector.expect_detect_esc_menu().return_const(has_menu);
+        detector
+            .expect_detect_change_channel_dialog()
+            .return_const(has_dialog);
+        detector
+            .expect_clone()
+            .returning(move || detector_with(has_menu, has_dialog));
+        detector
+    }
+
+    /// Updates `changing_channel` from a new timeout until the detection task completes
+    async fn advance_task(
+        context: &Context,
+        state: &mut PlayerState,
+        changing_channel: ChangingChannel,
+    ) -> Player {
+        let mut player = update_changing_channel_context(
+            context,
+            state,
+            Timeout::default(),
+            changing_channel,
+            false,
+        );
+        loop {
+            let Player::ChangingChannel(timeout, changing_channel) = player else {
+                unreachable!()
+            };
+            player =
+                update_changing_channel_context(context, state, timeout, changing_channel, false);
+            if state
+                .change_channel_task
+                .as_ref()
+                .is_some_and(|task| task.completed())
+            {
+                return player;
+            }
+            advance(Duration::from_millis(100)).await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opening_menu_to_opening_dialog_when_menu_detected() {
+        let mut keys = MockKeySender::new();
+        keys.expect_send()
+            .withf(|key| matches!(key, KeyKind::Esc))
+            .once()
+            .returning(|_| Ok(()));
+        let context = Context::new(Some(keys), Some(detector_with(true, false)));
+        let mut state = PlayerState::default();
+
+        let player = advance_task(&context, &mut state, ChangingChannel::OpeningMenu(false)).await;
+
+        assert_matches!(
+            player,
+            Player::ChangingChannel(
+                Timeout { started: false, .. },
+                ChangingChannel::OpeningDialog(false)
+            )
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opening_menu_presses_esc_again_when_menu_not_detected() {
+        let mut keys = MockKeySender::new();
+        keys.expect_send()
+            .withf(|key| matches!(key, KeyKind::Esc))
+            .times(2)
+            .returning(|_| Ok(()));
+        let context = Context::new(Some(keys), Some(detector_with(false, false)));
+        let mut state = PlayerState::default();
+
+        let player = advance_task(&context, &mut state, ChangingChannel::OpeningMenu(false)).await;
+
+        assert_matches!(
+            player,
+            Player::ChangingChannel(
+                Timeout { started: true, .. },
+                ChangingChannel::OpeningMenu(false)
+            )
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opening_dialog_to_selecting_when_dialog_detected() {
+        let mut keys = MockKeySender::new();
+        keys.expect_send()
+            .withf(|key| matches!(key, KeyKind::Enter))
+            .once()
+            .returning(|_| Ok(()));
+        let context = Context::new(Some(keys), Some(detector_with(true, true)));
+        let mut state = PlayerState::default();
+
+        let player =
+            advance_task(&context, &mut state, ChangingChannel::OpeningDialog(false)).await;
+
+        assert_matches!(
+            player,
+            Player::ChangingChannel(_, ChangingChannel::Selecting(1))
+        );
+    }
+
+    #[test]
+    fn opening_dialog_timeout_closes_menu() {
+        let mut keys = MockKeySender::new();
+        keys.expect_send()
+            .withf(|key| matches!(key, KeyKind::Esc))
+            .once()
+            .returning(|_| Ok(()));
+        let context = Context::new(Some(keys), None);
+        let mut state = PlayerState::default();
+
+        let player = update_changing_channel_context(
+            &context,
+            &mut state,
+            Timeout {
+                current: OPENING_DIALOG_TIMEOUT,
+                total: OPENING_DIALOG_TIMEOUT,
+                started: true,
+            },
+            ChangingChannel::OpeningDialog(false),
+            false,
+        );
+
+        assert_matches!(player, Player::Idle);
+        assert!(state.take_channel_changed());
+    }
+}
@@ -0,0 +1,59 @@
+use platforms::windows::KeyKind;
+
+use super::{
+    Player, PlayerState,
+    moving::Moving,
+    state::LastMovement,
+    timeout::{ChangeAxis, update_moving_axis_context},
+};
+use crate::context::Context;
+
+/// Updates the [`Player::Climbing`] contextual state.
+///
+/// This state can only be transitioned via [`Player::Moving`] when the player has reached the
+/// rope x-wise and the intermediate point has `MovementHint::ClimbRope` hint.
+///
+/// This state holds the up key to climb the rope and jumps if the rope cannot be grabbed from
+/// the current position. The up key is released once the player reached the destination y-wise
+/// or stopped moving vertically.
+///
+/// The state times out after three move timeouts without moving vertically and jumps to grab the
+/// rope after one move timeout.
+pub fn update_climbing_context(
+    context: &Context,
+    state: &mut PlayerState,
+    moving: Moving,
+) -> Player {
+    if !moving.timeout.started {
+        state.last_movement = Some(LastMovement::Climbing);
+    }
+
+    let cur_pos = state.last_known_pos.unwrap();
+    let (_, y_direction) = moving.y_distance_direction_from(true, cur_pos);
+    let jump_key = state.config.jump_key;
+    let grab_jump_tick = state.config.movement.move_timeout;
+
+    update_moving_axis_context(
+        moving,
+        cur_pos,
+        state.config.movement.move_timeout * 3,
+        |moving| {
+            let _ = context.keys.send_down(KeyKind::Up);
+            Player::Climbing(moving)
+        },
+        Some(|| {
+            let _ = context.keys.send_up(KeyKind::Up);
+        }),
+        |moving| {
+            if y_direction <= 0 {
+                let _ = context.keys.send_up(KeyKind::Up);
+                return Player::Moving(moving.dest, moving.exact, moving.intermediates);
+            }
+            if moving.timeout.current == grab_jump_tick {
+                let _ = context.keys.send(jump_key);
+            }
+            Player::Climbing(moving)
+        },
+        ChangeAxis::Vertical,
+    )
+}
@@ -15,35 +15,17 @@ use crate::{
     ActionKeyDirection, ActionKeyWith,
     context::Context,
     player::{
-        moving::MOVE_TIMEOUT,
         state::LastMovement,
         timeout::{ChangeAxis, Timeout, update_moving_axis_context},
     },
 };
 
-/// Minimum x distance from the destination required to perform a double jump.
-pub const DOUBLE_JUMP_THRESHOLD: i32 = 25;
-
 /// Minimum x distance from the destination required to perform a double jump in auto mobbing.
 pub const DOUBLE_JUMP_AUTO_MOB_THRESHOLD: i32 = 15;
 
-/// Minimum x distance from the destination required to transition to [`Player::UseKey`].
-const USE_KEY_X_THRESHOLD: i32 = DOUBLE_JUMP_THRESHOLD;
-
 /// Minimum y distance from the destination required to transition to [`Player::UseKey`].
 const USE_KEY_Y_THRESHOLD: i32 = 10;
 
-/// Maximum number of ticks before timing out.
-///
-/// Note: Even in auto mob, also use the non-auto mob threshold.
-const TIMEOUT: u32 = MOVE_TIMEOUT * 2;
-
-/// Number of ticks to wait after a double jump.
-///
-/// A heuristic to mostly avoid mid-air jump keys sending. The current approach of using velocity
-/// does not send much keys after double jumped, but only few are sent mid-air.
-const COOLDOWN_TIMEOUT: u32 = MOVE_TIMEOUT;
-
 /// Minimum x distance from the destination required to transition to [`Player::Grappling`].
 const GRAPPLING_THRESHOLD: i32 = 4;
 
@@ -90,9 +72,9 @@ impl DoubleJumping {
 /// Updates the [`Player::DoubleJumping`] contextual state.
 ///
 /// This state continues to double jump as long as the distance x-wise is still
-/// `>= DOUBLE_JUMP_THRESHOLD`. Or when [`DoubleJumping::forced`], this state will attempt
-/// a single double jump. When [`DoubleJumping::require_stationary`], this state will wait for
-/// the player to be stationary before double jumping.
+/// `>= double_jump_threshold` of the movement profile. Or when [`DoubleJumping::forced`], this
+/// state will attempt a single double jump. When [`DoubleJumping::require_stationary`], this
+/// state will wait for the player to be stationary before double jumping.
 ///
 /// [`DoubleJumping::forced`] is currently true when it is transitioned
 /// from [`Player::Idle`], [`Player::Moving`], [`Player::Adjusting`], and
@@ -102,6 +84,11 @@ impl DoubleJumping {
 /// [`DoubleJumping::require_stationary`] is currently true when it is transitioned
 /// from [`Player::Idle`] and [`Player::UseKey`] with [`PlayerState::last_known_direction`] matches
 /// the [`PlayerAction::Key`] direction.
+///
+/// The state times out after two move timeouts. After a double jump, it waits for one move
+/// timeout before sending jump keys again. This is a heuristic to mostly avoid mid-air jump keys
+/// sending. The current approach of using velocity does not send much keys after double jumped,
+/// but only few are sent mid-air.
 pub fn update_double_jumping_context(
     context: &Context,
     state: &mut PlayerState,
@@ -135,10 +122,12 @@ pub fn update_double_jumping_context(
         state.last_movement = Some(LastMovement::DoubleJumping);
     }
 
+    // Even in auto mob, also use the non-auto mob timeout
+    let move_timeout = state.config.movement.move_timeout;
     update_moving_axis_context(
         moving,
         cur_pos,
-        TIMEOUT,
+        move_timeout * 2,
         |moving| Player::DoubleJumping(double_jumping.moving(moving)),
         Some(|| {
             let _ = context.keys.send_up(KeyKind::Right);
@@ -185,7 +174,7 @@ pub fn update_double_jumping_context(
                     } else {
                         double_jumping.cooldown_timeout = update_with_timeout(
                             double_jumping.cooldown_timeout,
-                            COOLDOWN_TIMEOUT,
+                            move_timeout,
                             |timeout| timeout,
                             Timeout::default,
                             |timeout| timeout,
@@ -218,7 +207,7 @@ pub fn update_double_jumping_context(
                     {
                         debug!(target: "player", "performs grappling on double jump");
                         Player::Grappling(moving.completed(false).timeout(Timeout::default()))
-                    } else if moving.completed && moving.timeout.current >= MOVE_TIMEOUT {
+                    } else if moving.completed && moving.timeout.current >= move_timeout {
                         Player::Moving(moving.dest, moving.exact, moving.intermediates)
                     } else {
                         Player::DoubleJumping(double_jumping.moving(moving))
@@ -262,6 +251,7 @@ fn on_player_action(
             cur_pos,
             bound,
             direction,
+            state.config.movement.double_jump_threshold,
             double_jumped_or_flying,
             state.config.grappling_key.is_some(),
         ),
@@ -279,7 +269,7 @@ fn on_player_action(
             // player is already near the destination.
             if forced
                 || (!moving.exact
-                    && x_distance <= USE_KEY_X_THRESHOLD
+                    && x_distance <= state.config.movement.double_jump_threshold
                     && y_distance <= USE_KEY_Y_THRESHOLD)
             {
                 Some((Player::UseKey(UseKey::from_action(action)), false))
@@ -292,7 +282,9 @@ fn on_player_action(
             ..
         })
         | PlayerAction::SolveRune
-        | PlayerAction::Move { .. } => None,
+        | PlayerAction::ReadMesos
+        | PlayerAction::Move { .. }
+        | PlayerAction::Sequence(_) => None,
     }
 }
 
@@ -304,19 +296,21 @@ fn on_player_action(
 ///   - Transition to [`Player::Falling`] or [`Player::UpJumping`] with a chance to simulate vertical movement
 ///   - Transition to [`Player::UseKey`] otherwise
 #[inline]
+#[allow(clippy::too_many_arguments)]
 fn on_ping_pong_use_key_action(
     context: &Context,
     action: PlayerAction,
     cur_pos: Point,
     bound: Rect,
     direction: PingPongDirection,
+    double_jump_threshold: i32,
     double_jumped: bool,
     has_grappling: bool,
 ) -> Option<(Player, bool)> {
     let hit_x_bound_edge = match direction {
-        PingPongDirection::Left => (cur_pos.x - bound.x).abs() <= DOUBLE_JUMP_THRESHOLD,
+        PingPongDirection::Left => (cur_pos.x - bound.x).abs() <= double_jump_threshold,
         PingPongDirection::Right => {
-            (cur_pos.x - bound.x - bound.width).abs() <= DOUBLE_JUMP_THRESHOLD
+            (cur_pos.x - bound.x - bound.width).abs() <= double_jump_threshold
         }
     };
     if hit_x_bound_edge {
@@ -500,6 +494,7 @@ mod tests {
             cur_pos,
             bound,
             PingPongDirection::Left,
+            25,
             true,
             false,
         );
@@ -523,6 +518,7 @@ mod tests {
             cur_pos,
             bound,
             PingPongDirection::Right,
+            25,
             false, // hasn't double jumped
             true,
         );
@@ -548,6 +544,7 @@ mod tests {
             cur_pos,
             bound,
             PingPongDirection::Right,
+            25,
             true,
             false, // no grappling
         );
@@ -559,6 +556,7 @@ mod tests {
             cur_pos,
             bound,
             PingPongDirection::Right,
+            25,
             true,
             true,
         );
@@ -584,6 +582,7 @@ mod tests {
             cur_pos,
             bound,
             PingPongDirection::Right,
+            25,
             true,
             false,
         );
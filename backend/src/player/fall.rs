@@ -6,7 +6,7 @@ use crate::{
     ActionKeyWith,
     context::Context,
     player::{
-        MOVE_TIMEOUT, PlayerAction,
+        PlayerAction,
         actions::{on_action, on_auto_mob_use_key_action},
         state::LastMovement,
         timeout::{ChangeAxis, update_moving_axis_context},
@@ -23,13 +23,6 @@ const FALLING_TO_USE_KEY_THRESHOLD: i32 = 5;
 /// Tick to stop helding down [`KeyKind::Down`] at.
 const STOP_DOWN_KEY_TICK: u32 = 3;
 
-/// Maximum number of ticks before timing out.
-const TIMEOUT: u32 = MOVE_TIMEOUT + 3;
-
-/// Maximum y distance from the destination allowed to skip normal falling and use teleportation
-/// for mage.
-const TELEPORT_FALL_THRESHOLD: i32 = 15;
-
 /// Updates the [`Player::Falling`] contextual state.
 ///
 /// This state performs a drop down action. It is completed as soon as the player current `y`
@@ -63,15 +56,17 @@ pub fn update_falling_context(
     let y_changed = cur_pos.y - anchor.y;
     let jump_key = state.config.jump_key;
     let teleport_key = state.config.teleport_key;
+    let teleport_fall_threshold = state.config.movement.teleport_fall_threshold;
+    let timeout = state.config.movement.move_timeout + 3;
 
     update_moving_axis_context(
         moving,
         cur_pos,
-        TIMEOUT,
+        timeout,
         |moving| {
             let _ = context.keys.send_down(KeyKind::Down);
             if let Some(key) = teleport_key
-                && y_distance < TELEPORT_FALL_THRESHOLD
+                && y_distance < teleport_fall_threshold
             {
                 let _ = context.keys.send(key);
             } else {
@@ -89,7 +84,7 @@ pub fn update_falling_context(
             if !moving.completed && y_changed < 0 {
                 moving = moving.completed(true);
             } else if moving.completed && timeout_on_complete {
-                moving = moving.timeout_current(TIMEOUT);
+                moving = moving.timeout_current(timeout);
             }
 
             on_action(
@@ -139,7 +134,9 @@ fn on_player_action(
         })
         | PlayerAction::PingPong(_)
         | PlayerAction::Move(_)
-        | PlayerAction::SolveRune => None,
+        | PlayerAction::Sequence(_)
+        | PlayerAction::SolveRune
+        | PlayerAction::ReadMesos => None,
     }
 }
 
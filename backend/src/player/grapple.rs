@@ -6,24 +6,9 @@ use super::{
 };
 use crate::{
     context::Context,
-    player::{
-        MOVE_TIMEOUT,
-        timeout::{ChangeAxis, update_moving_axis_context},
-    },
+    player::timeout::{ChangeAxis, update_moving_axis_context},
 };
 
-/// Minimum y distance from the destination required to perform a grappling hook.
-pub const GRAPPLING_THRESHOLD: i32 = 24;
-
-/// Maximum y distance from the destination allowed to perform a grappling hook.
-pub const GRAPPLING_MAX_THRESHOLD: i32 = 41;
-
-/// Timeout for grappling.
-const TIMEOUT: u32 = MOVE_TIMEOUT * 10;
-
-/// Timeout after stopping grappling.
-const STOPPING_TIMEOUT: u32 = MOVE_TIMEOUT * 2;
-
 /// Maximum y distance allowed to stop grpapling.
 const STOPPING_THRESHOLD: i32 = 4;
 
@@ -46,28 +31,32 @@ pub fn update_grappling_context(
     let key = state.config.grappling_key.unwrap(); // Cannot transition if None
     let x_changed = cur_pos.x != moving.pos.x;
     let (y_distance, y_direction) = moving.y_distance_direction_from(true, moving.pos);
+    let move_timeout = state.config.movement.move_timeout;
+    let double_jump_threshold = state.config.movement.double_jump_threshold;
+    let timeout = move_timeout * 10;
+    let stopping_timeout = move_timeout * 2;
 
     update_moving_axis_context(
         moving,
         cur_pos,
-        TIMEOUT,
+        timeout,
         |moving| {
             let _ = context.keys.send(key);
             Player::Grappling(moving)
         },
         None::<fn()>,
         |mut moving| {
-            if moving.timeout.current >= MOVE_TIMEOUT && x_changed {
+            if moving.timeout.current >= move_timeout && x_changed {
                 // during double jump and grappling failed
-                moving = moving.timeout_current(TIMEOUT).completed(true);
+                moving = moving.timeout_current(timeout).completed(true);
             }
             if !moving.completed {
                 if y_direction <= 0 || y_distance <= STOPPING_THRESHOLD {
                     let _ = context.keys.send(key);
                     moving = moving.completed(true);
                 }
-            } else if moving.timeout.current >= STOPPING_TIMEOUT {
-                moving = moving.timeout_current(TIMEOUT);
+            } else if moving.timeout.current >= stopping_timeout {
+                moving = moving.timeout_current(timeout);
             }
 
             on_action(
@@ -88,17 +77,25 @@ pub fn update_grappling_context(
                         bound, direction, ..
                     }) => {
                         if cur_pos.y >= bound.y
-                            && moving.timeout.total % MOVE_TIMEOUT == 0 // Interval roll dice
+                            && moving.timeout.total % move_timeout == 0 // Interval roll dice
                             && rand::random_bool(0.7)
                         {
                             Some(on_ping_pong_double_jump_action(
-                                context, cur_pos, bound, direction,
+                                context,
+                                cur_pos,
+                                bound,
+                                direction,
+                                double_jump_threshold,
                             ))
                         } else {
                             None
                         }
                     }
-                    PlayerAction::Key(_) | PlayerAction::Move(_) | PlayerAction::SolveRune => None,
+                    PlayerAction::Key(_)
+                    | PlayerAction::Move(_)
+                    | PlayerAction::Sequence(_)
+                    | PlayerAction::SolveRune
+                    | PlayerAction::ReadMesos => None,
                 },
                 || Player::Grappling(moving),
             )
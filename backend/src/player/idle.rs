@@ -4,10 +4,15 @@ use platforms::windows::KeyKind;
 
 use super::{
     Player, PlayerAction, PlayerActionAutoMob, PlayerActionKey, PlayerActionMove, PlayerState,
-    actions::{PlayerActionPingPong, on_action_state_mut, on_ping_pong_double_jump_action},
+    actions::{
+        PlayerActionPingPong, PlayerActionSequence, on_action_state_mut,
+        on_ping_pong_double_jump_action,
+    },
     double_jump::DoubleJumping,
     moving::{Moving, find_intermediate_points},
+    timeout::Timeout,
     use_key::UseKey,
+    use_sequence::UseSequence,
 };
 use crate::{ActionKeyDirection, ActionKeyWith, Position, context::Context, minimap::Minimap};
 
@@ -40,14 +45,16 @@ fn on_player_action(
     match action {
         PlayerAction::AutoMob(PlayerActionAutoMob { position, .. }) => {
             let point = Point::new(position.x, position.y);
+            let up_jump_only = state.config.auto_mob_platforms_pathing_up_jump_only;
             let intermediates = if state.config.auto_mob_platforms_pathing {
                 match context.minimap {
-                    Minimap::Idle(idle) => find_intermediate_points(
-                        &idle.platforms,
-                        state.last_known_pos.unwrap(),
+                    Minimap::Idle(_) => find_intermediate_points(
+                        context,
+                        state,
+                        cur_pos,
                         point,
                         position.allow_adjusting,
-                        state.config.auto_mob_platforms_pathing_up_jump_only,
+                        up_jump_only,
                         false,
                     ),
                     _ => unreachable!(),
@@ -57,33 +64,62 @@ fn on_player_action(
             };
             let next = intermediates
                 .map(|mut intermediates| {
-                    let (point, exact) = intermediates.next().unwrap();
+                    let (point, exact) = intermediates.next(state).unwrap();
                     Player::Moving(point, exact, Some(intermediates))
                 })
                 .unwrap_or(Player::Moving(point, position.allow_adjusting, None));
 
             state.last_destinations = intermediates
-                .map(|intermediates| {
-                    intermediates
-                        .inner()
-                        .into_iter()
-                        .map(|(point, _, _)| point)
+                .map(|_| {
+                    state
+                        .intermediate_points
+                        .iter()
+                        .map(|(point, _, _)| *point)
                         .collect::<Vec<_>>()
                 })
                 .or(Some(vec![point]));
             Some((next, false))
         }
-        PlayerAction::Move(PlayerActionMove { position, .. }) => {
+        PlayerAction::Move(PlayerActionMove {
+            position,
+            platforms_pathing,
+            ..
+        }) => {
             let x = get_x_destination(position);
+            let point = Point::new(x, position.y);
             debug!(target: "player", "handling move: {} {}", x, position.y);
-            Some((
-                Player::Moving(Point::new(x, position.y), position.allow_adjusting, None),
-                false,
-            ))
+            if platforms_pathing && state.config.auto_mob_platforms_pathing {
+                let up_jump_only = state.config.auto_mob_platforms_pathing_up_jump_only;
+                let intermediates = find_intermediate_points(
+                    context,
+                    state,
+                    cur_pos,
+                    point,
+                    position.allow_adjusting,
+                    up_jump_only,
+                    false,
+                );
+                if let Some(mut intermediates) = intermediates {
+                    state.last_destinations = Some(
+                        state
+                            .intermediate_points
+                            .iter()
+                            .map(|(point, _, _)| *point)
+                            .collect(),
+                    );
+                    let (point, exact) = intermediates.next(state).unwrap();
+                    return Some((Player::Moving(point, exact, Some(intermediates)), false));
+                }
+            }
+            Some((Player::Moving(point, position.allow_adjusting, None), false))
         }
         PlayerAction::Key(PlayerActionKey {
             position: Some(position),
             ..
+        })
+        | PlayerAction::Sequence(PlayerActionSequence {
+            position: Some(position),
+            ..
         }) => {
             let x = get_x_destination(position);
             debug!(target: "player", "handling move: {} {}", x, position.y);
@@ -118,6 +154,9 @@ fn on_player_action(
             with: ActionKeyWith::Any | ActionKeyWith::Stationary,
             ..
         }) => Some((Player::UseKey(UseKey::from_action(action)), false)),
+        PlayerAction::Sequence(PlayerActionSequence { position: None, .. }) => {
+            Some((Player::UseSequence(UseSequence::from_action(action)), false))
+        }
         PlayerAction::SolveRune => {
             if let Minimap::Idle(idle) = context.minimap
                 && let Some(rune) = idle.rune
@@ -126,23 +165,25 @@ fn on_player_action(
                     if !state.is_stationary {
                         return Some((Player::Idle, false));
                     }
+                    let up_jump_only = state.config.rune_platforms_pathing_up_jump_only;
                     let intermediates = find_intermediate_points(
-                        &idle.platforms,
+                        context,
+                        state,
                         cur_pos,
                         rune,
                         true,
-                        state.config.rune_platforms_pathing_up_jump_only,
+                        up_jump_only,
                         true,
                     );
                     if let Some(mut intermediates) = intermediates {
                         state.last_destinations = Some(
-                            intermediates
-                                .inner()
-                                .into_iter()
-                                .map(|(point, _, _)| point)
+                            state
+                                .intermediate_points
+                                .iter()
+                                .map(|(point, _, _)| *point)
                                 .collect(),
                         );
-                        let (point, exact) = intermediates.next().unwrap();
+                        let (point, exact) = intermediates.next(state).unwrap();
                         return Some((Player::Moving(point, exact, Some(intermediates)), false));
                     }
                 }
@@ -151,10 +192,20 @@ fn on_player_action(
             }
             Some((Player::Idle, true))
         }
+        PlayerAction::ReadMesos => {
+            if !state.is_stationary {
+                return Some((Player::Idle, false));
+            }
+            Some((Player::ReadingMesos(Timeout::default()), false))
+        }
         PlayerAction::PingPong(PlayerActionPingPong {
             bound, direction, ..
         }) => Some(on_ping_pong_double_jump_action(
-            context, cur_pos, bound, direction,
+            context,
+            cur_pos,
+            bound,
+            direction,
+            state.config.movement.double_jump_threshold,
         )),
     }
 }
@@ -164,3 +215,84 @@ fn get_x_destination(position: Position) -> i32 {
     let x_max = position.x.saturating_add(position.x_random_range + 1);
     rand::random_range(x_min..x_max)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use opencv::core::Point;
+
+    use super::on_player_action;
+    use crate::{
+        MovementProfile, Position,
+        context::Context,
+        pathing::{Platform, find_neighbors},
+        player::{Player, PlayerAction, PlayerActionMove, PlayerState},
+    };
+
+    fn move_action(platforms_pathing: bool) -> PlayerAction {
+        PlayerAction::Move(PlayerActionMove {
+            position: Position {
+                x: 20,
+                x_random_range: 0,
+                y: 60,
+                allow_adjusting: false,
+            },
+            wait_after_move_ticks: 0,
+            platforms_pathing,
+        })
+    }
+
+    /// Makes a context where the top platform is only reachable through the middle platform
+    fn make_context_with_platforms() -> Context {
+        let platforms = [
+            Platform::new(0..50, 10),
+            Platform::new(0..50, 35),
+            Platform::new(0..50, 60),
+        ];
+        Context {
+            platforms: find_neighbors(&platforms, &[], &[], MovementProfile::default()),
+            ..Context::new(None, None)
+        }
+    }
+
+    #[test]
+    fn move_platforms_pathing_through_intermediate_platform() {
+        let context = make_context_with_platforms();
+        let mut state = PlayerState::default();
+        state.config.auto_mob_platforms_pathing = true;
+        state.last_known_pos = Some(Point::new(10, 10));
+
+        let (player, is_terminal) =
+            on_player_action(&context, &mut state, move_action(true)).unwrap();
+
+        assert!(!is_terminal);
+        assert_matches!(player, Player::Moving(_, _, Some(_)));
+        assert!(
+            state
+                .intermediate_points
+                .iter()
+                .any(|(point, _, _)| point.y == 35)
+        );
+        assert_eq!(
+            state
+                .last_destinations
+                .as_ref()
+                .and_then(|points| points.last().copied()),
+            Some(Point::new(20, 60))
+        );
+    }
+
+    #[test]
+    fn move_without_platforms_pathing_moves_directly() {
+        let context = make_context_with_platforms();
+        let mut state = PlayerState::default();
+        state.config.auto_mob_platforms_pathing = true;
+        state.last_known_pos = Some(Point::new(10, 10));
+
+        let (player, _) = on_player_action(&context, &mut state, move_action(false)).unwrap();
+
+        assert_matches!(player, Player::Moving(point, false, None) if point == Point::new(20, 60));
+        assert!(state.intermediate_points.is_empty());
+    }
+}
@@ -1,13 +1,11 @@
 use super::{
     Player, PlayerState,
-    moving::{MOVE_TIMEOUT, Moving},
+    moving::Moving,
     state::LastMovement,
     timeout::{ChangeAxis, update_moving_axis_context},
 };
 use crate::context::Context;
 
-const TIMEOUT: u32 = MOVE_TIMEOUT + 3;
-
 pub fn update_jumping_context(
     context: &Context,
     state: &mut PlayerState,
@@ -20,7 +18,7 @@ pub fn update_jumping_context(
     update_moving_axis_context(
         moving,
         state.last_known_pos.unwrap(),
-        TIMEOUT,
+        state.config.movement.move_timeout + 3,
         |moving| {
             let _ = context.keys.send(state.config.jump_key);
             Player::Jumping(moving)
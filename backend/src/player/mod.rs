@@ -1,14 +1,18 @@
 use actions::{on_action, on_action_state_mut};
 use adjust::{ADJUSTING_MEDIUM_THRESHOLD, update_adjusting_context};
 use cash_shop::{CashShop, update_cash_shop_context};
+use change_channel::{ChangingChannel, update_changing_channel_context};
+use climb::update_climbing_context;
 use double_jump::{DoubleJumping, update_double_jumping_context};
 use fall::update_falling_context;
 use grapple::update_grappling_context;
 use idle::update_idle_context;
 use jump::update_jumping_context;
-use moving::{MOVE_TIMEOUT, Moving, MovingIntermediates, update_moving_context};
+use moving::{Moving, MovingIntermediates, update_moving_context};
 use opencv::core::Point;
 use platforms::windows::KeyKind;
+use read_mesos::{close_inventory_if_interrupted, update_reading_mesos_context};
+use revive::{Reviving, update_reviving_context};
 use solve_rune::{SolvingRune, update_solving_rune_context};
 use stall::update_stalling_context;
 use state::LastMovement;
@@ -17,6 +21,8 @@ use timeout::{Timeout, update_with_timeout};
 use unstuck::update_unstucking_context;
 use up_jump::update_up_jumping_context;
 use use_key::{UseKey, update_use_key_context};
+use use_portal::update_using_portal_context;
+use use_sequence::{UseSequence, update_use_sequence_context};
 
 use crate::{
     context::{Context, Contextual, ControlFlow},
@@ -27,12 +33,16 @@ use crate::{
 mod actions;
 mod adjust;
 mod cash_shop;
+mod change_channel;
+mod climb;
 mod double_jump;
 mod fall;
 mod grapple;
 mod idle;
 mod jump;
 mod moving;
+mod read_mesos;
+mod revive;
 mod solve_rune;
 mod stall;
 mod state;
@@ -40,17 +50,15 @@ mod timeout;
 mod unstuck;
 mod up_jump;
 mod use_key;
+mod use_portal;
+mod use_sequence;
 
 pub use {
     actions::PingPongDirection, actions::PlayerAction, actions::PlayerActionAutoMob,
     actions::PlayerActionKey, actions::PlayerActionMove, actions::PlayerActionPingPong,
-    double_jump::DOUBLE_JUMP_THRESHOLD, grapple::GRAPPLING_MAX_THRESHOLD,
-    grapple::GRAPPLING_THRESHOLD, state::PlayerState,
+    actions::PlayerActionSequence, state::PlayerActionOutcome, state::PlayerState,
 };
 
-/// Minimum y distance from the destination required to perform a jump
-pub const JUMP_THRESHOLD: i32 = 7;
-
 /// The player contextual states
 #[derive(Clone, Copy, Debug, Display)]
 pub enum Player {
@@ -61,6 +69,8 @@ pub enum Player {
     /// Acts as entry to other state when there is a [`PlayerAction`]
     Idle,
     UseKey(UseKey),
+    /// Uses all the keys of a [`PlayerAction::Sequence`] in order
+    UseSequence(UseSequence),
     /// Movement-related coordinator state
     Moving(Point, bool, Option<MovingIntermediates>),
     /// Performs walk or small adjustment x-wise action
@@ -75,6 +85,10 @@ pub enum Player {
     UpJumping(Moving),
     /// Performs a falling action
     Falling(Moving, Point, bool),
+    /// Climbs a rope or ladder by holding the up key
+    Climbing(Moving),
+    /// Enters a portal by pressing the up or interact key and waits for the teleport
+    UsingPortal(Moving),
     /// Unstucks when inside non-detecting position or because of [`PlayerState::unstuck_counter`]
     Unstucking(Timeout, Option<bool>, bool),
     /// Stalls for time and return to [`Player::Idle`] or [`PlayerState::stalling_timeout_state`]
@@ -83,6 +97,12 @@ pub enum Player {
     SolvingRune(SolvingRune),
     /// Enters the cash shop then exit after 10 seconds
     CashShopThenExit(Timeout, CashShop),
+    /// Opens the inventory to read the mesos amount then closes it
+    ReadingMesos(Timeout),
+    /// Accepts the revive dialog and waits for the player to respawn
+    Reviving(Timeout, Reviving),
+    /// Changes to the next or a random channel and waits for the player to appear
+    ChangingChannel(Timeout, ChangingChannel),
 }
 
 impl Player {
@@ -93,6 +113,7 @@ impl Player {
             | Player::Idle
             | Player::Moving(_, _, _)
             | Player::DoubleJumping(DoubleJumping { forced: false, .. }) => true,
+            Player::UseKey(use_key) if use_key.is_holding() => true,
             Player::Adjusting(moving) => {
                 let (distance, _) = moving.x_distance_direction_from(true, moving.pos);
                 distance >= ADJUSTING_MEDIUM_THRESHOLD
@@ -103,12 +124,25 @@ impl Player {
             | Player::Falling(moving, _, _) => moving.completed,
             Player::SolvingRune(_)
             | Player::CashShopThenExit(_, _)
+            | Player::ReadingMesos(_)
+            | Player::Reviving(_, _)
+            | Player::ChangingChannel(_, _)
+            | Player::Climbing(_)
+            | Player::UsingPortal(_)
             | Player::Unstucking(_, _, _)
             | Player::DoubleJumping(DoubleJumping { forced: true, .. })
             | Player::UseKey(_)
+            | Player::UseSequence(_)
             | Player::Stalling(_, _) => false,
         }
     }
+
+    /// Whether the keys in [`PlayerState::held_keys`] should stay held down
+    #[inline]
+    fn is_holding_keys(&self) -> bool {
+        matches!(self, Player::UseSequence(_))
+            || matches!(self, Player::UseKey(use_key) if use_key.is_holding())
+    }
 }
 
 impl Contextual for Player {
@@ -116,6 +150,14 @@ impl Contextual for Player {
 
     // TODO: Detect if a point is reachable after number of retries?
     fn update(self, context: &Context, state: &mut PlayerState) -> ControlFlow<Self> {
+        if !state.held_keys.is_empty()
+            && (state.reset_to_idle_next_update || !self.is_holding_keys())
+        {
+            for key in state.held_keys.drain(..) {
+                let _ = context.keys.send_up(key);
+            }
+        }
+        close_inventory_if_interrupted(context, state, self);
         if state.rune_cash_shop {
             let _ = context.keys.send_up(KeyKind::Up);
             let _ = context.keys.send_up(KeyKind::Down);
@@ -128,6 +170,35 @@ impl Contextual for Player {
                 CashShop::Entering,
             ));
         }
+        if state.is_dead
+            && state.config.revive_on_death
+            && !context.halting
+            && !matches!(self, Player::Reviving(_, _))
+        {
+            let _ = context.keys.send_up(KeyKind::Up);
+            let _ = context.keys.send_up(KeyKind::Down);
+            let _ = context.keys.send_up(KeyKind::Left);
+            let _ = context.keys.send_up(KeyKind::Right);
+            state.clear_actions_aborted();
+            state.reset_to_idle_next_update = false;
+            return ControlFlow::Next(Player::Reviving(Timeout::default(), Reviving::Accepting));
+        }
+        if let Some(random) = state.change_channel
+            && !context.halting
+            && self.can_action_override_current_state()
+        {
+            let _ = context.keys.send_up(KeyKind::Up);
+            let _ = context.keys.send_up(KeyKind::Down);
+            let _ = context.keys.send_up(KeyKind::Left);
+            let _ = context.keys.send_up(KeyKind::Right);
+            state.change_channel = None;
+            state.clear_actions_aborted();
+            state.reset_to_idle_next_update = false;
+            return ControlFlow::Next(Player::ChangingChannel(
+                Timeout::default(),
+                ChangingChannel::OpeningMenu(random),
+            ));
+        }
 
         let has_position = if state.ignore_pos_update {
             state.last_known_pos.is_some()
@@ -144,7 +215,10 @@ impl Contextual for Player {
             //
             // `update_non_positional_context` is here to continue updating
             // `Player::Unstucking` returned from below when the player
-            // is inside the edges of the minimap. And also `Player::CashShopThenExit`.
+            // is inside the edges of the minimap. And also `Player::CashShopThenExit` and
+            // `Player::ReadingMesos` since the inventory can overlap the minimap and
+            // `Player::Reviving` and `Player::ChangingChannel` since the player can appear on a
+            // different map.
             if let Some(next) = update_non_positional_context(self, context, state, true) {
                 return ControlFlow::Next(next);
             }
@@ -198,6 +272,8 @@ fn update_non_positional_context(
         Player::UseKey(use_key) => {
             (!failed_to_detect_player).then(|| update_use_key_context(context, state, use_key))
         }
+        Player::UseSequence(use_sequence) => (!failed_to_detect_player)
+            .then(|| update_use_sequence_context(context, state, use_sequence)),
         Player::Unstucking(timeout, has_settings, gamba_mode) => Some(update_unstucking_context(
             context,
             state,
@@ -217,6 +293,25 @@ fn update_non_positional_context(
             cash_shop,
             failed_to_detect_player,
         )),
+        Player::ReadingMesos(timeout) => {
+            Some(update_reading_mesos_context(context, state, timeout))
+        }
+        Player::Reviving(timeout, reviving) => Some(update_reviving_context(
+            context,
+            state,
+            timeout,
+            reviving,
+            failed_to_detect_player,
+        )),
+        Player::ChangingChannel(timeout, changing_channel) => {
+            Some(update_changing_channel_context(
+                context,
+                state,
+                timeout,
+                changing_channel,
+                failed_to_detect_player,
+            ))
+        }
         Player::Detecting
         | Player::Idle
         | Player::Moving(_, _, _)
@@ -225,7 +320,9 @@ fn update_non_positional_context(
         | Player::Grappling(_)
         | Player::Jumping(_)
         | Player::UpJumping(_)
-        | Player::Falling(_, _, _) => None,
+        | Player::Falling(_, _, _)
+        | Player::Climbing(_)
+        | Player::UsingPortal(_) => None,
     }
 }
 
@@ -252,10 +349,16 @@ fn update_positional_context(
         Player::Falling(moving, anchor, timeout_on_complete) => {
             update_falling_context(context, state, moving, anchor, timeout_on_complete)
         }
+        Player::Climbing(moving) => update_climbing_context(context, state, moving),
+        Player::UsingPortal(moving) => update_using_portal_context(context, state, moving),
         Player::UseKey(_)
+        | Player::UseSequence(_)
         | Player::Unstucking(_, _, _)
         | Player::Stalling(_, _)
         | Player::SolvingRune(_)
-        | Player::CashShopThenExit(_, _) => unreachable!(),
+        | Player::CashShopThenExit(_, _)
+        | Player::ReadingMesos(_)
+        | Player::Reviving(_, _)
+        | Player::ChangingChannel(_, _) => unreachable!(),
     }
 }
@@ -3,59 +3,101 @@ use opencv::core::Point;
 use platforms::windows::KeyKind;
 
 use super::{
-    GRAPPLING_MAX_THRESHOLD, JUMP_THRESHOLD, Player, PlayerState,
+    Player, PlayerState,
     actions::{PlayerAction, PlayerActionKey, PlayerActionMove},
-    double_jump::{DOUBLE_JUMP_THRESHOLD, DoubleJumping},
+    double_jump::DoubleJumping,
     state::LastMovement,
     timeout::Timeout,
 };
 use crate::{
-    ActionKeyDirection, ActionKeyWith, MAX_PLATFORMS_COUNT,
-    array::Array,
+    ActionKeyDirection, ActionKeyWith,
     context::Context,
-    pathing::{MovementHint, PlatformWithNeighbors, find_points_with},
+    pathing::{MovementHint, find_points_excluding_portals},
     player::{
         adjust::{ADJUSTING_MEDIUM_THRESHOLD, ADJUSTING_SHORT_THRESHOLD},
-        grapple::GRAPPLING_THRESHOLD,
         on_action,
         solve_rune::SolvingRune,
         use_key::UseKey,
+        use_sequence::UseSequence,
     },
 };
 
-/// Maximum amount of ticks a change in x or y direction must be detected
-pub const MOVE_TIMEOUT: u32 = 5;
-
-const UP_JUMP_THRESHOLD: i32 = 10;
-
 /// Intermediate points to move by.
 ///
-/// The last point is the destination.
+/// The points are stored in [`PlayerState::intermediate_points`] and this struct only refers to
+/// them by index so that it stays [`Copy`] regardless of the number of points. The last point is
+/// the destination.
+///
+/// The points can be replaced by a newer path while this struct is still held, for example by
+/// [`PlayerState::stalling_timeout_state`]. The indices are then stale and only the last point,
+/// which is stored in this struct, is moved to.
 #[derive(Clone, Copy, Debug)]
 pub struct MovingIntermediates {
+    /// The [`PlayerState::intermediate_points_generation`] of the points this struct refers to
+    generation: u32,
     current: usize,
-    inner: Array<(Point, MovementHint, bool), 16>,
+    len: usize,
+    /// The movement hint of the point at `current - 1`
+    hint: MovementHint,
+    /// The last point and whether it is exact
+    last: (Point, bool),
+    /// Whether the points were found using only up jump(s)
+    up_jump_only: bool,
+    /// Whether the points were found with movement hints
+    enable_hint: bool,
 }
 
 impl MovingIntermediates {
     #[inline]
-    pub fn inner(&self) -> Array<(Point, MovementHint, bool), 16> {
-        self.inner
+    pub fn has_next(&self) -> bool {
+        self.current < self.len
+    }
+
+    /// The last point and whether it is exact.
+    #[inline]
+    pub fn last(&self) -> (Point, bool) {
+        self.last
     }
 
+    /// Whether [`PlayerState::intermediate_points`] are still the points this struct refers to.
     #[inline]
-    pub fn has_next(&self) -> bool {
-        self.current < self.inner.len()
+    fn is_current(&self, state: &PlayerState) -> bool {
+        self.generation == state.intermediate_points_generation
+    }
+
+    /// The point reached before the current destination in [`PlayerState::intermediate_points`].
+    ///
+    /// Returns [`None`] if the points have been replaced.
+    #[inline]
+    pub fn previous(&self, state: &PlayerState) -> Option<Point> {
+        if !self.is_current(state) {
+            return None;
+        }
+        state
+            .intermediate_points
+            .get(self.current.checked_sub(2)?)
+            .map(|(point, _, _)| *point)
     }
 
+    /// Advances to the next point in [`PlayerState::intermediate_points`] and returns the point
+    /// and whether it is exact.
+    ///
+    /// If the points have been replaced, advances to the last point directly.
     #[inline]
-    pub fn next(&mut self) -> Option<(Point, bool)> {
-        if self.current >= self.inner.len() {
+    pub fn next(&mut self, state: &PlayerState) -> Option<(Point, bool)> {
+        if self.current >= self.len {
             return None;
         }
-        let next = self.inner[self.current];
+        if !self.is_current(state) {
+            debug!(target: "player", "intermediate points replaced, moving to last point");
+            self.current = self.len;
+            self.hint = MovementHint::Infer;
+            return Some(self.last);
+        }
+        let (point, hint, exact) = state.intermediate_points[self.current];
         self.current += 1;
-        Some((next.0, next.2))
+        self.hint = hint;
+        Some((point, exact))
     }
 }
 
@@ -135,8 +177,7 @@ impl Moving {
 
     #[inline]
     fn intermediate_hint(&self) -> Option<MovementHint> {
-        self.intermediates
-            .map(|intermediates| intermediates.inner[intermediates.current.saturating_sub(1)].1)
+        self.intermediates.map(|intermediates| intermediates.hint)
     }
 
     /// Computes the x distance and direction between [`Self::dest`] and `cur_pos`.
@@ -193,8 +234,7 @@ impl Moving {
     #[inline]
     fn last_destination(&self) -> Point {
         if self.is_destination_intermediate() {
-            let points = self.intermediates.unwrap().inner;
-            points[points.len() - 1].0
+            self.intermediates.unwrap().last.0
         } else {
             self.dest
         }
@@ -221,8 +261,8 @@ impl Moving {
                     && y_direction >= 0)
                     || (matches!(state.last_movement, Some(LastMovement::UpJumping))
                         && y_direction <= 0)
-                    || y_distance.abs() < JUMP_THRESHOLD;
-                x_distance < DOUBLE_JUMP_THRESHOLD && y_skippable
+                    || y_distance.abs() < state.config.movement.jump_threshold;
+                x_distance < state.config.movement.double_jump_threshold && y_skippable
             })
     }
 }
@@ -236,6 +276,8 @@ impl Moving {
 /// It will first transition to [`Player::DoubleJumping`] and [`Player::Adjusting`] for
 /// matching `x` of `dest`. Then, [`Player::Grappling`], [`Player::UpJumping`], [`Player::Jumping`]
 /// or [`Player::Falling`] for matching `y` of `dest`. (e.g. horizontal then vertical)
+/// Or [`Player::Climbing`] and [`Player::UsingPortal`] when the reached intermediate destination is
+/// at a rope or portal.
 ///
 /// In auto mob or intermediate destination, most of the movement thresholds are relaxed for
 /// more fluid movement.
@@ -261,6 +303,7 @@ pub fn update_moving_context(
     let (y_distance, y_direction) = moving.y_distance_direction_from(true, cur_pos);
     let skip_destination = moving.auto_mob_can_skip_current_destination(state);
     let is_intermediate = moving.is_destination_intermediate();
+    let movement = state.config.movement;
 
     match (skip_destination, x_distance, y_direction, y_distance) {
         (false, d, _, _) if d >= state.double_jump_threshold(is_intermediate) => {
@@ -283,26 +326,26 @@ pub fn update_moving_context(
         // y > 0: cur_pos is below dest
         // y < 0: cur_pos is above of dest
         (false, _, y, d)
-            if y > 0 && d >= GRAPPLING_THRESHOLD && !state.should_disable_grappling() =>
+            if y > 0 && d >= movement.grappling_threshold && !state.should_disable_grappling() =>
         {
             abort_action_on_state_repeat(Player::Grappling(moving), context, state)
         }
-        (false, _, y, d) if y > 0 && d >= UP_JUMP_THRESHOLD => {
+        (false, _, y, d) if y > 0 && d >= movement.up_jump_threshold => {
             // In auto mob with platforms pathing and up jump only, immediately aborts the action
             // if there are no intermediate points and the distance is too big to up jump.
             if state.has_auto_mob_action_only()
                 && state.config.auto_mob_platforms_pathing
                 && state.config.auto_mob_platforms_pathing_up_jump_only
                 && intermediates.is_none()
-                && d >= GRAPPLING_THRESHOLD
+                && d >= movement.grappling_threshold
             {
                 debug!(target: "player", "auto mob aborted because distance for up jump only is too big");
-                state.clear_action_completed();
+                state.clear_action_aborted();
                 return Player::Idle;
             }
             abort_action_on_state_repeat(Player::UpJumping(moving), context, state)
         }
-        (false, _, y, d) if y > 0 && d >= JUMP_THRESHOLD => {
+        (false, _, y, d) if y > 0 && d >= movement.jump_threshold => {
             abort_action_on_state_repeat(Player::Jumping(moving), context, state)
         }
         // this probably won't work if the platforms are far apart,
@@ -317,10 +360,17 @@ pub fn update_moving_context(
                 dest, cur_pos
             );
             if let Some(mut intermediates) = intermediates
-                && let Some((dest, exact)) = intermediates.next()
+                && let Some((dest, exact)) = intermediates.next(state)
             {
                 state.clear_unstucking(false);
                 state.clear_last_movement();
+                let next = Moving::new(cur_pos, dest, exact, Some(intermediates));
+                if matches!(moving.intermediate_hint(), Some(MovementHint::ClimbRope)) {
+                    return Player::Climbing(next);
+                }
+                if matches!(moving.intermediate_hint(), Some(MovementHint::UsePortal)) {
+                    return Player::UsingPortal(next);
+                }
                 if matches!(moving.intermediate_hint(), Some(MovementHint::WalkAndJump)) {
                     // TODO: Any better way ???
                     state.stalling_timeout_state = Some(Player::Jumping(Moving::new(
@@ -363,7 +413,7 @@ fn abort_action_on_state_repeat(
     if state.track_last_movement_repeated() {
         info!(target: "player", "abort action due to repeated state");
         state.auto_mob_track_ignore_xs(context, true);
-        state.clear_action_completed();
+        state.clear_action_aborted();
         return Player::Idle;
     }
     next
@@ -406,46 +456,312 @@ fn on_player_action(
             with: ActionKeyWith::Any | ActionKeyWith::Stationary,
             ..
         }) => Some((Player::UseKey(UseKey::from_action(action)), false)),
+        PlayerAction::Sequence(_) => {
+            Some((Player::UseSequence(UseSequence::from_action(action)), false))
+        }
         PlayerAction::AutoMob(_) => Some((
             Player::UseKey(UseKey::from_action_pos(action, Some(moving.pos))),
             false,
         )),
         PlayerAction::SolveRune => Some((Player::SolvingRune(SolvingRune::default()), false)),
+        PlayerAction::ReadMesos => Some((Player::Idle, false)),
         PlayerAction::PingPong(_) => Some((Player::Idle, true)),
     }
 }
 
+/// Finds the intermediate points from `cur_pos` to `dest` using [`Context::platforms`].
+///
+/// The found points replace [`PlayerState::intermediate_points`] and do not go through
+/// [`PlayerState::failed_portals`].
 #[inline]
 pub fn find_intermediate_points(
-    platforms: &Array<PlatformWithNeighbors, MAX_PLATFORMS_COUNT>,
+    context: &Context,
+    state: &mut PlayerState,
     cur_pos: Point,
     dest: Point,
     exact: bool,
     up_jump_only: bool,
     enable_hint: bool,
 ) -> Option<MovingIntermediates> {
+    let movement = state.config.movement;
     let vertical_threshold = if up_jump_only {
-        GRAPPLING_THRESHOLD
+        movement.grappling_threshold
     } else {
-        GRAPPLING_MAX_THRESHOLD
+        movement.grappling_max_threshold
     };
-    let vec = find_points_with(
-        platforms,
+    let vec = find_points_excluding_portals(
+        &context.platforms,
         cur_pos,
         dest,
         enable_hint,
-        DOUBLE_JUMP_THRESHOLD,
-        JUMP_THRESHOLD,
+        movement.double_jump_threshold,
+        movement.jump_threshold,
         vertical_threshold,
+        &state.failed_portals,
     )?;
     let len = vec.len();
-    let array = Array::from_iter(
-        vec.into_iter()
-            .enumerate()
-            .map(|(i, (point, hint))| (point, hint, if i == len - 1 { exact } else { false })),
-    );
+    // Rope and portal must be reached precisely to be climbed or entered
+    state.intermediate_points_generation = state.intermediate_points_generation.wrapping_add(1);
+    state.intermediate_points.clear();
+    state
+        .intermediate_points
+        .extend(vec.into_iter().enumerate().map(|(i, (point, hint))| {
+            let exact = if i == len - 1 {
+                exact
+            } else {
+                matches!(hint, MovementHint::ClimbRope | MovementHint::UsePortal)
+            };
+            (point, hint, exact)
+        }));
+    let (last, _, last_exact) = state.intermediate_points[len - 1];
     Some(MovingIntermediates {
+        generation: state.intermediate_points_generation,
         current: 0,
-        inner: array,
+        len,
+        hint: MovementHint::Infer,
+        last: (last, last_exact),
+        up_jump_only,
+        enable_hint,
     })
 }
+
+/// Finds new intermediate points from `cur_pos` to the last point of `intermediates`.
+///
+/// The points are found with the same options as `intermediates` were found with.
+#[inline]
+pub fn refind_intermediate_points(
+    context: &Context,
+    state: &mut PlayerState,
+    cur_pos: Point,
+    intermediates: MovingIntermediates,
+) -> Option<MovingIntermediates> {
+    let (dest, exact) = intermediates.last;
+    find_intermediate_points(
+        context,
+        state,
+        cur_pos,
+        dest,
+        exact,
+        intermediates.up_jump_only,
+        intermediates.enable_hint,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate test;
+
+    use opencv::core::Point;
+    use test::{Bencher, black_box};
+
+    use super::{MovingIntermediates, find_intermediate_points, update_moving_context};
+    use crate::{
+        MovementProfile,
+        array::Array,
+        context::Context,
+        pathing::{MovementHint, Platform, find_neighbors},
+        player::{Player, PlayerState},
+    };
+
+    /// The intermediate points stored inline with a fixed capacity as before
+    /// [`MovingIntermediates`] referred to [`PlayerState::intermediate_points`]
+    ///
+    /// Only used as the baseline for benchmarking.
+    #[derive(Clone, Copy)]
+    struct FixedIntermediates {
+        current: usize,
+        inner: Array<(Point, MovementHint, bool), 16>,
+    }
+
+    impl FixedIntermediates {
+        fn next(&mut self) -> Option<(Point, bool)> {
+            if self.current >= self.inner.len() {
+                return None;
+            }
+            let next = self.inner[self.current];
+            self.current += 1;
+            Some((next.0, next.2))
+        }
+    }
+
+    /// Finds the intermediate points from the bottom to the top platform of `context`
+    fn find_column_intermediate_points(
+        context: &Context,
+        state: &mut PlayerState,
+        levels: i32,
+    ) -> MovingIntermediates {
+        find_intermediate_points(
+            context,
+            state,
+            Point::new(10, 10),
+            Point::new(20, 10 + (levels - 1) * 25),
+            true,
+            false,
+            false,
+        )
+        .unwrap()
+    }
+
+    /// Moves through all `intermediates` by updating [`Player::Moving`] once per point as if the
+    /// player reached each point in a tick
+    fn move_through_intermediates(
+        context: &Context,
+        state: &mut PlayerState,
+        mut intermediates: MovingIntermediates,
+    ) -> Player {
+        let (dest, exact) = intermediates.next(state).unwrap();
+        let mut player = Player::Moving(dest, exact, Some(intermediates));
+        while let Player::Moving(dest, exact, intermediates) = player {
+            state.last_known_pos = Some(dest);
+            player = update_moving_context(context, state, dest, exact, intermediates);
+        }
+        player
+    }
+
+    /// Makes a context with a single column of `levels` platforms each reachable by grappling
+    fn make_context_with_platforms(levels: i32) -> Context {
+        let platforms = (0..levels)
+            .map(|level| Platform::new(0..50, 10 + level * 25))
+            .collect::<Vec<_>>();
+        Context {
+            platforms: find_neighbors(&platforms, &[], &[], MovementProfile::default()),
+            ..Context::new(None, None)
+        }
+    }
+
+    #[test]
+    fn find_intermediate_points_more_than_16_points() {
+        let context = make_context_with_platforms(32);
+        let mut state = PlayerState::default();
+        let dest = Point::new(20, 10 + 31 * 25);
+
+        let mut intermediates = find_intermediate_points(
+            &context,
+            &mut state,
+            Point::new(10, 10),
+            dest,
+            true,
+            false,
+            false,
+        )
+        .unwrap();
+
+        assert!(state.intermediate_points.len() > 16);
+        assert_eq!(intermediates.last(), (dest, true));
+        let mut count = 0;
+        while intermediates.next(&state).is_some() {
+            count += 1;
+        }
+        assert_eq!(count, state.intermediate_points.len());
+        assert!(!intermediates.has_next());
+    }
+
+    #[test]
+    fn moving_intermediates_replaced_points_moves_to_last_point() {
+        let context = make_context_with_platforms(8);
+        let mut state = PlayerState::default();
+        let mut stale = find_column_intermediate_points(&context, &mut state, 8);
+        let _ = stale.next(&state);
+        let _ = find_column_intermediate_points(&context, &mut state, 4);
+
+        assert_eq!(stale.next(&state), Some(stale.last()));
+        assert!(!stale.has_next());
+        assert_eq!(stale.next(&state), None);
+        assert_eq!(stale.previous(&state), None);
+    }
+
+    #[test]
+    fn moving_through_intermediates_ends_idle() {
+        let context = make_context_with_platforms(32);
+        let mut state = PlayerState::default();
+        let intermediates = find_column_intermediate_points(&context, &mut state, 32);
+
+        let player = move_through_intermediates(&context, &mut state, intermediates);
+
+        assert!(matches!(player, Player::Idle));
+    }
+
+    /// Advances through a 12-point path copying the contextual state on each point as a tick
+    /// does with the points stored in [`PlayerState`]
+    ///
+    /// Compare with [`bench_advance_intermediates_12_platforms_fixed_array_baseline`].
+    #[bench]
+    fn bench_advance_intermediates_12_platforms(bencher: &mut Bencher) {
+        let context = make_context_with_platforms(12);
+        let mut state = PlayerState::default();
+        let intermediates = find_column_intermediate_points(&context, &mut state, 12);
+
+        bencher.iter(|| {
+            let mut player =
+                black_box(Player::Moving(Point::default(), false, Some(intermediates)));
+            while let Player::Moving(_, _, Some(mut intermediates)) = player
+                && let Some((dest, exact)) = intermediates.next(&state)
+            {
+                player = black_box(Player::Moving(dest, exact, Some(intermediates)));
+            }
+            player
+        });
+    }
+
+    /// Same as [`bench_advance_intermediates_12_platforms`] with the points stored inline with a
+    /// fixed capacity and copied along with the contextual state on each point
+    #[bench]
+    fn bench_advance_intermediates_12_platforms_fixed_array_baseline(bencher: &mut Bencher) {
+        let context = make_context_with_platforms(12);
+        let mut state = PlayerState::default();
+        let _ = find_column_intermediate_points(&context, &mut state, 12);
+        let fixed = FixedIntermediates {
+            current: 0,
+            inner: Array::from_iter(state.intermediate_points.iter().copied()),
+        };
+
+        bencher.iter(|| {
+            let mut moving = black_box((Point::default(), false, Some(fixed)));
+            while let (_, _, Some(mut fixed)) = moving
+                && let Some((dest, exact)) = fixed.next()
+            {
+                moving = black_box((dest, exact, Some(fixed)));
+            }
+            moving
+        });
+    }
+
+    /// Ticks [`Player::Moving`] through a 12-point path that fits the previous fixed capacity
+    #[bench]
+    fn bench_moving_tick_12_platforms(bencher: &mut Bencher) {
+        let context = make_context_with_platforms(12);
+        let mut state = PlayerState::default();
+        let intermediates = find_column_intermediate_points(&context, &mut state, 12);
+
+        bencher.iter(|| move_through_intermediates(&context, &mut state, black_box(intermediates)));
+    }
+
+    /// Ticks [`Player::Moving`] through a path longer than the previous fixed capacity
+    #[bench]
+    fn bench_moving_tick_32_platforms(bencher: &mut Bencher) {
+        let context = make_context_with_platforms(32);
+        let mut state = PlayerState::default();
+        let intermediates = find_column_intermediate_points(&context, &mut state, 32);
+
+        bencher.iter(|| move_through_intermediates(&context, &mut state, black_box(intermediates)));
+    }
+
+    #[bench]
+    fn bench_find_intermediate_points_32_platforms(bencher: &mut Bencher) {
+        let context = make_context_with_platforms(32);
+        let mut state = PlayerState::default();
+        let dest = Point::new(20, 10 + 31 * 25);
+
+        bencher.iter(|| {
+            find_intermediate_points(
+                black_box(&context),
+                &mut state,
+                Point::new(10, 10),
+                dest,
+                true,
+                false,
+                false,
+            )
+        });
+    }
+}
@@ -0,0 +1,175 @@
+use super::{
+    Player, PlayerState,
+    actions::{PlayerAction, on_action_state_mut},
+    timeout::{Timeout, update_with_timeout},
+};
+use crate::{
+    context::Context,
+    task::{Update, update_detection_task},
+};
+
+const TIMEOUT: u32 = 150;
+
+/// The number of ticks to wait for the inventory to open before reading
+const READ_START_TICK: u32 = 15;
+
+/// Updates the [`Player::ReadingMesos`] contextual state
+///
+/// Though this state can only be transitioned via [`Player::Idle`] with
+/// [`PlayerAction::ReadMesos`], it is not required. This state does:
+/// - On timeout start, opens the inventory
+/// - On timeout update, reads the mesos amount inside [`PlayerConfiguration::mesos_region`]
+/// - On timeout end or mesos is read before timing out, closes the inventory and transitions
+///   to `Player::Idle`
+///
+/// When interrupted before closing, the inventory is closed by [`close_inventory_if_interrupted`].
+///
+/// [`PlayerConfiguration::mesos_region`]: super::state::PlayerConfiguration::mesos_region
+pub fn update_reading_mesos_context(
+    context: &Context,
+    state: &mut PlayerState,
+    timeout: Timeout,
+) -> Player {
+    if !timeout.started {
+        state.mesos_task = None;
+    }
+    let next = update_with_timeout(
+        timeout,
+        TIMEOUT,
+        |timeout| {
+            let _ = context.keys.send(state.config.inventory_key);
+            Player::ReadingMesos(timeout)
+        },
+        || {
+            let _ = context.keys.send(state.config.inventory_key);
+            Player::Idle
+        },
+        |timeout| {
+            if timeout.total <= READ_START_TICK {
+                return Player::ReadingMesos(timeout);
+            }
+            let Some(region) = state.config.mesos_region else {
+                let _ = context.keys.send(state.config.inventory_key);
+                return Player::Idle;
+            };
+            match update_detection_task(context, 0, &mut state.mesos_task, move |detector| {
+                detector.detect_mesos(region)
+            }) {
+                Update::Ok(mesos) => {
+                    state.mesos = Some(mesos);
+                    let _ = context.keys.send(state.config.inventory_key);
+                    Player::Idle
+                }
+                Update::Err(_) => {
+                    let _ = context.keys.send(state.config.inventory_key);
+                    Player::Idle
+                }
+                Update::Pending => Player::ReadingMesos(timeout),
+            }
+        },
+    );
+    state.inventory_opened = matches!(next, Player::ReadingMesos(_));
+
+    on_action_state_mut(
+        state,
+        |_, action| match action {
+            PlayerAction::ReadMesos => Some((next, matches!(next, Player::Idle))),
+            PlayerAction::PingPong(_)
+            | PlayerAction::AutoMob(_)
+            | PlayerAction::Key(_)
+            | PlayerAction::Move(_)
+            | PlayerAction::Sequence(_)
+            | PlayerAction::SolveRune => {
+                unreachable!()
+            }
+        },
+        || next,
+    )
+}
+
+/// Closes the inventory opened by [`Player::ReadingMesos`] if `player` is no longer reading mesos
+///
+/// The player can stop reading mesos without closing the inventory when its action is aborted
+/// (e.g. halting) or another state takes over (e.g. [`Player::Reviving`]).
+pub fn close_inventory_if_interrupted(context: &Context, state: &mut PlayerState, player: Player) {
+    if state.inventory_opened
+        && (state.reset_to_idle_next_update || !matches!(player, Player::ReadingMesos(_)))
+    {
+        let _ = context.keys.send(state.config.inventory_key);
+        state.inventory_opened = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use platforms::windows::KeyKind;
+
+    use super::{READ_START_TICK, close_inventory_if_interrupted, update_reading_mesos_context};
+    use crate::{
+        bridge::MockKeySender,
+        context::Context,
+        player::{Player, PlayerState, Timeout},
+    };
+
+    fn inventory_keys(times: usize) -> MockKeySender {
+        let mut keys = MockKeySender::new();
+        keys.expect_send()
+            .withf(|key| matches!(key, KeyKind::I))
+            .times(times)
+            .returning(|_| Ok(()));
+        keys
+    }
+
+    fn state_with_inventory_key() -> PlayerState {
+        let mut state = PlayerState::default();
+        state.config.inventory_key = KeyKind::I;
+        state
+    }
+
+    #[test]
+    fn reading_mesos_opens_and_closes_without_region() {
+        let context = Context::new(Some(inventory_keys(2)), None);
+        let mut state = state_with_inventory_key();
+
+        let mut player = update_reading_mesos_context(&context, &mut state, Timeout::default());
+        assert!(state.inventory_opened);
+        for _ in 0..READ_START_TICK {
+            let Player::ReadingMesos(timeout) = player else {
+                panic!("closed before the read start tick");
+            };
+            player = update_reading_mesos_context(&context, &mut state, timeout);
+            assert!(state.inventory_opened);
+        }
+        let Player::ReadingMesos(timeout) = player else {
+            panic!("closed before the read start tick");
+        };
+        player = update_reading_mesos_context(&context, &mut state, timeout);
+        assert!(matches!(player, Player::Idle));
+        assert!(!state.inventory_opened);
+    }
+
+    #[test]
+    fn close_inventory_when_interrupted() {
+        let context = Context::new(Some(inventory_keys(2)), None);
+        let mut state = state_with_inventory_key();
+        let reading = Player::ReadingMesos(Timeout::default());
+
+        // Still reading
+        state.inventory_opened = true;
+        close_inventory_if_interrupted(&context, &mut state, reading);
+        assert!(state.inventory_opened);
+
+        // Replaced by another state (e.g. reviving)
+        close_inventory_if_interrupted(&context, &mut state, Player::Idle);
+        assert!(!state.inventory_opened);
+
+        // Aborted (e.g. halting)
+        state.inventory_opened = true;
+        state.reset_to_idle_next_update = true;
+        close_inventory_if_interrupted(&context, &mut state, reading);
+        assert!(!state.inventory_opened);
+
+        // Already closed
+        close_inventory_if_interrupted(&context, &mut state, Player::Idle);
+    }
+}
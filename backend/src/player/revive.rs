@@ -0,0 +1,296 @@
+use platforms::windows::KeyKind;
+
+use super::{
+    Player, PlayerState,
+    timeout::{Timeout, update_with_timeout},
+};
+use crate::{
+    context::Context,
+    minimap::Minimap,
+    task::{Update, update_detection_task},
+};
+
+/// The maximum number of ticks to accept the revive dialog
+const ACCEPTING_TIMEOUT: u32 = 305;
+
+/// The number of milliseconds between each detection of the revive dialog
+const ACCEPTING_INTERVAL_MILLIS: u64 = 1000;
+
+/// The maximum number of ticks to wait for the player to respawn
+const RESPAWNING_TIMEOUT: u32 = 305;
+
+/// The minimum number of ticks to wait after accepting the revive dialog
+const RESPAWNING_MIN_TICKS: u32 = 90;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Reviving {
+    /// Accepts the revive dialog until the player is no longer dead
+    Accepting,
+    /// Waits for the player and the minimap to be detected again
+    Respawning,
+}
+
+/// Updates the [`Player::Reviving`] contextual state
+///
+/// This state is transitioned to when [`PlayerState::is_dead`], reviving is enabled through
+/// [`super::PlayerConfiguration::revive_on_death`] and actions are not halting. This state does:
+/// - Presses the enter key to accept the revive dialog while it is detected
+/// - Once the player is no longer dead or timed out, waits for the player to respawn
+/// - On respawned or timed out, tracks the revive and transitions to `Player::Idle`
+///
+/// Whether the rotation can resume after reviving is decided outside of this state.
+pub fn update_reviving_context(
+    context: &Context,
+    state: &mut PlayerState,
+    timeout: Timeout,
+    reviving: Reviving,
+    failed_to_detect_player: bool,
+) -> Player {
+    if !timeout.started {
+        state.revive_task = None;
+    }
+    match reviving {
+        Reviving::Accepting => update_with_timeout(
+            timeout,
+            ACCEPTING_TIMEOUT,
+            |timeout| Player::Reviving(timeout, reviving),
+            || Player::Reviving(Timeout::default(), Reviving::Respawning),
+            |timeout| match update_detection_task(
+                context,
+                ACCEPTING_INTERVAL_MILLIS,
+                &mut state.revive_task,
+                |detector| {
+                    Ok((
+                        detector.detect_player_revive_dialog(),
+                        detector.detect_player_is_dead(),
+                    ))
+                },
+            ) {
+                Update::Ok((true, _)) => {
+                    let _ = context.keys.send(KeyKind::Enter);
+                    Player::Reviving(timeout, reviving)
+                }
+                Update::Ok((false, false)) => {
+                    Player::Reviving(Timeout::default(), Reviving::Respawning)
+                }
+                Update::Ok((false, true)) | Update::Err(_) | Update::Pending => {
+                    Player::Reviving(timeout, reviving)
+                }
+            },
+        ),
+        Reviving::Respawning => {
+            let next = update_with_timeout(
+                timeout,
+                RESPAWNING_TIMEOUT,
+                |timeout| Player::Reviving(timeout, reviving),
+                || Player::Idle,
+                |timeout| {
+                    if failed_to_detect_player
+                        || timeout.current < RESPAWNING_MIN_TICKS
+                        || !matches!(context.minimap, Minimap::Idle(_))
+                    {
+                        Player::Reviving(timeout, reviving)
+                    } else {
+                        Player::Idle
+                    }
+                },
+            );
+            if matches!(next, Player::Idle) {
+                state.track_revived(context.clock.now());
+            }
+            next
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{assert_matches::assert_matches, time::Duration};
+
+    use platforms::windows::KeyKind;
+    use tokio::time::advance;
+
+    use super::{
+        ACCEPTING_TIMEOUT, RESPAWNING_MIN_TICKS, RESPAWNING_TIMEOUT, Reviving,
+        update_reviving_context,
+    };
+    use crate::{
+        bridge::MockKeySender,
+        context::Context,
+        detect::MockDetector,
+        minimap::{Minimap, MinimapIdle},
+        player::{Player, PlayerState, Timeout},
+    };
+
+    fn started_timeout(current: u32) -> Timeout {
+        Timeout {
+            current,
+            total: current,
+            started: true,
+        }
+    }
+
+    fn detector_with(has_dialog: bool, is_dead: bool) -> MockDetector {
+        let mut detector = MockDetector::new();
+        detector
+            .expect_detect_player_revive_dialog()
+            .return_const(has_dialog);
+        detector
+            .expect_detect_player_is_dead()
+            .return_const(is_dead);
+        detector
+            .expect_clone()
+            .returning(move || detector_with(has_dialog, is_dead));
+        detector
+    }
+
+    /// Updates [`Reviving::Accepting`] until the detection task completes
+    async fn advance_accepting(context: &Context, state: &mut PlayerState) -> Player {
+        let mut player = Player::Reviving(started_timeout(0), Reviving::Accepting);
+        loop {
+            let Player::Reviving(timeout, reviving @ Reviving::Accepting) = player else {
+                unreachable!()
+            };
+            player = update_reviving_context(context, state, timeout, reviving, false);
+            if state
+                .revive_task
+                .as_ref()
+                .is_some_and(|task| task.completed())
+            {
+                return player;
+            }
+            advance(Duration::from_millis(100)).await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn accepting_presses_enter_while_dialog_detected() {
+        let mut keys = MockKeySender::new();
+        keys.expect_send()
+            .withf(|key| matches!(key, KeyKind::Enter))
+            .once()
+            .returning(|_| Ok(()));
+        let context = Context::new(Some(keys), Some(detector_with(true, true)));
+        let mut state = PlayerState::default();
+
+        let player = advance_accepting(&context, &mut state).await;
+
+        assert_matches!(player, Player::Reviving(_, Reviving::Accepting));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn accepting_waits_while_still_dead() {
+        let context = Context::new(None, Some(detector_with(false, true)));
+        let mut state = PlayerState::default();
+
+        let player = advance_accepting(&context, &mut state).await;
+
+        assert_matches!(
+            player,
+            Player::Reviving(Timeout { started: true, .. }, Reviving::Accepting)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn accepting_to_respawning_when_no_longer_dead() {
+        let context = Context::new(None, Some(detector_with(false, false)));
+        let mut state = PlayerState::default();
+
+        let player = advance_accepting(&context, &mut state).await;
+
+        assert_matches!(
+            player,
+            Player::Reviving(Timeout { started: false, .. }, Reviving::Respawning)
+        );
+    }
+
+    #[test]
+    fn accepting_to_respawning_on_timeout() {
+        let context = Context::new(None, None);
+        let mut state = PlayerState::default();
+
+        let player = update_reviving_context(
+            &context,
+            &mut state,
+            started_timeout(ACCEPTING_TIMEOUT),
+            Reviving::Accepting,
+            false,
+        );
+
+        assert_matches!(
+            player,
+            Player::Reviving(Timeout { started: false, .. }, Reviving::Respawning)
+        );
+    }
+
+    #[test]
+    fn respawning_waits_for_min_ticks_player_and_minimap() {
+        let mut context = Context::new(None, None);
+        let mut state = PlayerState::default();
+
+        let player = update_reviving_context(
+            &context,
+            &mut state,
+            started_timeout(RESPAWNING_MIN_TICKS - 2),
+            Reviving::Respawning,
+            false,
+        );
+        assert_matches!(player, Player::Reviving(_, Reviving::Respawning));
+
+        let player = update_reviving_context(
+            &context,
+            &mut state,
+            started_timeout(RESPAWNING_MIN_TICKS),
+            Reviving::Respawning,
+            false,
+        );
+        assert_matches!(player, Player::Reviving(_, Reviving::Respawning));
+
+        context.minimap = Minimap::Idle(MinimapIdle::default());
+        let player = update_reviving_context(
+            &context,
+            &mut state,
+            started_timeout(RESPAWNING_MIN_TICKS),
+            Reviving::Respawning,
+            true,
+        );
+        assert_matches!(player, Player::Reviving(_, Reviving::Respawning));
+        assert_eq!(state.take_revived_count(), None);
+    }
+
+    #[test]
+    fn respawning_to_idle_and_tracks_revive() {
+        let mut context = Context::new(None, None);
+        context.minimap = Minimap::Idle(MinimapIdle::default());
+        let mut state = PlayerState::default();
+
+        let player = update_reviving_context(
+            &context,
+            &mut state,
+            started_timeout(RESPAWNING_MIN_TICKS - 1),
+            Reviving::Respawning,
+            false,
+        );
+
+        assert_matches!(player, Player::Idle);
+        assert_eq!(state.take_revived_count(), Some(1));
+        assert_eq!(state.take_revived_count(), None);
+    }
+
+    #[test]
+    fn respawning_to_idle_on_timeout_and_tracks_revive() {
+        let context = Context::new(None, None);
+        let mut state = PlayerState::default();
+
+        let player = update_reviving_context(
+            &context,
+            &mut state,
+            started_timeout(RESPAWNING_TIMEOUT),
+            Reviving::Respawning,
+            false,
+        );
+
+        assert_matches!(player, Player::Idle);
+        assert_eq!(state.take_revived_count(), Some(1));
+    }
+}
@@ -109,7 +109,9 @@ pub fn update_solving_rune_context(
             PlayerAction::PingPong(_)
             | PlayerAction::AutoMob(_)
             | PlayerAction::Key(_)
-            | PlayerAction::Move(_) => {
+            | PlayerAction::Move(_)
+            | PlayerAction::Sequence(_)
+            | PlayerAction::ReadMesos => {
                 unreachable!()
             }
         },
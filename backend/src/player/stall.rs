@@ -44,7 +44,9 @@ pub fn update_stalling_context(
             PlayerAction::PingPong(_)
             | PlayerAction::Key(_)
             | PlayerAction::Move(_)
-            | PlayerAction::SolveRune => Some((next, matches!(next, Player::Idle))),
+            | PlayerAction::Sequence(_)
+            | PlayerAction::SolveRune
+            | PlayerAction::ReadMesos => Some((next, matches!(next, Player::Idle))),
         },
         || next,
     )
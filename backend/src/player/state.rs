@@ -1,4 +1,8 @@
-use std::{collections::HashMap, range::Range};
+use std::{
+    collections::HashMap,
+    range::Range,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 use log::debug;
@@ -7,17 +11,17 @@ use platforms::windows::KeyKind;
 use rand::seq::IteratorRandom;
 
 use super::{
-    DOUBLE_JUMP_THRESHOLD, JUMP_THRESHOLD, MOVE_TIMEOUT, Player, PlayerAction,
-    double_jump::DOUBLE_JUMP_AUTO_MOB_THRESHOLD, fall::FALLING_THRESHOLD, timeout::Timeout,
+    Player, PlayerAction, double_jump::DOUBLE_JUMP_AUTO_MOB_THRESHOLD, fall::FALLING_THRESHOLD,
+    timeout::Timeout,
 };
 use crate::{
-    ActionKeyDirection, Class,
-    array::Array,
+    ActionKeyDirection, MovementProfile,
     buff::{Buff, BuffKind},
     context::Context,
     detect::ArrowsState,
     minimap::Minimap,
     network::NotificationKind,
+    pathing::MovementHint,
     player::timeout::update_with_timeout,
     task::{Task, Update, update_detection_task},
 };
@@ -71,8 +75,8 @@ const UNSTUCK_COUNT_THRESHOLD: u32 = 6;
 /// The number of times [`Player::Unstucking`] can be transitioned to before entering GAMBA MODE.
 const UNSTUCK_GAMBA_MODE_COUNT: u32 = 3;
 
-/// The number of samples to store for approximating velocity.
-const VELOCITY_SAMPLES: usize = MOVE_TIMEOUT as usize;
+/// The duration in which the number of revives is counted.
+const REVIVED_COUNT_DURATION: Duration = Duration::from_secs(3600);
 
 /// The player previous movement-related contextual state.
 #[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
@@ -83,11 +87,13 @@ pub enum LastMovement {
     Grappling,
     UpJumping,
     Jumping,
+    Climbing,
 }
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct PlayerConfiguration {
-    pub class: Class,
+    /// The distances and timeouts used by movement states.
+    pub movement: MovementProfile,
     /// Enables platform pathing for rune.
     pub rune_platforms_pathing: bool,
     /// Uses only up jump(s) in rune platform pathing.
@@ -118,8 +124,23 @@ pub struct PlayerConfiguration {
     pub potion_key: KeyKind,
     /// Uses potion when health is below a percentage.
     pub use_potion_below_percent: Option<f32>,
-    /// Milliseconds interval to update current health.
+    /// Milliseconds interval to update current health with [`None`] indicating no update.
     pub update_health_millis: Option<u64>,
+    /// Milliseconds interval to update current mana with [`None`] indicating no update.
+    pub update_mana_millis: Option<u64>,
+    /// The inventory key.
+    pub inventory_key: KeyKind,
+    /// The region of the mesos amount inside the opened inventory.
+    pub mesos_region: Option<Rect>,
+    /// Revives the player upon death.
+    pub revive_on_death: bool,
+}
+
+/// How an action provided by [`Rotator`] ended.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlayerActionOutcome {
+    Completed,
+    Aborted,
 }
 
 /// The player persistent states.
@@ -148,6 +169,24 @@ pub struct PlayerState {
     health_bar: Option<Rect>,
     /// The task for the health bar.
     health_bar_task: Option<Task<Result<Rect>>>,
+    /// Health ratios and milliseconds the health must stay at or below to be critical.
+    ///
+    /// Each threshold also tracks the instant health has been at or below its ratio since.
+    health_critical_thresholds: Vec<(f32, u64, Option<Instant>)>,
+    /// Whether the player health has stayed at or below any of the critical thresholds.
+    pub is_health_critical: bool,
+    /// The player current mana and max mana.
+    pub mana: Option<(u32, u32)>,
+    /// The task to update mana.
+    mana_task: Option<Task<Result<(u32, u32)>>>,
+    /// The rectangular mana bar region.
+    mana_bar: Option<Rect>,
+    /// The task for the mana bar.
+    mana_bar_task: Option<Task<Result<Rect>>>,
+    /// The player mesos amount last read by [`Player::ReadingMesos`].
+    pub mesos: Option<u64>,
+    /// The task to read mesos.
+    pub(super) mesos_task: Option<Task<Result<u64>>>,
     /// Track if the player moved within a specified ticks to determine if the player is
     /// stationary.
     is_stationary_timeout: Timeout,
@@ -157,12 +196,31 @@ pub struct PlayerState {
     pub is_dead: bool,
     /// The task for detecting if player is dead.
     is_dead_task: Option<Task<Result<bool>>>,
+    /// The task for detecting the revive dialog and if player is dead in [`Player::Reviving`].
+    pub(super) revive_task: Option<Task<Result<(bool, bool)>>>,
+    /// Whether [`Player::Reviving`] has completed since the last [`Self::take_revived_count`].
+    revived: bool,
+    /// The instants [`Player::Reviving`] completed within [`REVIVED_COUNT_DURATION`].
+    revived_instants: Vec<Instant>,
     /// Approximates the player direction for using key.
     pub(super) last_known_direction: ActionKeyDirection,
     /// Tracks last destination points for displaying to UI.
     ///
     /// Resets when all destinations are reached or in [`Player::Idle`].
     pub last_destinations: Option<Vec<Point>>,
+    /// The points of the current path referred to by [`super::moving::MovingIntermediates`].
+    ///
+    /// Replaced each time a new path is found in [`super::moving::find_intermediate_points`].
+    pub(super) intermediate_points: Vec<(Point, MovementHint, bool)>,
+    /// Incremented each time [`Self::intermediate_points`] is replaced.
+    ///
+    /// Kept across [`Self::reset`] so that a [`super::moving::MovingIntermediates`] created
+    /// before resetting is never mistaken for the current points.
+    pub(super) intermediate_points_generation: u32,
+    /// The `UsePortal` points of the portals that did not teleport the player.
+    ///
+    /// Excluded from the paths found in [`super::moving::find_intermediate_points`] until reset.
+    pub(super) failed_portals: Vec<Point>,
     /// Last known position after each detection.
     ///
     /// It is updated to latest current position on each tick.
@@ -223,6 +281,21 @@ pub struct PlayerState {
     rune_failed_count: u32,
     /// Indicates the state will be transitioned to [`Player::CashShopThenExit`] in the next tick.
     pub(super) rune_cash_shop: bool,
+    /// Indicates the state will be transitioned to [`Player::ChangingChannel`] once the current
+    /// state can be overridden.
+    ///
+    /// The inner boolean is whether to change to a random channel instead of the next one.
+    pub(super) change_channel: Option<bool>,
+    /// The task for detecting the ESC menu or the change channel dialog in
+    /// [`Player::ChangingChannel`].
+    pub(super) change_channel_task: Option<Task<Result<bool>>>,
+    /// Whether [`Player::ChangingChannel`] has completed since the last
+    /// [`Self::take_channel_changed`].
+    channel_changed: bool,
+    /// The last [`Instant`] [`Player::ChangingChannel`] completed.
+    ///
+    /// Initialized to the first time changing channel is polled for scheduling.
+    pub last_channel_changed: Option<Instant>,
     /// [`Timeout`] for validating whether the rune is solved.
     ///
     /// This is [`Some`] when [`Player::SolvingRune`] successfully detects the rune
@@ -232,10 +305,28 @@ pub struct PlayerState {
     ///
     /// Resets when [`Player::Stalling`] timed out or in [`Player::Idle`].
     pub(super) stalling_timeout_state: Option<Player>,
+    /// The keys currently held down by [`Player::UseKey`] or [`Player::UseSequence`].
+    ///
+    /// Released as soon as the player is no longer holding them (e.g. interrupted or halted).
+    pub(super) held_keys: Vec<KeyKind>,
+    /// Whether the inventory is opened by [`Player::ReadingMesos`].
+    ///
+    /// Closed as soon as the player is no longer reading mesos (e.g. interrupted or halted).
+    pub(super) inventory_opened: bool,
+    /// The number of times the player position has been updated.
+    ///
+    /// Used instead of [`Context::tick`] as the time base of [`Self::velocity`] so that velocity
+    /// counts updates like [`Timeout`] does.
+    position_update_count: u64,
     /// Stores a list of [`(Point, u64)`] pair samples for approximating velocity.
-    velocity_samples: Array<(Point, u64), VELOCITY_SAMPLES>,
-    /// Approximated player velocity.
+    ///
+    /// The number of samples is [`MovementProfile::velocity_samples`] and the [`u64`] is
+    /// [`Self::position_update_count`] at the time of sampling.
+    velocity_samples: Vec<(Point, u64)>,
+    /// Approximated player velocity in minimap pixels per position update.
     pub(super) velocity: (f32, f32),
+    /// The ids and outcomes of the actions ended since the last [`Self::take_action_outcomes`].
+    action_outcomes: Vec<(u32, PlayerActionOutcome)>,
 }
 
 impl PlayerState {
@@ -246,6 +337,15 @@ impl PlayerState {
     pub fn reset(&mut self) {
         *self = PlayerState {
             config: self.config,
+            held_keys: std::mem::take(&mut self.held_keys),
+            inventory_opened: self.inventory_opened,
+            revived_instants: std::mem::take(&mut self.revived_instants),
+            intermediate_points_generation: self.intermediate_points_generation,
+            health_critical_thresholds: self
+                .health_critical_thresholds
+                .iter()
+                .map(|(ratio, millis, _)| (*ratio, *millis, None))
+                .collect(),
             reset_to_idle_next_update: true,
             ..PlayerState::default()
         };
@@ -257,6 +357,12 @@ impl PlayerState {
         self.normal_action.map(|action| action.to_string())
     }
 
+    /// The current normal action.
+    #[inline]
+    pub fn normal_action(&self) -> Option<PlayerAction> {
+        self.normal_action
+    }
+
     /// The normal action id provided by [`Rotator`].
     #[inline]
     pub fn normal_action_id(&self) -> Option<u32> {
@@ -280,6 +386,10 @@ impl PlayerState {
     /// Removes the current normal action.
     #[inline]
     pub fn reset_normal_action(&mut self) {
+        if let Some(id) = self.normal_action_id() {
+            self.action_outcomes
+                .push((id, PlayerActionOutcome::Aborted));
+        }
         self.reset_to_idle_next_update = true;
         self.normal_action = None;
     }
@@ -290,6 +400,12 @@ impl PlayerState {
         self.priority_action.map(|action| action.to_string())
     }
 
+    /// The current priority action.
+    #[inline]
+    pub fn priority_action(&self) -> Option<PlayerAction> {
+        self.priority_action
+    }
+
     /// The priority action id provided by [`Rotator`].
     #[inline]
     pub fn priority_action_id(&self) -> Option<u32> {
@@ -345,6 +461,12 @@ impl PlayerState {
         matches!(self.priority_action, Some(PlayerAction::SolveRune))
     }
 
+    /// The approximated direction the player is facing.
+    #[inline]
+    pub fn last_known_direction(&self) -> ActionKeyDirection {
+        self.last_known_direction
+    }
+
     /// Whether there is only auto mob action.
     #[inline]
     pub(super) fn has_auto_mob_action_only(&self) -> bool {
@@ -360,6 +482,14 @@ impl PlayerState {
     /// Clears both on-going normal and priority actions due to being aborted.
     #[inline]
     pub fn clear_actions_aborted(&mut self) {
+        if let Some(id) = self.priority_action_id() {
+            self.action_outcomes
+                .push((id, PlayerActionOutcome::Aborted));
+        }
+        if let Some(id) = self.normal_action_id() {
+            self.action_outcomes
+                .push((id, PlayerActionOutcome::Aborted));
+        }
         self.reset_to_idle_next_update = true;
         self.priority_action = None;
         self.normal_action = None;
@@ -367,16 +497,37 @@ impl PlayerState {
 
     /// Clears either normal or priority due to completion.
     #[inline]
-    pub(super) fn clear_action_completed(&mut self) {
+    pub fn clear_action_completed(&mut self) {
+        self.clear_action(PlayerActionOutcome::Completed);
+    }
+
+    /// Clears either normal or priority due to being aborted (e.g. repeated movement).
+    #[inline]
+    pub(super) fn clear_action_aborted(&mut self) {
+        self.clear_action(PlayerActionOutcome::Aborted);
+    }
+
+    #[inline]
+    fn clear_action(&mut self, outcome: PlayerActionOutcome) {
         self.clear_last_movement();
-        if self.has_priority_action() {
+        if let Some(id) = self.priority_action_id() {
+            self.action_outcomes.push((id, outcome));
             self.priority_action = None;
         } else {
+            if let Some(id) = self.normal_action_id() {
+                self.action_outcomes.push((id, outcome));
+            }
             self.auto_mob_reachable_y = None;
             self.normal_action = None;
         }
     }
 
+    /// Takes the ids and outcomes of the actions ended since the last call.
+    #[inline]
+    pub fn take_action_outcomes(&mut self) -> Vec<(u32, PlayerActionOutcome)> {
+        std::mem::take(&mut self.action_outcomes)
+    }
+
     /// Clears the last movement tracking for either normal or priority action.
     #[inline]
     pub(super) fn clear_last_movement(&mut self) {
@@ -453,7 +604,8 @@ impl PlayerState {
             LastMovement::Falling
             | LastMovement::Grappling
             | LastMovement::UpJumping
-            | LastMovement::Jumping => {
+            | LastMovement::Jumping
+            | LastMovement::Climbing => {
                 if self.has_auto_mob_action_only() {
                     AUTO_MOB_VERTICAL_MOVEMENT_REPEAT_COUNT
                 } else {
@@ -483,7 +635,7 @@ impl PlayerState {
     #[inline]
     pub(super) fn falling_threshold(&self, is_intermediate: bool) -> i32 {
         if self.has_auto_mob_action_only() || is_intermediate {
-            JUMP_THRESHOLD
+            self.config.movement.jump_threshold
         } else {
             FALLING_THRESHOLD
         }
@@ -500,7 +652,7 @@ impl PlayerState {
         } else if self.has_ping_pong_action_only() {
             0 // Ping pong double jumps forever
         } else {
-            DOUBLE_JUMP_THRESHOLD
+            self.config.movement.double_jump_threshold
         }
     }
 
@@ -522,7 +674,7 @@ impl PlayerState {
     #[inline]
     pub fn auto_mob_pathing_point(&mut self, context: &Context) -> Option<Point> {
         let (minimap_width, platforms) = match context.minimap {
-            Minimap::Idle(idle) => (idle.bbox.width, idle.platforms),
+            Minimap::Idle(idle) => (idle.bbox.width, &context.platforms),
             _ => unreachable!(),
         };
         let point = self
@@ -537,7 +689,7 @@ impl PlayerState {
         point.map(|(_, point)| point).or_else(|| {
             // Flip a coin, use platform as pathing point
             if !platforms.is_empty() && rand::random_bool(0.5) {
-                let platform = platforms[rand::random_range(0..platforms.len())];
+                let platform = &platforms[rand::random_range(0..platforms.len())];
                 let xs = platform.xs();
                 let y = platform.y();
                 let x = rand::random_range(xs.start..xs.end);
@@ -640,9 +792,9 @@ impl PlayerState {
 
     fn auto_mob_populate_reachable_y(&mut self, context: &Context) {
         match context.minimap {
-            Minimap::Idle(idle) => {
+            Minimap::Idle(_) => {
                 // Believes in user input lets goo...
-                for platform in idle.platforms {
+                for platform in &context.platforms {
                     self.auto_mob_reachable_y_map
                         .insert(platform.y(), AUTO_MOB_REACHABLE_Y_SOLIDIFY_COUNT);
                 }
@@ -706,7 +858,9 @@ impl PlayerState {
             PlayerAction::PingPong(_)
             | PlayerAction::Key(_)
             | PlayerAction::Move(_)
-            | PlayerAction::SolveRune => {
+            | PlayerAction::Sequence(_)
+            | PlayerAction::SolveRune
+            | PlayerAction::ReadMesos => {
                 unreachable!()
             }
         };
@@ -776,7 +930,7 @@ impl PlayerState {
 
     pub(super) fn auto_mob_populate_ignore_xs(&mut self, context: &Context) {
         let (platforms, minimap_width) = match context.minimap {
-            Minimap::Idle(idle) => (idle.platforms, idle.bbox.width),
+            Minimap::Idle(idle) => (&context.platforms, idle.bbox.width),
             Minimap::Detecting => unreachable!(),
         };
         if platforms.is_empty() {
@@ -820,14 +974,15 @@ impl PlayerState {
 
     /// Updates the [`PlayerState`] on each tick.
     ///
-    /// This function updates the player states including current position, health, whether the
-    /// player is dead, stationary state and rune validation state. It also resets
+    /// This function updates the player states including current position, health, mana, whether
+    /// the player is dead, stationary state and rune validation state. It also resets
     /// [`PlayerState::unstuck_counter`] and [`PlayerState::unstuck_consecutive_counter`] when the
     /// player position changes.
     #[inline]
     pub(super) fn update_state(&mut self, context: &Context) -> bool {
         if self.update_position_state(context) {
             self.update_health_state(context);
+            self.update_mana_state(context);
             self.update_rune_validating_state(context);
             self.update_is_dead_state(context);
             return true;
@@ -865,11 +1020,12 @@ impl PlayerState {
             self.unstuck_transitioned_count = 0;
             self.is_stationary_timeout = Timeout::default();
         }
-        self.update_velocity(pos, context.tick);
+        self.position_update_count += 1;
+        self.update_velocity(pos, self.position_update_count);
 
         let (is_stationary, is_stationary_timeout) = update_with_timeout(
             self.is_stationary_timeout,
-            MOVE_TIMEOUT,
+            self.config.movement.move_timeout,
             |timeout| (false, timeout),
             || (true, self.is_stationary_timeout),
             |timeout| (false, timeout),
@@ -880,23 +1036,22 @@ impl PlayerState {
         true
     }
 
-    /// Approximates the player velocity.
+    /// Approximates the player velocity from `pos` sampled at the position update `count`.
     #[inline]
-    fn update_velocity(&mut self, pos: Point, tick: u64) {
-        if self.velocity_samples.len() == VELOCITY_SAMPLES {
-            self.velocity_samples.remove(0);
+    fn update_velocity(&mut self, pos: Point, count: u64) {
+        let max_samples = (self.config.movement.velocity_samples as usize).max(2);
+        if self.velocity_samples.len() >= max_samples {
+            let excess = self.velocity_samples.len() + 1 - max_samples;
+            self.velocity_samples.drain(..excess);
         }
-        self.velocity_samples.push((pos, tick));
+        self.velocity_samples.push((pos, count));
 
         if self.velocity_samples.len() >= 2 {
-            let (weighted_sum, total_weight) = self
-                .velocity_samples
-                .as_slice()
-                .windows(2)
-                .enumerate()
-                .fold(((0.0, 0.0), 0.0), |(acc_sum, acc_weight), (i, window)| {
-                    let a = window[0].unwrap();
-                    let b = window[1].unwrap();
+            let (weighted_sum, total_weight) = self.velocity_samples.windows(2).enumerate().fold(
+                ((0.0, 0.0), 0.0),
+                |(acc_sum, acc_weight), (i, window)| {
+                    let a = window[0];
+                    let b = window[1];
                     let dt = b.1 - a.1;
                     if dt == 0 {
                         return (acc_sum, acc_weight);
@@ -909,7 +1064,8 @@ impl PlayerState {
                         (acc_sum.0 + weight * dx, acc_sum.1 + weight * dy),
                         acc_weight + weight,
                     )
-                });
+                },
+            );
 
             if total_weight > 0.0 {
                 let avg_dx = (weighted_sum.0 / total_weight).abs();
@@ -963,16 +1119,17 @@ impl PlayerState {
         if let Player::SolvingRune(_) = context.player {
             return;
         }
-        if self.config.use_potion_below_percent.is_none() {
+        let Some(update_millis) = self.config.update_health_millis else {
             {
                 let this = &mut *self;
                 this.health = None;
                 this.health_task = None;
                 this.health_bar = None;
                 this.health_bar_task = None;
+                this.is_health_critical = false;
             };
             return;
-        }
+        };
 
         let Some(health_bar) = self.health_bar else {
             let update =
@@ -987,7 +1144,7 @@ impl PlayerState {
 
         let Update::Ok(health) = update_detection_task(
             context,
-            self.config.update_health_millis.unwrap_or(1000),
+            update_millis,
             &mut self.health_task,
             move |detector| {
                 let (current_bar, max_bar) =
@@ -1000,16 +1157,125 @@ impl PlayerState {
             return;
         };
 
-        let percentage = self.config.use_potion_below_percent.unwrap();
         let (current, max) = health;
         let ratio = current as f32 / max as f32;
 
         self.health = Some(health);
-        if ratio <= percentage {
+        self.update_health_critical_state(context.clock.now(), ratio);
+        if let Some(percentage) = self.config.use_potion_below_percent
+            && ratio <= percentage
+        {
             let _ = context.keys.send(self.config.potion_key);
         }
     }
 
+    /// Takes the number of revives within the last hour if the player has revived since the
+    /// last call.
+    #[inline]
+    pub fn take_revived_count(&mut self) -> Option<usize> {
+        std::mem::take(&mut self.revived).then_some(self.revived_instants.len())
+    }
+
+    /// Requests the player to change channel once the current state can be overridden.
+    ///
+    /// Changes to a random channel if `random` is true or the next channel otherwise.
+    #[inline]
+    pub fn request_change_channel(&mut self, random: bool) {
+        self.change_channel = Some(random);
+    }
+
+    /// Takes whether the player has changed channel since the last call.
+    #[inline]
+    pub fn take_channel_changed(&mut self) -> bool {
+        std::mem::take(&mut self.channel_changed)
+    }
+
+    /// Tracks the player has changed channel at `now`.
+    pub(super) fn track_channel_changed(&mut self, now: Instant) {
+        self.channel_changed = true;
+        self.last_channel_changed = Some(now);
+    }
+
+    /// Tracks the player has revived at `now`.
+    pub(super) fn track_revived(&mut self, now: Instant) {
+        self.is_dead = false;
+        self.is_dead_task = None;
+        self.revived = true;
+        self.revived_instants
+            .retain(|instant| now.saturating_duration_since(*instant) < REVIVED_COUNT_DURATION);
+        self.revived_instants.push(now);
+    }
+
+    /// Sets the health ratios and milliseconds the health must stay at or below to be critical.
+    pub fn set_health_critical_thresholds(&mut self, thresholds: Vec<(f32, u64)>) {
+        self.health_critical_thresholds = thresholds
+            .into_iter()
+            .map(|(ratio, millis)| (ratio, millis, None))
+            .collect();
+        self.is_health_critical = false;
+    }
+
+    /// Updates whether the player health is critical from the current health `ratio`.
+    #[inline]
+    fn update_health_critical_state(&mut self, now: Instant, ratio: f32) {
+        self.is_health_critical = false;
+        for (threshold, millis, since) in self.health_critical_thresholds.iter_mut() {
+            if ratio > *threshold {
+                *since = None;
+                continue;
+            }
+            let since = *since.get_or_insert(now);
+            if now.saturating_duration_since(since).as_millis() >= *millis as u128 {
+                self.is_health_critical = true;
+            }
+        }
+    }
+
+    /// Updates the player current mana.
+    ///
+    /// Similar to [`Self::update_health_state`] but the MP bar is detected right below the HP bar.
+    /// Using mana potion is left to [`Rotator`] since it only needs [`PlayerState::mana`].
+    #[inline]
+    fn update_mana_state(&mut self, context: &Context) {
+        if let Player::SolvingRune(_) = context.player {
+            return;
+        }
+        let Some(update_millis) = self.config.update_mana_millis else {
+            self.mana = None;
+            self.mana_task = None;
+            self.mana_bar = None;
+            self.mana_bar_task = None;
+            return;
+        };
+
+        let Some(mana_bar) = self.mana_bar else {
+            let update =
+                update_detection_task(context, 1000, &mut self.mana_bar_task, move |detector| {
+                    let health_bar = detector.detect_player_health_bar()?;
+                    detector.detect_player_mana_bar(health_bar)
+                });
+            if let Update::Ok(mana_bar) = update {
+                self.mana_bar = Some(mana_bar);
+            }
+            return;
+        };
+
+        if let Update::Ok(mana) = update_detection_task(
+            context,
+            update_millis,
+            &mut self.mana_task,
+            move |detector| {
+                let (current_bar, max_bar) =
+                    detector.detect_player_current_max_mana_bars(mana_bar)?;
+                let mana = detector.detect_player_mana(current_bar, max_bar)?;
+                debug!(target: "player", "mana updated {:?}", mana);
+                Ok(mana)
+            },
+        ) {
+            self.mana = Some(mana);
+        }
+    }
+
     /// Updates whether the player is dead.
     ///
     /// Upon being dead, a notification will be scheduled to notify the user.
@@ -1041,19 +1307,125 @@ fn auto_mob_ignore_xs_range_value(x: i32) -> (Range<i32>, u32) {
 
 #[cfg(test)]
 mod tests {
-    use std::{assert_matches::assert_matches, collections::HashMap};
+    use std::{
+        assert_matches::assert_matches,
+        collections::HashMap,
+        time::{Duration, Instant},
+    };
 
     use opencv::core::{Point, Rect};
 
     use crate::{
-        Position,
-        array::Array,
-        context::Context,
+        MovementProfile, Position,
+        clock::{Clock, ManualClock},
+        context::{Context, MS_PER_TICK},
+        detect::MockDetector,
         minimap::{Minimap, MinimapIdle},
         pathing::{Platform, find_neighbors},
         player::{PlayerAction, PlayerActionAutoMob, PlayerState},
     };
 
+    #[test]
+    fn update_health_critical_state_after_millis() {
+        let mut state = PlayerState::default();
+        let now = Instant::now();
+        state.set_health_critical_thresholds(vec![(0.3, 5000)]);
+
+        state.update_health_critical_state(now, 0.2);
+        assert!(!state.is_health_critical);
+        state.update_health_critical_state(now + Duration::from_millis(5000), 0.25);
+        assert!(state.is_health_critical);
+
+        // Recovered health resets the tracking instant
+        state.update_health_critical_state(now + Duration::from_millis(6000), 0.5);
+        assert!(!state.is_health_critical);
+        state.update_health_critical_state(now + Duration::from_millis(7000), 0.2);
+        assert!(!state.is_health_critical);
+    }
+
+    #[test]
+    fn track_revived_count_within_hour() {
+        let mut state = PlayerState {
+            is_dead: true,
+            ..PlayerState::default()
+        };
+        let now = Instant::now();
+        assert_eq!(state.take_revived_count(), None);
+
+        state.track_revived(now);
+        assert!(!state.is_dead);
+        assert_eq!(state.take_revived_count(), Some(1));
+        assert_eq!(state.take_revived_count(), None);
+
+        state.track_revived(now + Duration::from_secs(1800));
+        state.reset();
+        assert_eq!(state.take_revived_count(), None);
+        state.track_revived(now + Duration::from_secs(3600));
+        assert_eq!(state.take_revived_count(), Some(2));
+    }
+
+    #[test]
+    fn update_velocity_keeps_profile_samples() {
+        let mut state = PlayerState::default();
+        state.config.movement = MovementProfile {
+            velocity_samples: 3,
+            ..MovementProfile::default()
+        };
+
+        for count in 0..5 {
+            state.update_velocity(Point::new(count as i32 * 2, 0), count);
+        }
+        assert_eq!(state.velocity_samples.len(), 3);
+        assert_eq!(state.velocity_samples[0], (Point::new(4, 0), 2));
+        assert!(state.velocity.0 > 0.0);
+        assert_eq!(state.velocity.1, 0.0);
+    }
+
+    #[test]
+    fn update_velocity_per_position_update_with_skipped_ticks() {
+        let clock = ManualClock::default();
+        let mut detector = MockDetector::new();
+        let mut x = 0;
+        detector.expect_detect_player().returning(move |_| {
+            x += 2;
+            Ok(Rect::new(x, 0, 2, 2))
+        });
+        let mut context = Context {
+            clock: Box::new(clock.clone()),
+            minimap: Minimap::Idle(MinimapIdle {
+                bbox: Rect::new(0, 0, 100, 100),
+                ..MinimapIdle::default()
+            }),
+            ..Context::new(None, Some(detector))
+        };
+        let started = clock.now();
+        let mut state = PlayerState::default();
+
+        // Every other update takes four ticks
+        for i in 0..20 {
+            let ticks = if i % 2 == 0 { 1 } else { 4 };
+            clock.advance(Duration::from_millis(ticks * MS_PER_TICK));
+            context.tick = clock.ticks_since(started);
+            assert!(state.update_position_state(&context));
+        }
+        assert!((state.velocity.0 - 2.0).abs() < 0.01);
+        assert_eq!(state.velocity.1, 0.0);
+    }
+
+    #[test]
+    fn track_channel_changed_once() {
+        let mut state = PlayerState::default();
+        let now = Instant::now();
+        assert!(!state.take_channel_changed());
+
+        state.request_change_channel(true);
+        assert_eq!(state.change_channel, Some(true));
+        state.track_channel_changed(now);
+        assert!(state.take_channel_changed());
+        assert!(!state.take_channel_changed());
+        assert_eq!(state.last_channel_changed, Some(now));
+    }
+
     #[test]
     fn auto_mob_pick_reachable_y_should_ignore_solidified_x_range() {
         let context = Context::new(None, None);
@@ -1190,14 +1562,14 @@ mod tests {
             Platform::new(20..25, 10),
             Platform::new(0..10, 5), // A different y-level
         ];
-        let platforms = find_neighbors(&platforms, 25, 7, 41);
+        let platforms = find_neighbors(&platforms, &[], &[], MovementProfile::default());
 
         let mut idle = MinimapIdle::default();
-        idle.platforms = Array::from_iter(platforms);
         idle.bbox = Rect::new(0, 0, 100, 100);
 
         let context = Context {
             minimap: Minimap::Idle(idle),
+            platforms,
             ..Context::new(None, None)
         };
 
@@ -20,6 +20,14 @@ pub enum ChangeAxis {
 /// an action will be performed or a state can be transitioned. So timeout is used to retry
 /// such action/state and to avoid looping in a single state forever. Or
 /// for some contextual states to perform an action only after timing out.
+///
+/// Unlike the [`crate::rotator::Rotator`] timings, a timeout counts updates of a contextual state
+/// instead of reading [`crate::clock::Clock`]. Each update observes a new player position from a
+/// new frame, so a movement timeout means "this many frames without the expected change". If the
+/// timeout were measured in wall-clock time, a slow tick would time out a movement before enough
+/// frames were observed to tell whether the player moved. A state can also be updated more than
+/// once in the same tick through [`crate::context::ControlFlow::Immediate`], which wall-clock
+/// time cannot distinguish.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Timeout {
     /// The current timeout tick.
@@ -5,7 +5,7 @@ use super::{PlayerState, timeout::Timeout};
 use crate::{
     context::Context,
     minimap::Minimap,
-    player::{MOVE_TIMEOUT, Player, timeout::update_with_timeout},
+    player::{Player, timeout::update_with_timeout},
     task::{Update, update_detection_task},
 };
 
@@ -55,7 +55,7 @@ pub fn update_unstucking_context(
 
     update_with_timeout(
         timeout,
-        MOVE_TIMEOUT,
+        state.config.movement.move_timeout,
         |timeout| {
             if has_settings.unwrap_or_default() || (gamba_mode && rand::random_bool(0.5)) {
                 let _ = context.keys.send(KeyKind::Esc);
@@ -10,7 +10,7 @@ use crate::{
     context::Context,
     minimap::Minimap,
     player::{
-        MOVE_TIMEOUT, PlayerAction,
+        PlayerAction,
         actions::{on_action, on_auto_mob_use_key_action},
         state::LastMovement,
         timeout::{ChangeAxis, update_moving_axis_context},
@@ -19,10 +19,8 @@ use crate::{
 
 const SPAM_DELAY: u32 = 7;
 const STOP_UP_KEY_TICK: u32 = 3;
-const TIMEOUT: u32 = MOVE_TIMEOUT + 3;
 const UP_JUMPED_Y_VELOCITY_THRESHOLD: f32 = 1.3;
 const X_NEAR_STATIONARY_THRESHOLD: f32 = 0.28;
-const TELEPORT_UP_JUMP_THRESHOLD: i32 = 14;
 
 /// Updates the [`Player::UpJumping`] contextual state
 ///
@@ -40,13 +38,15 @@ pub fn update_up_jumping_context(
     let (y_distance, y_direction) = moving.y_distance_direction_from(true, cur_pos);
     let up_jump_key = state.config.upjump_key;
     let has_teleport_key = state.config.teleport_key.is_some();
+    let movement = state.config.movement;
+    let move_timeout = movement.move_timeout;
 
     if !moving.timeout.started {
         if state.velocity.0 > X_NEAR_STATIONARY_THRESHOLD {
             return Player::UpJumping(moving);
         }
-        if let Minimap::Idle(idle) = context.minimap {
-            for portal in idle.portals {
+        if matches!(context.minimap, Minimap::Idle(_)) {
+            for portal in context.portals.iter() {
                 if portal.x <= cur_pos.x
                     && cur_pos.x < portal.x + portal.width
                     && portal.y >= cur_pos.y
@@ -65,7 +65,7 @@ pub fn update_up_jumping_context(
     update_moving_axis_context(
         moving,
         cur_pos,
-        TIMEOUT,
+        move_timeout + 3,
         |moving| {
             // Only send Up key when the key is not of a Demon Slayer
             if !matches!(up_jump_key, Some(KeyKind::Up)) {
@@ -75,8 +75,13 @@ pub fn update_up_jumping_context(
                 // This is a generic class, a mage or a Demon Slayer
                 (None, _) | (Some(_), true) | (Some(KeyKind::Up), false) => {
                     // This if is for mage. It means if the player is a mage and the y distance
-                    // is less than `TELEPORT_UP_JUMP_THRESHOLD`, do not send jump key.
-                    if !can_mage_skip_jump_key(up_jump_key, has_teleport_key, y_distance) {
+                    // is less than `teleport_up_jump_threshold`, do not send jump key.
+                    if !can_mage_skip_jump_key(
+                        up_jump_key,
+                        has_teleport_key,
+                        y_distance,
+                        movement.teleport_up_jump_threshold,
+                    ) {
                         let _ = context.keys.send(jump_key);
                     }
                 }
@@ -108,9 +113,9 @@ pub fn update_up_jumping_context(
                 }
                 (false, Some(key), _) => {
                     // If the player is a mage and y distance is less
-                    // than `TELEPORT_UP_JUMP_THRESHOLD`, send the teleport key immediately.
+                    // than `teleport_up_jump_threshold`, send the teleport key immediately.
                     if !has_teleport_key
-                        || (y_distance <= TELEPORT_UP_JUMP_THRESHOLD
+                        || (y_distance <= movement.teleport_up_jump_threshold
                             || moving.timeout.total >= SPAM_DELAY)
                     {
                         let _ = context.keys.send(key);
@@ -157,11 +162,15 @@ pub fn update_up_jumping_context(
                         bound, direction, ..
                     }) => {
                         if moving.completed
-                            && moving.timeout.total % MOVE_TIMEOUT == 0 // Interval roll dice
+                            && moving.timeout.total % move_timeout == 0 // Interval roll dice
                             && rand::random_bool(0.7)
                         {
                             Some(on_ping_pong_double_jump_action(
-                                context, cur_pos, bound, direction,
+                                context,
+                                cur_pos,
+                                bound,
+                                direction,
+                                movement.double_jump_threshold,
                             ))
                         } else {
                             None
@@ -172,7 +181,9 @@ pub fn update_up_jumping_context(
                         ..
                     })
                     | PlayerAction::Move(_)
-                    | PlayerAction::SolveRune => None,
+                    | PlayerAction::Sequence(_)
+                    | PlayerAction::SolveRune
+                    | PlayerAction::ReadMesos => None,
                 },
                 || Player::UpJumping(moving),
             )
@@ -186,10 +197,11 @@ fn can_mage_skip_jump_key(
     up_jump_key: Option<KeyKind>,
     has_teleport_key: bool,
     y_distance: i32,
+    teleport_up_jump_threshold: i32,
 ) -> bool {
     // It means if the player is a mage and the y distance
-    // is less than `TELEPORT_UP_JUMP_THRESHOLD`, do not send jump key or wait for stationary.
-    up_jump_key.is_some() && has_teleport_key && y_distance <= TELEPORT_UP_JUMP_THRESHOLD
+    // is less than `teleport_up_jump_threshold`, do not send jump key or wait for stationary.
+    up_jump_key.is_some() && has_teleport_key && y_distance <= teleport_up_jump_threshold
 }
 
 #[cfg(test)]
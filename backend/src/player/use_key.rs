@@ -11,11 +11,9 @@ use super::{
     double_jump::DoubleJumping,
 };
 use crate::{
-    ActionKeyDirection, ActionKeyWith, Class, KeyBinding, LinkKeyBinding,
+    ActionKeyDirection, ActionKeyWith, KeyBinding, LinkKeyBinding, MovementProfile,
     context::Context,
-    player::{
-        LastMovement, MOVE_TIMEOUT, Moving, Player, on_action_state_mut, update_with_timeout,
-    },
+    player::{LastMovement, Moving, Player, on_action_state_mut, update_with_timeout},
 };
 
 /// The total number of ticks for changing direction before timing out
@@ -42,6 +40,12 @@ pub enum UseKeyStage {
     /// Uses the actual key with optional [`LinkKeyBinding`] and stalls
     /// for [`UseKey::wait_after_use_ticks`]
     Using(Timeout, bool),
+    /// Keeps the key down for [`UseKey::hold_ticks`] then releases it and stalls
+    /// for [`UseKey::wait_after_use_ticks`]
+    ///
+    /// Pressed again every [`UseKey::hold_repeat_ticks`] if provided. The key is always released
+    /// by [`Player`] when this stage is interrupted.
+    Holding(Timeout),
     /// Ensures all [`UseKey::count`] times executed
     Postcondition,
 }
@@ -56,10 +60,18 @@ pub struct UseKey {
     with: ActionKeyWith,
     wait_before_use_ticks: u32,
     wait_after_use_ticks: u32,
+    hold_ticks: u32,
+    hold_repeat_ticks: Option<u32>,
     stage: UseKeyStage,
 }
 
 impl UseKey {
+    /// Whether the key is being held down in [`UseKeyStage::Holding`]
+    #[inline]
+    pub fn is_holding(&self) -> bool {
+        matches!(self.stage, UseKeyStage::Holding(_))
+    }
+
     #[inline]
     pub fn from_action(action: PlayerAction) -> Self {
         UseKey::from_action_pos(action, None)
@@ -77,6 +89,9 @@ impl UseKey {
                 wait_before_use_ticks_random_range,
                 wait_after_use_ticks,
                 wait_after_use_ticks_random_range,
+                hold_ticks,
+                hold_ticks_random_range,
+                hold_repeat_ticks,
                 ..
             }) => {
                 let wait_before_min =
@@ -91,6 +106,14 @@ impl UseKey {
                     wait_after_use_ticks.saturating_add(wait_after_use_ticks_random_range + 1);
                 let wait_after = rand::random_range(wait_after_min..wait_after_max);
 
+                let hold_min = hold_ticks.saturating_sub(hold_ticks_random_range);
+                let hold_max = hold_ticks.saturating_add(hold_ticks_random_range + 1);
+                let hold = if hold_ticks > 0 {
+                    rand::random_range(hold_min..hold_max).max(1)
+                } else {
+                    0
+                };
+
                 Self {
                     key,
                     link_key,
@@ -100,6 +123,8 @@ impl UseKey {
                     with,
                     wait_before_use_ticks: wait_before,
                     wait_after_use_ticks: wait_after,
+                    hold_ticks: hold,
+                    hold_repeat_ticks,
                     stage: UseKeyStage::Precondition,
                 }
             }
@@ -119,6 +144,8 @@ impl UseKey {
                 with: ActionKeyWith::Any,
                 wait_before_use_ticks: mob.wait_before_ticks,
                 wait_after_use_ticks: mob.wait_after_ticks,
+                hold_ticks: 0,
+                hold_repeat_ticks: None,
                 stage: UseKeyStage::Precondition,
             },
             PlayerAction::PingPong(ping_pong) => Self {
@@ -134,11 +161,14 @@ impl UseKey {
                 with: ActionKeyWith::Any,
                 wait_before_use_ticks: ping_pong.wait_before_ticks,
                 wait_after_use_ticks: ping_pong.wait_after_ticks,
+                hold_ticks: 0,
+                hold_repeat_ticks: None,
                 stage: UseKeyStage::Precondition,
             },
-            PlayerAction::SolveRune | PlayerAction::Move { .. } => {
-                unreachable!()
-            }
+            PlayerAction::SolveRune
+            | PlayerAction::ReadMesos
+            | PlayerAction::Move { .. }
+            | PlayerAction::Sequence(_) => unreachable!(),
         }
     }
 }
@@ -255,7 +285,7 @@ pub fn update_use_key_context(
                     if !completed {
                         return update_link_key(
                             context,
-                            state.config.class,
+                            state.config.movement,
                             state.config.jump_key,
                             use_key,
                             timeout,
@@ -265,13 +295,16 @@ pub fn update_use_key_context(
                 }
                 Some(LinkKeyBinding::AtTheSame(key)) => {
                     let _ = context.keys.send(key.into());
+                    if use_key.hold_ticks > 0 {
+                        return start_holding(context, state, use_key);
+                    }
                     let _ = context.keys.send(use_key.key.into());
                 }
                 Some(LinkKeyBinding::Along(_)) => {
                     if !completed {
                         return update_link_key(
                             context,
-                            state.config.class,
+                            state.config.movement,
                             state.config.jump_key,
                             use_key,
                             timeout,
@@ -283,7 +316,7 @@ pub fn update_use_key_context(
                     if use_key.link_key.is_some() && !completed {
                         return update_link_key(
                             context,
-                            state.config.class,
+                            state.config.movement,
                             state.config.jump_key,
                             use_key,
                             timeout,
@@ -291,19 +324,40 @@ pub fn update_use_key_context(
                         );
                     }
                     debug_assert!(use_key.link_key.is_none() || completed);
+                    if use_key.hold_ticks > 0 {
+                        return start_holding(context, state, use_key);
+                    }
                     let _ = context.keys.send(use_key.key.into());
                 }
             }
-            let next = Player::UseKey(UseKey {
-                stage: UseKeyStage::Postcondition,
-                ..use_key
-            });
-            if use_key.wait_after_use_ticks > 0 {
-                state.stalling_timeout_state = Some(next);
-                Player::Stalling(Timeout::default(), use_key.wait_after_use_ticks)
-            } else {
-                next
+            update_after_use(state, use_key)
+        }
+        UseKeyStage::Holding(timeout) => {
+            debug_assert!(state.stalling_timeout_state.is_none());
+            let key = use_key.key.into();
+            let holding = update_with_timeout(
+                timeout,
+                use_key.hold_ticks,
+                Some,
+                || None,
+                |timeout| {
+                    if let Some(repeat_ticks) = use_key.hold_repeat_ticks
+                        && timeout.total % repeat_ticks == 0
+                    {
+                        let _ = context.keys.send_down(key);
+                    }
+                    Some(timeout)
+                },
+            );
+            if let Some(timeout) = holding {
+                return Player::UseKey(UseKey {
+                    stage: UseKeyStage::Holding(timeout),
+                    ..use_key
+                });
             }
+            let _ = context.keys.send_up(key);
+            state.held_keys.retain(|held| *held != key);
+            update_after_use(state, use_key)
         }
         UseKeyStage::Postcondition => {
             debug_assert!(state.stalling_timeout_state.is_none());
@@ -328,7 +382,8 @@ pub fn update_use_key_context(
                     state.auto_mob_populate_pathing_points(context);
                     state.auto_mob_track_ignore_xs(context, false);
                     if state.auto_mob_reachable_y_require_update() {
-                        return Some((Player::Stalling(Timeout::default(), MOVE_TIMEOUT), false));
+                        let timeout = state.config.movement.move_timeout;
+                        return Some((Player::Stalling(Timeout::default(), timeout), false));
                     }
                 }
                 Some((next, is_terminal))
@@ -343,18 +398,51 @@ pub fn update_use_key_context(
                         state.last_known_pos.unwrap(),
                         bound,
                         direction,
+                        state.config.movement.double_jump_threshold,
                     ))
                 } else {
                     None
                 }
             }
             PlayerAction::Key(_) => Some((next, matches!(next, Player::Idle))),
-            PlayerAction::Move(_) | PlayerAction::SolveRune => None,
+            PlayerAction::Move(_)
+            | PlayerAction::Sequence(_)
+            | PlayerAction::SolveRune
+            | PlayerAction::ReadMesos => None,
         },
         || next,
     )
 }
 
+/// Presses down the key and transitions to [`UseKeyStage::Holding`]
+#[inline]
+fn start_holding(context: &Context, state: &mut PlayerState, use_key: UseKey) -> Player {
+    debug_assert!(use_key.hold_ticks > 0);
+    let key = use_key.key.into();
+    let _ = context.keys.send_down(key);
+    state.held_keys.push(key);
+    Player::UseKey(UseKey {
+        stage: UseKeyStage::Holding(Timeout::default()),
+        ..use_key
+    })
+}
+
+/// Transitions to [`UseKeyStage::Postcondition`] after stalling for
+/// [`UseKey::wait_after_use_ticks`]
+#[inline]
+fn update_after_use(state: &mut PlayerState, use_key: UseKey) -> Player {
+    let next = Player::UseKey(UseKey {
+        stage: UseKeyStage::Postcondition,
+        ..use_key
+    });
+    if use_key.wait_after_use_ticks > 0 {
+        state.stalling_timeout_state = Some(next);
+        Player::Stalling(Timeout::default(), use_key.wait_after_use_ticks)
+    } else {
+        next
+    }
+}
+
 #[inline]
 fn ensure_direction(state: &PlayerState, direction: ActionKeyDirection) -> bool {
     match direction {
@@ -379,7 +467,7 @@ fn ensure_use_with(state: &PlayerState, use_key: UseKey) -> bool {
 #[inline]
 fn update_link_key(
     context: &Context,
-    class: Class,
+    movement: MovementProfile,
     jump_key: KeyKind,
     use_key: UseKey,
     timeout: Timeout,
@@ -390,12 +478,7 @@ fn update_link_key(
     let link_key_timeout = if matches!(link_key, LinkKeyBinding::Along(_)) {
         4
     } else {
-        match class {
-            Class::Cadena => 4,
-            Class::Blaster => 8,
-            Class::Ark => 10,
-            Class::Generic => 5,
-        }
+        movement.link_key_timeout
     };
     update_with_timeout(
         timeout,
@@ -414,7 +497,7 @@ fn update_link_key(
         || {
             if let LinkKeyBinding::After(key) = link_key {
                 let _ = context.keys.send(key.into());
-                if matches!(class, Class::Blaster) && KeyKind::from(key) != jump_key {
+                if movement.jump_after_link_key && KeyKind::from(key) != jump_key {
                     let _ = context.keys.send(jump_key);
                 }
             } else if let LinkKeyBinding::Along(key) = link_key {
@@ -468,6 +551,8 @@ mod tests {
             with: ActionKeyWith::Stationary,
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
+            hold_ticks: 0,
+            hold_repeat_ticks: None,
             stage: UseKeyStage::Precondition,
         };
 
@@ -514,6 +599,8 @@ mod tests {
             with: ActionKeyWith::Any,
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
+            hold_ticks: 0,
+            hold_repeat_ticks: None,
             stage: UseKeyStage::Precondition,
         };
 
@@ -578,6 +665,8 @@ mod tests {
             with: ActionKeyWith::Any,
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
+            hold_ticks: 0,
+            hold_repeat_ticks: None,
             stage: UseKeyStage::Precondition,
         };
 
@@ -631,6 +720,8 @@ mod tests {
             with: ActionKeyWith::Any,
             wait_before_use_ticks: 10,
             wait_after_use_ticks: 20,
+            hold_ticks: 0,
+            hold_repeat_ticks: None,
             stage: UseKeyStage::Precondition,
         };
 
@@ -678,6 +769,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn use_key_holding() {
+        let mut state = PlayerState::default();
+        let mut context = Context::new(None, None);
+        let use_key = UseKey {
+            key: KeyBinding::A,
+            link_key: None,
+            count: 1,
+            current_count: 0,
+            direction: ActionKeyDirection::Any,
+            with: ActionKeyWith::Any,
+            wait_before_use_ticks: 0,
+            wait_after_use_ticks: 0,
+            hold_ticks: 4,
+            hold_repeat_ticks: Some(2),
+            stage: UseKeyStage::Using(Timeout::default(), false),
+        };
+
+        // Starts by holding down A
+        let mut keys = MockKeySender::new();
+        keys.expect_send_down()
+            .withf(|key| matches!(key, KeyKind::A))
+            .once()
+            .return_once(|_| Ok(()));
+        context.keys = Box::new(keys);
+        assert_matches!(
+            update_use_key_context(&context, &mut state, use_key),
+            Player::UseKey(UseKey {
+                stage: UseKeyStage::Holding(Timeout { started: false, .. }),
+                ..
+            })
+        );
+        assert_eq!(state.held_keys, vec![KeyKind::A]);
+        let _ = context.keys; // test check point by dropping
+
+        // Presses A again at the repeat interval
+        let mut keys = MockKeySender::new();
+        keys.expect_send_down()
+            .withf(|key| matches!(key, KeyKind::A))
+            .once()
+            .return_once(|_| Ok(()));
+        context.keys = Box::new(keys);
+        let player = update_use_key_context(
+            &context,
+            &mut state,
+            UseKey {
+                stage: UseKeyStage::Holding(Timeout {
+                    started: true,
+                    current: 1,
+                    total: 1,
+                }),
+                ..use_key
+            },
+        );
+        assert_matches!(
+            player,
+            Player::UseKey(UseKey {
+                stage: UseKeyStage::Holding(Timeout { total: 2, .. }),
+                ..
+            })
+        );
+        let _ = context.keys; // test check point by dropping
+
+        // Ends by releasing A
+        let mut keys = MockKeySender::new();
+        keys.expect_send_up()
+            .withf(|key| matches!(key, KeyKind::A))
+            .once()
+            .return_once(|_| Ok(()));
+        context.keys = Box::new(keys);
+        let player = update_use_key_context(
+            &context,
+            &mut state,
+            UseKey {
+                stage: UseKeyStage::Holding(Timeout {
+                    started: true,
+                    current: 4,
+                    total: 4,
+                }),
+                ..use_key
+            },
+        );
+        assert_matches!(
+            player,
+            Player::UseKey(UseKey {
+                stage: UseKeyStage::Postcondition,
+                ..
+            })
+        );
+        assert!(state.held_keys.is_empty());
+    }
+
     #[test]
     fn use_key_link_along() {
         let mut state = PlayerState::default();
@@ -691,6 +874,8 @@ mod tests {
             with: ActionKeyWith::Any,
             wait_before_use_ticks: 0,
             wait_after_use_ticks: 0,
+            hold_ticks: 0,
+            hold_repeat_ticks: None,
             stage: UseKeyStage::Using(Timeout::default(), false),
         };
 
@@ -0,0 +1,163 @@
+use log::debug;
+use platforms::windows::KeyKind;
+
+use super::{
+    Player, PlayerState,
+    moving::{Moving, refind_intermediate_points},
+    timeout::update_with_timeout,
+};
+use crate::context::Context;
+
+/// Updates the [`Player::UsingPortal`] contextual state.
+///
+/// This state can only be transitioned via [`Player::Moving`] when the player has reached the
+/// portal and the intermediate point has `MovementHint::UsePortal` hint. The destination of
+/// `moving` is the portal destination.
+///
+/// This state presses the up key to enter the portal and the interact key if the player has not
+/// been teleported after a while. If the player is still not teleported on timeout, the portal is
+/// added to [`PlayerState::failed_portals`] and a new path without it is found to the last
+/// destination. The player moves to the last destination directly if there is no such path.
+///
+/// The player is considered not teleported after eight move timeouts and the interact key is
+/// pressed after four.
+pub fn update_using_portal_context(
+    context: &Context,
+    state: &mut PlayerState,
+    moving: Moving,
+) -> Player {
+    let cur_pos = state.last_known_pos.unwrap();
+    let (x_distance, _) = moving.x_distance_direction_from(true, cur_pos);
+    let (y_distance, _) = moving.y_distance_direction_from(true, cur_pos);
+    let interact_key = state.config.interact_key;
+    let movement = state.config.movement;
+
+    update_with_timeout(
+        moving.timeout,
+        movement.move_timeout * 8,
+        |timeout| {
+            let _ = context.keys.send(KeyKind::Up);
+            Player::UsingPortal(moving.timeout(timeout))
+        },
+        || {
+            let Some(intermediates) = moving.intermediates else {
+                return Player::Moving(moving.dest, moving.exact, None);
+            };
+            if let Some(portal) = intermediates.previous(state)
+                && !state.failed_portals.contains(&portal)
+            {
+                debug!(target: "player", "portal at {portal:?} did not teleport, excluding it");
+                state.failed_portals.push(portal);
+            }
+            let Some(mut intermediates) =
+                refind_intermediate_points(context, state, cur_pos, intermediates)
+            else {
+                let (dest, exact) = intermediates.last();
+                debug!(target: "player", "no other path found, moving to last destination");
+                return Player::Moving(dest, exact, None);
+            };
+            state.last_destinations = Some(
+                state
+                    .intermediate_points
+                    .iter()
+                    .map(|(point, _, _)| *point)
+                    .collect(),
+            );
+            let (point, exact) = intermediates.next(state).unwrap();
+            Player::Moving(point, exact, Some(intermediates))
+        },
+        |timeout| {
+            if x_distance < movement.double_jump_threshold && y_distance < movement.jump_threshold {
+                return Player::Moving(moving.dest, moving.exact, moving.intermediates);
+            }
+            if timeout.current == movement.move_timeout * 4 {
+                let _ = context.keys.send(interact_key);
+            }
+            Player::UsingPortal(moving.timeout(timeout))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use opencv::core::Point;
+
+    use super::update_using_portal_context;
+    use crate::{
+        MovementProfile,
+        context::Context,
+        pathing::{MovementHint, Platform, Portal, find_neighbors},
+        player::{
+            Player, PlayerState, Timeout,
+            moving::{Moving, find_intermediate_points},
+        },
+    };
+
+    /// Makes a context with two platforms only connected by two portals
+    fn make_context_with_portals() -> Context {
+        let platforms = [Platform::new(0..50, 10), Platform::new(150..200, 100)];
+        let portals = [Portal::new(40, 10, 160, 100), Portal::new(20, 10, 180, 100)];
+        Context {
+            platforms: find_neighbors(&platforms, &[], &portals, MovementProfile::default()),
+            ..Context::new(None, None)
+        }
+    }
+
+    /// Makes a timed out [`Moving`] for the portal the player is currently standing at
+    fn make_timed_out_moving(state: &mut PlayerState, context: &Context) -> (Moving, Point) {
+        let mut intermediates = find_intermediate_points(
+            context,
+            state,
+            state.last_known_pos.unwrap(),
+            Point::new(190, 100),
+            true,
+            false,
+            true,
+        )
+        .unwrap();
+        let (portal, _) = intermediates.next(state).unwrap();
+        let (dest, exact) = intermediates.next(state).unwrap();
+        let timeout = Timeout {
+            current: state.config.movement.move_timeout * 8,
+            total: state.config.movement.move_timeout * 8,
+            started: true,
+        };
+        state.last_known_pos = Some(portal);
+        let moving = Moving::new(portal, dest, exact, Some(intermediates)).timeout(timeout);
+        (moving, portal)
+    }
+
+    #[test]
+    fn using_portal_timeout_excludes_portal_and_finds_other_path() {
+        let context = make_context_with_portals();
+        let mut state = PlayerState::default();
+        state.last_known_pos = Some(Point::new(10, 10));
+        let (moving, portal) = make_timed_out_moving(&mut state, &context);
+
+        let player = update_using_portal_context(&context, &mut state, moving);
+
+        assert_eq!(state.failed_portals, vec![portal]);
+        assert_matches!(
+            player,
+            Player::Moving(point, true, Some(_)) if point != portal && point.y == 10
+        );
+        assert_matches!(state.intermediate_points[0].1, MovementHint::UsePortal);
+    }
+
+    #[test]
+    fn using_portal_timeout_moves_directly_without_other_path() {
+        let context = make_context_with_portals();
+        let mut state = PlayerState::default();
+        state.last_known_pos = Some(Point::new(10, 10));
+        let (moving, first_portal) = make_timed_out_moving(&mut state, &context);
+        let _ = update_using_portal_context(&context, &mut state, moving);
+        let (moving, second_portal) = make_timed_out_moving(&mut state, &context);
+
+        let player = update_using_portal_context(&context, &mut state, moving);
+
+        assert_eq!(state.failed_portals, vec![first_portal, second_portal]);
+        assert_matches!(player, Player::Moving(point, true, None) if point == Point::new(190, 100));
+    }
+}
@@ -0,0 +1,275 @@
+use platforms::windows::KeyKind;
+
+use super::{
+    Player, PlayerState, Timeout,
+    actions::{PlayerAction, PlayerActionSequence, PlayerActionSequenceStep},
+    on_action_state_mut, update_with_timeout,
+};
+use crate::{
+    ActionKeyDirection, ActionSequenceStepKind, MAX_SEQUENCE_STEPS_COUNT, array::Array,
+    context::Context,
+};
+
+/// The different stages of using a key sequence
+#[derive(Clone, Copy, Debug)]
+pub enum UseSequenceStage {
+    /// Presses the key of the current step
+    Pressing,
+    /// Waits for the delay of the current step before moving to the next step
+    Delaying(Timeout, u32),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct UseSequence {
+    steps: Array<PlayerActionSequenceStep, MAX_SEQUENCE_STEPS_COUNT>,
+    index: usize,
+    stage: UseSequenceStage,
+}
+
+impl UseSequence {
+    pub fn from_action(action: PlayerAction) -> Self {
+        match action {
+            PlayerAction::Sequence(PlayerActionSequence { steps, .. }) => Self {
+                steps,
+                index: 0,
+                stage: UseSequenceStage::Pressing,
+            },
+            PlayerAction::Key(_)
+            | PlayerAction::Move(_)
+            | PlayerAction::SolveRune
+            | PlayerAction::ReadMesos
+            | PlayerAction::AutoMob(_)
+            | PlayerAction::PingPong(_) => unreachable!(),
+        }
+    }
+}
+
+/// Updates the [`Player::UseSequence`] contextual state
+///
+/// This state executes all the steps of [`PlayerAction::Sequence`] as a single unit and cannot
+/// be overridden by other actions. Any key left held down is released upon completion.
+pub fn update_use_sequence_context(
+    context: &Context,
+    state: &mut PlayerState,
+    use_sequence: UseSequence,
+) -> Player {
+    let next = if use_sequence.index >= use_sequence.steps.len() {
+        for key in state.held_keys.drain(..) {
+            let _ = context.keys.send_up(key);
+        }
+        Player::Idle
+    } else {
+        let step = use_sequence.steps[use_sequence.index];
+        match use_sequence.stage {
+            UseSequenceStage::Pressing => {
+                press_step(context, state, step);
+                let delay_min = step
+                    .delay_ticks
+                    .saturating_sub(step.delay_ticks_random_range);
+                let delay_max = step
+                    .delay_ticks
+                    .saturating_add(step.delay_ticks_random_range + 1);
+                let delay = rand::random_range(delay_min..delay_max);
+                if delay > 0 {
+                    Player::UseSequence(UseSequence {
+                        stage: UseSequenceStage::Delaying(Timeout::default(), delay),
+                        ..use_sequence
+                    })
+                } else {
+                    state.use_immediate_control_flow = true;
+                    Player::UseSequence(UseSequence {
+                        index: use_sequence.index + 1,
+                        ..use_sequence
+                    })
+                }
+            }
+            UseSequenceStage::Delaying(timeout, delay) => update_with_timeout(
+                timeout,
+                delay,
+                |timeout| {
+                    Player::UseSequence(UseSequence {
+                        stage: UseSequenceStage::Delaying(timeout, delay),
+                        ..use_sequence
+                    })
+                },
+                || {
+                    Player::UseSequence(UseSequence {
+                        index: use_sequence.index + 1,
+                        stage: UseSequenceStage::Pressing,
+                        ..use_sequence
+                    })
+                },
+                |timeout| {
+                    Player::UseSequence(UseSequence {
+                        stage: UseSequenceStage::Delaying(timeout, delay),
+                        ..use_sequence
+                    })
+                },
+            ),
+        }
+    };
+
+    on_action_state_mut(
+        state,
+        |_, action| match action {
+            PlayerAction::Sequence(_) => Some((next, matches!(next, Player::Idle))),
+            PlayerAction::Key(_)
+            | PlayerAction::Move(_)
+            | PlayerAction::SolveRune
+            | PlayerAction::ReadMesos
+            | PlayerAction::AutoMob(_)
+            | PlayerAction::PingPong(_) => None,
+        },
+        || next,
+    )
+}
+
+#[inline]
+fn press_step(context: &Context, state: &mut PlayerState, step: PlayerActionSequenceStep) {
+    match step.direction {
+        ActionKeyDirection::Left => {
+            let _ = context.keys.send(KeyKind::Left);
+            state.last_known_direction = ActionKeyDirection::Left;
+        }
+        ActionKeyDirection::Right => {
+            let _ = context.keys.send(KeyKind::Right);
+            state.last_known_direction = ActionKeyDirection::Right;
+        }
+        ActionKeyDirection::Any => (),
+    }
+
+    let key = step.key.into();
+    match step.kind {
+        ActionSequenceStepKind::Tap => {
+            let _ = context.keys.send(key);
+        }
+        ActionSequenceStepKind::Down => {
+            let _ = context.keys.send_down(key);
+            if !state.held_keys.contains(&key) {
+                state.held_keys.push(key);
+            }
+        }
+        ActionSequenceStepKind::Up => {
+            let _ = context.keys.send_up(key);
+            state.held_keys.retain(|held| *held != key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches::assert_matches;
+
+    use platforms::windows::KeyKind;
+
+    use super::{UseSequence, UseSequenceStage, update_use_sequence_context};
+    use crate::{
+        ActionKeyDirection, ActionSequenceStepKind, KeyBinding,
+        bridge::MockKeySender,
+        context::Context,
+        player::{Player, PlayerState, Timeout, actions::PlayerActionSequenceStep},
+    };
+
+    fn step(
+        key: KeyBinding,
+        kind: ActionSequenceStepKind,
+        delay_ticks: u32,
+    ) -> PlayerActionSequenceStep {
+        PlayerActionSequenceStep {
+            key,
+            kind,
+            direction: ActionKeyDirection::Any,
+            delay_ticks,
+            delay_ticks_random_range: 0,
+        }
+    }
+
+    #[test]
+    fn use_sequence_steps_in_order() {
+        let mut state = PlayerState::default();
+        let mut context = Context::new(None, None);
+        let use_sequence = UseSequence {
+            steps: [
+                step(KeyBinding::A, ActionSequenceStepKind::Down, 2),
+                step(KeyBinding::B, ActionSequenceStepKind::Tap, 0),
+            ]
+            .into_iter()
+            .collect(),
+            index: 0,
+            stage: UseSequenceStage::Pressing,
+        };
+
+        // Holds down A then delays
+        let mut keys = MockKeySender::new();
+        keys.expect_send_down()
+            .withf(|key| matches!(key, KeyKind::A))
+            .once()
+            .return_once(|_| Ok(()));
+        context.keys = Box::new(keys);
+        let player = update_use_sequence_context(&context, &mut state, use_sequence);
+        assert_matches!(
+            player,
+            Player::UseSequence(UseSequence {
+                index: 0,
+                stage: UseSequenceStage::Delaying(_, 2),
+                ..
+            })
+        );
+        assert_eq!(state.held_keys, vec![KeyKind::A]);
+
+        // Moves to the next step after the delay
+        let player = update_use_sequence_context(
+            &context,
+            &mut state,
+            UseSequence {
+                stage: UseSequenceStage::Delaying(
+                    Timeout {
+                        started: true,
+                        current: 2,
+                        total: 2,
+                    },
+                    2,
+                ),
+                ..use_sequence
+            },
+        );
+        assert_matches!(
+            player,
+            Player::UseSequence(UseSequence {
+                index: 1,
+                stage: UseSequenceStage::Pressing,
+                ..
+            })
+        );
+
+        // Taps B without delay
+        let mut keys = MockKeySender::new();
+        keys.expect_send()
+            .withf(|key| matches!(key, KeyKind::B))
+            .once()
+            .return_once(|_| Ok(()));
+        context.keys = Box::new(keys);
+        let Player::UseSequence(use_sequence) = player else {
+            unreachable!()
+        };
+        let player = update_use_sequence_context(&context, &mut state, use_sequence);
+        assert_matches!(player, Player::UseSequence(UseSequence { index: 2, .. }));
+        assert!(state.use_immediate_control_flow);
+
+        // Releases the still held A on completion
+        let mut keys = MockKeySender::new();
+        keys.expect_send_up()
+            .withf(|key| matches!(key, KeyKind::A))
+            .once()
+            .return_once(|_| Ok(()));
+        context.keys = Box::new(keys);
+        let Player::UseSequence(use_sequence) = player else {
+            unreachable!()
+        };
+        assert_matches!(
+            update_use_sequence_context(&context, &mut state, use_sequence),
+            Player::Idle
+        );
+        assert!(state.held_keys.is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::Instant;
+
+use crate::{clock::Clock, database::PlaylistLimit};
+
+/// Tracks the currently running entry of [`crate::Minimap::playlist`]
+#[derive(Debug, Default)]
+pub struct PlaylistState {
+    index: usize,
+    /// The number of milliseconds the current entry has been running while not halting
+    elapsed_millis: u128,
+    /// The [`Instant`] of the last [`Self::update`]
+    ///
+    /// Cleared on [`Self::pause`] so that halting time is not counted.
+    last_update: Option<Instant>,
+    /// The total normal action cycles when the current entry started
+    start_cycles: Option<u32>,
+}
+
+impl PlaylistState {
+    /// The index of the current entry
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Resets back to the first entry
+    #[inline]
+    pub fn reset(&mut self) {
+        *self = PlaylistState::default();
+    }
+
+    /// Stops counting the elapsed time until the next [`Self::update`]
+    #[inline]
+    pub fn pause(&mut self) {
+        self.last_update = None;
+    }
+
+    /// Updates the elapsed time and returns whether `limit` of the current entry is reached
+    ///
+    /// `cycles` is the total number of normal action cycles completed. The cycles of the current
+    /// entry are counted from the first update after it started.
+    pub fn update(&mut self, clock: &dyn Clock, limit: PlaylistLimit, cycles: u32) -> bool {
+        let now = clock.now();
+        if let Some(last_update) = self.last_update {
+            self.elapsed_millis += now.saturating_duration_since(last_update).as_millis();
+        }
+        self.last_update = Some(now);
+        let start_cycles = *self.start_cycles.get_or_insert(cycles);
+        match limit.non_zero() {
+            PlaylistLimit::Millis(millis) => self.elapsed_millis >= millis as u128,
+            PlaylistLimit::Cycles(count) => cycles.saturating_sub(start_cycles) >= count,
+        }
+    }
+
+    /// Moves to the next entry, wrapping back to the first entry after the last
+    #[inline]
+    pub fn advance(&mut self, len: usize) {
+        debug_assert!(len > 0);
+        self.index = (self.index + 1) % len;
+        self.elapsed_millis = 0;
+        self.start_cycles = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::PlaylistState;
+    use crate::{clock::ManualClock, database::PlaylistLimit};
+
+    #[test]
+    fn playlist_update_millis_excludes_paused() {
+        let clock = ManualClock::default();
+        let mut state = PlaylistState::default();
+        let limit = PlaylistLimit::Millis(1000);
+
+        assert!(!state.update(&clock, limit, 0));
+        clock.advance(Duration::from_millis(600));
+        assert!(!state.update(&clock, limit, 0));
+
+        state.pause();
+        clock.advance(Duration::from_millis(5000));
+        assert!(!state.update(&clock, limit, 0));
+
+        clock.advance(Duration::from_millis(400));
+        assert!(state.update(&clock, limit, 0));
+    }
+
+    #[test]
+    fn playlist_update_cycles_and_advance() {
+        let clock = ManualClock::default();
+        let mut state = PlaylistState::default();
+        let limit = PlaylistLimit::Cycles(2);
+
+        assert!(!state.update(&clock, limit, 3));
+        assert!(!state.update(&clock, limit, 4));
+        assert!(state.update(&clock, limit, 5));
+
+        state.advance(2);
+        assert_eq!(state.index(), 1);
+        assert!(!state.update(&clock, limit, 5));
+        assert!(state.update(&clock, limit, 7));
+        state.advance(2);
+        assert_eq!(state.index(), 0);
+    }
+
+    #[test]
+    fn playlist_update_zero_limit_uses_non_zero() {
+        let clock = ManualClock::default();
+        let mut state = PlaylistState::default();
+
+        assert!(!state.update(&clock, PlaylistLimit::Cycles(0), 0));
+        assert!(state.update(&clock, PlaylistLimit::Cycles(0), 1));
+
+        state.advance(2);
+        assert!(!state.update(&clock, PlaylistLimit::Millis(0), 0));
+        clock.advance(Duration::from_millis(1000));
+        assert!(!state.update(&clock, PlaylistLimit::Millis(0), 0));
+    }
+}
@@ -0,0 +1,235 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use log::debug;
+
+use crate::{
+    clock::Clock,
+    context::Context,
+    database::SessionRecord,
+    task::{Task, Update, update_detection_task},
+};
+
+/// Milliseconds interval to detect the EXP percentage
+const EXP_UPDATE_MILLIS: u64 = 5000;
+
+/// The minimum drop in EXP percentage to be counted as a level up
+///
+/// A smaller drop is EXP lost from dying and is not counted.
+const LEVEL_UP_DROP_PERCENT: f32 = 50.0;
+
+/// Tracks the EXP and mesos progress of the current [`SessionRecord`]
+///
+/// A session starts on the first [`Self::update`] and lasts until [`Self::end`].
+#[derive(Debug, Default)]
+pub struct ProgressState {
+    /// The task for detecting the EXP percentage
+    task: Option<Task<Result<f32>>>,
+    /// The last detected EXP percentage
+    percent: Option<f32>,
+    /// The last read mesos amount
+    ///
+    /// Unlike the EXP percentage, it is kept across sessions because mesos are only read
+    /// periodically and do not change while halting.
+    mesos: Option<u64>,
+    session: Option<SessionRecord>,
+    /// The [`Instant`] of the last [`Self::update`]
+    last_update: Option<Instant>,
+    /// The session elapsed milliseconds at the last [`Self::should_report`]
+    last_report_millis: u64,
+}
+
+impl ProgressState {
+    /// The last detected EXP percentage
+    #[inline]
+    pub fn percent(&self) -> Option<f32> {
+        self.percent
+    }
+
+    /// The last read mesos amount
+    #[inline]
+    pub fn mesos(&self) -> Option<u64> {
+        self.mesos
+    }
+
+    /// The current session if any
+    #[inline]
+    pub fn session(&self) -> Option<&SessionRecord> {
+        self.session.as_ref()
+    }
+
+    /// The current session if any for saving to history
+    #[inline]
+    pub fn session_mut(&mut self) -> Option<&mut SessionRecord> {
+        self.session.as_mut()
+    }
+
+    /// Ends and returns the current session if any
+    ///
+    /// The next [`Self::update`] will start a new session.
+    pub fn end(&mut self) -> Option<SessionRecord> {
+        self.task = None;
+        self.percent = None;
+        self.last_update = None;
+        self.last_report_millis = 0;
+        self.session.take()
+    }
+
+    /// Updates the elapsed time of the current session or starts a new session on `map`
+    pub fn update(&mut self, clock: &dyn Clock, map: &str) {
+        let now = clock.now();
+        let session = self.session.get_or_insert_with(|| SessionRecord {
+            map: map.to_string(),
+            started_at_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            ..SessionRecord::default()
+        });
+        if let Some(last_update) = self.last_update {
+            session.elapsed_millis += now.saturating_duration_since(last_update).as_millis() as u64;
+        }
+        self.last_update = Some(now);
+    }
+
+    /// Detects the EXP percentage and records the progress to the current session
+    pub fn update_exp(&mut self, context: &Context) {
+        if let Update::Ok(percent) =
+            update_detection_task(context, EXP_UPDATE_MILLIS, &mut self.task, |detector| {
+                detector.detect_player_exp()
+            })
+        {
+            debug!(target: "progress", "EXP updated {percent}%");
+            self.record(percent);
+        }
+    }
+
+    /// Records the mesos gained since the last read to the current session
+    ///
+    /// A decrease is from spending mesos and is not counted.
+    pub fn update_mesos(&mut self, mesos: u64) {
+        debug!(target: "progress", "mesos updated {mesos}");
+        if let Some(session) = self.session.as_mut()
+            && let Some(previous) = self.mesos
+        {
+            session.mesos_gained += mesos.saturating_sub(previous);
+        }
+        self.mesos = Some(mesos);
+    }
+
+    /// Returns whether at least `millis` of the current session has passed since the last report
+    pub fn should_report(&mut self, millis: u64) -> bool {
+        let Some(session) = self.session.as_ref() else {
+            return false;
+        };
+        if session.elapsed_millis < self.last_report_millis + millis {
+            return false;
+        }
+        self.last_report_millis = session.elapsed_millis;
+        true
+    }
+
+    /// Formats the current session progress for reporting
+    pub fn report(&self) -> Option<String> {
+        let session = self.session.as_ref()?;
+        let current = self
+            .percent
+            .map(|percent| format!("{percent:.2}%"))
+            .unwrap_or("unknown".to_string());
+        let mut report = format!(
+            "EXP on {} is at {current}, gained {:.2}% and {} level(s) in {} minute(s), {:.2}%/h",
+            session.map,
+            session.exp_gained_percent,
+            session.levels_gained,
+            session.elapsed_millis / 60_000,
+            session.exp_per_hour_percent()
+        );
+        if let Some(mesos) = self.mesos {
+            report.push_str(&format!(
+                ", mesos at {mesos}, gained {} mesos, {} mesos/h",
+                session.mesos_gained,
+                session.mesos_per_hour()
+            ));
+        }
+        Some(report)
+    }
+
+    fn record(&mut self, percent: f32) {
+        if let Some(session) = self.session.as_mut()
+            && let Some(previous) = self.percent
+        {
+            if percent >= previous {
+                session.exp_gained_percent += percent - previous;
+            } else if previous - percent >= LEVEL_UP_DROP_PERCENT {
+                session.levels_gained += 1;
+                session.exp_gained_percent += 100.0 - previous + percent;
+            }
+        }
+        self.percent = Some(percent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::ProgressState;
+    use crate::clock::ManualClock;
+
+    #[test]
+    fn exp_record_level_up_and_death() {
+        let clock = ManualClock::default();
+        let mut state = ProgressState::default();
+        state.update(&clock, "map");
+
+        state.record(90.0);
+        state.record(95.0);
+        // Level up
+        state.record(5.0);
+        // Died
+        state.record(2.0);
+        state.record(4.0);
+
+        let session = state.session().unwrap();
+        assert_eq!(session.levels_gained, 1);
+        assert_eq!(session.exp_gained_percent, 17.0);
+    }
+
+    #[test]
+    fn progress_should_report_and_end() {
+        let clock = ManualClock::default();
+        let mut state = ProgressState::default();
+        state.update(&clock, "map");
+
+        clock.advance(Duration::from_millis(500));
+        state.update(&clock, "map");
+        assert!(!state.should_report(1000));
+
+        clock.advance(Duration::from_millis(500));
+        state.update(&clock, "map");
+        assert!(state.should_report(1000));
+        assert!(!state.should_report(1000));
+
+        assert_eq!(
+            state.report().unwrap(),
+            "EXP on map is at unknown, gained 0.00% and 0 level(s) in 0 minute(s), 0.00%/h"
+        );
+        state.update_mesos(1000);
+        state.update_mesos(1500);
+        // Spent
+        state.update_mesos(1200);
+        state.update_mesos(1300);
+        assert_eq!(
+            state.report().unwrap(),
+            "EXP on map is at unknown, gained 0.00% and 0 level(s) in 0 minute(s), 0.00%/h, \
+             mesos at 1300, gained 600 mesos, 2160000 mesos/h"
+        );
+
+        let session = state.end().unwrap();
+        assert_eq!(session.elapsed_millis, 1000);
+        assert_eq!(session.mesos_gained, 600);
+        assert_eq!(state.mesos(), Some(1300));
+        assert!(state.session().is_none());
+        assert!(!state.should_report(1000));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 #[cfg(debug_assertions)]
 use std::sync::LazyLock;
 #[cfg(debug_assertions)]
@@ -6,13 +7,16 @@ use std::time::Instant;
 #[cfg(debug_assertions)]
 use include_dir::{Dir, include_dir};
 use log::debug;
-use opencv::core::{MatTraitConst, MatTraitConstManual, Vec4b};
 #[cfg(debug_assertions)]
 use opencv::{
-    core::{Mat, ModifyInplace, Vector},
+    core::{Mat, ModifyInplace},
     imgcodecs::{IMREAD_COLOR, imdecode},
     imgproc::{COLOR_BGR2BGRA, cvt_color_def},
 };
+use opencv::{
+    core::{MatTraitConst, MatTraitConstManual, Rect, Vec4b, Vector, VectorToVec},
+    imgcodecs::imencode_def,
+};
 use platforms::windows::{Handle, KeyInputKind, KeyKind, KeyReceiver, query_capture_handles};
 #[cfg(debug_assertions)]
 use rand::distr::{Alphanumeric, SampleString};
@@ -30,26 +34,37 @@ use crate::{
     Action, ActionCondition, ActionKey, Bound, Configuration, GameState, KeyBinding,
     KeyBindingConfiguration, Minimap as MinimapData, PotionMode, RequestHandler, Settings,
     bridge::{ImageCapture, ImageCaptureKind, KeySenderMethod},
-    buff::{BuffKind, BuffState},
+    buff::{BuffId, BuffKind, BuffState},
     context::Context,
-    database::InputMethod,
-    minimap::{Minimap, MinimapState},
-    player::PlayerState,
+    database::{InputMethod, upsert_session},
+    detect::crop_to_buffs_region,
+    minimap::{Minimap, MinimapState, is_same_fingerprint, platforms_from_data},
+    player::{Player, PlayerState},
+    playlist::PlaylistState,
     poll_request,
-    rotator::Rotator,
-    skill::SkillKind,
+    progress::ProgressState,
+    rotator::{ActionStatistics, BuildActionsConfig, Rotator, RotatorMode},
+    simulator::SimulationInput,
+    skill::{SkillKind, SkillState},
 };
 
+/// The minimum milliseconds between changing channel due to a stranger
+const CHANGE_CHANNEL_STRANGER_COOLDOWN_MILLIS: u128 = 60000;
+
 pub struct DefaultRequestHandler<'a> {
     pub context: &'a mut Context,
     pub config: &'a mut Configuration,
     pub settings: &'a mut Settings,
-    pub buffs: &'a mut Vec<(BuffKind, KeyBinding)>,
+    pub buffs: &'a mut Vec<(BuffId, KeyBinding, Option<u64>)>,
     pub buff_states: &'a mut Vec<BuffState>,
+    pub custom_skill_states: &'a mut Vec<SkillState>,
+    pub custom_buff_states: &'a mut Vec<BuffState>,
     pub actions: &'a mut Vec<Action>,
     pub rotator: &'a mut Rotator,
     pub player: &'a mut PlayerState,
     pub minimap: &'a mut MinimapState,
+    pub playlist: &'a mut PlaylistState,
+    pub progress: &'a mut ProgressState,
     pub key_sender: &'a broadcast::Sender<KeyBinding>,
     pub key_receiver: &'a mut KeyReceiver,
     pub image_capture: &'a mut ImageCapture,
@@ -70,6 +85,137 @@ impl DefaultRequestHandler<'_> {
         poll_key(self);
     }
 
+    /// Switches to the next playlist entry when the current one is over
+    ///
+    /// Switching only happens at a safe point so that linked actions are not cut in half.
+    pub fn poll_playlist(&mut self) {
+        let Some(minimap) = self.minimap.data() else {
+            return;
+        };
+        if !minimap.playlist_enabled || minimap.playlist.is_empty() || self.context.halting {
+            self.playlist.pause();
+            return;
+        }
+        let limit = minimap.playlist[self.playlist.index()].limit;
+        if !self.playlist.update(
+            self.context.clock.as_ref(),
+            limit,
+            self.rotator.normal_cycles(),
+        ) || !self.rotator.is_at_safe_point(self.player)
+        {
+            return;
+        }
+
+        self.playlist.advance(minimap.playlist.len());
+        let preset = &minimap.playlist[self.playlist.index()].preset;
+        debug!(target: "playlist", "switching to preset {preset}");
+        *self.actions = minimap.actions.get(preset).cloned().unwrap_or_default();
+        self.update_rotator_actions();
+    }
+
+    /// Decides whether the rotation can resume after the player has revived
+    ///
+    /// Halts when the player respawned on a different map or has died more than
+    /// [`Settings::max_deaths_per_hour`]. Returns the reason to notify if halted.
+    pub fn poll_revive(&mut self) -> Option<String> {
+        let count = self.player.take_revived_count()?;
+        if self.context.halting {
+            return None;
+        }
+        let minimap = self.minimap.data()?;
+        let reason = if !is_same_minimap(self.context.minimap, minimap) {
+            format!(
+                "the player respawned on a different map than {}",
+                minimap.name
+            )
+        } else if let Some(max) = self.settings.max_deaths_per_hour
+            && count > max as usize
+        {
+            format!("the player died {count} time(s) in the last hour, more than {max}")
+        } else {
+            debug!(target: "player", "revived on the same map, resuming actions");
+            return None;
+        };
+        self.on_rotate_actions(true);
+        Some(reason)
+    }
+
+    /// Requests changing channel on schedule or when a stranger appears and verifies the map
+    /// after changing
+    ///
+    /// Halts when the minimap is not the same map after changing channel. Returns the reason to
+    /// notify if halted.
+    pub fn poll_change_channel(&mut self) -> Option<String> {
+        let changed = self.player.take_channel_changed();
+        if self.context.halting {
+            return None;
+        }
+        let minimap = self.minimap.data()?;
+        if changed {
+            if is_same_minimap(self.context.minimap, minimap) {
+                debug!(target: "player", "changed channel on the same map, resuming actions");
+                return None;
+            }
+            let reason = format!("the map is no longer {}", minimap.name);
+            self.on_rotate_actions(true);
+            return Some(reason);
+        }
+        if matches!(self.context.player, Player::ChangingChannel(_, _)) {
+            return None;
+        }
+        let Minimap::Idle(idle) = self.context.minimap else {
+            return None;
+        };
+
+        let now = self.context.clock.now();
+        let last_changed = *self.player.last_channel_changed.get_or_insert(now);
+        let elapsed_millis = now.saturating_duration_since(last_changed).as_millis();
+        let scheduled = self
+            .settings
+            .change_channel_millis
+            .is_some_and(|millis| elapsed_millis >= millis as u128);
+        let stranger = self.settings.change_channel_on_stranger
+            && idle.has_stranger_player()
+            && elapsed_millis >= CHANGE_CHANNEL_STRANGER_COOLDOWN_MILLIS;
+        if scheduled || stranger {
+            debug!(target: "player", "changing channel, scheduled {scheduled} stranger {stranger}");
+            self.player
+                .request_change_channel(self.settings.change_channel_random);
+        }
+        None
+    }
+
+    /// Tracks the EXP and mesos progress of the current session while not halting
+    ///
+    /// The session is saved to history when it ends and on each report. Returns the report to
+    /// notify if one is due.
+    pub fn poll_progress(&mut self) -> Option<String> {
+        let minimap = self.minimap.data().filter(|_| !self.context.halting);
+        let Some(minimap) = minimap else {
+            if let Some(mut session) = self.progress.end()
+                && session.elapsed_millis > 0
+            {
+                let _ = upsert_session(&mut session);
+            }
+            return None;
+        };
+        self.progress
+            .update(self.context.clock.as_ref(), &minimap.name);
+        self.progress.update_exp(self.context);
+        if let Some(mesos) = self.player.mesos.take() {
+            self.progress.update_mesos(mesos);
+        }
+
+        let millis = self.settings.notifications.exp_report_millis?;
+        if !self.progress.should_report(millis) {
+            return None;
+        }
+        if let Some(session) = self.progress.session_mut() {
+            let _ = upsert_session(session);
+        }
+        self.progress.report()
+    }
+
     #[cfg(debug_assertions)]
     pub fn poll_debug(&mut self) {
         if let Some((calibrating, instant)) = self.infering_rune.as_ref().copied() {
@@ -112,27 +258,31 @@ impl DefaultRequestHandler<'_> {
         let mode = self
             .minimap
             .data()
-            .map(|minimap| minimap.rotation_mode)
-            .unwrap_or_default()
-            .into();
+            .map(RotatorMode::from)
+            .unwrap_or_default();
         let reset_on_erda = self
             .minimap
             .data()
             .map(|minimap| minimap.actions_any_reset_on_erda_condition)
             .unwrap_or_default();
 
-        self.rotator.build_actions(
+        self.rotator.build_actions(BuildActionsConfig {
             mode,
-            config_actions(self.config)
+            actions: config_actions(self.config)
                 .into_iter()
                 .chain(self.actions.iter().copied())
                 .collect::<Vec<_>>()
                 .as_slice(),
-            self.buffs,
-            self.config.potion_key.key,
-            self.settings.enable_rune_solving,
-            reset_on_erda,
-        );
+            macros: &self.config.macros,
+            buffs: self.buffs,
+            placeables: &self.config.placeable_skills,
+            potion_rules: &self.config.potion_rules,
+            potion_key: self.config.potion_key.key,
+            mana_potion: config_mana_potion(self.config),
+            read_mesos_millis: config_read_mesos_millis(self.config),
+            enable_rune_solving: self.settings.enable_rune_solving,
+            reset_normal_actions_on_erda: reset_on_erda,
+        });
     }
 }
 
@@ -151,12 +301,25 @@ impl RequestHandler for DefaultRequestHandler<'_> {
         self.context.halting
     }
 
+    #[inline]
+    fn on_reset_timers(&mut self) {
+        self.rotator.reset_timers();
+    }
+
+    fn on_change_channel(&mut self) {
+        if self.minimap.data().is_some() && !self.context.halting {
+            self.player
+                .request_change_channel(self.settings.change_channel_random);
+        }
+    }
+
     fn on_create_minimap(&self, name: String) -> Option<MinimapData> {
         if let Minimap::Idle(idle) = self.context.minimap {
             Some(MinimapData {
                 name,
                 width: idle.bbox.width,
                 height: idle.bbox.height,
+                fingerprint: Some(idle.fingerprint),
                 ..MinimapData::default()
             })
         } else {
@@ -168,6 +331,7 @@ impl RequestHandler for DefaultRequestHandler<'_> {
         self.minimap.set_data(minimap);
 
         let minimap = self.minimap.data().unwrap();
+        self.context.platforms = platforms_from_data(minimap, self.player.config.movement);
         self.player.reset();
         self.player.config.rune_platforms_pathing = minimap.rune_platforms_pathing;
         self.player.config.rune_platforms_pathing_up_jump_only =
@@ -176,6 +340,15 @@ impl RequestHandler for DefaultRequestHandler<'_> {
         self.player.config.auto_mob_platforms_pathing_up_jump_only =
             minimap.auto_mob_platforms_pathing_up_jump_only;
         self.player.config.auto_mob_platforms_bound = minimap.auto_mob_platforms_bound;
+        let preset = if minimap.playlist_enabled && !minimap.playlist.is_empty() {
+            if self.playlist.index() >= minimap.playlist.len() {
+                self.playlist.reset();
+            }
+            Some(minimap.playlist[self.playlist.index()].preset.clone())
+        } else {
+            self.playlist.reset();
+            preset
+        };
         *self.actions = preset
             .and_then(|preset| minimap.actions.get(&preset).cloned())
             .unwrap_or_default();
@@ -186,7 +359,10 @@ impl RequestHandler for DefaultRequestHandler<'_> {
         *self.config = config;
         *self.buffs = config_buffs(self.config);
         self.player.reset();
-        self.player.config.class = self.config.class;
+        self.player.config.movement = self.config.movement_profile();
+        if let Some(minimap) = self.minimap.data() {
+            self.context.platforms = platforms_from_data(minimap, self.player.config.movement);
+        }
         self.player.config.interact_key = self.config.interact_key.key.into();
         self.player.config.grappling_key = self.config.ropelift_key.map(|key| key.key.into());
         self.player.config.teleport_key = self.config.teleport_key.map(|key| key.key.into());
@@ -199,10 +375,30 @@ impl RequestHandler for DefaultRequestHandler<'_> {
                 (false, _) | (_, PotionMode::EveryMillis(_)) => None,
                 (_, PotionMode::Percentage(percent)) => Some(percent / 100.0),
             };
-        self.player.config.update_health_millis = Some(self.config.health_update_millis);
+        let has_potion_rules = self.config.potion_rules.iter().any(|rule| rule.key.enabled);
+        self.player.config.update_health_millis =
+            (self.player.config.use_potion_below_percent.is_some() || has_potion_rules)
+                .then_some(self.config.health_update_millis);
+        self.player.set_health_critical_thresholds(
+            self.config
+                .potion_rules
+                .iter()
+                .filter(|rule| rule.key.enabled)
+                .filter_map(|rule| Some((rule.below_percent / 100.0, rule.halt_after_millis?)))
+                .collect(),
+        );
+        self.player.config.update_mana_millis = config_mana_potion(self.config)
+            .is_some()
+            .then_some(self.config.mana_update_millis);
+        self.player.config.inventory_key = self.config.inventory_key.key.into();
+        self.player.config.mesos_region = self.config.mesos_region.map(Rect::from);
         self.buff_states.iter_mut().for_each(|state| {
             state.update_enabled_state(self.config, self.settings);
         });
+        *self.custom_skill_states = config_custom_skill_states(self.config);
+        self.context.custom_skills.clear();
+        *self.custom_buff_states = config_custom_buff_states(self.config);
+        self.context.custom_buffs.clear();
         self.update_rotator_actions();
     }
 
@@ -236,6 +432,7 @@ impl RequestHandler for DefaultRequestHandler<'_> {
             ));
         }
 
+        self.player.config.revive_on_death = settings.revive_on_death;
         *self.settings = settings;
         self.buff_states.iter_mut().for_each(|state| {
             state.update_enabled_state(self.config, self.settings);
@@ -253,10 +450,28 @@ impl RequestHandler for DefaultRequestHandler<'_> {
         GameState {
             position: self.player.last_known_pos.map(|pos| (pos.x, pos.y)),
             health: self.player.health,
+            mana: self.player.mana,
+            exp: self.progress.percent(),
+            mesos: self.progress.mesos(),
+            exp_session: self.progress.session().cloned(),
             state: self.context.player.to_string(),
             normal_action: self.player.normal_action_name(),
             priority_action: self.player.priority_action_name(),
             erda_shower_state: self.context.skills[SkillKind::ErdaShower].to_string(),
+            custom_skill_states: self
+                .config
+                .custom_skills
+                .iter()
+                .map(|skill| {
+                    let state = self
+                        .context
+                        .custom_skills
+                        .get(&skill.id)
+                        .map(|skill| skill.to_string())
+                        .unwrap_or("Unknown".to_string());
+                    (skill.name.clone(), state)
+                })
+                .collect(),
             destinations: self
                 .player
                 .last_destinations
@@ -268,9 +483,49 @@ impl RequestHandler for DefaultRequestHandler<'_> {
                         .collect::<Vec<_>>()
                 })
                 .unwrap_or_default(),
+            playlist: self
+                .minimap
+                .data()
+                .filter(|minimap| minimap.playlist_enabled)
+                .and_then(|minimap| minimap.playlist.get(self.playlist.index()))
+                .map(|entry| (self.playlist.index(), entry.preset.clone())),
+            buff_mismatches: self
+                .rotator
+                .buff_mismatches(self.context)
+                .into_iter()
+                .map(|id| match id {
+                    BuffId::Kind(kind) => kind.to_string(),
+                    BuffId::Custom(id) => self
+                        .config
+                        .custom_buffs
+                        .iter()
+                        .find(|buff| buff.id == id)
+                        .map(|buff| buff.name.clone())
+                        .unwrap_or_default(),
+                })
+                .collect(),
         }
     }
 
+    fn on_action_statistics(&self) -> HashMap<usize, ActionStatistics> {
+        let offset = config_actions(self.config).len();
+        self.rotator
+            .action_statistics(self.context.clock.now())
+            .into_iter()
+            .filter_map(|(index, statistics)| Some((index.checked_sub(offset)?, statistics)))
+            .collect()
+    }
+
+    fn on_simulate_rotation(&self) -> Option<SimulationInput> {
+        let minimap = self.minimap.data()?;
+        Some(SimulationInput::new(
+            minimap.clone(),
+            config_actions(self.config),
+            self.actions.as_slice(),
+            self.config.macros.clone(),
+        ))
+    }
+
     #[inline]
     fn on_minimap_frame(&self) -> Option<(Vec<u8>, usize, usize)> {
         self.context
@@ -280,6 +535,53 @@ impl RequestHandler for DefaultRequestHandler<'_> {
             .and_then(|mat| extract_minimap(self.context, mat))
     }
 
+    fn on_buffs_region_frame(&self) -> Option<(Vec<u8>, usize, usize)> {
+        self.context
+            .detector
+            .as_ref()
+            .map(|detector| extract_buffs_region(detector.mat()))
+    }
+
+    fn on_capture_custom_buff_template(&self, bound: Bound) -> Option<Vec<u8>> {
+        let detector = self.context.detector.as_ref()?;
+        let rect = Rect::new(bound.x, bound.y, bound.width, bound.height);
+        let template = crop_to_buffs_region(detector.mat())
+            .roi(rect)
+            .ok()?
+            .clone_pointee();
+        let mut bytes = Vector::new();
+        imencode_def(".png", &template, &mut bytes).ok()?;
+        Some(bytes.to_vec())
+    }
+
+    fn on_game_frame(&self) -> Option<(Vec<u8>, usize, usize)> {
+        let mat = self.context.detector.as_ref()?.mat();
+        let size = mat.size().ok()?;
+        Some((
+            to_rgba_bytes(mat),
+            size.width as usize,
+            size.height as usize,
+        ))
+    }
+
+    fn on_capture_custom_skill_template(&self, bound: Bound) -> Option<Vec<u8>> {
+        let detector = self.context.detector.as_ref()?;
+        let template = detector.mat().roi(bound.into()).ok()?.clone_pointee();
+        let mut bytes = Vector::new();
+        imencode_def(".png", &template, &mut bytes).ok()?;
+        Some(bytes.to_vec())
+    }
+
+    fn on_capture_custom_skill_anchor(&self, bound: Bound) -> Option<[u8; 4]> {
+        let detector = self.context.detector.as_ref()?;
+        let bbox = Rect::from(bound);
+        let pixel = detector
+            .mat()
+            .at_pt::<Vec4b>((bbox.tl() + bbox.br()) / 2)
+            .ok()?;
+        Some(pixel.0)
+    }
+
     fn on_minimap_platforms_bound(&self) -> Option<Bound> {
         if let Minimap::Idle(idle) = self.context.minimap {
             idle.platforms_bound.map(|bound| bound.into())
@@ -422,22 +724,33 @@ fn poll_key(handler: &mut DefaultRequestHandler) {
 #[inline]
 fn extract_minimap(context: &Context, mat: &impl MatTraitConst) -> Option<(Vec<u8>, usize, usize)> {
     if let Minimap::Idle(idle) = context.minimap {
-        let minimap = mat
-            .roi(idle.bbox)
-            .unwrap()
-            .iter::<Vec4b>()
-            .unwrap()
-            .flat_map(|bgra| {
-                let bgra = bgra.1;
-                [bgra[2], bgra[1], bgra[0], 255]
-            })
-            .collect::<Vec<u8>>();
+        let minimap = to_rgba_bytes(&mat.roi(idle.bbox).unwrap());
         return Some((minimap, idle.bbox.width as usize, idle.bbox.height as usize));
     }
     None
 }
 
-pub fn config_buffs(config: &Configuration) -> Vec<(BuffKind, KeyBinding)> {
+fn extract_buffs_region(mat: &impl MatTraitConst) -> (Vec<u8>, usize, usize) {
+    let region = crop_to_buffs_region(mat);
+    let size = region.size().unwrap();
+    (
+        to_rgba_bytes(&region),
+        size.width as usize,
+        size.height as usize,
+    )
+}
+
+fn to_rgba_bytes(mat: &impl MatTraitConst) -> Vec<u8> {
+    mat.iter::<Vec4b>()
+        .unwrap()
+        .flat_map(|bgra| {
+            let bgra = bgra.1;
+            [bgra[2], bgra[1], bgra[0], 255]
+        })
+        .collect::<Vec<u8>>()
+}
+
+pub fn config_buffs(config: &Configuration) -> Vec<(BuffId, KeyBinding, Option<u64>)> {
     let mut buffs = Vec::new();
     if let KeyBindingConfiguration { key, enabled: true } = config.sayram_elixir_key {
         buffs.push((BuffKind::SayramElixir, key));
@@ -464,6 +777,84 @@ pub fn config_buffs(config: &Configuration) -> Vec<(BuffKind, KeyBinding)> {
         buffs.push((BuffKind::LegionWealth, key));
     }
     buffs
+        .into_iter()
+        .map(|(kind, key)| {
+            let duration = config
+                .buff_durations
+                .iter()
+                .find(|duration| duration.kind == kind)
+                .map(|duration| duration.millis);
+            (BuffId::Kind(kind), key, duration)
+        })
+        .chain(
+            config
+                .custom_buffs
+                .iter()
+                .filter(|buff| buff.key.enabled)
+                .map(|buff| (BuffId::Custom(buff.id), buff.key.key, buff.duration_millis)),
+        )
+        .collect()
+}
+
+pub fn config_custom_buff_states(config: &Configuration) -> Vec<BuffState> {
+    config
+        .custom_buffs
+        .iter()
+        .filter_map(|buff| match BuffState::new_custom(buff) {
+            Ok(state) => Some(state),
+            Err(err) => {
+                debug!(target: "buff", "skipping custom buff {}: {err}", buff.name);
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn config_custom_skill_states(config: &Configuration) -> Vec<SkillState> {
+    config
+        .custom_skills
+        .iter()
+        .filter_map(|skill| match SkillState::new_custom(skill) {
+            Ok(state) => Some(state),
+            Err(err) => {
+                debug!(target: "skill", "skipping custom skill {}: {err}", skill.name);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Retrieves the mana potion key and the mana ratio to use it below
+fn config_mana_potion(config: &Configuration) -> Option<(KeyBinding, f32)> {
+    match (config.mana_potion_key, config.mana_potion_mode) {
+        (KeyBindingConfiguration { enabled: false, .. }, _) | (_, PotionMode::EveryMillis(_)) => {
+            None
+        }
+        (KeyBindingConfiguration { key, .. }, PotionMode::Percentage(percent)) => {
+            Some((key, percent / 100.0))
+        }
+    }
+}
+
+/// Whether the detected `minimap` is the same map as the `data`
+///
+/// Compares the size and the fingerprint if the `data` has one.
+#[inline]
+fn is_same_minimap(minimap: Minimap, data: &MinimapData) -> bool {
+    let Minimap::Idle(idle) = minimap else {
+        return false;
+    };
+    idle.bbox.width == data.width
+        && idle.bbox.height == data.height
+        && data
+            .fingerprint
+            .is_none_or(|fingerprint| is_same_fingerprint(idle.fingerprint, fingerprint))
+}
+
+/// Retrieves the milliseconds interval to read mesos from the inventory
+fn config_read_mesos_millis(config: &Configuration) -> Option<u64> {
+    (config.inventory_key.enabled && config.mesos_region.is_some())
+        .then_some(config.mesos_update_millis)
 }
 
 fn config_actions(config: &Configuration) -> Vec<Action> {
@@ -493,6 +884,18 @@ fn config_actions(config: &Configuration) -> Vec<Action> {
             ..ActionKey::default()
         }));
     }
+    if let KeyBindingConfiguration { key, enabled: true } = config.mana_potion_key
+        && let PotionMode::EveryMillis(millis) = config.mana_potion_mode
+    {
+        vec.push(Action::Key(ActionKey {
+            key,
+            count: 1,
+            condition: ActionCondition::EveryMillis(millis),
+            wait_before_use_millis: 350,
+            wait_after_use_millis: 350,
+            ..ActionKey::default()
+        }));
+    }
     vec.extend(
         config
             .actions
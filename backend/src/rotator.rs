@@ -1,6 +1,8 @@
 use std::{
     assert_matches::debug_assert_matches,
-    collections::VecDeque,
+    cell::Cell,
+    collections::{HashMap, VecDeque},
+    rc::Rc,
     sync::atomic::{AtomicU32, Ordering},
     time::Instant,
 };
@@ -9,25 +11,37 @@ use anyhow::Result;
 use log::debug;
 use opencv::core::{Point, Rect};
 use ordered_hash_map::OrderedHashMap;
-use rand::seq::IteratorRandom;
+use rand::seq::{IteratorRandom, SliceRandom};
 
 use crate::{
     ActionKeyDirection, ActionKeyWith, AutoMobbing, KeyBinding, Position, RotationMode,
-    buff::{Buff, BuffKind},
-    context::{Context, MS_PER_TICK},
-    database::{Action, ActionCondition, ActionKey, ActionMove, PingPong},
+    buff::{Buff, BuffId, BuffKind},
+    clock::millis_to_ticks,
+    context::Context,
+    database::{
+        Action, ActionBranch, ActionBranchPredicate, ActionCondition, ActionKey, ActionMacro,
+        ActionMacroRef, ActionMove, ActionSequence, Minimap as MinimapData, PatrolBehavior,
+        PatrolZone, PingPong, PlaceableSkill, PotionRule,
+    },
     minimap::Minimap,
     player::{
-        GRAPPLING_THRESHOLD, PingPongDirection, Player, PlayerAction, PlayerActionAutoMob,
-        PlayerActionKey, PlayerActionPingPong, PlayerState,
+        PingPongDirection, Player, PlayerAction, PlayerActionAutoMob, PlayerActionKey,
+        PlayerActionMove, PlayerActionOutcome, PlayerActionPingPong, PlayerState,
     },
-    skill::{Skill, SkillKind},
+    skill::{Skill, SkillId, SkillKind},
     task::{Task, Update, update_detection_task},
 };
 
-const COOLDOWN_BETWEEN_QUEUE_MILLIS: u128 = 20_000;
+pub const COOLDOWN_BETWEEN_QUEUE_MILLIS: u128 = 20_000;
 const COOLDOWN_BETWEEN_POTION_QUEUE_MILLIS: u128 = 2_000;
 
+/// The number of [`ActionCondition::EveryMillis`] intervals an action can go without running
+/// before it is considered starved
+const STARVATION_INTERVAL_MULTIPLIER: u64 = 2;
+
+/// How long before a buff with a known duration expires to use it again
+const PREBUFF_BEFORE_EXPIRY_MILLIS: u64 = 10_000;
+
 type ConditionFn = Box<dyn Fn(&Context, &mut PlayerState, Option<Instant>) -> bool>;
 
 /// Predicate for when a priority action can be queued
@@ -64,7 +78,8 @@ struct PriorityAction {
     queue_to_front: bool,
     /// Whether this action is being ignored
     ///
-    /// While ignored, [`Self::last_queued_time`] will be updated to [`Instant::now`].
+    /// While ignored, [`Self::last_queued_time`] will be updated to the current [`Context::clock`]
+    /// time.
     /// The action is ignored for as long as it is still in the queue or the player
     /// is still executing it.
     ignoring: bool,
@@ -72,6 +87,115 @@ struct PriorityAction {
     last_queued_time: Option<Instant>,
 }
 
+/// Identifies a [`PriorityAction`] across multiple [`Rotator::build_actions`]
+///
+/// Used to carry over [`PriorityAction::last_queued_time`] so that editing unrelated actions
+/// does not restart the timers of the others.
+#[derive(PartialEq, Debug)]
+enum PriorityActionKey {
+    /// Built from the provided actions and the n-th occurrence of the same actions
+    Actions(Vec<Action>, usize),
+    ElitePotionSpam,
+    ManaPotion,
+    ReadMesos,
+    SolveRune,
+    Buff(BuffId),
+    /// Built from a [`PlaceableSkill`] with the id
+    Placeable(u32),
+    /// Built from a [`PotionRule`] with the id
+    PotionRule(u32),
+}
+
+/// Where and when a [`PlaceableSkill`] was last placed
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Placement {
+    /// The player minimap position at the time of placing
+    position: Point,
+    /// The [`Context::clock`] instant at the time of placing
+    placed_at: Instant,
+}
+
+/// Execution statistics of an action built by [`Rotator`]
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct ActionStatistics {
+    /// The number of times the action was queued
+    pub queued_count: u32,
+    /// The number of times the action was passed to the player
+    pub started_count: u32,
+    pub completed_count: u32,
+    pub aborted_count: u32,
+    /// The average milliseconds from being queued to being passed to the player
+    pub average_latency_millis: u64,
+    /// The milliseconds since the action was last passed to the player
+    pub millis_since_last_run: Option<u64>,
+    /// Whether an [`ActionCondition::EveryMillis`] action has not run for
+    /// [`STARVATION_INTERVAL_MULTIPLIER`] times its interval
+    pub starved: bool,
+}
+
+/// Tracks the [`ActionStatistics`] of an action id
+#[derive(Default, Debug)]
+struct ActionStatisticsTracker {
+    queued_count: u32,
+    started_count: u32,
+    completed_count: u32,
+    aborted_count: u32,
+    total_latency_millis: u64,
+    latency_count: u32,
+    /// The [`Instant`] the action was queued but not yet passed to the player
+    pending_queued_time: Option<Instant>,
+    last_started_time: Option<Instant>,
+    /// The interval of an [`ActionCondition::EveryMillis`] action
+    interval_millis: Option<u64>,
+}
+
+impl ActionStatisticsTracker {
+    #[inline]
+    fn on_queued(&mut self, now: Instant) {
+        self.queued_count = self.queued_count.saturating_add(1);
+        self.pending_queued_time = Some(now);
+    }
+
+    #[inline]
+    fn on_started(&mut self, now: Instant) {
+        self.started_count = self.started_count.saturating_add(1);
+        self.last_started_time = Some(now);
+        if let Some(time) = self.pending_queued_time.take() {
+            self.total_latency_millis = self
+                .total_latency_millis
+                .saturating_add(now.saturating_duration_since(time).as_millis() as u64);
+            self.latency_count += 1;
+        }
+    }
+
+    /// Computes the [`ActionStatistics`] at `now`
+    ///
+    /// `since` is the [`Instant`] the rotator started rotating and is used in place of the last
+    /// run when it is more recent.
+    fn statistics(&self, now: Instant, since: Option<Instant>) -> ActionStatistics {
+        let millis_since = |time: Instant| now.saturating_duration_since(time).as_millis() as u64;
+        let starved = self
+            .interval_millis
+            .zip(self.last_started_time.max(since))
+            .is_some_and(|(interval, time)| {
+                millis_since(time) > interval.saturating_mul(STARVATION_INTERVAL_MULTIPLIER)
+            });
+
+        ActionStatistics {
+            queued_count: self.queued_count,
+            started_count: self.started_count,
+            completed_count: self.completed_count,
+            aborted_count: self.aborted_count,
+            average_latency_millis: self
+                .total_latency_millis
+                .checked_div(self.latency_count as u64)
+                .unwrap_or_default(),
+            millis_since_last_run: self.last_started_time.map(millis_since),
+            starved,
+        }
+    }
+}
+
 /// The action that will be passed to the player
 ///
 /// There are [`RotatorAction::Single`] and [`RotatorAction::Linked`] actions.
@@ -87,44 +211,116 @@ enum RotatorAction {
 /// A linked list of actions
 #[derive(Clone, Debug)]
 struct LinkedAction {
-    inner: PlayerAction,
+    inner: LinkedActionKind,
     next: Option<Box<LinkedAction>>,
 }
 
+/// The kind of a [`LinkedAction`] node
+#[derive(Clone, Debug)]
+enum LinkedActionKind {
+    /// An action passed to the player
+    Action(PlayerAction),
+    /// A branch evaluated when it is reached in [`Rotator::rotate_queuing_linked_action`]
+    ///
+    /// The actions of the taken branch are placed in front of [`LinkedAction::next`].
+    Branch(LinkedBranch),
+}
+
+#[derive(Clone, Debug)]
+struct LinkedBranch {
+    predicate: ActionBranchPredicate,
+    then: Option<Box<LinkedAction>>,
+    otherwise: Option<Box<LinkedAction>>,
+    /// Whether to drop [`LinkedAction::next`] when [`Self::then`] is taken
+    then_stop: bool,
+}
+
 /// The rotator's rotation mode
 #[derive(Default, Debug)]
 pub enum RotatorMode {
     StartToEnd,
     #[default]
     StartToEndThenReverse,
+    Random,
+    Weighted,
+    ShuffleEachCycle,
     AutoMobbing(AutoMobbing),
     PingPong(PingPong),
+    Patrol(Vec<PatrolZone>),
 }
 
-impl From<RotationMode> for RotatorMode {
-    fn from(mode: RotationMode) -> Self {
-        match mode {
+impl From<&MinimapData> for RotatorMode {
+    fn from(minimap: &MinimapData) -> Self {
+        match minimap.rotation_mode {
             RotationMode::StartToEnd => RotatorMode::StartToEnd,
             RotationMode::StartToEndThenReverse => RotatorMode::StartToEndThenReverse,
+            RotationMode::Random => RotatorMode::Random,
+            RotationMode::Weighted => RotatorMode::Weighted,
+            RotationMode::ShuffleEachCycle => RotatorMode::ShuffleEachCycle,
             RotationMode::AutoMobbing(auto_mobbing) => RotatorMode::AutoMobbing(auto_mobbing),
             RotationMode::PingPong(ping_pong) => RotatorMode::PingPong(ping_pong),
+            RotationMode::Patrol => RotatorMode::Patrol(minimap.patrol_zones.clone()),
         }
     }
 }
 
+/// The actions and their settings used by [`Rotator::build_actions`]
+#[derive(Default, Debug)]
+pub struct BuildActionsConfig<'a> {
+    pub mode: RotatorMode,
+    pub actions: &'a [Action],
+    pub macros: &'a [ActionMacro],
+    /// The buffs to use paired with their keys and optional known durations
+    pub buffs: &'a [(BuffId, KeyBinding, Option<u64>)],
+    pub placeables: &'a [PlaceableSkill],
+    pub potion_rules: &'a [PotionRule],
+    pub potion_key: KeyBinding,
+    /// The mana potion key and the mana ratio below which it is used
+    pub mana_potion: Option<(KeyBinding, f32)>,
+    pub read_mesos_millis: Option<u64>,
+    pub enable_rune_solving: bool,
+    pub reset_normal_actions_on_erda: bool,
+}
+
 #[derive(Default, Debug)]
 pub struct Rotator {
     // This is literally free postfix increment!
     id_counter: AtomicU32,
     normal_actions: Vec<(u32, RotatorAction)>,
+    /// The weight of each action in [`Self::normal_actions`] for [`RotatorMode::Weighted`]
+    normal_actions_weight: Vec<u32>,
+    /// The shuffled indices of [`Self::normal_actions`] for [`RotatorMode::ShuffleEachCycle`]
+    ///
+    /// Shuffled again each time [`Self::normal_index`] wraps back to the start.
+    normal_actions_shuffled: Vec<usize>,
+    /// The last index of [`Self::normal_actions`] used by [`RotatorMode::Random`]
+    normal_last_index: Option<usize>,
     normal_queuing_linked_action: Option<(u32, Box<LinkedAction>)>,
     normal_index: usize,
+    /// The number of normal actions rotated in the current cycle
+    ///
+    /// Reset by [`Self::build_actions`] because the normal actions may change.
+    normal_rotated_count: u32,
+    /// The number of normal action cycles completed
+    ///
+    /// Kept across [`Self::build_actions`] so that rebuilding does not restart the count.
+    normal_cycles: u32,
     /// Whether [`Self::normal_actions`] is being accessed from the end
     normal_actions_backward: bool,
     normal_actions_reset_on_erda: bool,
     normal_rotate_mode: RotatorMode,
+    /// The health ratio at or below which actions from the preset are not rotated
+    ///
+    /// Built from [`PotionRule::stop_attacking`].
+    stop_attacking_below_ratio: Option<f32>,
     /// The [`Task`] used when [`Self::normal_rotate_mode`] is [`RotatorMode::AutoMobbing`]
     auto_mob_task: Option<Task<Result<Vec<Point>>>>,
+    /// The index of the current zone when [`Self::normal_rotate_mode`] is [`RotatorMode::Patrol`]
+    patrol_index: usize,
+    /// The [`Instant`] the player entered the current patrol zone
+    ///
+    /// The dwell time of the zone only starts counting after the player entered it.
+    patrol_zone_entered: Option<Instant>,
     priority_actions: OrderedHashMap<u32, PriorityAction>,
     /// The currently executing [`RotatorAction::Linked`] action
     priority_queuing_linked_action: Option<(u32, Box<LinkedAction>)>,
@@ -132,107 +328,442 @@ pub struct Rotator {
     ///
     /// Populates from [`Self::priority_actions`] when its predicate for queuing is true
     priority_actions_queue: VecDeque<u32>,
+    /// Maps a [`PriorityAction`] id to its [`PriorityActionKey`]
+    priority_action_keys: HashMap<u32, PriorityActionKey>,
+    /// Maps a buff [`PriorityAction`] id to its [`BuffId`] and known duration
+    buff_durations: HashMap<u32, (BuffId, u64)>,
+    /// Maps a [`PlaceableSkill`] id to its last [`Placement`]
+    ///
+    /// Shared with its [`PriorityAction`] and kept across [`Self::build_actions`]. The placement
+    /// is only recorded once the action completes.
+    placements: HashMap<u32, Rc<Cell<Option<Placement>>>>,
+    /// Maps an action id to the index of the [`Action`] it was built from
+    ///
+    /// For [`RotatorAction::Linked`] action, the index is of the first action.
+    action_indices: HashMap<u32, usize>,
+    /// Tracks the statistics of each action in [`Self::action_indices`]
+    action_statistics: HashMap<u32, ActionStatisticsTracker>,
+    /// The [`Instant`] the rotator started rotating since the last [`Self::reset_queue`]
+    statistics_since: Option<Instant>,
 }
 
 impl Rotator {
-    pub fn build_actions(
-        &mut self,
-        mode: RotatorMode,
-        actions: &[Action],
-        buffs: &[(BuffKind, KeyBinding)],
-        potion_key: KeyBinding,
-        enable_rune_solving: bool,
-        reset_normal_actions_on_erda: bool,
-    ) {
+    pub fn build_actions(&mut self, config: BuildActionsConfig<'_>) {
+        let BuildActionsConfig {
+            mode,
+            actions,
+            macros,
+            buffs,
+            placeables,
+            potion_rules,
+            potion_key,
+            mana_potion,
+            read_mesos_millis,
+            enable_rune_solving,
+            reset_normal_actions_on_erda,
+        } = config;
         debug!(target: "rotator", "preparing actions {actions:?} {buffs:?}");
         self.reset_queue();
         self.normal_actions.clear();
+        self.normal_actions_weight.clear();
+        self.normal_rotated_count = 0;
         self.normal_rotate_mode = mode;
         self.normal_actions_reset_on_erda = reset_normal_actions_on_erda;
+        let last_queued_times = self
+            .priority_actions
+            .iter()
+            .filter_map(|(id, action)| {
+                let key = self.priority_action_keys.remove(id)?;
+                Some((key, action.last_queued_time?))
+            })
+            .collect::<Vec<_>>();
         self.priority_actions.clear();
+        self.priority_action_keys.clear();
+        self.buff_durations.clear();
+        self.action_indices.clear();
+        self.action_statistics.clear();
 
+        let (actions, indices) = expand_macros(actions, macros);
+        let actions = actions.as_slice();
         let mut i = 0;
         while i < actions.len() {
             let action = actions[i];
             let condition = match action {
                 Action::Move(ActionMove { condition, .. })
-                | Action::Key(ActionKey { condition, .. }) => condition,
+                | Action::Key(ActionKey { condition, .. })
+                | Action::Sequence(ActionSequence { condition, .. })
+                | Action::Macro(ActionMacroRef { condition, .. })
+                | Action::Branch(ActionBranch { condition, .. }) => condition,
             };
             let queue_to_front = match action {
                 Action::Move(_) => false,
-                Action::Key(ActionKey { queue_to_front, .. }) => queue_to_front.unwrap_or_default(),
+                Action::Key(ActionKey { queue_to_front, .. })
+                | Action::Sequence(ActionSequence { queue_to_front, .. })
+                | Action::Macro(ActionMacroRef { queue_to_front, .. })
+                | Action::Branch(ActionBranch { queue_to_front, .. }) => {
+                    queue_to_front.unwrap_or_default()
+                }
+            };
+            let weight = match action {
+                Action::Move(ActionMove { weight, .. })
+                | Action::Key(ActionKey { weight, .. })
+                | Action::Sequence(ActionSequence { weight, .. })
+                | Action::Macro(ActionMacroRef { weight, .. })
+                | Action::Branch(ActionBranch { weight, .. }) => weight,
             };
             let (action, offset) = rotator_action(action, i, actions);
+            let index = indices[i];
+            let source = &actions[i..i + offset];
             debug_assert!(i != 0 || !matches!(condition, ActionCondition::Linked));
             // Should not move i below the match because it could cause
             // infinite loop due to auto mobbing ignoring Any condition
             i += offset;
             match condition {
-                ActionCondition::EveryMillis(_) | ActionCondition::ErdaShowerOffCooldown => {
-                    self.priority_actions.insert(
-                        self.id_counter.fetch_add(1, Ordering::Relaxed),
-                        priority_action(action, condition, queue_to_front),
+                ActionCondition::EveryMillis(_)
+                | ActionCondition::ErdaShowerOffCooldown
+                | ActionCondition::SkillOffCooldown(_) => {
+                    let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
+                    let interval_millis = match condition {
+                        ActionCondition::EveryMillis(millis) => Some(millis),
+                        ActionCondition::ErdaShowerOffCooldown
+                        | ActionCondition::SkillOffCooldown(_)
+                        | ActionCondition::Any
+                        | ActionCondition::Linked => None,
+                    };
+                    let nth = self
+                        .priority_action_keys
+                        .values()
+                        .filter(|key| match key {
+                            PriorityActionKey::Actions(actions, _) => actions.as_slice() == source,
+                            PriorityActionKey::ElitePotionSpam
+                            | PriorityActionKey::ManaPotion
+                            | PriorityActionKey::ReadMesos
+                            | PriorityActionKey::SolveRune
+                            | PriorityActionKey::Buff(_)
+                            | PriorityActionKey::Placeable(_)
+                            | PriorityActionKey::PotionRule(_) => false,
+                        })
+                        .count();
+                    self.priority_actions
+                        .insert(id, priority_action(action, condition, queue_to_front));
+                    self.priority_action_keys
+                        .insert(id, PriorityActionKey::Actions(source.to_vec(), nth));
+                    self.action_indices.insert(id, index);
+                    self.action_statistics.insert(
+                        id,
+                        ActionStatisticsTracker {
+                            interval_millis,
+                            ..ActionStatisticsTracker::default()
+                        },
                     );
                 }
                 ActionCondition::Any => {
                     if matches!(self.normal_rotate_mode, RotatorMode::AutoMobbing(_)) {
                         continue;
                     }
-                    self.normal_actions
-                        .push((self.id_counter.fetch_add(1, Ordering::Relaxed), action))
+                    let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
+                    self.normal_actions.push((id, action));
+                    self.normal_actions_weight.push(weight);
+                    self.action_indices.insert(id, index);
+                    self.action_statistics
+                        .insert(id, ActionStatisticsTracker::default());
                 }
                 ActionCondition::Linked => unreachable!(),
             }
         }
 
-        self.priority_actions.insert(
-            self.id_counter.fetch_add(1, Ordering::Relaxed),
-            elite_boss_potion_spam_priority_action(potion_key),
-        );
+        let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
+        self.priority_actions
+            .insert(id, elite_boss_potion_spam_priority_action(potion_key));
+        self.priority_action_keys
+            .insert(id, PriorityActionKey::ElitePotionSpam);
+        if let Some((key, below_ratio)) = mana_potion {
+            let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
+            self.priority_actions
+                .insert(id, mana_potion_priority_action(key, below_ratio));
+            self.priority_action_keys
+                .insert(id, PriorityActionKey::ManaPotion);
+        }
+        if let Some(millis) = read_mesos_millis {
+            let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
+            self.priority_actions
+                .insert(id, read_mesos_priority_action(millis));
+            self.priority_action_keys
+                .insert(id, PriorityActionKey::ReadMesos);
+        }
         if enable_rune_solving {
-            self.priority_actions.insert(
-                self.id_counter.fetch_add(1, Ordering::Relaxed),
-                solve_rune_priority_action(),
-            );
+            let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
+            self.priority_actions
+                .insert(id, solve_rune_priority_action());
+            self.priority_action_keys
+                .insert(id, PriorityActionKey::SolveRune);
         }
-        for (i, key) in buffs.iter().copied() {
-            self.priority_actions.insert(
-                self.id_counter.fetch_add(1, Ordering::Relaxed),
-                buff_priority_action(i, key),
-            );
+        for (buff, key, duration) in buffs.iter().copied() {
+            let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
+            self.priority_actions
+                .insert(id, buff_priority_action(buff, key, duration));
+            self.priority_action_keys
+                .insert(id, PriorityActionKey::Buff(buff));
+            if let Some(duration) = duration {
+                self.buff_durations.insert(id, (buff, duration));
+            }
+        }
+        self.placements
+            .retain(|id, _| placeables.iter().any(|placeable| placeable.id == *id));
+        for placeable in placeables.iter().filter(|placeable| placeable.key.enabled) {
+            let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
+            let placement = self.placements.entry(placeable.id).or_default().clone();
+            self.priority_actions
+                .insert(id, placeable_priority_action(placeable, placement));
+            self.priority_action_keys
+                .insert(id, PriorityActionKey::Placeable(placeable.id));
+        }
+        let potion_rules = potion_rules
+            .iter()
+            .filter(|rule| rule.key.enabled)
+            .collect::<Vec<_>>();
+        self.stop_attacking_below_ratio = potion_rules
+            .iter()
+            .filter(|rule| rule.stop_attacking)
+            .map(|rule| rule.below_percent / 100.0)
+            .reduce(f32::max);
+        for rule in potion_rules.iter().copied() {
+            let lower_percent = potion_rules
+                .iter()
+                .map(|other| other.below_percent)
+                .filter(|percent| *percent < rule.below_percent)
+                .reduce(f32::max);
+            let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
+            self.priority_actions
+                .insert(id, potion_rule_priority_action(rule, lower_percent));
+            self.priority_action_keys
+                .insert(id, PriorityActionKey::PotionRule(rule.id));
         }
+
+        for (key, last_queued_time) in last_queued_times {
+            let id = self
+                .priority_action_keys
+                .iter()
+                .find_map(|(id, other)| (*other == key).then_some(*id));
+            if let Some(action) = id.and_then(|id| self.priority_actions.get_mut(&id)) {
+                action.last_queued_time = Some(last_queued_time);
+            }
+        }
+    }
+
+    /// Resets the last queued time of all priority actions
+    ///
+    /// Priority actions can be queued again immediately after reset.
+    pub fn reset_timers(&mut self) {
+        for action in self.priority_actions.values_mut() {
+            action.last_queued_time = None;
+        }
+        for placement in self.placements.values() {
+            placement.set(None);
+        }
+    }
+
+    /// Returns the index of the [`Action`] the action `id` was built from
+    ///
+    /// Returns [`None`] if the action was not built from the provided actions (e.g. buffs).
+    #[inline]
+    pub fn action_index(&self, id: u32) -> Option<usize> {
+        self.action_indices.get(&id).copied()
+    }
+
+    /// Returns the [`ActionStatistics`] of each action keyed by the index of the [`Action`] it was
+    /// built from
+    pub fn action_statistics(&self, now: Instant) -> HashMap<usize, ActionStatistics> {
+        self.action_statistics
+            .iter()
+            .filter_map(|(id, tracker)| {
+                let index = self.action_index(*id)?;
+                Some((index, tracker.statistics(now, self.statistics_since)))
+            })
+            .collect()
+    }
+
+    /// Returns the [`BuffId`]s whose detected icon disagrees with the known duration timer
+    ///
+    /// The timer starts from the last time the buff was used. A mismatch is only reported after
+    /// [`COOLDOWN_BETWEEN_QUEUE_MILLIS`] to give the detection time to catch up.
+    pub fn buff_mismatches(&self, context: &Context) -> Vec<BuffId> {
+        let now = context.clock.now();
+        self.buff_durations
+            .iter()
+            .filter_map(|(id, (buff, duration))| {
+                let action = self.priority_actions.get(id)?;
+                let last_queued_time = action.last_queued_time;
+                if action.ignoring
+                    || last_queued_time.is_none()
+                    || !at_least_millis_passed_since(
+                        now,
+                        last_queued_time,
+                        COOLDOWN_BETWEEN_QUEUE_MILLIS,
+                    )
+                {
+                    return None;
+                }
+                let has_buff = matches!(context.buff(*buff), Buff::HasBuff);
+                let has_timer =
+                    !at_least_millis_passed_since(now, last_queued_time, *duration as u128);
+                (has_buff != has_timer).then_some(*buff)
+            })
+            .collect()
+    }
+
+    /// Returns the number of normal action cycles completed since the rotator was created
+    ///
+    /// A cycle is counted:
+    /// - Each time the number of rotated normal actions reaches the number of normal actions
+    /// - Each auto mobbing or ping pong normal action for [`RotatorMode::AutoMobbing`] and
+    ///   [`RotatorMode::PingPong`]
+    /// - Each time all zones are visited for [`RotatorMode::Patrol`]
+    #[inline]
+    pub fn normal_cycles(&self) -> u32 {
+        self.normal_cycles
+    }
+
+    /// Whether it is safe to change the actions without interrupting a linked action
+    #[inline]
+    pub fn is_at_safe_point(&self, player: &PlayerState) -> bool {
+        self.priority_queuing_linked_action.is_none()
+            && !self.has_priority_linked_action_executing(player)
+            && !self.has_normal_linked_action_queuing_or_executing(player)
     }
 
     #[inline]
     pub fn reset_queue(&mut self) {
+        self.statistics_since = None;
+        for tracker in self.action_statistics.values_mut() {
+            tracker.pending_queued_time = None;
+        }
         self.normal_actions_backward = false;
+        self.patrol_index = 0;
+        self.patrol_zone_entered = None;
         self.reset_normal_actions_queue();
-        self.priority_actions_queue.clear();
+        for id in std::mem::take(&mut self.priority_actions_queue) {
+            if matches!(
+                self.priority_action_keys.get(&id),
+                Some(PriorityActionKey::Placeable(_))
+            ) {
+                self.on_placeable_dropped(id);
+            }
+        }
         self.priority_queuing_linked_action = None;
     }
 
+    /// Allows the placeable action `id` to be queued again after it was dropped without placing
+    #[inline]
+    fn on_placeable_dropped(&mut self, id: u32) {
+        if let Some(action) = self.priority_actions.get_mut(&id) {
+            action.last_queued_time = None;
+        }
+    }
+
     #[inline]
     fn reset_normal_actions_queue(&mut self) {
         self.normal_index = 0;
+        self.normal_last_index = None;
         self.normal_queuing_linked_action = None;
     }
 
     #[inline]
     pub fn rotate_action(&mut self, context: &Context, player: &mut PlayerState) {
-        if context.halting || matches!(context.player, Player::CashShopThenExit(_, _)) {
+        self.track_action_outcomes(context.clock.now(), player);
+        if context.halting
+            || matches!(
+                context.player,
+                Player::CashShopThenExit(_, _)
+                    | Player::Reviving(_, _)
+                    | Player::ChangingChannel(_, _)
+            )
+        {
             return;
         }
-        self.rotate_priority_actions(context, player);
+        self.statistics_since.get_or_insert(context.clock.now());
+        let stop_attacking = self.should_stop_attacking(player);
+        if stop_attacking && player.has_normal_action() {
+            debug!(target: "rotator", "health critical, stop attacking");
+            player.reset_normal_action();
+        }
+        self.rotate_priority_actions(context, player, stop_attacking);
         self.rotate_priority_actions_queue(context, player);
-        if !player.has_priority_action() && !player.has_normal_action() {
+        if !stop_attacking && !player.has_priority_action() && !player.has_normal_action() {
             match self.normal_rotate_mode {
-                RotatorMode::StartToEnd => self.rotate_start_to_end(player),
-                RotatorMode::StartToEndThenReverse => self.rotate_start_to_end_then_reverse(player),
+                RotatorMode::StartToEnd => self.rotate_start_to_end(context, player),
+                RotatorMode::StartToEndThenReverse => {
+                    self.rotate_start_to_end_then_reverse(context, player)
+                }
+                RotatorMode::Random => self.rotate_random(context, player),
+                RotatorMode::Weighted => self.rotate_weighted(context, player),
+                RotatorMode::ShuffleEachCycle => self.rotate_shuffle_each_cycle(context, player),
                 RotatorMode::AutoMobbing(auto_mobbing) => {
-                    self.rotate_auto_mobbing(context, player, auto_mobbing)
+                    self.rotate_auto_mobbing(context, player, auto_mobbing);
+                    if player.has_normal_action() {
+                        self.count_normal_rotated(1);
+                    }
                 }
                 RotatorMode::PingPong(ping_pong) => {
-                    self.rotate_ping_pong(context, player, ping_pong)
+                    self.rotate_ping_pong(context, player, ping_pong, None);
+                    if player.has_normal_action() {
+                        self.count_normal_rotated(1);
+                    }
+                }
+                RotatorMode::Patrol(_) => self.rotate_patrol(context, player),
+            }
+        }
+    }
+
+    /// Whether the player health is at or below [`Self::stop_attacking_below_ratio`]
+    #[inline]
+    fn should_stop_attacking(&self, player: &PlayerState) -> bool {
+        let Some(below_ratio) = self.stop_attacking_below_ratio else {
+            return false;
+        };
+        player
+            .health
+            .filter(|(_, max)| *max > 0)
+            .is_some_and(|(current, max)| current as f32 / max as f32 <= below_ratio)
+    }
+
+    /// Updates the completed and aborted counts of [`Self::action_statistics`]
+    ///
+    /// A linked action is only counted as completed once its last action completes. A completed
+    /// placeable action also records its [`Placement`] at `now`.
+    fn track_action_outcomes(&mut self, now: Instant, player: &mut PlayerState) {
+        for (id, outcome) in player.take_action_outcomes() {
+            if let Some(PriorityActionKey::Placeable(placeable_id)) =
+                self.priority_action_keys.get(&id)
+            {
+                match outcome {
+                    PlayerActionOutcome::Completed => {
+                        if let Some(placement) = self.placements.get(placeable_id) {
+                            placement.set(player.last_known_pos.map(|position| Placement {
+                                position,
+                                placed_at: now,
+                            }));
+                        }
+                    }
+                    PlayerActionOutcome::Aborted => self.on_placeable_dropped(id),
+                }
+            }
+            let is_linked_action_queuing = self
+                .normal_queuing_linked_action
+                .iter()
+                .chain(self.priority_queuing_linked_action.iter())
+                .any(|(action_id, _)| *action_id == id);
+            let Some(tracker) = self.action_statistics.get_mut(&id) else {
+                continue;
+            };
+            match outcome {
+                PlayerActionOutcome::Completed => {
+                    if !is_linked_action_queuing {
+                        tracker.completed_count = tracker.completed_count.saturating_add(1);
+                    }
+                }
+                PlayerActionOutcome::Aborted => {
+                    tracker.aborted_count = tracker.aborted_count.saturating_add(1);
                 }
             }
         }
@@ -261,44 +792,62 @@ impl Rotator {
         })
     }
 
-    /// Checks if the player or the queue has a [`ActionCondition::ErdaShowerOffCooldown`] action
+    /// Checks if the player or the queue has an action with the skill `condition`
+    ///
+    /// The `condition` is either [`ActionCondition::ErdaShowerOffCooldown`] or
+    /// [`ActionCondition::SkillOffCooldown`].
     #[inline]
-    fn has_erda_action_queuing_or_executing(&self, player: &PlayerState) -> bool {
+    fn has_skill_action_queuing_or_executing(
+        &self,
+        player: &PlayerState,
+        condition: ActionCondition,
+    ) -> bool {
         if player.priority_action_id().is_some_and(|id| {
-            self.priority_actions.get(&id).is_some_and(|action| {
-                matches!(
-                    action.condition_kind,
-                    Some(ActionCondition::ErdaShowerOffCooldown)
-                )
-            })
+            self.priority_actions
+                .get(&id)
+                .is_some_and(|action| action.condition_kind == Some(condition))
         }) {
             return true;
         }
-        self.priority_actions_queue.iter().any(|id| {
-            matches!(
-                self.priority_actions.get(id).unwrap().condition_kind,
-                Some(ActionCondition::ErdaShowerOffCooldown)
-            )
-        })
+        self.priority_actions_queue
+            .iter()
+            .any(|id| self.priority_actions.get(id).unwrap().condition_kind == Some(condition))
     }
 
     /// Rotates the actions inside the [`Self::priority_actions`]
     ///
     /// This function does not pass the action to the player but only pushes the action to
     /// [`Self::priority_actions_queue`]. It is responsible for checking queuing condition.
-    fn rotate_priority_actions(&mut self, context: &Context, player: &mut PlayerState) {
-        // Keeps ignoring while there is any type of erda condition action inside the queue
-        let has_erda_action = self.has_erda_action_queuing_or_executing(player);
+    ///
+    /// Actions from the preset are not queued when `stop_attacking` is true.
+    fn rotate_priority_actions(
+        &mut self,
+        context: &Context,
+        player: &mut PlayerState,
+        stop_attacking: bool,
+    ) {
+        let now = context.clock.now();
         let ids = self.priority_actions.keys().copied().collect::<Vec<_>>(); // why?
         let mut did_queue_erda_action = false;
         for id in ids {
             // Ignores for as long as the action is a linked action that is queuing
             // or executing
             let has_linked_action = self.is_priority_linked_action_queuing_or_executing(player, id);
+            // Keeps ignoring while there is any action of the same skill condition inside the
+            // queue
+            let has_skill_action = self
+                .priority_actions
+                .get(&id)
+                .unwrap()
+                .condition_kind
+                .is_some_and(|condition| {
+                    self.has_skill_action_queuing_or_executing(player, condition)
+                });
             let action = self.priority_actions.get_mut(&id).unwrap();
             action.ignoring = match action.condition_kind {
-                Some(ActionCondition::ErdaShowerOffCooldown) => {
-                    has_erda_action || has_linked_action
+                Some(ActionCondition::ErdaShowerOffCooldown)
+                | Some(ActionCondition::SkillOffCooldown(_)) => {
+                    has_skill_action || has_linked_action
                 }
                 Some(ActionCondition::Linked) | Some(ActionCondition::EveryMillis(_)) | None => {
                     player // The player currently executing action
@@ -313,7 +862,15 @@ impl Rotator {
                 Some(ActionCondition::Any) => unreachable!(),
             };
             if action.ignoring {
-                action.last_queued_time = Some(Instant::now());
+                action.last_queued_time = Some(now);
+                continue;
+            }
+            if stop_attacking
+                && matches!(
+                    self.priority_action_keys.get(&id),
+                    Some(PriorityActionKey::Actions(_, _))
+                )
+            {
                 continue;
             }
             if (action.condition.0)(context, player, action.last_queued_time) {
@@ -322,7 +879,10 @@ impl Rotator {
                 } else {
                     self.priority_actions_queue.push_back(id);
                 }
-                action.last_queued_time = Some(Instant::now());
+                if let Some(tracker) = self.action_statistics.get_mut(&id) {
+                    tracker.on_queued(now);
+                }
+                action.last_queued_time = Some(now);
                 if !did_queue_erda_action {
                     did_queue_erda_action = matches!(
                         action.condition_kind,
@@ -384,10 +944,13 @@ impl Rotator {
         {
             return;
         }
-        if self.rotate_queuing_linked_action(player, true) {
+        if self.rotate_queuing_linked_action(context, player, true) {
             return;
         }
-        let id = *self.priority_actions_queue.front().unwrap();
+        // The queuing linked action can end without passing any action due to branching
+        let Some(id) = self.priority_actions_queue.front().copied() else {
+            return;
+        };
         let Some(action) = self.priority_actions.get(&id) else {
             self.priority_actions_queue.pop_front();
             return;
@@ -407,6 +970,9 @@ impl Rotator {
             return;
         }
         self.priority_actions_queue.pop_front();
+        if let Some(tracker) = self.action_statistics.get_mut(&id) {
+            tracker.on_started(context.clock.now());
+        }
         match action.inner.clone() {
             RotatorAction::Single(inner) => {
                 if action.queue_to_front {
@@ -424,7 +990,7 @@ impl Rotator {
                     self.priority_actions_queue.push_front(id);
                 }
                 self.priority_queuing_linked_action = Some((id, Box::new(linked)));
-                self.rotate_queuing_linked_action(player, true);
+                self.rotate_queuing_linked_action(context, player, true);
             }
         }
     }
@@ -436,6 +1002,28 @@ impl Rotator {
         auto_mobbing: AutoMobbing,
     ) {
         debug_assert!(!player.has_normal_action() && !player.has_priority_action());
+        let Minimap::Idle(idle) = context.minimap else {
+            return;
+        };
+        let bound = if player.config.auto_mob_platforms_bound {
+            idle.platforms_bound.unwrap_or(auto_mobbing.bound.into())
+        } else {
+            auto_mobbing.bound.into()
+        };
+        self.rotate_auto_mobbing_inside(context, player, auto_mobbing, bound);
+    }
+
+    /// Detects mobs inside `bound` and sets the [`PlayerAction::AutoMob`] normal action
+    ///
+    /// `bound` is in minimap coordinate relative to top-left and is used instead of the bound
+    /// inside `auto_mobbing`.
+    fn rotate_auto_mobbing_inside(
+        &mut self,
+        context: &Context,
+        player: &mut PlayerState,
+        auto_mobbing: AutoMobbing,
+        bound: Rect,
+    ) {
         let Minimap::Idle(idle) = context.minimap else {
             return;
         };
@@ -443,17 +1031,13 @@ impl Rotator {
             return;
         };
         let AutoMobbing {
-            bound,
             key,
             key_count,
             key_wait_before_millis,
             key_wait_after_millis,
+            ..
         } = auto_mobbing;
-        let bound = if player.config.auto_mob_platforms_bound {
-            idle.platforms_bound.unwrap_or(bound.into())
-        } else {
-            bound.into()
-        };
+        let grappling_threshold = player.config.movement.grappling_threshold;
         let Update::Ok(points) =
             update_detection_task(context, 0, &mut self.auto_mob_task, move |detector| {
                 detector.detect_mobs(idle.bbox, bound, pos)
@@ -465,7 +1049,7 @@ impl Rotator {
             .iter()
             .filter(|point| {
                 let y = idle.bbox.height - point.y;
-                y <= pos.y || (y - pos.y).abs() <= GRAPPLING_THRESHOLD
+                y <= pos.y || (y - pos.y).abs() <= grappling_threshold
             })
             .choose(&mut rand::rng())
             .map(|point| Point::new(point.x, idle.bbox.height - point.y))
@@ -486,8 +1070,8 @@ impl Rotator {
             PlayerAction::AutoMob(PlayerActionAutoMob {
                 key,
                 count: key_count.max(1),
-                wait_before_ticks: (key_wait_before_millis / MS_PER_TICK) as u32,
-                wait_after_ticks: (key_wait_after_millis / MS_PER_TICK) as u32,
+                wait_before_ticks: millis_to_ticks(key_wait_before_millis),
+                wait_after_ticks: millis_to_ticks(key_wait_after_millis),
                 position: Position {
                     x: point.x,
                     x_random_range: 0,
@@ -503,6 +1087,7 @@ impl Rotator {
         context: &Context,
         player: &mut PlayerState,
         ping_pong: PingPong,
+        direction: Option<PingPongDirection>,
     ) {
         debug_assert!(!player.has_normal_action() && !player.has_priority_action());
         let Minimap::Idle(idle) = context.minimap else {
@@ -522,11 +1107,11 @@ impl Rotator {
         let bbox = idle.bbox;
         let dist_left = pos.x - bbox.x;
         let dist_right = (bbox.x + bbox.width) - pos.x;
-        let direction = if dist_left > dist_right {
+        let direction = direction.unwrap_or(if dist_left > dist_right {
             PingPongDirection::Left
         } else {
             PingPongDirection::Right
-        };
+        });
         let bound = Rect::new(
             bound.x,
             bbox.height - (bound.y + bound.height),
@@ -539,42 +1124,152 @@ impl Rotator {
             PlayerAction::PingPong(PlayerActionPingPong {
                 key,
                 count: key_count.max(1),
-                wait_before_ticks: (key_wait_before_millis / MS_PER_TICK) as u32,
-                wait_after_ticks: (key_wait_after_millis / MS_PER_TICK) as u32,
+                wait_before_ticks: millis_to_ticks(key_wait_before_millis),
+                wait_after_ticks: millis_to_ticks(key_wait_after_millis),
                 bound,
                 direction,
             }),
         );
     }
 
-    fn rotate_start_to_end(&mut self, player: &mut PlayerState) {
+    fn rotate_patrol(&mut self, context: &Context, player: &mut PlayerState) {
         debug_assert!(!player.has_normal_action() && !player.has_priority_action());
-        if self.normal_actions.is_empty() {
+        let Minimap::Idle(idle) = context.minimap else {
             return;
+        };
+        let Some(pos) = player.last_known_pos else {
+            return;
+        };
+        let RotatorMode::Patrol(zones) = &self.normal_rotate_mode else {
+            unreachable!()
+        };
+        if zones.is_empty() {
+            return;
+        }
+
+        let mut index = self.patrol_index.min(zones.len() - 1);
+        if self.patrol_zone_entered.is_some_and(|entered| {
+            context.clock.millis_since(entered) >= zones[index].dwell_millis as u128
+        }) {
+            index = (index + 1) % zones.len();
+            self.patrol_zone_entered = None;
+            if index == 0 {
+                self.normal_cycles = self.normal_cycles.saturating_add(1);
+            }
+            debug!(target: "rotator", "patrol moving to zone {index}");
         }
-        if self.rotate_queuing_linked_action(player, false) {
+        let zone = zones[index];
+        self.patrol_index = index;
+
+        let bbox = idle.bbox;
+        let bound = Rect::new(
+            zone.bound.x,
+            bbox.height - (zone.bound.y + zone.bound.height),
+            zone.bound.width,
+            zone.bound.height,
+        );
+        if !bound.contains(pos) {
+            // Prefers a platform inside the zone so that platforms pathing can be used
+            let point = context
+                .platforms
+                .iter()
+                .find(|platform| {
+                    let xs = platform.xs();
+                    let y = platform.y();
+                    y >= bound.y
+                        && y < bound.y + bound.height
+                        && xs.start < bound.x + bound.width
+                        && xs.end > bound.x
+                })
+                .map(|platform| {
+                    let xs = platform.xs();
+                    let x = (bound.x + bound.width / 2)
+                        .clamp(xs.start.max(bound.x), xs.end.min(bound.x + bound.width) - 1);
+                    Point::new(x, platform.y())
+                })
+                .unwrap_or(Point::new(bound.x + bound.width / 2, bound.y));
+            player.set_normal_action(
+                u32::MAX - 2,
+                PlayerAction::Move(PlayerActionMove {
+                    position: Position {
+                        x: point.x,
+                        x_random_range: 0,
+                        y: point.y,
+                        allow_adjusting: false,
+                    },
+                    wait_after_move_ticks: 0,
+                    platforms_pathing: true,
+                }),
+            );
             return;
         }
-        debug_assert!(self.normal_index < self.normal_actions.len());
-        let (id, action) = self.normal_actions[self.normal_index].clone();
-        self.normal_index = (self.normal_index + 1) % self.normal_actions.len();
-        match action {
-            RotatorAction::Single(action) => {
-                player.set_normal_action(id, action);
+
+        let entering = self.patrol_zone_entered.is_none();
+        if entering {
+            self.patrol_zone_entered = Some(context.clock.now());
+        }
+        let PatrolZone {
+            bound,
+            behavior,
+            key,
+            key_count,
+            key_wait_before_millis,
+            key_wait_after_millis,
+            direction,
+            ..
+        } = zone;
+        match behavior {
+            PatrolBehavior::PingPong => {
+                let direction =
+                    entering
+                        .then_some(direction)
+                        .and_then(|direction| match direction {
+                            ActionKeyDirection::Any => None,
+                            ActionKeyDirection::Left => Some(PingPongDirection::Left),
+                            ActionKeyDirection::Right => Some(PingPongDirection::Right),
+                        });
+                let ping_pong = PingPong {
+                    bound,
+                    key,
+                    key_count,
+                    key_wait_before_millis,
+                    key_wait_after_millis,
+                };
+                self.rotate_ping_pong(context, player, ping_pong, direction);
             }
-            RotatorAction::Linked(action) => {
-                self.normal_queuing_linked_action = Some((id, Box::new(action)));
-                self.rotate_queuing_linked_action(player, false);
+            PatrolBehavior::AutoMobbing => {
+                let auto_mobbing = AutoMobbing {
+                    bound,
+                    key,
+                    key_count,
+                    key_wait_before_millis,
+                    key_wait_after_millis,
+                };
+                self.rotate_auto_mobbing_inside(context, player, auto_mobbing, bound.into());
             }
         }
     }
 
-    fn rotate_start_to_end_then_reverse(&mut self, player: &mut PlayerState) {
+    fn rotate_start_to_end(&mut self, context: &Context, player: &mut PlayerState) {
         debug_assert!(!player.has_normal_action() && !player.has_priority_action());
         if self.normal_actions.is_empty() {
             return;
         }
-        if self.rotate_queuing_linked_action(player, false) {
+        if self.rotate_queuing_linked_action(context, player, false) {
+            return;
+        }
+        debug_assert!(self.normal_index < self.normal_actions.len());
+        let i = self.normal_index;
+        self.normal_index = (self.normal_index + 1) % self.normal_actions.len();
+        self.rotate_normal_action_at(context, player, i);
+    }
+
+    fn rotate_start_to_end_then_reverse(&mut self, context: &Context, player: &mut PlayerState) {
+        debug_assert!(!player.has_normal_action() && !player.has_priority_action());
+        if self.normal_actions.is_empty() {
+            return;
+        }
+        if self.rotate_queuing_linked_action(context, player, false) {
             return;
         }
         debug_assert!(self.normal_index < self.normal_actions.len());
@@ -587,48 +1282,246 @@ impl Rotator {
         if (self.normal_index + 1) == len {
             self.normal_actions_backward = !self.normal_actions_backward
         }
-        let (id, action) = self.normal_actions[i].clone();
         self.normal_index = (self.normal_index + 1) % len;
-        match action {
-            RotatorAction::Single(action) => {
-                player.set_normal_action(id, action);
-            }
-            RotatorAction::Linked(action) => {
-                self.normal_queuing_linked_action = Some((id, Box::new(action)));
-                self.rotate_queuing_linked_action(player, false);
-            }
+        self.rotate_normal_action_at(context, player, i);
+    }
+
+    fn rotate_random(&mut self, context: &Context, player: &mut PlayerState) {
+        debug_assert!(!player.has_normal_action() && !player.has_priority_action());
+        if self.normal_actions.is_empty() {
+            return;
+        }
+        if self.rotate_queuing_linked_action(context, player, false) {
+            return;
         }
+        let i = (0..self.normal_actions.len())
+            .filter(|i| self.normal_actions.len() == 1 || Some(*i) != self.normal_last_index)
+            .choose(&mut rand::rng())
+            .unwrap();
+        self.normal_last_index = Some(i);
+        self.rotate_normal_action_at(context, player, i);
     }
 
-    #[inline]
-    fn rotate_queuing_linked_action(
-        &mut self,
-        player: &mut PlayerState,
-        is_priority: bool,
-    ) -> bool {
-        let linked_action = if is_priority {
-            &mut self.priority_queuing_linked_action
-        } else {
-            &mut self.normal_queuing_linked_action
-        };
-        if linked_action.is_none() {
-            return false;
+    fn rotate_weighted(&mut self, context: &Context, player: &mut PlayerState) {
+        debug_assert!(!player.has_normal_action() && !player.has_priority_action());
+        debug_assert_eq!(self.normal_actions.len(), self.normal_actions_weight.len());
+        if self.normal_actions.is_empty() {
+            return;
         }
-        let (id, action) = linked_action.take().unwrap();
-        *linked_action = action.next.map(|action| (id, action));
-        if is_priority {
-            player.set_priority_action(id, action.inner);
-        } else {
-            player.set_normal_action(id, action.inner);
+        if self.rotate_queuing_linked_action(context, player, false) {
+            return;
         }
-        true
+        let total = self
+            .normal_actions_weight
+            .iter()
+            .map(|weight| *weight as u64)
+            .sum::<u64>();
+        let i = if total == 0 {
+            rand::random_range(0..self.normal_actions.len())
+        } else {
+            let mut value = rand::random_range(0..total);
+            self.normal_actions_weight
+                .iter()
+                .position(|weight| {
+                    let weight = *weight as u64;
+                    if value < weight {
+                        return true;
+                    }
+                    value -= weight;
+                    false
+                })
+                .unwrap()
+        };
+        self.rotate_normal_action_at(context, player, i);
     }
-}
+
+    fn rotate_shuffle_each_cycle(&mut self, context: &Context, player: &mut PlayerState) {
+        debug_assert!(!player.has_normal_action() && !player.has_priority_action());
+        if self.normal_actions.is_empty() {
+            return;
+        }
+        if self.rotate_queuing_linked_action(context, player, false) {
+            return;
+        }
+        debug_assert!(self.normal_index < self.normal_actions.len());
+        let len = self.normal_actions.len();
+        if self.normal_index == 0 || self.normal_actions_shuffled.len() != len {
+            self.normal_actions_shuffled = (0..len).collect();
+            self.normal_actions_shuffled.shuffle(&mut rand::rng());
+        }
+        let i = self.normal_actions_shuffled[self.normal_index];
+        self.normal_index = (self.normal_index + 1) % len;
+        self.rotate_normal_action_at(context, player, i);
+    }
+
+    /// Counts a rotated normal action and completes a cycle after `len` actions
+    #[inline]
+    fn count_normal_rotated(&mut self, len: usize) {
+        self.normal_rotated_count += 1;
+        if self.normal_rotated_count as usize >= len {
+            self.normal_rotated_count = 0;
+            self.normal_cycles = self.normal_cycles.saturating_add(1);
+        }
+    }
+
+    /// Sets the normal action at index `i` of [`Self::normal_actions`] to the player
+    #[inline]
+    fn rotate_normal_action_at(&mut self, context: &Context, player: &mut PlayerState, i: usize) {
+        let (id, action) = self.normal_actions[i].clone();
+        self.count_normal_rotated(self.normal_actions.len());
+        if let Some(tracker) = self.action_statistics.get_mut(&id) {
+            let now = context.clock.now();
+            tracker.on_queued(now);
+            tracker.on_started(now);
+        }
+        match action {
+            RotatorAction::Single(action) => {
+                player.set_normal_action(id, action);
+            }
+            RotatorAction::Linked(action) => {
+                self.normal_queuing_linked_action = Some((id, Box::new(action)));
+                self.rotate_queuing_linked_action(context, player, false);
+            }
+        }
+    }
+
+    /// Passes the next action of the queuing linked action to the player
+    ///
+    /// Any [`LinkedActionKind::Branch`] reached is evaluated against `context` and `player`
+    /// without passing an action. Returns `false` when there is no action left to pass.
+    #[inline]
+    fn rotate_queuing_linked_action(
+        &mut self,
+        context: &Context,
+        player: &mut PlayerState,
+        is_priority: bool,
+    ) -> bool {
+        let linked_action = if is_priority {
+            &mut self.priority_queuing_linked_action
+        } else {
+            &mut self.normal_queuing_linked_action
+        };
+        let mut ended_id = None;
+        while let Some((id, action)) = linked_action.take() {
+            let LinkedAction { inner, next } = *action;
+            match inner {
+                LinkedActionKind::Action(action) => {
+                    *linked_action = next.map(|action| (id, action));
+                    if is_priority {
+                        player.set_priority_action(id, action);
+                    } else {
+                        player.set_normal_action(id, action);
+                    }
+                    return true;
+                }
+                LinkedActionKind::Branch(branch) => {
+                    let is_true = is_branch_predicate_true(context, player, branch.predicate);
+                    let (head, next) = if is_true {
+                        (branch.then, if branch.then_stop { None } else { next })
+                    } else {
+                        (branch.otherwise, next)
+                    };
+                    *linked_action = append_linked_action(head, next).map(|action| (id, action));
+                    ended_id = Some(id);
+                }
+            }
+        }
+        // The linked action ended on a branch without any action left to pass
+        if let Some(tracker) = ended_id.and_then(|id| self.action_statistics.get_mut(&id)) {
+            tracker.completed_count = tracker.completed_count.saturating_add(1);
+        }
+        false
+    }
+}
+
+/// Expands each [`Action::Macro`] in `actions` into the actions of the referenced [`ActionMacro`]
+///
+/// Returns the expanded actions along with the index of the action in `actions` each of them
+/// was expanded from. A reference to a missing or empty macro is removed together with the
+/// actions linked to it. Nested macro references are not expanded.
+fn expand_macros(actions: &[Action], macros: &[ActionMacro]) -> (Vec<Action>, Vec<usize>) {
+    let mut expanded = Vec::with_capacity(actions.len());
+    let mut indices = Vec::with_capacity(actions.len());
+    let mut removing_linked = false;
+
+    for (index, action) in actions.iter().copied().enumerate() {
+        let condition = match action {
+            Action::Move(ActionMove { condition, .. })
+            | Action::Key(ActionKey { condition, .. })
+            | Action::Sequence(ActionSequence { condition, .. })
+            | Action::Macro(ActionMacroRef { condition, .. })
+            | Action::Branch(ActionBranch { condition, .. }) => condition,
+        };
+        if removing_linked && matches!(condition, ActionCondition::Linked) {
+            continue;
+        }
+        removing_linked = false;
+
+        let Action::Macro(ActionMacroRef {
+            id,
+            condition,
+            queue_to_front,
+            weight,
+        }) = action
+        else {
+            expanded.push(action);
+            indices.push(index);
+            continue;
+        };
+        let macro_actions = macros
+            .iter()
+            .find(|action_macro| action_macro.id == id)
+            .map(|action_macro| action_macro.actions.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .copied()
+            .filter(|action| !matches!(action, Action::Macro(_)));
+        let len = expanded.len();
+        for action in macro_actions {
+            let condition = if expanded.len() == len {
+                condition
+            } else {
+                ActionCondition::Linked
+            };
+            expanded.push(match action {
+                Action::Move(action) => Action::Move(ActionMove {
+                    condition,
+                    weight,
+                    ..action
+                }),
+                Action::Key(action) => Action::Key(ActionKey {
+                    condition,
+                    queue_to_front,
+                    weight,
+                    ..action
+                }),
+                Action::Sequence(action) => Action::Sequence(ActionSequence {
+                    condition,
+                    queue_to_front,
+                    weight,
+                    ..action
+                }),
+                Action::Branch(action) => Action::Branch(ActionBranch {
+                    condition,
+                    queue_to_front,
+                    weight,
+                    ..action
+                }),
+                Action::Macro(_) => unreachable!(),
+            });
+            indices.push(index);
+        }
+        removing_linked = expanded.len() == len && !matches!(condition, ActionCondition::Linked);
+    }
+
+    (expanded, indices)
+}
 
 /// Creates a [`RotatorAction`] with `start_action` as the initial action
 ///
-/// If `start_action` is linked, this function returns [`RotatorAction::Linked`] with [`usize`] as
-/// the offset from `start_index` to the next non-linked action.
+/// If `start_action` is linked or is an [`Action::Branch`], this function returns
+/// [`RotatorAction::Linked`] with [`usize`] as the offset from `start_index` to the next
+/// non-linked action.
 /// Otherwise, this returns [`RotatorAction::Single`] with [`usize`] offset of 1.
 #[inline]
 fn rotator_action(
@@ -636,47 +1529,107 @@ fn rotator_action(
     start_index: usize,
     actions: &[Action],
 ) -> (RotatorAction, usize) {
-    if start_index + 1 < actions.len() {
-        match actions[start_index + 1] {
-            Action::Move(ActionMove {
-                condition: ActionCondition::Linked,
-                ..
-            })
-            | Action::Key(ActionKey {
-                condition: ActionCondition::Linked,
-                ..
-            }) => (),
-            _ => return (RotatorAction::Single(start_action.into()), 1),
-        }
+    let offset = 1 + actions
+        .iter()
+        .skip(start_index + 1)
+        .take_while(|action| {
+            matches!(
+                action,
+                Action::Move(ActionMove {
+                    condition: ActionCondition::Linked,
+                    ..
+                }) | Action::Key(ActionKey {
+                    condition: ActionCondition::Linked,
+                    ..
+                }) | Action::Sequence(ActionSequence {
+                    condition: ActionCondition::Linked,
+                    ..
+                }) | Action::Branch(ActionBranch {
+                    condition: ActionCondition::Linked,
+                    ..
+                })
+            )
+        })
+        .count();
+    if offset == 1 && !matches!(start_action, Action::Branch(_)) {
+        return (RotatorAction::Single(start_action.into()), 1);
     }
-    let mut head = LinkedAction {
-        inner: start_action.into(),
-        next: None,
+    let head = linked_action(&actions[start_index..start_index + offset]).unwrap();
+    (RotatorAction::Linked(*head), offset)
+}
+
+/// Creates a [`LinkedAction`] list from `actions` in order
+///
+/// The actions following an [`Action::Branch`] are grouped into the true and false branches
+/// according to [`ActionBranch::then_count`] and [`ActionBranch::else_count`]. The counts are
+/// clamped to the remaining actions.
+fn linked_action(actions: &[Action]) -> Option<Box<LinkedAction>> {
+    let (action, rest) = actions.split_first()?;
+    let action = match *action {
+        Action::Branch(ActionBranch {
+            predicate,
+            then_count,
+            else_count,
+            then_stop,
+            ..
+        }) => {
+            let then_end = (then_count as usize).min(rest.len());
+            let else_end = (then_end + else_count as usize).min(rest.len());
+            LinkedAction {
+                inner: LinkedActionKind::Branch(LinkedBranch {
+                    predicate,
+                    then: linked_action(&rest[..then_end]),
+                    otherwise: linked_action(&rest[then_end..else_end]),
+                    then_stop,
+                }),
+                next: linked_action(&rest[else_end..]),
+            }
+        }
+        action => LinkedAction {
+            inner: LinkedActionKind::Action(action.into()),
+            next: linked_action(rest),
+        },
+    };
+    Some(Box::new(action))
+}
+
+/// Appends `tail` to the end of `head`
+#[inline]
+fn append_linked_action(
+    head: Option<Box<LinkedAction>>,
+    tail: Option<Box<LinkedAction>>,
+) -> Option<Box<LinkedAction>> {
+    let Some(mut head) = head else {
+        return tail;
     };
     let mut current = &mut head;
-    let mut offset = 1;
-    for action in actions.iter().skip(start_index + 1) {
-        match action {
-            Action::Move(ActionMove {
-                condition: ActionCondition::Linked,
-                ..
-            })
-            | Action::Key(ActionKey {
-                condition: ActionCondition::Linked,
-                ..
-            }) => {
-                let action = LinkedAction {
-                    inner: (*action).into(),
-                    next: None,
-                };
-                current.next = Some(Box::new(action));
-                current = current.next.as_mut().unwrap();
-                offset += 1;
-            }
-            _ => break,
+    while current.next.is_some() {
+        current = current.next.as_mut().unwrap();
+    }
+    current.next = tail;
+    Some(head)
+}
+
+#[inline]
+fn is_branch_predicate_true(
+    context: &Context,
+    player: &PlayerState,
+    predicate: ActionBranchPredicate,
+) -> bool {
+    let has_elite_boss = matches!(context.minimap, Minimap::Idle(idle) if idle.has_elite_boss);
+    match predicate {
+        ActionBranchPredicate::FacingLeft => {
+            matches!(player.last_known_direction(), ActionKeyDirection::Left)
+        }
+        ActionBranchPredicate::FacingRight => {
+            matches!(player.last_known_direction(), ActionKeyDirection::Right)
+        }
+        ActionBranchPredicate::EliteBossPresent => has_elite_boss,
+        ActionBranchPredicate::EliteBossAbsent => !has_elite_boss,
+        ActionBranchPredicate::ErdaShowerOffCooldown => {
+            matches!(context.skills[SkillKind::ErdaShower], Skill::Idle(_, _))
         }
     }
-    (RotatorAction::Linked(head), offset)
 }
 
 #[inline]
@@ -687,7 +1640,9 @@ fn priority_action(
 ) -> PriorityAction {
     debug_assert_matches!(
         condition,
-        ActionCondition::EveryMillis(_) | ActionCondition::ErdaShowerOffCooldown
+        ActionCondition::EveryMillis(_)
+            | ActionCondition::ErdaShowerOffCooldown
+            | ActionCondition::SkillOffCooldown(_)
     );
     PriorityAction {
         inner: action,
@@ -706,8 +1661,11 @@ fn priority_action(
 fn elite_boss_potion_spam_priority_action(key: KeyBinding) -> PriorityAction {
     PriorityAction {
         condition: Condition(Box::new(|context, _, last_queued_time| {
-            if !at_least_millis_passed_since(last_queued_time, COOLDOWN_BETWEEN_POTION_QUEUE_MILLIS)
-            {
+            if !at_least_millis_passed_since(
+                context.clock.now(),
+                last_queued_time,
+                COOLDOWN_BETWEEN_POTION_QUEUE_MILLIS,
+            ) {
                 return false;
             }
             if let Minimap::Idle(idle) = context.minimap {
@@ -727,6 +1685,97 @@ fn elite_boss_potion_spam_priority_action(key: KeyBinding) -> PriorityAction {
             wait_before_use_ticks_random_range: 0,
             wait_after_use_ticks: 0,
             wait_after_use_ticks_random_range: 0,
+            hold_ticks: 0,
+            hold_ticks_random_range: 0,
+            hold_repeat_ticks: None,
+        })),
+        queue_to_front: true,
+        ignoring: false,
+        last_queued_time: None,
+    }
+}
+
+/// Creates a [`PlayerAction::Key`] priority action that uses health potion from `rule`
+///
+/// The conditions for triggering this action are:
+/// - At least [`PotionRule::cooldown_millis`] have been passed since last action queue
+/// - The player current health percentage is at or below [`PotionRule::below_percent`]
+/// - The player current health percentage is above `lower_percent` of another rule if any
+#[inline]
+fn potion_rule_priority_action(rule: &PotionRule, lower_percent: Option<f32>) -> PriorityAction {
+    let below_ratio = rule.below_percent / 100.0;
+    let lower_ratio = lower_percent.map(|percent| percent / 100.0);
+    let cooldown = rule.cooldown_millis as u128;
+
+    PriorityAction {
+        condition: Condition(Box::new(move |context, player, last_queued_time| {
+            if !at_least_millis_passed_since(context.clock.now(), last_queued_time, cooldown) {
+                return false;
+            }
+            let Some((current, max)) = player.health.filter(|(_, max)| *max > 0) else {
+                return false;
+            };
+            let ratio = current as f32 / max as f32;
+            ratio <= below_ratio && lower_ratio.is_none_or(|lower_ratio| ratio > lower_ratio)
+        })),
+        condition_kind: None,
+        inner: RotatorAction::Single(PlayerAction::Key(PlayerActionKey {
+            key: rule.key.key,
+            link_key: None,
+            count: 1,
+            position: None,
+            direction: ActionKeyDirection::Any,
+            with: ActionKeyWith::Any,
+            wait_before_use_ticks: 0,
+            wait_before_use_ticks_random_range: 0,
+            wait_after_use_ticks: 0,
+            wait_after_use_ticks_random_range: 0,
+            hold_ticks: 0,
+            hold_ticks_random_range: 0,
+            hold_repeat_ticks: None,
+        })),
+        queue_to_front: true,
+        ignoring: false,
+        last_queued_time: None,
+    }
+}
+
+/// Creates a [`PlayerAction::Key`] priority action that uses mana potion
+///
+/// The conditions for triggering this action are:
+/// - At least [`COOLDOWN_BETWEEN_POTION_QUEUE_MILLIS`] have been passed since last action queue
+/// - The player current mana ratio is at or below `below_ratio`
+#[inline]
+fn mana_potion_priority_action(key: KeyBinding, below_ratio: f32) -> PriorityAction {
+    PriorityAction {
+        condition: Condition(Box::new(move |context, player, last_queued_time| {
+            if !at_least_millis_passed_since(
+                context.clock.now(),
+                last_queued_time,
+                COOLDOWN_BETWEEN_POTION_QUEUE_MILLIS,
+            ) {
+                return false;
+            }
+            match player.mana {
+                Some((current, max)) if max > 0 => current as f32 / max as f32 <= below_ratio,
+                Some(_) | None => false,
+            }
+        })),
+        condition_kind: None,
+        inner: RotatorAction::Single(PlayerAction::Key(PlayerActionKey {
+            key,
+            link_key: None,
+            count: 1,
+            position: None,
+            direction: ActionKeyDirection::Any,
+            with: ActionKeyWith::Any,
+            wait_before_use_ticks: 0,
+            wait_before_use_ticks_random_range: 0,
+            wait_after_use_ticks: 0,
+            wait_after_use_ticks_random_range: 0,
+            hold_ticks: 0,
+            hold_ticks_random_range: 0,
+            hold_repeat_ticks: None,
         })),
         queue_to_front: true,
         ignoring: false,
@@ -734,6 +1783,32 @@ fn elite_boss_potion_spam_priority_action(key: KeyBinding) -> PriorityAction {
     }
 }
 
+/// Creates a [`PlayerAction::ReadMesos`] priority action
+///
+/// The conditions for triggering this action are:
+/// - At least `millis` have been passed since last action queue
+/// - The minimap is in [`Minimap::Idle`] state
+/// - The player does not have any other priority action
+///
+/// It is not queued to front so that the inventory is only opened when the player is free.
+#[inline]
+fn read_mesos_priority_action(millis: u64) -> PriorityAction {
+    PriorityAction {
+        condition: Condition(Box::new(move |context, player, last_queued_time| {
+            if !at_least_millis_passed_since(context.clock.now(), last_queued_time, millis as u128)
+            {
+                return false;
+            }
+            matches!(context.minimap, Minimap::Idle(_)) && !player.has_priority_action()
+        })),
+        condition_kind: None,
+        inner: RotatorAction::Single(PlayerAction::ReadMesos),
+        queue_to_front: false,
+        ignoring: false,
+        last_queued_time: None,
+    }
+}
+
 /// Creates a [`PlayerAction::SolveRune`] priority action
 ///
 /// The conditions for triggering this action are:
@@ -748,7 +1823,11 @@ fn solve_rune_priority_action() -> PriorityAction {
             if player.is_validating_rune() {
                 return false;
             }
-            if !at_least_millis_passed_since(last_queued_time, COOLDOWN_BETWEEN_QUEUE_MILLIS) {
+            if !at_least_millis_passed_since(
+                context.clock.now(),
+                last_queued_time,
+                COOLDOWN_BETWEEN_QUEUE_MILLIS,
+            ) {
                 return false;
             }
             if let Minimap::Idle(idle) = context.minimap {
@@ -765,17 +1844,36 @@ fn solve_rune_priority_action() -> PriorityAction {
     }
 }
 
+/// Creates a [`PlayerAction::Key`] priority action that uses the buff `key`
+///
+/// The buff is used when its icon is not detected. When `duration_millis` is known and the buff
+/// has been used before, it is also used [`PREBUFF_BEFORE_EXPIRY_MILLIS`] before it is predicted
+/// to expire even if the icon is still detected.
 #[inline]
-fn buff_priority_action(buff: BuffKind, key: KeyBinding) -> PriorityAction {
+fn buff_priority_action(
+    buff: BuffId,
+    key: KeyBinding,
+    duration_millis: Option<u64>,
+) -> PriorityAction {
     PriorityAction {
         condition: Condition(Box::new(move |context, _, last_queued_time| {
-            if !at_least_millis_passed_since(last_queued_time, COOLDOWN_BETWEEN_QUEUE_MILLIS) {
+            let now = context.clock.now();
+            if !at_least_millis_passed_since(now, last_queued_time, COOLDOWN_BETWEEN_QUEUE_MILLIS) {
                 return false;
             }
             if !matches!(context.minimap, Minimap::Idle(_)) {
                 return false;
             }
-            matches!(context.buffs[buff], Buff::NoBuff)
+            if matches!(context.buff(buff), Buff::NoBuff) {
+                return true;
+            }
+            match (duration_millis, last_queued_time) {
+                (Some(millis), Some(_)) => {
+                    let millis = millis.saturating_sub(PREBUFF_BEFORE_EXPIRY_MILLIS);
+                    at_least_millis_passed_since(now, last_queued_time, millis as u128)
+                }
+                (Some(_), None) | (None, _) => false,
+            }
         })),
         condition_kind: None,
         inner: RotatorAction::Single(PlayerAction::Key(PlayerActionKey {
@@ -789,6 +1887,9 @@ fn buff_priority_action(buff: BuffKind, key: KeyBinding) -> PriorityAction {
             wait_before_use_ticks_random_range: 0,
             wait_after_use_ticks: 10,
             wait_after_use_ticks_random_range: 0,
+            hold_ticks: 0,
+            hold_ticks_random_range: 0,
+            hold_repeat_ticks: None,
         })),
         queue_to_front: true,
         ignoring: false,
@@ -796,10 +1897,72 @@ fn buff_priority_action(buff: BuffKind, key: KeyBinding) -> PriorityAction {
     }
 }
 
+/// Creates a [`PriorityAction`] that places `placeable` again when it expired or the player
+/// has moved too far away from its last `placement`
+///
+/// The `placement` is recorded by [`Rotator`] when the action completes and its expiry is
+/// measured from that time. The action is always queued when there is no `placement`.
+fn placeable_priority_action(
+    placeable: &PlaceableSkill,
+    placement: Rc<Cell<Option<Placement>>>,
+) -> PriorityAction {
+    let duration_millis = placeable.duration_millis;
+    let max_distance = placeable.max_distance as i32;
+    PriorityAction {
+        condition: Condition(Box::new(move |context, player, last_queued_time| {
+            let now = context.clock.now();
+            if !at_least_millis_passed_since(now, last_queued_time, COOLDOWN_BETWEEN_QUEUE_MILLIS) {
+                return false;
+            }
+            if !matches!(context.minimap, Minimap::Idle(_)) {
+                return false;
+            }
+            let Some(pos) = player.last_known_pos else {
+                return false;
+            };
+            let Some(placement) = placement.get() else {
+                return true;
+            };
+            let expired = at_least_millis_passed_since(
+                now,
+                Some(placement.placed_at),
+                duration_millis as u128,
+            );
+            let dx = placement.position.x - pos.x;
+            let dy = placement.position.y - pos.y;
+            let out_of_range = dx * dx + dy * dy > max_distance * max_distance;
+            expired || out_of_range
+        })),
+        condition_kind: None,
+        inner: RotatorAction::Single(PlayerAction::Key(PlayerActionKey {
+            key: placeable.key.key,
+            link_key: None,
+            count: 1,
+            position: None,
+            direction: ActionKeyDirection::Any,
+            with: ActionKeyWith::Stationary,
+            wait_before_use_ticks: 0,
+            wait_before_use_ticks_random_range: 0,
+            wait_after_use_ticks: 10,
+            wait_after_use_ticks_random_range: 0,
+            hold_ticks: 0,
+            hold_ticks_random_range: 0,
+            hold_repeat_ticks: None,
+        })),
+        queue_to_front: false,
+        ignoring: false,
+        last_queued_time: None,
+    }
+}
+
 #[inline]
-fn at_least_millis_passed_since(last_queued_time: Option<Instant>, millis: u128) -> bool {
+fn at_least_millis_passed_since(
+    now: Instant,
+    last_queued_time: Option<Instant>,
+    millis: u128,
+) -> bool {
     last_queued_time
-        .map(|instant| Instant::now().duration_since(instant).as_millis() >= millis)
+        .map(|instant| now.saturating_duration_since(instant).as_millis() >= millis)
         .unwrap_or(true)
 }
 
@@ -809,17 +1972,21 @@ fn should_queue_fixed_action(
     last_queued_time: Option<Instant>,
     condition: ActionCondition,
 ) -> bool {
-    let millis_should_passed = match condition {
-        ActionCondition::EveryMillis(millis) => millis as u128,
-        ActionCondition::ErdaShowerOffCooldown => COOLDOWN_BETWEEN_QUEUE_MILLIS,
+    let (millis_should_passed, skill) = match condition {
+        ActionCondition::EveryMillis(millis) => (millis as u128, None),
+        ActionCondition::ErdaShowerOffCooldown => (
+            COOLDOWN_BETWEEN_QUEUE_MILLIS,
+            Some(SkillId::Kind(SkillKind::ErdaShower)),
+        ),
+        ActionCondition::SkillOffCooldown(id) => {
+            (COOLDOWN_BETWEEN_QUEUE_MILLIS, Some(SkillId::Custom(id)))
+        }
         ActionCondition::Linked | ActionCondition::Any => unreachable!(),
     };
-    if !at_least_millis_passed_since(last_queued_time, millis_should_passed) {
+    if !at_least_millis_passed_since(context.clock.now(), last_queued_time, millis_should_passed) {
         return false;
     }
-    if matches!(condition, ActionCondition::ErdaShowerOffCooldown)
-        && !matches!(context.skills[SkillKind::ErdaShower], Skill::Idle(_, _))
-    {
+    if skill.is_some_and(|skill| !matches!(context.skill(skill), Skill::Idle(_, _))) {
         return false;
     }
     true
@@ -827,12 +1994,21 @@ fn should_queue_fixed_action(
 
 #[cfg(test)]
 mod tests {
-    use std::time::{Duration, Instant};
+    use std::{
+        assert_matches::assert_matches,
+        time::{Duration, Instant},
+    };
 
     use opencv::core::{Point, Vec4b};
 
     use super::*;
-    use crate::{Position, buff::BuffKind, minimap::MinimapIdle, skill::SkillKind};
+    use crate::{
+        Bound, KeyBindingConfiguration, Position,
+        buff::BuffKind,
+        clock::{Clock, ManualClock},
+        minimap::MinimapIdle,
+        skill::SkillKind,
+    };
 
     const NORMAL_ACTION: Action = Action::Move(ActionMove {
         position: Position {
@@ -843,6 +2019,7 @@ mod tests {
         },
         condition: ActionCondition::Any,
         wait_after_move_millis: 0,
+        weight: 1,
     });
     const PRIORITY_ACTION: Action = Action::Move(ActionMove {
         position: Position {
@@ -853,95 +2030,751 @@ mod tests {
         },
         condition: ActionCondition::ErdaShowerOffCooldown,
         wait_after_move_millis: 0,
+        weight: 1,
     });
 
     #[test]
     fn rotator_at_least_millis_passed_since() {
         let now = Instant::now();
-        assert!(at_least_millis_passed_since(None, 1000));
+        assert!(at_least_millis_passed_since(now, None, 1000));
         assert!(at_least_millis_passed_since(
-            Some(now - Duration::from_millis(2000)),
+            now + Duration::from_millis(2000),
+            Some(now),
             1000
         ));
         assert!(!at_least_millis_passed_since(
-            Some(now - Duration::from_millis(500)),
+            now + Duration::from_millis(500),
+            Some(now),
             1000
         ));
     }
 
     #[test]
     fn rotator_should_queue_fixed_action_every_millis() {
-        let context = Context::new(None, None);
-        let now = Instant::now();
+        let clock = ManualClock::default();
+        let context = Context {
+            clock: Box::new(clock.clone()),
+            ..Context::new(None, None)
+        };
+        let last_queued_time = Some(clock.now());
 
-        assert!(should_queue_fixed_action(
+        clock.advance(Duration::from_millis(1000));
+        assert!(!should_queue_fixed_action(
             &context,
-            Some(now - Duration::from_millis(3000)),
+            last_queued_time,
             ActionCondition::EveryMillis(2000)
         ));
-        assert!(!should_queue_fixed_action(
+
+        clock.advance(Duration::from_millis(2000));
+        assert!(should_queue_fixed_action(
             &context,
-            Some(now - Duration::from_millis(1000)),
+            last_queued_time,
             ActionCondition::EveryMillis(2000)
         ));
     }
 
     #[test]
     fn rotator_should_queue_fixed_action_erda_shower() {
-        let mut context = Context::new(None, None);
-        let now = Instant::now();
+        let clock = ManualClock::default();
+        let mut context = Context {
+            clock: Box::new(clock.clone()),
+            ..Context::new(None, None)
+        };
+        let last_queued_time = Some(clock.now());
 
         context.skills[SkillKind::ErdaShower] = Skill::Idle(Point::default(), Vec4b::default());
+        clock.advance(Duration::from_millis(
+            COOLDOWN_BETWEEN_QUEUE_MILLIS as u64 - 1000,
+        ));
         assert!(!should_queue_fixed_action(
             &context,
-            Some(now - Duration::from_millis(COOLDOWN_BETWEEN_QUEUE_MILLIS as u64 - 1000)),
+            last_queued_time,
             ActionCondition::ErdaShowerOffCooldown
         ));
+
+        clock.advance(Duration::from_millis(1000));
         assert!(should_queue_fixed_action(
             &context,
-            Some(now - Duration::from_millis(COOLDOWN_BETWEEN_QUEUE_MILLIS as u64)),
+            last_queued_time,
             ActionCondition::ErdaShowerOffCooldown
         ));
 
         context.skills[SkillKind::ErdaShower] = Skill::Detecting;
         assert!(!should_queue_fixed_action(
             &context,
-            Some(now - Duration::from_millis(COOLDOWN_BETWEEN_QUEUE_MILLIS as u64)),
+            last_queued_time,
             ActionCondition::ErdaShowerOffCooldown
         ));
     }
 
     #[test]
-    fn rotator_build_actions() {
-        let mut rotator = Rotator::default();
-        let actions = vec![NORMAL_ACTION, NORMAL_ACTION, PRIORITY_ACTION];
-        let buffs = vec![(BuffKind::Rune, KeyBinding::default()); 4];
-
-        rotator.build_actions(
-            RotatorMode::default(),
-            &actions,
-            &buffs,
-            KeyBinding::A,
-            true,
-            false,
-        );
-        assert_eq!(rotator.priority_actions.len(), 7);
-        assert_eq!(rotator.normal_actions.len(), 2);
+    fn rotator_should_queue_fixed_action_custom_skill() {
+        let clock = ManualClock::default();
+        let mut context = Context {
+            clock: Box::new(clock.clone()),
+            ..Context::new(None, None)
+        };
+        let last_queued_time = Some(clock.now());
+
+        clock.advance(Duration::from_millis(COOLDOWN_BETWEEN_QUEUE_MILLIS as u64));
+        assert!(!should_queue_fixed_action(
+            &context,
+            last_queued_time,
+            ActionCondition::SkillOffCooldown(1)
+        ));
+
+        context
+            .custom_skills
+            .insert(1, Skill::Idle(Point::default(), Vec4b::default()));
+        assert!(should_queue_fixed_action(
+            &context,
+            last_queued_time,
+            ActionCondition::SkillOffCooldown(1)
+        ));
+        assert!(!should_queue_fixed_action(
+            &context,
+            last_queued_time,
+            ActionCondition::SkillOffCooldown(2)
+        ));
     }
 
     #[test]
-    fn rotator_rotate_action_start_to_end_then_reverse() {
+    fn rotator_priority_action_every_millis_uses_clock() {
+        let clock = ManualClock::default();
+        let context = Context {
+            clock: Box::new(clock.clone()),
+            ..Context::new(None, None)
+        };
         let mut rotator = Rotator::default();
         let mut player = PlayerState::default();
-        let context = Context::new(None, None);
-        rotator.normal_rotate_mode = RotatorMode::StartToEndThenReverse;
-        for i in 0..2 {
-            rotator
-                .normal_actions
-                .push((i, RotatorAction::Single(NORMAL_ACTION.into())));
-        }
-
-        rotator.rotate_action(&context, &mut player);
+        rotator.priority_actions.insert(
+            2,
+            priority_action(
+                RotatorAction::Single(NORMAL_ACTION.into()),
+                ActionCondition::EveryMillis(5000),
+                false,
+            ),
+        );
+
+        rotator.rotate_action(&context, &mut player);
+        assert_eq!(player.priority_action_id(), Some(2));
+
+        // Still ignored while executing so the last queued time keeps moving
+        clock.advance(Duration::from_millis(5000));
+        rotator.rotate_action(&context, &mut player);
+        assert!(rotator.priority_actions_queue.is_empty());
+
+        player.clear_actions_aborted();
+        clock.advance(Duration::from_millis(4999));
+        rotator.rotate_action(&context, &mut player);
+        assert!(!player.has_priority_action());
+
+        clock.advance(Duration::from_millis(1));
+        rotator.rotate_action(&context, &mut player);
+        assert_eq!(player.priority_action_id(), Some(2));
+    }
+
+    #[test]
+    fn rotator_action_statistics() {
+        let clock = ManualClock::default();
+        let context = Context {
+            clock: Box::new(clock.clone()),
+            ..Context::new(None, None)
+        };
+        let mut rotator = Rotator::default();
+        let mut player = PlayerState::default();
+        let actions = vec![Action::Move(ActionMove {
+            condition: ActionCondition::EveryMillis(1000),
+            ..ActionMove::default()
+        })];
+        rotator.build_actions(BuildActionsConfig {
+            actions: &actions,
+            ..BuildActionsConfig::default()
+        });
+
+        rotator.rotate_action(&context, &mut player);
+        player.clear_action_completed();
+        clock.advance(Duration::from_millis(500));
+        rotator.rotate_action(&context, &mut player);
+        let statistics = rotator.action_statistics(clock.now());
+        assert_eq!(
+            statistics.get(&0),
+            Some(&ActionStatistics {
+                queued_count: 1,
+                started_count: 1,
+                completed_count: 1,
+                aborted_count: 0,
+                average_latency_millis: 0,
+                millis_since_last_run: Some(500),
+                starved: false,
+            })
+        );
+
+        // Not run for more than twice the interval
+        clock.advance(Duration::from_millis(1501));
+        let statistics = rotator.action_statistics(clock.now());
+        assert!(statistics.get(&0).unwrap().starved);
+    }
+
+    #[test]
+    fn rotator_build_actions_keeps_last_queued_time() {
+        let clock = ManualClock::default();
+        let context = Context {
+            clock: Box::new(clock.clone()),
+            ..Context::new(None, None)
+        };
+        let mut rotator = Rotator::default();
+        let mut player = PlayerState::default();
+        let action = Action::Move(ActionMove {
+            condition: ActionCondition::EveryMillis(1000),
+            ..ActionMove::default()
+        });
+        let build = |rotator: &mut Rotator, actions: &[Action]| {
+            rotator.build_actions(BuildActionsConfig {
+                actions,
+                ..BuildActionsConfig::default()
+            });
+        };
+        let last_queued_time = |rotator: &Rotator| {
+            rotator
+                .priority_actions
+                .values()
+                .find(|action| action.condition_kind.is_some())
+                .unwrap()
+                .last_queued_time
+        };
+
+        build(&mut rotator, &[action]);
+        rotator.rotate_action(&context, &mut player);
+        player.clear_action_completed();
+        let queued_time = last_queued_time(&rotator);
+        assert!(queued_time.is_some());
+
+        // Unrelated action added
+        build(&mut rotator, &[NORMAL_ACTION, action]);
+        assert_eq!(last_queued_time(&rotator), queued_time);
+
+        clock.advance(Duration::from_millis(500));
+        rotator.rotate_action(&context, &mut player);
+        assert!(!player.has_priority_action());
+
+        rotator.reset_timers();
+        assert_eq!(last_queued_time(&rotator), None);
+        rotator.rotate_action(&context, &mut player);
+        assert!(player.has_priority_action());
+    }
+
+    #[test]
+    fn rotator_buff_priority_action_known_duration() {
+        let clock = ManualClock::default();
+        let mut context = Context {
+            clock: Box::new(clock.clone()),
+            minimap: Minimap::Idle(MinimapIdle::default()),
+            ..Context::new(None, None)
+        };
+        let mut player = PlayerState::default();
+        let action = buff_priority_action(
+            BuffId::Kind(BuffKind::SayramElixir),
+            KeyBinding::default(),
+            Some(60_000),
+        );
+
+        // Relies on the detection without the timer
+        context.buffs[BuffKind::SayramElixir] = Buff::NoBuff;
+        assert!((action.condition.0)(&context, &mut player, None));
+
+        // Trusts the missing icon over the timer
+        let last_queued_time = Some(clock.now());
+        clock.advance(Duration::from_millis(30_000));
+        assert!((action.condition.0)(
+            &context,
+            &mut player,
+            last_queued_time
+        ));
+
+        // Does not use again before the timer with the icon detected
+        context.buffs[BuffKind::SayramElixir] = Buff::HasBuff;
+        assert!(!(action.condition.0)(
+            &context,
+            &mut player,
+            last_queued_time
+        ));
+
+        // Uses again before expiry even with the icon detected
+        clock.advance(Duration::from_millis(30_000 - PREBUFF_BEFORE_EXPIRY_MILLIS));
+        assert!((action.condition.0)(
+            &context,
+            &mut player,
+            last_queued_time
+        ));
+    }
+
+    #[test]
+    fn rotator_placeable_priority_action_out_of_range() {
+        let clock = ManualClock::default();
+        let context = Context {
+            clock: Box::new(clock.clone()),
+            minimap: Minimap::Idle(MinimapIdle::default()),
+            ..Context::new(None, None)
+        };
+        let mut player = PlayerState::default();
+        let placement = Rc::new(Cell::new(None));
+        let placeable = PlaceableSkill {
+            duration_millis: 60_000,
+            max_distance: 10,
+            ..PlaceableSkill::default()
+        };
+        let action = placeable_priority_action(&placeable, placement.clone());
+
+        // Places at the current position
+        player.last_known_pos = Some(Point::new(0, 0));
+        assert!((action.condition.0)(&context, &mut player, None));
+        assert_eq!(placement.get(), None);
+        let last_queued_time = Some(clock.now());
+        clock.advance(Duration::from_millis(5_000));
+        placement.set(Some(Placement {
+            position: Point::new(0, 0),
+            placed_at: clock.now(),
+        }));
+
+        // Does not place again while in range and not expired
+        clock.advance(Duration::from_millis(COOLDOWN_BETWEEN_QUEUE_MILLIS as u64));
+        player.last_known_pos = Some(Point::new(6, 6));
+        assert!(!(action.condition.0)(
+            &context,
+            &mut player,
+            last_queued_time
+        ));
+
+        // Places again when out of range
+        player.last_known_pos = Some(Point::new(20, 0));
+        assert!((action.condition.0)(
+            &context,
+            &mut player,
+            last_queued_time
+        ));
+
+        // Does not place again before expiry measured from the placement
+        player.last_known_pos = Some(Point::new(0, 0));
+        clock.advance(Duration::from_millis(
+            60_000 - 5_000 - COOLDOWN_BETWEEN_QUEUE_MILLIS as u64,
+        ));
+        assert!(!(action.condition.0)(
+            &context,
+            &mut player,
+            last_queued_time
+        ));
+
+        // Places again when expired
+        clock.advance(Duration::from_millis(5_000));
+        assert!((action.condition.0)(
+            &context,
+            &mut player,
+            last_queued_time
+        ));
+    }
+
+    #[test]
+    fn rotator_placeable_priority_action_records_placement_on_completion() {
+        let clock = ManualClock::default();
+        let context = Context {
+            clock: Box::new(clock.clone()),
+            minimap: Minimap::Idle(MinimapIdle::default()),
+            ..Context::new(None, None)
+        };
+        let mut rotator = Rotator::default();
+        let mut player = PlayerState::default();
+        let placeable = PlaceableSkill {
+            key: KeyBindingConfiguration {
+                key: KeyBinding::A,
+                enabled: true,
+            },
+            ..PlaceableSkill::default()
+        };
+        rotator.build_actions(BuildActionsConfig {
+            placeables: &[placeable],
+            ..BuildActionsConfig::default()
+        });
+        let placement = rotator.placements[&0].clone();
+        player.last_known_pos = Some(Point::new(10, 10));
+
+        rotator.rotate_action(&context, &mut player);
+        assert!(player.has_priority_action());
+        assert_eq!(placement.get(), None);
+
+        // Dropped before the key is sent, queues again without recording the placement
+        player.clear_actions_aborted();
+        rotator.rotate_action(&context, &mut player);
+        assert!(player.has_priority_action());
+        assert_eq!(placement.get(), None);
+
+        // Records the position and time once completed
+        clock.advance(Duration::from_millis(1000));
+        player.last_known_pos = Some(Point::new(20, 10));
+        player.clear_action_completed();
+        rotator.rotate_action(&context, &mut player);
+        assert!(!player.has_priority_action());
+        assert_eq!(
+            placement.get(),
+            Some(Placement {
+                position: Point::new(20, 10),
+                placed_at: clock.now(),
+            })
+        );
+    }
+
+    #[test]
+    fn rotator_mana_potion_priority_action() {
+        let clock = ManualClock::default();
+        let context = Context {
+            clock: Box::new(clock.clone()),
+            ..Context::new(None, None)
+        };
+        let mut player = PlayerState::default();
+        let action = mana_potion_priority_action(KeyBinding::A, 0.5);
+
+        // Unknown mana
+        assert!(!(action.condition.0)(&context, &mut player, None));
+
+        player.mana = Some((60, 100));
+        assert!(!(action.condition.0)(&context, &mut player, None));
+
+        player.mana = Some((50, 100));
+        assert!((action.condition.0)(&context, &mut player, None));
+
+        // Still in cooldown
+        let last_queued_time = Some(clock.now());
+        assert!(!(action.condition.0)(
+            &context,
+            &mut player,
+            last_queued_time
+        ));
+        clock.advance(Duration::from_millis(
+            COOLDOWN_BETWEEN_POTION_QUEUE_MILLIS as u64,
+        ));
+        assert!((action.condition.0)(
+            &context,
+            &mut player,
+            last_queued_time
+        ));
+    }
+
+    #[test]
+    fn rotator_read_mesos_priority_action() {
+        let clock = ManualClock::default();
+        let mut context = Context {
+            clock: Box::new(clock.clone()),
+            ..Context::new(None, None)
+        };
+        let mut player = PlayerState::default();
+        let action = read_mesos_priority_action(1000);
+
+        // Minimap not detected
+        assert!(!(action.condition.0)(&context, &mut player, None));
+
+        context.minimap = Minimap::Idle(MinimapIdle::default());
+        assert!((action.condition.0)(&context, &mut player, None));
+
+        // Has other priority action
+        player.set_priority_action(0, PlayerAction::SolveRune);
+        assert!(!(action.condition.0)(&context, &mut player, None));
+        player.clear_actions_aborted();
+
+        let last_queued_time = Some(clock.now());
+        assert!(!(action.condition.0)(
+            &context,
+            &mut player,
+            last_queued_time
+        ));
+        clock.advance(Duration::from_millis(1000));
+        assert!((action.condition.0)(
+            &context,
+            &mut player,
+            last_queued_time
+        ));
+    }
+
+    #[test]
+    fn rotator_potion_rule_stop_attacking() {
+        let context = Context::new(None, None);
+        let mut player = PlayerState::default();
+        let mut rotator = Rotator::default();
+        rotator.build_actions(BuildActionsConfig {
+            mode: RotatorMode::StartToEnd,
+            actions: &[NORMAL_ACTION],
+            potion_rules: &[PotionRule {
+                key: KeyBindingConfiguration {
+                    key: KeyBinding::A,
+                    enabled: true,
+                },
+                below_percent: 30.0,
+                stop_attacking: true,
+                ..PotionRule::default()
+            }],
+            ..BuildActionsConfig::default()
+        });
+
+        player.health = Some((50, 100));
+        rotator.rotate_action(&context, &mut player);
+        assert!(player.has_normal_action());
+        assert!(!player.has_priority_action());
+
+        // Aborts the normal action and only uses the potion
+        player.health = Some((20, 100));
+        rotator.rotate_action(&context, &mut player);
+        assert!(!player.has_normal_action());
+        assert!(player.has_priority_action());
+
+        player.clear_actions_aborted();
+        rotator.rotate_action(&context, &mut player);
+        assert!(!player.has_normal_action());
+
+        // Resumes once recovered
+        player.health = Some((50, 100));
+        rotator.rotate_action(&context, &mut player);
+        assert!(player.has_normal_action());
+    }
+
+    #[test]
+    fn rotator_potion_rules_lowest_reached() {
+        let context = Context::new(None, None);
+        let mut player = PlayerState::default();
+        let rule = |id, below_percent| PotionRule {
+            id,
+            key: KeyBindingConfiguration {
+                key: KeyBinding::A,
+                enabled: true,
+            },
+            below_percent,
+            ..PotionRule::default()
+        };
+        let mut rotator = Rotator::default();
+        rotator.build_actions(BuildActionsConfig {
+            potion_rules: &[rule(0, 60.0), rule(1, 30.0)],
+            ..BuildActionsConfig::default()
+        });
+        let action = |rotator: &Rotator, rule_id| {
+            let id = rotator
+                .priority_action_keys
+                .iter()
+                .find_map(|(id, key)| {
+                    (*key == PriorityActionKey::PotionRule(rule_id)).then_some(*id)
+                })
+                .unwrap();
+            rotator.priority_actions.get(&id).unwrap()
+        };
+
+        player.health = Some((70, 100));
+        assert!(!(action(&rotator, 0).condition.0)(
+            &context,
+            &mut player,
+            None
+        ));
+        assert!(!(action(&rotator, 1).condition.0)(
+            &context,
+            &mut player,
+            None
+        ));
+
+        player.health = Some((50, 100));
+        assert!((action(&rotator, 0).condition.0)(
+            &context,
+            &mut player,
+            None
+        ));
+        assert!(!(action(&rotator, 1).condition.0)(
+            &context,
+            &mut player,
+            None
+        ));
+
+        player.health = Some((20, 100));
+        assert!(!(action(&rotator, 0).condition.0)(
+            &context,
+            &mut player,
+            None
+        ));
+        assert!((action(&rotator, 1).condition.0)(
+            &context,
+            &mut player,
+            None
+        ));
+    }
+
+    #[test]
+    fn rotator_buff_mismatches() {
+        let clock = ManualClock::default();
+        let mut context = Context {
+            clock: Box::new(clock.clone()),
+            minimap: Minimap::Idle(MinimapIdle::default()),
+            ..Context::new(None, None)
+        };
+        let mut rotator = Rotator::default();
+        rotator.build_actions(BuildActionsConfig {
+            buffs: &[(
+                BuffId::Kind(BuffKind::SayramElixir),
+                KeyBinding::default(),
+                Some(60_000),
+            )],
+            ..BuildActionsConfig::default()
+        });
+        for action in rotator.priority_actions.values_mut() {
+            action.last_queued_time = Some(clock.now());
+        }
+
+        // Waits for the detection to catch up
+        context.buffs[BuffKind::SayramElixir] = Buff::NoBuff;
+        assert!(rotator.buff_mismatches(&context).is_empty());
+
+        clock.advance(Duration::from_millis(COOLDOWN_BETWEEN_QUEUE_MILLIS as u64));
+        assert_eq!(
+            rotator.buff_mismatches(&context),
+            vec![BuffId::Kind(BuffKind::SayramElixir)]
+        );
+
+        context.buffs[BuffKind::SayramElixir] = Buff::HasBuff;
+        assert!(rotator.buff_mismatches(&context).is_empty());
+
+        clock.advance(Duration::from_millis(60_000));
+        assert_eq!(
+            rotator.buff_mismatches(&context),
+            vec![BuffId::Kind(BuffKind::SayramElixir)]
+        );
+    }
+
+    #[test]
+    fn rotator_build_actions() {
+        let mut rotator = Rotator::default();
+        let actions = vec![NORMAL_ACTION, NORMAL_ACTION, PRIORITY_ACTION];
+        let buffs = vec![(BuffId::Kind(BuffKind::Rune), KeyBinding::default(), None); 4];
+
+        rotator.build_actions(BuildActionsConfig {
+            actions: &actions,
+            buffs: &buffs,
+            potion_key: KeyBinding::A,
+            enable_rune_solving: true,
+            ..BuildActionsConfig::default()
+        });
+        assert_eq!(rotator.priority_actions.len(), 7);
+        assert_eq!(rotator.normal_actions.len(), 2);
+    }
+
+    #[test]
+    fn rotator_build_actions_expand_macros() {
+        let mut rotator = Rotator::default();
+        let macros = vec![ActionMacro {
+            id: 1,
+            name: "Burst".to_string(),
+            actions: vec![PRIORITY_ACTION, NORMAL_ACTION, NORMAL_ACTION],
+        }];
+        let actions = vec![
+            NORMAL_ACTION,
+            Action::Macro(ActionMacroRef {
+                id: 1,
+                condition: ActionCondition::EveryMillis(1000),
+                ..ActionMacroRef::default()
+            }),
+            // Missing macro is removed along with its linked action
+            Action::Macro(ActionMacroRef {
+                id: 2,
+                ..ActionMacroRef::default()
+            }),
+            Action::Move(ActionMove {
+                condition: ActionCondition::Linked,
+                ..ActionMove::default()
+            }),
+        ];
+
+        let (expanded, indices) = expand_macros(&actions, &macros);
+        assert_eq!(indices, vec![0, 1, 1, 1]);
+        assert_matches!(
+            expanded[1],
+            Action::Move(ActionMove {
+                condition: ActionCondition::EveryMillis(1000),
+                ..
+            })
+        );
+        assert_matches!(
+            expanded[2],
+            Action::Move(ActionMove {
+                condition: ActionCondition::Linked,
+                ..
+            })
+        );
+
+        rotator.build_actions(BuildActionsConfig {
+            actions: &actions,
+            macros: &macros,
+            potion_key: KeyBinding::A,
+            ..BuildActionsConfig::default()
+        });
+        assert_eq!(rotator.normal_actions.len(), 1);
+        // Includes elite boss potion spam action
+        assert_eq!(rotator.priority_actions.len(), 2);
+        assert_matches!(
+            rotator.priority_actions.get(&1).unwrap().inner,
+            RotatorAction::Linked(_)
+        );
+        assert_eq!(rotator.action_index(1), Some(1));
+    }
+
+    #[test]
+    fn rotator_linked_action_branch() {
+        let mut rotator = Rotator::default();
+        let mut player = PlayerState::default();
+        let context = Context::new(None, None);
+        let key = |key, condition| {
+            Action::Key(ActionKey {
+                key,
+                condition,
+                ..ActionKey::default()
+            })
+        };
+        let actions = vec![
+            key(KeyBinding::A, ActionCondition::Any),
+            Action::Branch(ActionBranch {
+                condition: ActionCondition::Linked,
+                predicate: ActionBranchPredicate::EliteBossPresent,
+                then_count: 1,
+                else_count: 1,
+                ..ActionBranch::default()
+            }),
+            key(KeyBinding::B, ActionCondition::Linked),
+            key(KeyBinding::C, ActionCondition::Linked),
+            key(KeyBinding::D, ActionCondition::Linked),
+        ];
+        rotator.build_actions(BuildActionsConfig {
+            mode: RotatorMode::StartToEnd,
+            actions: &actions,
+            ..BuildActionsConfig::default()
+        });
+        assert_eq!(rotator.normal_actions.len(), 1);
+
+        // No elite boss so the true branch is skipped
+        for expected in [KeyBinding::A, KeyBinding::C, KeyBinding::D] {
+            rotator.rotate_action(&context, &mut player);
+            assert_matches!(
+                player.normal_action(),
+                Some(PlayerAction::Key(PlayerActionKey { key, .. })) if key == expected
+            );
+            player.clear_action_completed();
+        }
+        assert!(rotator.normal_queuing_linked_action.is_none());
+    }
+
+    #[test]
+    fn rotator_rotate_action_start_to_end_then_reverse() {
+        let mut rotator = Rotator::default();
+        let mut player = PlayerState::default();
+        let context = Context::new(None, None);
+        rotator.normal_rotate_mode = RotatorMode::StartToEndThenReverse;
+        for i in 0..2 {
+            rotator
+                .normal_actions
+                .push((i, RotatorAction::Single(NORMAL_ACTION.into())));
+        }
+
+        rotator.rotate_action(&context, &mut player);
         assert!(player.has_normal_action());
         assert!(!rotator.normal_actions_backward);
         assert_eq!(rotator.normal_index, 1);
@@ -970,6 +2803,7 @@ mod tests {
         assert!(player.has_normal_action());
         assert!(!rotator.normal_actions_backward);
         assert_eq!(rotator.normal_index, 1);
+        assert_eq!(rotator.normal_cycles(), 0);
 
         player.clear_actions_aborted();
 
@@ -977,6 +2811,230 @@ mod tests {
         assert!(player.has_normal_action());
         assert!(!rotator.normal_actions_backward);
         assert_eq!(rotator.normal_index, 0);
+        assert_eq!(rotator.normal_cycles(), 1);
+    }
+
+    #[test]
+    fn rotator_rotate_action_random() {
+        let mut rotator = Rotator::default();
+        let mut player = PlayerState::default();
+        let context = Context::new(None, None);
+        rotator.normal_rotate_mode = RotatorMode::Random;
+        for i in 0..2 {
+            rotator
+                .normal_actions
+                .push((i, RotatorAction::Single(NORMAL_ACTION.into())));
+        }
+
+        rotator.rotate_action(&context, &mut player);
+        assert!(player.has_normal_action());
+        let id = player.normal_action_id().unwrap();
+        assert_eq!(rotator.normal_last_index, Some(id as usize));
+
+        for _ in 0..10 {
+            player.clear_actions_aborted();
+
+            // No immediate repeat
+            rotator.rotate_action(&context, &mut player);
+            assert!(player.has_normal_action());
+            assert_ne!(player.normal_action_id(), Some(id));
+
+            player.clear_actions_aborted();
+            rotator.rotate_action(&context, &mut player);
+            assert_eq!(player.normal_action_id(), Some(id));
+        }
+    }
+
+    #[test]
+    fn rotator_rotate_action_weighted() {
+        let mut rotator = Rotator::default();
+        let mut player = PlayerState::default();
+        let context = Context::new(None, None);
+        rotator.normal_rotate_mode = RotatorMode::Weighted;
+        for i in 0..3 {
+            rotator
+                .normal_actions
+                .push((i, RotatorAction::Single(NORMAL_ACTION.into())));
+        }
+        rotator.normal_actions_weight = vec![0, 5, 0];
+
+        for _ in 0..10 {
+            rotator.rotate_action(&context, &mut player);
+            assert!(player.has_normal_action());
+            assert_eq!(player.normal_action_id(), Some(1));
+
+            player.clear_actions_aborted();
+        }
+    }
+
+    #[test]
+    fn rotator_rotate_action_shuffle_each_cycle() {
+        let mut rotator = Rotator::default();
+        let mut player = PlayerState::default();
+        let context = Context::new(None, None);
+        rotator.normal_rotate_mode = RotatorMode::ShuffleEachCycle;
+        for i in 0..3 {
+            rotator
+                .normal_actions
+                .push((i, RotatorAction::Single(NORMAL_ACTION.into())));
+        }
+
+        for _ in 0..2 {
+            let mut ids = Vec::new();
+            for _ in 0..3 {
+                rotator.rotate_action(&context, &mut player);
+                assert!(player.has_normal_action());
+                ids.push(player.normal_action_id().unwrap());
+
+                player.clear_actions_aborted();
+            }
+            assert_eq!(rotator.normal_index, 0);
+            ids.sort();
+            assert_eq!(ids, vec![0, 1, 2]);
+        }
+    }
+
+    #[test]
+    fn rotator_mode_from_minimap_patrol_keeps_zones() {
+        let zone = PatrolZone {
+            dwell_millis: 1000,
+            ..PatrolZone::default()
+        };
+        let minimap = MinimapData {
+            rotation_mode: RotationMode::Patrol,
+            patrol_zones: vec![zone, zone],
+            ..MinimapData::default()
+        };
+
+        assert_matches!(
+            RotatorMode::from(&minimap),
+            RotatorMode::Patrol(zones) if zones == vec![zone, zone]
+        );
+    }
+
+    #[test]
+    fn rotator_rotate_action_patrol() {
+        let clock = ManualClock::default();
+        let mut minimap = MinimapIdle::default();
+        minimap.bbox = Rect::new(0, 0, 100, 100);
+        let context = Context {
+            clock: Box::new(clock.clone()),
+            minimap: Minimap::Idle(minimap),
+            ..Context::new(None, None)
+        };
+        let mut rotator = Rotator::default();
+        let mut player = PlayerState::default();
+        let zone = PatrolZone {
+            bound: Bound {
+                x: 0,
+                y: 0,
+                width: 50,
+                height: 100,
+            },
+            dwell_millis: 1000,
+            direction: ActionKeyDirection::Left,
+            ..PatrolZone::default()
+        };
+        rotator.normal_rotate_mode = RotatorMode::Patrol(vec![
+            zone,
+            PatrolZone {
+                bound: Bound {
+                    x: 50,
+                    ..zone.bound
+                },
+                ..zone
+            },
+        ]);
+        player.last_known_pos = Some(Point::new(10, 10));
+
+        // Inside the first zone uses the initial direction
+        rotator.rotate_action(&context, &mut player);
+        assert_eq!(player.normal_action_id(), Some(u32::MAX - 1));
+        assert_matches!(
+            player.normal_action(),
+            Some(PlayerAction::PingPong(PlayerActionPingPong {
+                direction: PingPongDirection::Left,
+                ..
+            }))
+        );
+        assert_eq!(rotator.patrol_index, 0);
+        assert!(rotator.patrol_zone_entered.is_some());
+
+        // Dwell time passed, moves to the second zone
+        player.clear_actions_aborted();
+        clock.advance(Duration::from_millis(1000));
+        rotator.rotate_action(&context, &mut player);
+        assert_eq!(rotator.patrol_index, 1);
+        assert!(rotator.patrol_zone_entered.is_none());
+        assert_eq!(rotator.normal_cycles(), 0);
+        assert_eq!(player.normal_action_id(), Some(u32::MAX - 2));
+        assert_matches!(
+            player.normal_action(),
+            Some(PlayerAction::Move(PlayerActionMove {
+                position: Position { x: 75, y: 0, .. },
+                platforms_pathing: true,
+                ..
+            }))
+        );
+
+        // Entered the second zone
+        player.clear_actions_aborted();
+        player.last_known_pos = Some(Point::new(60, 10));
+        rotator.rotate_action(&context, &mut player);
+        assert_eq!(rotator.patrol_index, 1);
+        assert!(rotator.patrol_zone_entered.is_some());
+        assert_eq!(player.normal_action_id(), Some(u32::MAX - 1));
+
+        // Visited all zones, wraps back to the first zone
+        player.clear_actions_aborted();
+        clock.advance(Duration::from_millis(1000));
+        rotator.rotate_action(&context, &mut player);
+        assert_eq!(rotator.patrol_index, 0);
+        assert_eq!(rotator.normal_cycles(), 1);
+    }
+
+    #[test]
+    fn rotator_rotate_action_ping_pong_counts_cycles() {
+        let mut minimap = MinimapIdle::default();
+        minimap.bbox = Rect::new(0, 0, 100, 100);
+        let context = Context {
+            minimap: Minimap::Idle(minimap),
+            ..Context::new(None, None)
+        };
+        let mut rotator = Rotator::default();
+        let mut player = PlayerState::default();
+        rotator.normal_rotate_mode = RotatorMode::PingPong(PingPong::default());
+        player.last_known_pos = Some(Point::new(10, 10));
+
+        rotator.rotate_action(&context, &mut player);
+        assert_eq!(rotator.normal_cycles(), 1);
+
+        player.clear_actions_aborted();
+        rotator.rotate_action(&context, &mut player);
+        assert_eq!(rotator.normal_cycles(), 2);
+    }
+
+    #[test]
+    fn rotator_build_actions_keeps_normal_cycles() {
+        let mut rotator = Rotator::default();
+        let mut player = PlayerState::default();
+        let context = Context::new(None, None);
+        let build = |rotator: &mut Rotator| {
+            rotator.build_actions(BuildActionsConfig {
+                mode: RotatorMode::StartToEnd,
+                actions: &[NORMAL_ACTION],
+                ..BuildActionsConfig::default()
+            });
+        };
+        build(&mut rotator);
+
+        rotator.rotate_action(&context, &mut player);
+        assert_eq!(rotator.normal_cycles(), 1);
+
+        build(&mut rotator);
+        player.clear_actions_aborted();
+        rotator.rotate_action(&context, &mut player);
+        assert_eq!(rotator.normal_cycles(), 2);
     }
 
     #[test]
@@ -1095,9 +3153,9 @@ mod tests {
                 condition: Condition(Box::new(|_, _, _| true)),
                 condition_kind: None,
                 inner: RotatorAction::Linked(LinkedAction {
-                    inner: NORMAL_ACTION.into(),
+                    inner: LinkedActionKind::Action(NORMAL_ACTION.into()),
                     next: Some(Box::new(LinkedAction {
-                        inner: NORMAL_ACTION.into(),
+                        inner: LinkedActionKind::Action(NORMAL_ACTION.into()),
                         next: None,
                     })),
                 }),
@@ -0,0 +1,553 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use anyhow::Result;
+use platforms::windows::{Handle, KeyKind};
+
+use crate::{
+    Action, ActionBranch, ActionCondition, ActionKey, ActionMacro, ActionMacroRef, ActionMove,
+    ActionSequence, Minimap as MinimapData, Settings,
+    bridge::{KeySender, KeySenderMethod},
+    buff::{Buff, BuffKind},
+    clock::ManualClock,
+    context::{Context, MS_PER_TICK},
+    minimap::Minimap,
+    network::DiscordNotification,
+    player::{
+        Player, PlayerAction, PlayerActionKey, PlayerActionMove, PlayerActionSequence, PlayerState,
+    },
+    rotator::{BuildActionsConfig, Rotator, RotatorMode},
+    skill::{Skill, SkillKind},
+};
+
+/// The maximum total simulated time
+///
+/// [`SimulationOptions::duration_millis`] larger than this is capped.
+pub const MAX_SIMULATION_DURATION_MILLIS: u64 = 6 * 60 * 60 * 1000;
+
+/// The modeled durations used when simulating a rotation
+#[derive(Clone, Copy, Debug)]
+pub struct SimulationOptions {
+    /// The total simulated time capped at [`MAX_SIMULATION_DURATION_MILLIS`]
+    pub duration_millis: u64,
+    /// The time a move action takes before reaching its destination
+    pub move_millis: u64,
+    /// The time a single key press takes
+    pub key_millis: u64,
+    /// The Erda Shower cooldown after an [`ActionCondition::ErdaShowerOffCooldown`] action
+    pub erda_shower_cooldown_millis: u64,
+}
+
+impl Default for SimulationOptions {
+    fn default() -> Self {
+        Self {
+            duration_millis: 10 * 60 * 1000,
+            move_millis: 1500,
+            key_millis: 500,
+            erda_shower_cooldown_millis: 60_000,
+        }
+    }
+}
+
+/// Whether a simulated action came from the normal or priority rotation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimulatedActionKind {
+    Normal,
+    Priority,
+}
+
+/// A single action execution in a simulated timeline
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulatedAction {
+    /// The index of the [`Action`] in the provided actions
+    pub index: usize,
+    pub kind: SimulatedActionKind,
+    pub start_millis: u64,
+    /// The time the action completed or got overridden
+    ///
+    /// [`None`] if the action was still executing when the simulation ended.
+    pub end_millis: Option<u64>,
+    /// Whether the action was overridden by a priority action before completing
+    pub overridden: bool,
+}
+
+/// A potential issue found by [`simulate_rotation`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SimulationWarning {
+    /// The action at the index never started during the simulation
+    NeverFired(usize),
+    /// The [`ActionCondition::EveryMillis`] action at the index has an interval not longer than
+    /// its own simulated execution time
+    ///
+    /// Such action is queued again as soon as it completes and can starve other actions.
+    EveryMillisBelowExecutionTime(usize),
+}
+
+/// The result of [`simulate_rotation`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SimulationReport {
+    pub timeline: Vec<SimulatedAction>,
+    pub warnings: Vec<SimulationWarning>,
+    /// The longest time no normal action was executing because of priority actions
+    pub longest_normal_starvation_millis: u64,
+}
+
+/// The running minimap preset captured for [`simulate_rotation`]
+///
+/// It is captured inside the update loop and simulated outside of it in a blocking task.
+#[derive(Debug)]
+pub struct SimulationInput {
+    minimap: MinimapData,
+    /// The configuration actions followed by the preset actions
+    actions: Vec<Action>,
+    macros: Vec<ActionMacro>,
+    /// The number of configuration actions before the preset actions in [`Self::actions`]
+    preset_offset: usize,
+}
+
+impl SimulationInput {
+    pub fn new(
+        minimap: MinimapData,
+        config_actions: Vec<Action>,
+        preset_actions: &[Action],
+        macros: Vec<ActionMacro>,
+    ) -> Self {
+        let preset_offset = config_actions.len();
+        let actions = config_actions
+            .into_iter()
+            .chain(preset_actions.iter().copied())
+            .collect();
+        Self {
+            minimap,
+            actions,
+            macros,
+            preset_offset,
+        }
+    }
+
+    /// Simulates the captured actions with [`simulate_rotation`]
+    ///
+    /// The indices in the returned [`SimulationReport`] are the indices in the preset. Same as
+    /// action statistics, configuration actions are not part of the preset and are excluded.
+    pub fn simulate(&self, options: SimulationOptions) -> SimulationReport {
+        let offset = self.preset_offset;
+        let report = simulate_rotation(&self.minimap, &self.actions, &self.macros, options);
+
+        SimulationReport {
+            timeline: report
+                .timeline
+                .into_iter()
+                .filter_map(|mut action| {
+                    action.index = action.index.checked_sub(offset)?;
+                    Some(action)
+                })
+                .collect(),
+            warnings: report
+                .warnings
+                .into_iter()
+                .filter_map(|warning| match warning {
+                    SimulationWarning::NeverFired(index) => {
+                        Some(SimulationWarning::NeverFired(index.checked_sub(offset)?))
+                    }
+                    SimulationWarning::EveryMillisBelowExecutionTime(index) => {
+                        Some(SimulationWarning::EveryMillisBelowExecutionTime(
+                            index.checked_sub(offset)?,
+                        ))
+                    }
+                })
+                .collect(),
+            longest_normal_starvation_millis: report.longest_normal_starvation_millis,
+        }
+    }
+}
+
+/// A [`KeySender`] that does nothing
+///
+/// The simulation does not update [`Player`] so no key is ever sent.
+#[derive(Debug)]
+struct NoopKeySender;
+
+impl KeySender for NoopKeySender {
+    fn set_method(&mut self, _: KeySenderMethod) {}
+
+    fn send(&self, _: KeyKind) -> Result<()> {
+        Ok(())
+    }
+
+    fn send_click_to_focus(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn send_up(&self, _: KeyKind) -> Result<()> {
+        Ok(())
+    }
+
+    fn send_down(&self, _: KeyKind) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The action the simulated player is currently executing
+#[derive(Clone, Copy, Debug)]
+struct Executing {
+    id: u32,
+    kind: SimulatedActionKind,
+    /// The index into [`SimulationReport::timeline`]
+    entry: usize,
+    started_tick: u64,
+}
+
+/// Simulates the [`Rotator`] offline over [`SimulationOptions::duration_millis`]
+///
+/// Instead of updating [`Player`], the player completes an action after its modeled
+/// duration. The minimap is never detected so buff, rune and elite boss priority actions are
+/// not simulated. Auto mobbing, ping pong and patrol normal actions also require detection
+/// and are not simulated. The cooldowns of user-registered skills are unknown so
+/// [`ActionCondition::SkillOffCooldown`] actions are never queued.
+pub fn simulate_rotation(
+    minimap: &MinimapData,
+    actions: &[Action],
+    macros: &[ActionMacro],
+    options: SimulationOptions,
+) -> SimulationReport {
+    let clock = ManualClock::default();
+    let mut context = Context {
+        handle: Handle::new(""),
+        keys: Box::new(NoopKeySender),
+        clock: Box::new(clock.clone()),
+        notification: DiscordNotification::new(Rc::new(RefCell::new(Settings::default()))),
+        detector: None,
+        minimap: Minimap::Detecting,
+        platforms: Vec::new(),
+        player: Player::Idle,
+        skills: [Skill::Cooldown; SkillKind::COUNT],
+        custom_skills: HashMap::new(),
+        buffs: [Buff::HasBuff; BuffKind::COUNT],
+        custom_buffs: HashMap::new(),
+        halting: false,
+        tick: 0,
+    };
+    let mut rotator = Rotator::default();
+    let mut player = PlayerState::default();
+    rotator.build_actions(BuildActionsConfig {
+        mode: RotatorMode::from(minimap),
+        actions,
+        macros,
+        reset_normal_actions_on_erda: minimap.actions_any_reset_on_erda_condition,
+        ..BuildActionsConfig::default()
+    });
+
+    let mut report = SimulationReport::default();
+    let mut executing = None::<Executing>;
+    let mut erda_shower_cooldown_until_tick = 0;
+    let mut normal_starving_since_tick = None;
+    let mut fired = HashMap::<usize, u32>::new();
+    let mut longest_execution_millis = HashMap::<usize, u64>::new();
+    let total_ticks = options.duration_millis.min(MAX_SIMULATION_DURATION_MILLIS) / MS_PER_TICK;
+
+    for tick in 1..=total_ticks {
+        clock.advance_ticks(1);
+        context.tick = tick;
+        context.skills[SkillKind::ErdaShower] = if tick >= erda_shower_cooldown_until_tick {
+            Skill::Idle(Default::default(), Default::default())
+        } else {
+            Skill::Cooldown
+        };
+
+        // Completes the executing action after its modeled duration
+        if let Some(current) = executing
+            && active_action(&player).is_some_and(|(id, _, _)| id == current.id)
+        {
+            let (_, _, action) = active_action(&player).unwrap();
+            if tick - current.started_tick >= action_duration_ticks(action, options) {
+                player.clear_action_completed();
+                let entry = &mut report.timeline[current.entry];
+                let execution_millis = (tick - current.started_tick) * MS_PER_TICK;
+                let longest = longest_execution_millis.entry(entry.index).or_default();
+                *longest = (*longest).max(execution_millis);
+                entry.end_millis = Some(tick * MS_PER_TICK);
+                executing = None;
+                if is_erda_shower_action(&rotator, actions, current.id) {
+                    erda_shower_cooldown_until_tick =
+                        tick + options.erda_shower_cooldown_millis / MS_PER_TICK;
+                }
+            }
+        }
+
+        rotator.rotate_action(&context, &mut player);
+
+        let active = active_action(&player);
+        if active.map(|(id, kind, _)| (id, kind))
+            != executing.map(|current| (current.id, current.kind))
+        {
+            if let Some(current) = executing.take() {
+                let entry = &mut report.timeline[current.entry];
+                entry.end_millis = Some(tick * MS_PER_TICK);
+                entry.overridden = true;
+            }
+            if let Some((id, kind, _)) = active
+                && let Some(index) = rotator.action_index(id)
+            {
+                *fired.entry(index).or_default() += 1;
+                report.timeline.push(SimulatedAction {
+                    index,
+                    kind,
+                    start_millis: tick * MS_PER_TICK,
+                    end_millis: None,
+                    overridden: false,
+                });
+                executing = Some(Executing {
+                    id,
+                    kind,
+                    entry: report.timeline.len() - 1,
+                    started_tick: tick,
+                });
+            }
+        }
+
+        let normal_starving = player.has_normal_action() && player.has_priority_action();
+        match (normal_starving, normal_starving_since_tick) {
+            (true, None) => normal_starving_since_tick = Some(tick),
+            (false, Some(since)) => {
+                report.longest_normal_starvation_millis = report
+                    .longest_normal_starvation_millis
+                    .max((tick - since) * MS_PER_TICK);
+                normal_starving_since_tick = None;
+            }
+            _ => (),
+        }
+    }
+
+    for (index, action) in actions.iter().enumerate() {
+        let condition = match action {
+            Action::Move(ActionMove { condition, .. })
+            | Action::Key(ActionKey { condition, .. })
+            | Action::Sequence(ActionSequence { condition, .. })
+            | Action::Macro(ActionMacroRef { condition, .. })
+            | Action::Branch(ActionBranch { condition, .. }) => *condition,
+        };
+        if matches!(
+            condition,
+            ActionCondition::Linked | ActionCondition::SkillOffCooldown(_)
+        ) {
+            continue;
+        }
+        if !fired.contains_key(&index) {
+            report.warnings.push(SimulationWarning::NeverFired(index));
+        }
+        if let ActionCondition::EveryMillis(millis) = condition
+            && let Some(execution_millis) = longest_execution_millis.get(&index)
+            && millis <= *execution_millis
+        {
+            report
+                .warnings
+                .push(SimulationWarning::EveryMillisBelowExecutionTime(index));
+        }
+    }
+
+    report
+}
+
+/// Returns the action the player is executing
+///
+/// Priority action is always executed before the normal action.
+#[inline]
+fn active_action(player: &PlayerState) -> Option<(u32, SimulatedActionKind, PlayerAction)> {
+    if let Some(id) = player.priority_action_id() {
+        return Some((
+            id,
+            SimulatedActionKind::Priority,
+            player.priority_action().unwrap(),
+        ));
+    }
+    player.normal_action_id().map(|id| {
+        (
+            id,
+            SimulatedActionKind::Normal,
+            player.normal_action().unwrap(),
+        )
+    })
+}
+
+#[inline]
+fn is_erda_shower_action(rotator: &Rotator, actions: &[Action], id: u32) -> bool {
+    rotator
+        .action_index(id)
+        .and_then(|index| actions.get(index))
+        .is_some_and(|action| match action {
+            Action::Move(ActionMove { condition, .. })
+            | Action::Key(ActionKey { condition, .. })
+            | Action::Sequence(ActionSequence { condition, .. })
+            | Action::Macro(ActionMacroRef { condition, .. })
+            | Action::Branch(ActionBranch { condition, .. }) => {
+                matches!(condition, ActionCondition::ErdaShowerOffCooldown)
+            }
+        })
+}
+
+#[inline]
+fn action_duration_ticks(action: PlayerAction, options: SimulationOptions) -> u64 {
+    let ticks = match action {
+        PlayerAction::Key(PlayerActionKey {
+            count,
+            position,
+            wait_before_use_ticks,
+            wait_before_use_ticks_random_range,
+            wait_after_use_ticks,
+            wait_after_use_ticks_random_range,
+            hold_ticks,
+            hold_ticks_random_range,
+            ..
+        }) => {
+            let move_ticks = position.map_or(0, |_| options.move_millis / MS_PER_TICK);
+            let wait_ticks = (wait_before_use_ticks
+                + wait_before_use_ticks_random_range / 2
+                + wait_after_use_ticks
+                + wait_after_use_ticks_random_range / 2
+                + hold_ticks
+                + hold_ticks_random_range / 2) as u64;
+            move_ticks + (count as u64) * (options.key_millis / MS_PER_TICK + wait_ticks)
+        }
+        PlayerAction::Move(PlayerActionMove {
+            wait_after_move_ticks,
+            ..
+        }) => options.move_millis / MS_PER_TICK + wait_after_move_ticks as u64,
+        PlayerAction::Sequence(PlayerActionSequence { position, steps }) => {
+            let move_ticks = position.map_or(0, |_| options.move_millis / MS_PER_TICK);
+            let steps_ticks = steps
+                .iter()
+                .map(|step| {
+                    options.key_millis / MS_PER_TICK
+                        + (step.delay_ticks + step.delay_ticks_random_range / 2) as u64
+                })
+                .sum::<u64>();
+            move_ticks + steps_ticks
+        }
+        PlayerAction::SolveRune | PlayerAction::AutoMob(_) | PlayerAction::PingPong(_) => {
+            options.move_millis / MS_PER_TICK
+        }
+        PlayerAction::ReadMesos => 2 * options.key_millis / MS_PER_TICK,
+    };
+    ticks.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AutoMobbing, Position, RotationMode};
+
+    const NORMAL_ACTION: Action = Action::Move(ActionMove {
+        position: Position {
+            x: 0,
+            x_random_range: 0,
+            y: 0,
+            allow_adjusting: false,
+        },
+        condition: ActionCondition::Any,
+        wait_after_move_millis: 0,
+        weight: 1,
+    });
+
+    fn priority_key_action(condition: ActionCondition) -> Action {
+        Action::Key(ActionKey {
+            condition,
+            queue_to_front: Some(false),
+            ..ActionKey::default()
+        })
+    }
+
+    #[test]
+    fn simulate_rotation_timeline() {
+        let actions = vec![
+            NORMAL_ACTION,
+            NORMAL_ACTION,
+            priority_key_action(ActionCondition::EveryMillis(30_000)),
+        ];
+        let minimap = MinimapData {
+            rotation_mode: RotationMode::StartToEnd,
+            ..MinimapData::default()
+        };
+        let report = simulate_rotation(
+            &minimap,
+            &actions,
+            &[],
+            SimulationOptions {
+                duration_millis: 60_000,
+                ..SimulationOptions::default()
+            },
+        );
+
+        let priority = report
+            .timeline
+            .iter()
+            .filter(|action| action.kind == SimulatedActionKind::Priority)
+            .collect::<Vec<_>>();
+        assert!(priority.iter().all(|action| action.index == 2));
+        assert!(priority.len() >= 2);
+        assert!(report.timeline.iter().any(|action| action.index == 0));
+        assert!(report.timeline.iter().any(|action| action.index == 1));
+        assert!(report.timeline.iter().any(|action| action.overridden));
+        assert!(report.longest_normal_starvation_millis > 0);
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn simulate_rotation_flags_never_fired_and_short_every_millis() {
+        let actions = vec![
+            NORMAL_ACTION,
+            priority_key_action(ActionCondition::EveryMillis(400)),
+            priority_key_action(ActionCondition::EveryMillis(5000)),
+        ];
+        let minimap = MinimapData {
+            rotation_mode: RotationMode::AutoMobbing(AutoMobbing::default()),
+            ..MinimapData::default()
+        };
+        let report = simulate_rotation(
+            &minimap,
+            &actions,
+            &[],
+            SimulationOptions {
+                duration_millis: 10_000,
+                ..SimulationOptions::default()
+            },
+        );
+
+        assert_eq!(
+            report.warnings,
+            vec![
+                SimulationWarning::NeverFired(0),
+                SimulationWarning::EveryMillisBelowExecutionTime(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn simulation_input_maps_to_preset_indices() {
+        let minimap = MinimapData {
+            rotation_mode: RotationMode::AutoMobbing(AutoMobbing::default()),
+            ..MinimapData::default()
+        };
+        let input = SimulationInput::new(
+            minimap,
+            vec![priority_key_action(ActionCondition::EveryMillis(5000))],
+            &[
+                NORMAL_ACTION,
+                priority_key_action(ActionCondition::EveryMillis(400)),
+            ],
+            vec![],
+        );
+        let report = input.simulate(SimulationOptions {
+            duration_millis: 10_000,
+            ..SimulationOptions::default()
+        });
+
+        assert!(report.timeline.iter().all(|action| action.index == 1));
+        assert!(!report.timeline.is_empty());
+        assert_eq!(
+            report.warnings,
+            vec![
+                SimulationWarning::NeverFired(0),
+                SimulationWarning::EveryMillisBelowExecutionTime(1)
+            ]
+        );
+    }
+}
@@ -1,14 +1,19 @@
 use std::{
     mem,
     ops::{Index, IndexMut},
+    sync::Arc,
 };
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use log::debug;
-use opencv::core::{MatTraitConst, Point, Rect, Vec4b};
+use opencv::{
+    core::{Mat, MatTraitConst, Point, Rect, Vec4b, Vector},
+    imgcodecs::{IMREAD_COLOR, imdecode},
+};
 use strum::{Display, EnumIter};
 
 use crate::{
+    CustomSkill, CustomSkillDetection,
     context::{Context, Contextual, ControlFlow},
     player::Player,
     task::{Task, Update, update_detection_task},
@@ -16,16 +21,72 @@ use crate::{
 
 #[derive(Debug)]
 pub struct SkillState {
-    kind: SkillKind,
+    /// The id of skill
+    id: SkillId,
+    /// The decoded detection for [`SkillId::Custom`] skill
+    custom_detection: Option<CustomDetection>,
     task: Option<Task<Result<(Point, Vec4b)>>>,
 }
 
 impl SkillState {
     pub fn new(kind: SkillKind) -> Self {
-        Self { kind, task: None }
+        Self {
+            id: SkillId::Kind(kind),
+            custom_detection: None,
+            task: None,
+        }
+    }
+
+    /// Creates a state for the user-registered `skill`
+    ///
+    /// Returns `Err` if the template of `skill` cannot be decoded or its region is empty.
+    pub fn new_custom(skill: &CustomSkill) -> Result<Self> {
+        let detection = match &skill.detection {
+            CustomSkillDetection::Template(template) => {
+                let template = imdecode(&Vector::<u8>::from_slice(template), IMREAD_COLOR)?;
+                if template.empty() {
+                    bail!("custom skill template is empty");
+                }
+                CustomDetection::Template(Arc::new(template), skill.threshold)
+            }
+            CustomSkillDetection::Region(bound, anchor) => {
+                if bound.width <= 0 || bound.height <= 0 {
+                    bail!("custom skill region is empty");
+                }
+                let bbox = Rect::from(*bound);
+                CustomDetection::Anchor((bbox.tl() + bbox.br()) / 2, Vec4b::from_array(*anchor))
+            }
+        };
+        Ok(Self {
+            id: SkillId::Custom(skill.id),
+            custom_detection: Some(detection),
+            task: None,
+        })
+    }
+
+    #[inline]
+    pub fn id(&self) -> SkillId {
+        self.id
     }
 }
 
+/// How a [`SkillId::Custom`] skill is detected
+#[derive(Clone, Debug)]
+enum CustomDetection {
+    /// The BGR template and threshold for matching the skill icon
+    Template(Arc<Mat>, f64),
+    /// The fixed anchor point and pixel of the skill icon when it is off cooldown
+    Anchor(Point, Vec4b),
+}
+
+/// Identifies a skill tracked by [`SkillState`]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SkillId {
+    Kind(SkillKind),
+    /// A user-registered skill with the [`CustomSkill::id`]
+    Custom(u32),
+}
+
 #[derive(Clone, Copy, Debug, Display)]
 pub enum Skill {
     Detecting,
@@ -61,7 +122,12 @@ impl Contextual for Skill {
     type Persistent = SkillState;
 
     fn update(self, context: &Context, state: &mut SkillState) -> ControlFlow<Self> {
-        let next = if matches!(context.player, Player::CashShopThenExit(_, _)) {
+        let next = if matches!(
+            context.player,
+            Player::CashShopThenExit(_, _)
+                | Player::ReadingMesos(_)
+                | Player::ChangingChannel(_, _)
+        ) {
             self
         } else {
             update_context(self, context, state)
@@ -96,10 +162,23 @@ fn update_detection(
     state: &mut SkillState,
     on_next: impl FnOnce(Point, Vec4b) -> Skill,
 ) -> Skill {
-    let kind = state.kind;
+    // The anchor of a fixed region is already known so there is no need to detect
+    if let Some(CustomDetection::Anchor(point, anchor)) = state.custom_detection {
+        return match context.detector_unwrap().mat().at_pt::<Vec4b>(point) {
+            Ok(pixel) if anchor_match(*pixel, anchor) => on_next(point, anchor),
+            Ok(_) => Skill::Cooldown,
+            Err(_) => Skill::Detecting,
+        };
+    }
+    let id = state.id;
+    let custom_detection = state.custom_detection.clone();
     let update = update_detection_task(context, 1000, &mut state.task, move |detector| {
-        let bbox = match kind {
-            SkillKind::ErdaShower => detector.detect_erda_shower()?,
+        let bbox = match (id, custom_detection) {
+            (SkillId::Kind(SkillKind::ErdaShower), _) => detector.detect_erda_shower()?,
+            (SkillId::Custom(_), Some(CustomDetection::Template(template, threshold))) => {
+                detector.detect_custom_skill(&template, threshold)?
+            }
+            (SkillId::Custom(_), Some(CustomDetection::Anchor(_, _)) | None) => unreachable!(),
         };
         Ok(get_anchor(detector.mat(), bbox))
     });
@@ -145,7 +224,7 @@ mod tests {
     use tokio::time::advance;
 
     use super::*;
-    use crate::detect::MockDetector;
+    use crate::{Bound, detect::MockDetector};
 
     fn create_test_mat_bbox(center_pixel: u8) -> (Mat, Rect) {
         let mut mat = Mat::zeros(100, 100, CV_8UC4).unwrap().to_mat().unwrap();
@@ -174,6 +253,22 @@ mod tests {
         (detector, rect)
     }
 
+    fn custom_region_skill(anchor: u8) -> CustomSkill {
+        CustomSkill {
+            id: 1,
+            detection: CustomSkillDetection::Region(
+                Bound {
+                    x: 0,
+                    y: 0,
+                    width: 100,
+                    height: 100,
+                },
+                [anchor; 4],
+            ),
+            ..CustomSkill::default()
+        }
+    }
+
     async fn advance_task(contextual: Skill, context: &Context, state: &mut SkillState) -> Skill {
         let mut skill = update_context(contextual, context, state);
         while !state.task.as_ref().unwrap().completed() {
@@ -250,4 +345,47 @@ mod tests {
         let skill = advance_task(Skill::Cooldown, &context, &mut state).await;
         assert_matches!(skill, Skill::Cooldown);
     }
+
+    #[test]
+    fn skill_custom_invalid_template() {
+        let skill = CustomSkill {
+            detection: CustomSkillDetection::Template(vec![1, 2, 3]),
+            ..CustomSkill::default()
+        };
+        assert!(SkillState::new_custom(&skill).is_err());
+    }
+
+    #[test]
+    fn skill_custom_region_detecting_to_idle() {
+        let (mat, rect) = create_test_mat_bbox(255);
+        let mut detector = MockDetector::new();
+        detector.expect_mat().return_const(mat.into());
+        let context = Context::new(None, Some(detector));
+        let mut state = SkillState::new_custom(&custom_region_skill(255)).unwrap();
+        assert_eq!(state.id(), SkillId::Custom(1));
+
+        let skill = update_context(Skill::Detecting, &context, &mut state);
+        match skill {
+            Skill::Idle(point, pixel) => {
+                assert_eq!(point, (rect.tl() + rect.br()) / 2);
+                assert_eq!(pixel, Vec4b::all(255));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn skill_custom_region_cooldown() {
+        let (mat, _) = create_test_mat_bbox(100);
+        let mut detector = MockDetector::new();
+        detector.expect_mat().return_const(mat.into());
+        let context = Context::new(None, Some(detector));
+        let mut state = SkillState::new_custom(&custom_region_skill(255)).unwrap();
+
+        let skill = update_context(Skill::Detecting, &context, &mut state);
+        assert_matches!(skill, Skill::Cooldown);
+        let skill = update_context(skill, &context, &mut state);
+        assert_matches!(skill, Skill::Cooldown);
+        assert!(state.task.is_none());
+    }
 }
@@ -1,16 +1,21 @@
 use std::{
     cmp::{Ordering, max, min},
+    collections::HashMap,
     fmt::Display,
     str::FromStr,
+    time::Duration,
 };
 
 use backend::{
-    Action, ActionCondition, ActionKey, ActionKeyDirection, ActionKeyWith, ActionMove,
-    IntoEnumIterator, LinkKeyBinding, Minimap, ParseError, Position, Settings,
+    Action, ActionBranch, ActionBranchPredicate, ActionCondition, ActionKey, ActionKeyDirection,
+    ActionKeyWith, ActionMacro, ActionMacroRef, ActionMove, ActionSequence, ActionSequenceStep,
+    ActionSequenceStepKind, ActionStatistics, Configuration, IntoEnumIterator, LinkKeyBinding,
+    MAX_SEQUENCE_STEPS_COUNT, Minimap, ParseError, Position, Settings, action_statistics,
 };
 use dioxus::{document::eval, prelude::*};
 use futures_util::StreamExt;
 use rand::distr::{Alphanumeric, SampleString};
+use tokio::time::sleep;
 
 use crate::{
     AppMessage,
@@ -19,8 +24,11 @@ use crate::{
         Checkbox, KeyBindingInput, MillisInput, NumberInputI32, NumberInputU32, use_auto_numeric,
     },
     platform::Platforms,
+    playlist::Playlist,
     rotation::Rotations,
-    select::{EnumSelect, TextSelect},
+    select::{EnumSelect, Select, TextSelect},
+    session::Sessions,
+    simulation::Simulation,
     tab::Tab,
 };
 
@@ -31,23 +39,30 @@ const INPUT_CLASS: &str = "w-22 h-full border border-gray-300 rounded text-xs te
 pub enum ActionsMessage {
     UpdateMinimap(Minimap),
     UpdatePreset(String),
+    UpdateConfig(Configuration),
 }
 
 #[component]
 pub fn Actions(
     app_coroutine: Coroutine<AppMessage>,
     minimap: ReadOnlySignal<Option<Minimap>>,
+    config: ReadOnlySignal<Option<Configuration>>,
     settings: ReadOnlySignal<Option<Settings>>,
     preset: ReadOnlySignal<Option<String>>,
     copy_position: ReadOnlySignal<Option<(i32, i32)>>,
 ) -> Element {
     const TAB_PRESET: &str = "Preset";
+    const TAB_MACROS: &str = "Macros";
     const TAB_ROTATION_MODE: &str = "Rotation Mode";
     const TAB_PLATFORMS: &str = "Platforms";
+    const TAB_PLAYLIST: &str = "Playlist";
+    const TAB_SIMULATION: &str = "Simulation";
+    const TAB_SESSIONS: &str = "Sessions";
 
     let mut editing_action = use_signal::<Option<(Action, usize)>>(|| None);
     let value_action = use_signal(|| Action::Move(ActionMove::default()));
     let mut active_tab = use_signal(|| TAB_PRESET.to_string());
+    let mut statistics = use_signal(HashMap::<usize, ActionStatistics>::new);
     let coroutine = use_coroutine(
         move |mut rx: UnboundedReceiver<ActionsMessage>| async move {
             while let Some(msg) = rx.next().await {
@@ -58,6 +73,9 @@ pub fn Actions(
                     ActionsMessage::UpdatePreset(preset) => {
                         app_coroutine.send(AppMessage::UpdatePreset(preset));
                     }
+                    ActionsMessage::UpdateConfig(config) => {
+                        app_coroutine.send(AppMessage::UpdateConfig(config, true));
+                    }
                 }
             }
         },
@@ -67,6 +85,12 @@ pub fn Actions(
             .map(|minimap| minimap.rotation_mode)
             .unwrap_or_default()
     });
+    let patrol_zones_view = use_memo(move || {
+        minimap()
+            .map(|minimap| minimap.patrol_zones)
+            .unwrap_or_default()
+    });
+    let macros_view = use_memo(move || config().map(|config| config.macros).unwrap_or_default());
     let reset_on_erda_view = use_memo(move || {
         minimap()
             .map(|minimap| minimap.actions_any_reset_on_erda_condition)
@@ -78,13 +102,26 @@ pub fn Actions(
             editing_action.set(None);
         }
     });
+    use_future(move || async move {
+        loop {
+            let new_statistics = action_statistics().await;
+            if *statistics.peek() != new_statistics {
+                statistics.set(new_statistics);
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+    });
 
     rsx! {
         Tab {
             tabs: vec![
                 TAB_PRESET.to_string(),
+                TAB_MACROS.to_string(),
                 TAB_ROTATION_MODE.to_string(),
                 TAB_PLATFORMS.to_string(),
+                TAB_PLAYLIST.to_string(),
+                TAB_SIMULATION.to_string(),
+                TAB_SESSIONS.to_string(),
             ],
             div_class: "px-2 pt-2 pb-1 mb-2",
             class: "text-xs px-2 pb-2 focus:outline-none",
@@ -101,6 +138,8 @@ pub fn Actions(
                     ActionPresetTab {
                         minimap,
                         preset,
+                        macros: macros_view(),
+                        statistics,
                         copy_position,
                         value_action,
                         editing_action,
@@ -112,6 +151,15 @@ pub fn Actions(
                         },
                     }
                 },
+                TAB_MACROS => rsx! {
+                    ActionMacroTab {
+                        config,
+                        copy_position,
+                        update_config: move |config| {
+                            coroutine.send(ActionsMessage::UpdateConfig(config));
+                        },
+                    }
+                },
                 TAB_ROTATION_MODE => rsx! {
                     Rotations {
                         disabled: minimap().is_none(),
@@ -127,8 +175,15 @@ pub fn Actions(
                                 coroutine.send(ActionsMessage::UpdateMinimap(minimap));
                             }
                         },
+                        on_patrol_zones: move |zones| {
+                            if let Some(mut minimap) = minimap.peek().clone() {
+                                minimap.patrol_zones = zones;
+                                coroutine.send(ActionsMessage::UpdateMinimap(minimap));
+                            }
+                        },
                         rotation_mode: rotation_mode_view(),
                         reset_on_erda: reset_on_erda_view(),
+                        patrol_zones: patrol_zones_view(),
                     }
                 },
                 TAB_PLATFORMS => rsx! {
@@ -141,6 +196,20 @@ pub fn Actions(
                         settings,
                     }
                 },
+                TAB_PLAYLIST => rsx! {
+                    Playlist {
+                        minimap,
+                        on_save: move |minimap| {
+                            coroutine.send(ActionsMessage::UpdateMinimap(minimap));
+                        },
+                    }
+                },
+                TAB_SIMULATION => rsx! {
+                    Simulation { disabled: minimap().is_none() }
+                },
+                TAB_SESSIONS => rsx! {
+                    Sessions { minimap }
+                },
                 _ => unreachable!(),
             }
         }
@@ -151,16 +220,196 @@ pub fn Actions(
 fn ActionPresetTab(
     minimap: ReadOnlySignal<Option<Minimap>>,
     preset: ReadOnlySignal<Option<String>>,
+    macros: ReadOnlySignal<Vec<ActionMacro>>,
+    statistics: ReadOnlySignal<HashMap<usize, ActionStatistics>>,
     copy_position: ReadOnlySignal<Option<(i32, i32)>>,
     value_action: Signal<Action>,
     editing_action: Signal<Option<(Action, usize)>>,
     update_minimap: EventHandler<Minimap>,
     update_preset: EventHandler<String>,
+) -> Element {
+    let presets = use_memo::<Vec<String>>(move || {
+        minimap()
+            .map(|minimap| minimap.actions.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default()
+    });
+    let actions = use_memo::<Vec<Action>>(move || {
+        minimap()
+            .zip(preset())
+            .and_then(|(minimap, preset)| minimap.actions.get(&preset).cloned())
+            .unwrap_or_default()
+    });
+
+    rsx! {
+        div { class: "flex flex-col h-full",
+            TextSelect {
+                create_text: "+ Create new preset",
+                on_create: move |created: String| {
+                    if let Some(mut minimap) = minimap.peek().clone() {
+                        let actions_inserted = minimap
+                            .actions
+                            .try_insert(created.clone(), vec![])
+                            .is_ok();
+                        if actions_inserted {
+                            update_minimap(minimap);
+                        }
+                        update_preset(created);
+                    }
+                },
+                disabled: minimap().is_none(),
+                on_select: move |(_, selected)| {
+                    update_preset(selected);
+                },
+                options: presets(),
+                selected: preset(),
+            }
+            ActionListEditor {
+                disabled: preset().is_none(),
+                copy_position,
+                value_action,
+                editing_action,
+                macros: macros(),
+                statistics: statistics(),
+                actions: actions(),
+                on_update: move |actions| {
+                    let minimap = minimap.peek().clone();
+                    if let Some((mut minimap, preset)) = minimap.zip(preset.peek().clone()) {
+                        minimap.actions.insert(preset, actions);
+                        update_minimap(minimap);
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[component]
+fn ActionMacroTab(
+    config: ReadOnlySignal<Option<Configuration>>,
+    copy_position: ReadOnlySignal<Option<(i32, i32)>>,
+    update_config: EventHandler<Configuration>,
+) -> Element {
+    let mut selected = use_signal::<Option<u32>>(|| None);
+    let mut editing_action = use_signal::<Option<(Action, usize)>>(|| None);
+    let value_action = use_signal(|| Action::Move(ActionMove::default()));
+    let macros = use_memo(move || config().map(|config| config.macros).unwrap_or_default());
+    let selected_macro = use_memo(move || {
+        selected().and_then(|id| {
+            macros()
+                .into_iter()
+                .find(|action_macro| action_macro.id == id)
+        })
+    });
+
+    use_effect(move || {
+        if selected_macro().is_none() {
+            editing_action.set(None);
+        }
+    });
+
+    rsx! {
+        div { class: "flex flex-col h-full",
+            ul { class: "list-disc text-xs text-gray-700 pl-4 mb-2",
+                li {
+                    "Macros are saved with the configuration and can be used in any preset with the Macro action type"
+                }
+                li { "A macro runs as a single linked action using the condition of the Macro action" }
+                li { "Conditions of the actions inside a macro are ignored" }
+            }
+            div { class: "flex space-x-2",
+                TextSelect {
+                    create_text: "+ Create new macro",
+                    on_create: move |name: String| {
+                        if let Some(mut config) = config.peek().clone() {
+                            let id = config
+                                .macros
+                                .iter()
+                                .map(|action_macro| action_macro.id + 1)
+                                .max()
+                                .unwrap_or_default();
+                            config.macros.push(ActionMacro {
+                                id,
+                                name,
+                                actions: vec![],
+                            });
+                            selected.set(Some(id));
+                            update_config(config);
+                        }
+                    },
+                    disabled: config().is_none(),
+                    on_select: move |(i, _): (usize, String)| {
+                        selected.set(macros.peek().get(i).map(|action_macro| action_macro.id));
+                    },
+                    options: macros()
+                        .into_iter()
+                        .map(|action_macro| action_macro.name)
+                        .collect::<Vec<_>>(),
+                    selected: selected_macro().map(|action_macro| action_macro.name),
+                }
+                if let Some(action_macro) = selected_macro() {
+                    button {
+                        class: "button-danger h-7 w-18",
+                        onclick: move |_| {
+                            if let Some(mut config) = config.peek().clone() {
+                                config.macros.retain(|inner| inner.id != action_macro.id);
+                                selected.set(None);
+                                update_config(config);
+                            }
+                        },
+                        "Delete"
+                    }
+                }
+            }
+            ActionListEditor {
+                disabled: selected_macro().is_none(),
+                copy_position,
+                value_action,
+                editing_action,
+                macros: vec![],
+                actions: selected_macro()
+                    .map(|action_macro| action_macro.actions)
+                    .unwrap_or_default(),
+                on_update: move |actions| {
+                    let Some(mut config) = config.peek().clone() else {
+                        return;
+                    };
+                    let id = *selected.peek();
+                    if let Some(action_macro) = config
+                        .macros
+                        .iter_mut()
+                        .find(|action_macro| Some(action_macro.id) == id)
+                    {
+                        action_macro.actions = actions;
+                        update_config(config);
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Edits a list of [`Action`]s
+///
+/// [`Action::Macro`] can only be selected when `macros` is not empty. `statistics` is keyed by
+/// the index of the action in `actions`.
+#[component]
+fn ActionListEditor(
+    disabled: bool,
+    copy_position: ReadOnlySignal<Option<(i32, i32)>>,
+    value_action: Signal<Action>,
+    editing_action: Signal<Option<(Action, usize)>>,
+    macros: Vec<ActionMacro>,
+    #[props(default = HashMap::new())] statistics: HashMap<usize, ActionStatistics>,
+    actions: Vec<Action>,
+    on_update: EventHandler<Vec<Action>>,
 ) -> Element {
     fn is_linked_condition_action(action: Action) -> bool {
         match action {
             Action::Move(ActionMove { condition, .. })
-            | Action::Key(ActionKey { condition, .. }) => {
+            | Action::Key(ActionKey { condition, .. })
+            | Action::Sequence(ActionSequence { condition, .. })
+            | Action::Macro(ActionMacroRef { condition, .. })
+            | Action::Branch(ActionBranch { condition, .. }) => {
                 matches!(condition, ActionCondition::Linked)
             }
         }
@@ -173,66 +422,55 @@ fn ActionPresetTab(
         false
     }
 
-    let presets = use_memo::<Vec<String>>(move || {
-        minimap()
-            .map(|minimap| minimap.actions.keys().cloned().collect::<Vec<_>>())
-            .unwrap_or_default()
-    });
-    let actions = use_memo::<Vec<Action>>(move || {
-        minimap()
-            .zip(preset())
-            .and_then(|(minimap, preset)| minimap.actions.get(&preset).cloned())
-            .unwrap_or_default()
-    });
+    let actions = use_memo(use_reactive!(|actions| actions));
     let on_edit = use_callback(move |action| {
         value_action.set(action);
     });
     let on_save = use_callback(move |index| {
-        if let Some((mut minimap, preset)) = minimap().zip(preset()) {
-            let actions = minimap.actions.get_mut(&preset).unwrap();
-            if let Some(index) = index {
-                *actions.get_mut(index).unwrap() = *value_action.peek();
-            } else {
-                actions.push(*value_action.peek());
-            }
-            update_minimap(minimap);
+        let mut actions = actions.peek().clone();
+        if let Some(index) = index {
+            *actions.get_mut(index).unwrap() = *value_action.peek();
+        } else {
+            actions.push(*value_action.peek());
         }
+        on_update(actions);
     });
     let on_remove = use_callback(move |index| {
-        if let Some((mut minimap, preset)) = minimap().zip(preset()) {
-            let actions = minimap.actions.get_mut(&preset).unwrap();
-            let is_linked_action =
-                is_linked_action(actions, index) && !is_linked_condition_action(actions[index]);
-            actions.remove(index);
-            if is_linked_action {
-                let action = actions.get_mut(index).unwrap();
-                match action {
-                    Action::Move(ActionMove { condition, .. })
-                    | Action::Key(ActionKey { condition, .. }) => {
-                        *condition = ActionCondition::Any;
-                    }
+        let mut actions = actions.peek().clone();
+        let is_linked_action =
+            is_linked_action(&actions, index) && !is_linked_condition_action(actions[index]);
+        actions.remove(index);
+        if is_linked_action {
+            let action = actions.get_mut(index).unwrap();
+            match action {
+                Action::Move(ActionMove { condition, .. })
+                | Action::Key(ActionKey { condition, .. })
+                | Action::Sequence(ActionSequence { condition, .. })
+                | Action::Macro(ActionMacroRef { condition, .. })
+                | Action::Branch(ActionBranch { condition, .. }) => {
+                    *condition = ActionCondition::Any;
                 }
             }
+        }
 
-            let editing = *editing_action.peek();
-            if let Some((action, i)) = editing {
-                match index.cmp(&i) {
-                    Ordering::Equal => {
-                        editing_action.set(None);
-                    }
-                    Ordering::Less => {
-                        let new_i = i.saturating_sub(1);
-                        if new_i == index {
-                            editing_action.set(Some((*actions.get(index).unwrap(), new_i)));
-                        } else {
-                            editing_action.set(Some((action, new_i)));
-                        }
+        let editing = *editing_action.peek();
+        if let Some((action, i)) = editing {
+            match index.cmp(&i) {
+                Ordering::Equal => {
+                    editing_action.set(None);
+                }
+                Ordering::Less => {
+                    let new_i = i.saturating_sub(1);
+                    if new_i == index {
+                        editing_action.set(Some((*actions.get(index).unwrap(), new_i)));
+                    } else {
+                        editing_action.set(Some((action, new_i)));
                     }
-                    Ordering::Greater => (),
                 }
+                Ordering::Greater => (),
             }
-            update_minimap(minimap);
         }
+        on_update(actions);
     });
     let on_change = use_callback(move |(a, b, swapping)| {
         editing_action.set(None); // FIXME
@@ -245,53 +483,56 @@ fn ActionPresetTab(
         //     }
         // }
         // FIXME: nawww this is way too cooked
-        if let Some((mut minimap, preset)) = minimap().zip(preset()) {
-            let actions = minimap.actions.get_mut(&preset).unwrap();
-            if swapping {
-                let tmp = a;
-                let a = min(tmp, b);
-                let b = max(tmp, b);
-                let is_a_linked_action = is_linked_action(actions, a);
-                let is_b_linked_action = is_linked_action(actions, b);
-                let mut a_actions = vec![];
-                let mut b_actions = vec![];
-                b_actions.push(actions.remove(b));
-                if is_b_linked_action {
-                    while b < actions.len() && is_linked_condition_action(actions[b]) {
-                        b_actions.push(actions.remove(b));
-                    }
-                }
-                a_actions.push(actions.remove(a));
-                if is_a_linked_action {
-                    while a < actions.len() && is_linked_condition_action(actions[a]) {
-                        a_actions.push(actions.remove(a));
-                    }
+        let mut actions = actions.peek().clone();
+        if swapping {
+            let tmp = a;
+            let a = min(tmp, b);
+            let b = max(tmp, b);
+            let is_a_linked_action = is_linked_action(&actions, a);
+            let is_b_linked_action = is_linked_action(&actions, b);
+            let mut a_actions = vec![];
+            let mut b_actions = vec![];
+            b_actions.push(actions.remove(b));
+            if is_b_linked_action {
+                while b < actions.len() && is_linked_condition_action(actions[b]) {
+                    b_actions.push(actions.remove(b));
                 }
-                let a_offset = b - (a + a_actions.len());
-                let a_insert = a_offset + a + b_actions.len();
-                for action in b_actions.into_iter().rev() {
-                    actions.insert(a, action);
-                }
-                for action in a_actions.into_iter().rev() {
-                    actions.insert(a_insert, action);
-                }
-            } else {
-                let is_a_linked_action = is_linked_action(actions, a);
-                let mut a_actions = vec![actions.remove(a)];
-                if is_a_linked_action {
-                    while a < actions.len() && is_linked_condition_action(actions[a]) {
-                        a_actions.push(actions.remove(a));
-                    }
+            }
+            a_actions.push(actions.remove(a));
+            if is_a_linked_action {
+                while a < actions.len() && is_linked_condition_action(actions[a]) {
+                    a_actions.push(actions.remove(a));
                 }
-                for action in a_actions.into_iter().rev() {
-                    actions.insert(b, action);
+            }
+            let a_offset = b - (a + a_actions.len());
+            let a_insert = a_offset + a + b_actions.len();
+            for action in b_actions.into_iter().rev() {
+                actions.insert(a, action);
+            }
+            for action in a_actions.into_iter().rev() {
+                actions.insert(a_insert, action);
+            }
+        } else {
+            let is_a_linked_action = is_linked_action(&actions, a);
+            let mut a_actions = vec![actions.remove(a)];
+            if is_a_linked_action {
+                while a < actions.len() && is_linked_condition_action(actions[a]) {
+                    a_actions.push(actions.remove(a));
                 }
             }
-            update_minimap(minimap);
+            for action in a_actions.into_iter().rev() {
+                actions.insert(b, action);
+            }
         }
+        on_update(actions);
     });
     let exclude_linked =
         use_memo(move || matches!(editing_action(), Some((_, 0))) || actions().is_empty());
+    let excludes = if macros.is_empty() {
+        vec![Action::Macro(ActionMacroRef::default())]
+    } else {
+        vec![]
+    };
 
     use_effect(move || {
         if actions().is_empty() {
@@ -300,112 +541,124 @@ fn ActionPresetTab(
     });
 
     rsx! {
-        div { class: "flex flex-col h-full",
-            TextSelect {
-                create_text: "+ Create new preset",
-                on_create: move |created: String| {
-                    if let Some(mut minimap) = minimap.peek().clone() {
-                        let actions_inserted = minimap
-                            .actions
-                            .try_insert(created.clone(), vec![])
-                            .is_ok();
-                        if actions_inserted {
-                            update_minimap(minimap);
-                        }
-                        update_preset(created);
-                    }
-                },
-                disabled: minimap().is_none(),
-                on_select: move |(_, selected)| {
-                    update_preset(selected);
-                },
-                options: presets(),
-                selected: preset(),
-            }
-            div { class: "flex space-x-2 overflow-y-auto flex-1",
-                div { class: "w-1/2 overflow-y-auto scrollbar pr-2",
-                    div { class: "flex flex-col space-y-2.5",
-                        ActionEnumSelect {
-                            label: "Type",
-                            on_input: move |action: Action| {
-                                if let Some((editing_action, _)) = *editing_action.peek() {
-                                    if editing_action.to_string() == action.to_string() {
-                                        on_edit(editing_action);
-                                        return;
-                                    }
-                                }
-                                on_edit(action);
-                            },
-                            disabled: preset().is_none(),
-                            value: value_action(),
-                        }
-                        match value_action() {
-                            Action::Move(_) => rsx! {
-                                ActionMoveInput {
-                                    copy_position,
-                                    on_input: move |action| {
-                                        on_edit(action);
-                                    },
-                                    disabled: preset().is_none(),
-                                    value: value_action(),
-                                    exclude_linked: exclude_linked(),
-                                }
-                            },
-                            Action::Key(_) => rsx! {
-                                ActionKeyInput {
-                                    copy_position,
-                                    on_input: move |action| {
-                                        on_edit(action);
-                                    },
-                                    disabled: preset().is_none(),
-                                    value: value_action(),
-                                    exclude_linked: exclude_linked(),
+        div { class: "flex space-x-2 overflow-y-auto flex-1",
+            div { class: "w-1/2 overflow-y-auto scrollbar pr-2",
+                div { class: "flex flex-col space-y-2.5",
+                    ActionEnumSelect {
+                        label: "Type",
+                        on_input: move |action: Action| {
+                            if let Some((editing_action, _)) = *editing_action.peek() {
+                                if editing_action.to_string() == action.to_string() {
+                                    on_edit(editing_action);
+                                    return;
                                 }
+                            }
+                            on_edit(action);
+                        },
+                        disabled,
+                        value: value_action(),
+                        excludes,
+                    }
+                    match value_action() {
+                        Action::Move(_) => rsx! {
+                            ActionMoveInput {
+                                copy_position,
+                                on_input: move |action| {
+                                    on_edit(action);
+                                },
+                                disabled,
+                                value: value_action(),
+                                exclude_linked: exclude_linked(),
+                            }
+                        },
+                        Action::Key(_) => rsx! {
+                            ActionKeyInput {
+                                copy_position,
+                                on_input: move |action| {
+                                    on_edit(action);
+                                },
+                                disabled,
+                                value: value_action(),
+                                exclude_linked: exclude_linked(),
+                            }
+                        },
+                        Action::Sequence(_) => rsx! {
+                            ActionSequenceInput {
+                                copy_position,
+                                on_input: move |action| {
+                                    on_edit(action);
+                                },
+                                disabled,
+                                value: value_action(),
+                                exclude_linked: exclude_linked(),
+                            }
+                        },
+                        Action::Branch(_) => rsx! {
+                            ActionBranchInput {
+                                on_input: move |action| {
+                                    on_edit(action);
+                                },
+                                disabled,
+                                value: value_action(),
+                                exclude_linked: exclude_linked(),
+                            }
+                        },
+                        Action::Macro(_) => rsx! {
+                            ActionMacroInput {
+                                on_input: move |action| {
+                                    on_edit(action);
+                                },
+                                disabled,
+                                macros: macros.clone(),
+                                value: value_action(),
+                                exclude_linked: exclude_linked(),
+                            }
+                        },
+                    }
+                    if editing_action().is_none() {
+                        button {
+                            class: "w-full button-primary h-6",
+                            disabled,
+                            onclick: move |_| {
+                                on_save(None);
                             },
+                            "Add action"
                         }
-                        if editing_action().is_none() {
+                    } else {
+                        div { class: "grid grid-cols-2 gap-x-2",
                             button {
-                                class: "w-full button-primary h-6",
-                                disabled: preset().is_none(),
+                                class: "button-primary h-6",
                                 onclick: move |_| {
-                                    on_save(None);
+                                    on_save(editing_action.replace(None).map(|tuple| tuple.1));
                                 },
-                                "Add action"
+                                "Save"
                             }
-                        } else {
-                            div { class: "grid grid-cols-2 gap-x-2",
-                                button {
-                                    class: "button-primary h-6",
-                                    onclick: move |_| {
-                                        on_save(editing_action.replace(None).map(|tuple| tuple.1));
-                                    },
-                                    "Save"
-                                }
-                                button {
-                                    class: "button-secondary h-6",
-                                    onclick: move |_| {
-                                        editing_action.set(None);
-                                    },
-                                    "Cancel"
-                                }
+                            button {
+                                class: "button-secondary h-6",
+                                onclick: move |_| {
+                                    editing_action.set(None);
+                                },
+                                "Cancel"
                             }
                         }
                     }
                 }
-                ActionItemList {
-                    disabled: preset().is_none(),
-                    actions: actions(),
-                    on_click: move |(action, index)| {
-                        editing_action.set(Some((action, index)));
-                        on_edit(action);
-                    },
-                    on_remove: move |index| {
-                        on_remove(index);
-                    },
-                    on_change: move |(a, b, swapping)| {
-                        on_change((a, b, swapping));
-                    },
-                }
+            }
+            ActionItemList {
+                disabled,
+                actions: actions(),
+                macros: macros.clone(),
+                statistics,
+                on_click: move |(action, index)| {
+                    editing_action.set(Some((action, index)));
+                    on_edit(action);
+                },
+                on_remove: move |index| {
+                    on_remove(index);
+                },
+                on_change: move |(a, b, swapping)| {
+                    on_change((a, b, swapping));
+                },
             }
         }
     }
@@ -415,6 +668,8 @@ fn ActionPresetTab(
 fn ActionItemList(
     disabled: bool,
     actions: Vec<Action>,
+    macros: Vec<ActionMacro>,
+    statistics: HashMap<usize, ActionStatistics>,
     on_click: EventHandler<(Action, usize)>,
     on_remove: EventHandler<usize>,
     on_change: EventHandler<(usize, usize, bool)>,
@@ -440,12 +695,28 @@ fn ActionItemList(
                         dragging: dragging(),
                         draggable: match action {
                             Action::Move(ActionMove { condition, .. })
-                            | Action::Key(ActionKey { condition, .. }) => {
+                            | Action::Key(ActionKey { condition, .. })
+                            | Action::Sequence(ActionSequence { condition, .. })
+                            | Action::Macro(ActionMacroRef { condition, .. })
+                            | Action::Branch(ActionBranch { condition, .. }) => {
                                 !matches!(condition, ActionCondition::Linked)
                             }
                         },
                         index: i,
                         action,
+                        statistics: statistics.get(&i).copied(),
+                        macro_name: match action {
+                            Action::Macro(ActionMacroRef { id, .. }) => {
+                                macros
+                                    .iter()
+                                    .find(|action_macro| action_macro.id == id)
+                                    .map(|action_macro| action_macro.name.clone())
+                            }
+                            Action::Move(_)
+                            | Action::Key(_)
+                            | Action::Sequence(_)
+                            | Action::Branch(_) => None,
+                        },
                         on_click: move |_| {
                             on_click((action, i));
                         },
@@ -473,6 +744,8 @@ fn ActionItemList(
 fn ActionItem(
     index: usize,
     action: Action,
+    macro_name: Option<String>,
+    statistics: Option<ActionStatistics>,
     dragging: bool,
     draggable: bool,
     on_click: EventHandler<()>,
@@ -496,6 +769,7 @@ fn ActionItem(
                 },
             condition,
             wait_after_move_millis,
+            weight,
         } = action;
         let wait_after_millis_id = use_memo(|| Alphanumeric.sample_string(&mut rand::rng(), 8));
 
@@ -529,6 +803,12 @@ fn ActionItem(
                 span { class: KEY, "Wait after" }
                 span { id: wait_after_millis_id(), class: VALUE }
             }
+            if matches!(condition, ActionCondition::Any) {
+                div { class: DIV,
+                    span { class: KEY, "Weight" }
+                    span { class: VALUE, {weight.to_string()} }
+                }
+            }
         }
     }
 
@@ -546,7 +826,11 @@ fn ActionItem(
             wait_before_use_millis_random_range,
             wait_after_use_millis,
             wait_after_use_millis_random_range,
+            hold_millis,
+            hold_millis_random_range,
+            hold_repeat_millis,
             queue_to_front,
+            weight,
         } = action;
         let wait_before_use_millis_id =
             use_memo(|| Alphanumeric.sample_string(&mut rand::rng(), 8));
@@ -649,40 +933,242 @@ fn ActionItem(
                 span { class: KEY, "Wait after random" }
                 span { id: wait_after_use_millis_random_range_id(), class: VALUE }
             }
+            if hold_millis > 0 {
+                div { class: DIV,
+                    span { class: KEY, "Hold" }
+                    span { class: VALUE, "{hold_millis}ms ± {hold_millis_random_range}ms" }
+                }
+                if let Some(repeat) = hold_repeat_millis {
+                    div { class: DIV,
+                        span { class: KEY, "Hold repeat" }
+                        span { class: VALUE, "{repeat}ms" }
+                    }
+                }
+            }
             if let Some(queue_to_front) = queue_to_front {
                 div { class: DIV,
                     span { class: KEY, "Queue to front" }
                     span { class: VALUE, {queue_to_front.to_string()} }
                 }
             }
+            if matches!(condition, ActionCondition::Any) {
+                div { class: DIV,
+                    span { class: KEY, "Weight" }
+                    span { class: VALUE, {weight.to_string()} }
+                }
+            }
         }
     }
 
-    let border_color = match action {
-        Action::Move(_) => "border-blue-300",
-        Action::Key(_) => "border-gray-300",
-    };
-    let mut did_drag = use_signal(|| false);
-    let cursor = if draggable { "cursor-move" } else { "" };
+    #[component]
+    fn ActionSequenceItem(action: ActionSequence) -> Element {
+        let ActionSequence {
+            position,
+            condition,
+            steps,
+            queue_to_front,
+            weight,
+        } = action;
 
-    use_effect(move || {
-        if did_drag() {
-            spawn(async move {
-                let _ = eval(
-                    r#"
-                    window.addEventListener("drop", handleDrop);
-                    async function handleDrop(e) {
-                        e.preventDefault();
-                        window.removeEventListener("drop", handleDrop);
-                        await dioxus.send(true);
+        rsx! {
+            if let Some(position) = position {
+                div { class: DIV,
+                    span { class: KEY, "Position" }
+                    span { class: VALUE, "{position.x}, {position.y}" }
+                }
+                div { class: DIV,
+                    span { class: KEY, "Position x random" }
+                    span { class: VALUE, "{position.x_random_range}" }
+                }
+                div { class: DIV,
+                    span { class: KEY, "Adjust" }
+                    span { class: VALUE, "{position.allow_adjusting}" }
+                }
+            }
+            div { class: DIV,
+                span { class: KEY, "Condition" }
+                span { class: VALUE, {condition.to_string()} }
+            }
+            for (i , step) in steps.into_iter().enumerate() {
+                div { class: DIV,
+                    span { class: KEY, {format!("Step {}", i + 1)} }
+                    span { class: VALUE,
+                        {
+                            format!(
+                                "{} {} {} / {}ms ± {}ms",
+                                step.kind,
+                                step.key,
+                                step.direction,
+                                step.delay_millis,
+                                step.delay_millis_random_range,
+                            )
+                        }
                     }
-                    "#,
-                )
-                .recv::<bool>()
-                .await;
-                did_drag.set(false);
-                on_drop((index, true));
-            });
+                }
+            }
+            if let Some(queue_to_front) = queue_to_front {
+                div { class: DIV,
+                    span { class: KEY, "Queue to front" }
+                    span { class: VALUE, {queue_to_front.to_string()} }
+                }
+            }
+            if matches!(condition, ActionCondition::Any) {
+                div { class: DIV,
+                    span { class: KEY, "Weight" }
+                    span { class: VALUE, {weight.to_string()} }
+                }
+            }
+        }
+    }
+
+    #[component]
+    fn ActionMacroItem(action: ActionMacroRef, name: Option<String>) -> Element {
+        let ActionMacroRef {
+            condition,
+            queue_to_front,
+            weight,
+            ..
+        } = action;
+        let name = name.unwrap_or_else(|| "Missing".to_string());
+
+        rsx! {
+            div { class: DIV,
+                span { class: KEY, "Macro" }
+                span { class: VALUE, {name} }
+            }
+            div { class: DIV,
+                span { class: KEY, "Condition" }
+                span { class: VALUE, {condition.to_string()} }
+            }
+            if let Some(queue_to_front) = queue_to_front {
+                div { class: DIV,
+                    span { class: KEY, "Queue to front" }
+                    span { class: VALUE, {queue_to_front.to_string()} }
+                }
+            }
+            if matches!(condition, ActionCondition::Any) {
+                div { class: DIV,
+                    span { class: KEY, "Weight" }
+                    span { class: VALUE, {weight.to_string()} }
+                }
+            }
+        }
+    }
+
+    #[component]
+    fn ActionBranchItem(action: ActionBranch) -> Element {
+        let ActionBranch {
+            condition,
+            predicate,
+            then_count,
+            else_count,
+            then_stop,
+            queue_to_front,
+            weight,
+        } = action;
+
+        rsx! {
+            div { class: DIV,
+                span { class: KEY, "Condition" }
+                span { class: VALUE, {condition.to_string()} }
+            }
+            div { class: DIV,
+                span { class: KEY, "If" }
+                span { class: VALUE, {predicate.to_string()} }
+            }
+            div { class: DIV,
+                span { class: KEY, "Then actions" }
+                span { class: VALUE, "{then_count}" }
+            }
+            div { class: DIV,
+                span { class: KEY, "Else actions" }
+                span { class: VALUE, "{else_count}" }
+            }
+            div { class: DIV,
+                span { class: KEY, "Stop after then" }
+                span { class: VALUE, "{then_stop}" }
+            }
+            if let Some(queue_to_front) = queue_to_front {
+                div { class: DIV,
+                    span { class: KEY, "Queue to front" }
+                    span { class: VALUE, {queue_to_front.to_string()} }
+                }
+            }
+            if matches!(condition, ActionCondition::Any) {
+                div { class: DIV,
+                    span { class: KEY, "Weight" }
+                    span { class: VALUE, {weight.to_string()} }
+                }
+            }
+        }
+    }
+
+    #[component]
+    fn ActionStatisticsItem(statistics: ActionStatistics) -> Element {
+        let ActionStatistics {
+            queued_count,
+            started_count,
+            completed_count,
+            aborted_count,
+            average_latency_millis,
+            millis_since_last_run,
+            starved,
+        } = statistics;
+        let last_run = millis_since_last_run
+            .map(|millis| format!("{}s ago", millis / 1000))
+            .unwrap_or_else(|| "Never".to_string());
+
+        rsx! {
+            div { class: DIV,
+                span { class: KEY, "Runs" }
+                span { class: VALUE,
+                    "{started_count} started, {completed_count} completed, {aborted_count} aborted"
+                }
+            }
+            div { class: DIV,
+                span { class: KEY, "Queued" }
+                span { class: VALUE, "{queued_count}, {average_latency_millis}ms average latency" }
+            }
+            div { class: DIV,
+                span { class: KEY, "Last run" }
+                span { class: VALUE, {last_run} }
+            }
+            if starved {
+                div { class: "font-mono text-xs text-red-500",
+                    "Starved: not run for more than twice its interval"
+                }
+            }
+        }
+    }
+
+    let border_color = match action {
+        Action::Move(_) => "border-blue-300",
+        Action::Key(_) => "border-gray-300",
+        Action::Sequence(_) => "border-purple-300",
+        Action::Macro(_) => "border-green-300",
+        Action::Branch(_) => "border-yellow-300",
+    };
+    let mut did_drag = use_signal(|| false);
+    let cursor = if draggable { "cursor-move" } else { "" };
+
+    use_effect(move || {
+        if did_drag() {
+            spawn(async move {
+                let _ = eval(
+                    r#"
+                    window.addEventListener("drop", handleDrop);
+                    async function handleDrop(e) {
+                        e.preventDefault();
+                        window.removeEventListener("drop", handleDrop);
+                        await dioxus.send(true);
+                    }
+                    "#,
+                )
+                .recv::<bool>()
+                .await;
+                did_drag.set(false);
+                on_drop((index, true));
+            });
         }
     });
 
@@ -718,6 +1204,18 @@ fn ActionItem(
                     Action::Key(action) => rsx! {
                         ActionKeyItem { action }
                     },
+                    Action::Sequence(action) => rsx! {
+                        ActionSequenceItem { action }
+                    },
+                    Action::Macro(action) => rsx! {
+                        ActionMacroItem { action, name: macro_name.clone() }
+                    },
+                    Action::Branch(action) => rsx! {
+                        ActionBranchItem { action }
+                    },
+                }
+                if let Some(statistics) = statistics {
+                    ActionStatisticsItem { statistics }
                 }
             }
             if draggable && dragging {
@@ -874,6 +1372,7 @@ fn ActionMoveInput(
         position,
         condition,
         wait_after_move_millis,
+        weight,
     } = value;
 
     rsx! {
@@ -894,6 +1393,15 @@ fn ActionMoveInput(
                 value: condition,
                 exclude_linked,
             }
+            if matches!(condition, ActionCondition::Any) {
+                ActionWeightInput {
+                    disabled,
+                    on_input: move |weight| {
+                        on_input(Action::Move(ActionMove { weight, ..value }));
+                    },
+                    value: weight,
+                }
+            }
             ActionMillisInput {
                 label: "Wait after action",
                 disabled,
@@ -934,7 +1442,11 @@ fn ActionKeyInput(
         wait_before_use_millis_random_range,
         wait_after_use_millis,
         wait_after_use_millis_random_range,
+        hold_millis,
+        hold_millis_random_range,
+        hold_repeat_millis,
         queue_to_front,
+        weight,
     } = value;
 
     use_effect(use_reactive!(|condition| {
@@ -1050,6 +1562,15 @@ fn ActionKeyInput(
                 value: condition,
                 exclude_linked,
             }
+            if matches!(condition, ActionCondition::Any) {
+                ActionWeightInput {
+                    disabled,
+                    on_input: move |weight| {
+                        on_input(Action::Key(ActionKey { weight, ..value }));
+                    },
+                    value: weight,
+                }
+            }
             if let Some(queue_to_front) = queue_to_front {
                 ActionCheckbox {
                     label: "Queue to front",
@@ -1133,6 +1654,449 @@ fn ActionKeyInput(
                 disabled,
                 value: wait_after_use_millis_random_range,
             }
+            ActionMillisInput {
+                label: "Hold",
+                on_input: move |hold_millis| {
+                    on_input(Action::Key(ActionKey { hold_millis, ..value }));
+                },
+                disabled,
+                value: hold_millis,
+            }
+            ActionMillisInput {
+                label: "Hold random range",
+                on_input: move |hold_millis_random_range| {
+                    on_input(
+                        Action::Key(ActionKey {
+                            hold_millis_random_range,
+                            ..value
+                        }),
+                    );
+                },
+                disabled: disabled || hold_millis == 0,
+                value: hold_millis_random_range,
+            }
+            ActionCheckbox {
+                label: "Repeat while holding",
+                disabled: disabled || hold_millis == 0,
+                on_input: move |checked: bool| {
+                    on_input(
+                        Action::Key(ActionKey {
+                            hold_repeat_millis: checked
+                                .then_some(hold_repeat_millis.unwrap_or(100)),
+                            ..value
+                        }),
+                    );
+                },
+                value: hold_repeat_millis.is_some(),
+            }
+            if let Some(repeat_millis) = hold_repeat_millis {
+                ActionMillisInput {
+                    label: "Repeat interval",
+                    on_input: move |repeat_millis| {
+                        on_input(
+                            Action::Key(ActionKey {
+                                hold_repeat_millis: Some(repeat_millis),
+                                ..value
+                            }),
+                        );
+                    },
+                    disabled: disabled || hold_millis == 0,
+                    value: repeat_millis,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ActionSequenceInput(
+    copy_position: ReadOnlySignal<Option<(i32, i32)>>,
+    on_input: EventHandler<Action>,
+    disabled: bool,
+    value: Action,
+    exclude_linked: bool,
+) -> Element {
+    let Action::Sequence(value) = value else {
+        unreachable!()
+    };
+    let ActionSequence {
+        position,
+        condition,
+        steps,
+        queue_to_front,
+        weight,
+    } = value;
+
+    use_effect(use_reactive!(|condition| {
+        on_input(Action::Sequence(ActionSequence {
+            queue_to_front: (!matches!(condition, ActionCondition::Any | ActionCondition::Linked))
+                .then_some(queue_to_front.unwrap_or_default()),
+            ..value
+        }));
+    }));
+
+    rsx! {
+        div { class: "flex flex-col space-y-3",
+            ActionCheckbox {
+                label: "Position",
+                disabled,
+                on_input: move |checked: bool| {
+                    on_input(
+                        Action::Sequence(ActionSequence {
+                            position: checked.then_some(Position::default()),
+                            ..value
+                        }),
+                    );
+                },
+                value: position.is_some(),
+            }
+            if let Some(position) = position {
+                PositionInput {
+                    copy_position,
+                    on_input: move |position| {
+                        on_input(
+                            Action::Sequence(ActionSequence {
+                                position: Some(position),
+                                ..value
+                            }),
+                        );
+                    },
+                    disabled,
+                    value: position,
+                }
+            }
+            ActionConditionInput {
+                on_input: move |condition| {
+                    on_input(Action::Sequence(ActionSequence { condition, ..value }));
+                },
+                disabled,
+                value: condition,
+                exclude_linked,
+            }
+            if matches!(condition, ActionCondition::Any) {
+                ActionWeightInput {
+                    disabled,
+                    on_input: move |weight| {
+                        on_input(Action::Sequence(ActionSequence { weight, ..value }));
+                    },
+                    value: weight,
+                }
+            }
+            if let Some(queue_to_front) = queue_to_front {
+                ActionCheckbox {
+                    label: "Queue to front",
+                    disabled,
+                    on_input: move |checked: bool| {
+                        on_input(
+                            Action::Sequence(ActionSequence {
+                                queue_to_front: Some(checked),
+                                ..value
+                            }),
+                        );
+                    },
+                    value: queue_to_front,
+                }
+            }
+            for (i , step) in steps.into_iter().enumerate() {
+                div { class: "flex items-center justify-between border-b border-gray-300 pb-1",
+                    p { class: "text-xs text-gray-700", {format!("Step {}", i + 1)} }
+                    button {
+                        class: "button-danger h-6 w-18",
+                        disabled,
+                        onclick: move |_| {
+                            let mut steps = steps;
+                            steps.remove(i);
+                            on_input(Action::Sequence(ActionSequence { steps, ..value }));
+                        },
+                        "Delete"
+                    }
+                }
+                ActionSequenceStepInput {
+                    disabled,
+                    on_input: move |step| {
+                        let mut steps = steps;
+                        steps[i] = step;
+                        on_input(Action::Sequence(ActionSequence { steps, ..value }));
+                    },
+                    value: step,
+                }
+            }
+            button {
+                class: "button-secondary h-6 w-full",
+                disabled: disabled || steps.len() >= MAX_SEQUENCE_STEPS_COUNT,
+                onclick: move |_| {
+                    let mut steps = steps;
+                    steps.push(ActionSequenceStep::default());
+                    on_input(Action::Sequence(ActionSequence { steps, ..value }));
+                },
+                "Add step"
+            }
+        }
+    }
+}
+
+#[component]
+fn ActionMacroInput(
+    on_input: EventHandler<Action>,
+    disabled: bool,
+    macros: Vec<ActionMacro>,
+    value: Action,
+    exclude_linked: bool,
+) -> Element {
+    let Action::Macro(value) = value else {
+        unreachable!()
+    };
+    let ActionMacroRef {
+        id,
+        condition,
+        queue_to_front,
+        weight,
+    } = value;
+    let options = macros
+        .iter()
+        .map(|action_macro| (action_macro.id, action_macro.name.clone()))
+        .collect::<Vec<_>>();
+    let first_id = macros.first().map(|action_macro| action_macro.id);
+    let has_id = macros.iter().any(|action_macro| action_macro.id == id);
+
+    use_effect(use_reactive!(|condition| {
+        on_input(Action::Macro(ActionMacroRef {
+            queue_to_front: (!matches!(condition, ActionCondition::Any | ActionCondition::Linked))
+                .then_some(queue_to_front.unwrap_or_default()),
+            ..value
+        }));
+    }));
+    use_effect(use_reactive!(|first_id, has_id| {
+        if has_id {
+            return;
+        }
+        if let Some(id) = first_id {
+            on_input(Action::Macro(ActionMacroRef { id, ..value }));
+        }
+    }));
+
+    rsx! {
+        div { class: "flex flex-col space-y-3",
+            Select {
+                label: "Macro",
+                div_class: DIV_CLASS,
+                label_class: LABEL_CLASS,
+                select_class: INPUT_CLASS,
+                disabled,
+                options,
+                on_select: move |(_, id): (usize, u32)| {
+                    on_input(Action::Macro(ActionMacroRef { id, ..value }));
+                },
+                selected: id,
+            }
+            ActionConditionInput {
+                on_input: move |condition| {
+                    on_input(Action::Macro(ActionMacroRef { condition, ..value }));
+                },
+                disabled,
+                value: condition,
+                exclude_linked,
+            }
+            if matches!(condition, ActionCondition::Any) {
+                ActionWeightInput {
+                    disabled,
+                    on_input: move |weight| {
+                        on_input(Action::Macro(ActionMacroRef { weight, ..value }));
+                    },
+                    value: weight,
+                }
+            }
+            if let Some(queue_to_front) = queue_to_front {
+                ActionCheckbox {
+                    label: "Queue to front",
+                    disabled,
+                    on_input: move |checked: bool| {
+                        on_input(
+                            Action::Macro(ActionMacroRef {
+                                queue_to_front: Some(checked),
+                                ..value
+                            }),
+                        );
+                    },
+                    value: queue_to_front,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ActionBranchInput(
+    on_input: EventHandler<Action>,
+    disabled: bool,
+    value: Action,
+    exclude_linked: bool,
+) -> Element {
+    let Action::Branch(value) = value else {
+        unreachable!()
+    };
+    let ActionBranch {
+        condition,
+        predicate,
+        then_count,
+        else_count,
+        then_stop,
+        queue_to_front,
+        weight,
+    } = value;
+
+    use_effect(use_reactive!(|condition| {
+        on_input(Action::Branch(ActionBranch {
+            queue_to_front: (!matches!(condition, ActionCondition::Any | ActionCondition::Linked))
+                .then_some(queue_to_front.unwrap_or_default()),
+            ..value
+        }));
+    }));
+
+    rsx! {
+        div { class: "flex flex-col space-y-3",
+            ActionConditionInput {
+                on_input: move |condition| {
+                    on_input(Action::Branch(ActionBranch { condition, ..value }));
+                },
+                disabled,
+                value: condition,
+                exclude_linked,
+            }
+            ActionEnumSelect::<ActionBranchPredicate> {
+                label: "If",
+                disabled,
+                on_input: move |predicate| {
+                    on_input(Action::Branch(ActionBranch { predicate, ..value }));
+                },
+                value: predicate,
+            }
+            NumberInputU32 {
+                label: "Then actions",
+                label_class: LABEL_CLASS,
+                div_class: DIV_CLASS,
+                input_class: "{INPUT_CLASS} p-1",
+                disabled,
+                minimum_value: 0,
+                on_input: move |then_count| {
+                    on_input(Action::Branch(ActionBranch { then_count, ..value }));
+                },
+                value: then_count,
+            }
+            NumberInputU32 {
+                label: "Else actions",
+                label_class: LABEL_CLASS,
+                div_class: DIV_CLASS,
+                input_class: "{INPUT_CLASS} p-1",
+                disabled,
+                minimum_value: 0,
+                on_input: move |else_count| {
+                    on_input(Action::Branch(ActionBranch { else_count, ..value }));
+                },
+                value: else_count,
+            }
+            ActionCheckbox {
+                label: "Stop chain after then actions",
+                disabled,
+                on_input: move |then_stop| {
+                    on_input(Action::Branch(ActionBranch { then_stop, ..value }));
+                },
+                value: then_stop,
+            }
+            if matches!(condition, ActionCondition::Any) {
+                ActionWeightInput {
+                    disabled,
+                    on_input: move |weight| {
+                        on_input(Action::Branch(ActionBranch { weight, ..value }));
+                    },
+                    value: weight,
+                }
+            }
+            if let Some(queue_to_front) = queue_to_front {
+                ActionCheckbox {
+                    label: "Queue to front",
+                    disabled,
+                    on_input: move |checked: bool| {
+                        on_input(
+                            Action::Branch(ActionBranch {
+                                queue_to_front: Some(checked),
+                                ..value
+                            }),
+                        );
+                    },
+                    value: queue_to_front,
+                }
+            }
+            p { class: "text-xs text-gray-700",
+                "The linked actions right after this branch are only used when the condition is true. The actions after those are only used when it is false."
+            }
+        }
+    }
+}
+
+#[component]
+fn ActionSequenceStepInput(
+    disabled: bool,
+    on_input: EventHandler<ActionSequenceStep>,
+    value: ActionSequenceStep,
+) -> Element {
+    let ActionSequenceStep {
+        key,
+        kind,
+        direction,
+        delay_millis,
+        delay_millis_random_range,
+    } = value;
+
+    rsx! {
+        KeyBindingInput {
+            label: "Key",
+            label_class: LABEL_CLASS,
+            div_class: DIV_CLASS,
+            input_class: INPUT_CLASS,
+            disabled,
+            on_input: move |key| {
+                on_input(ActionSequenceStep { key, ..value });
+            },
+            value: key,
+        }
+        ActionEnumSelect::<ActionSequenceStepKind> {
+            label: "Press",
+            on_input: move |kind| {
+                on_input(ActionSequenceStep { kind, ..value });
+            },
+            disabled,
+            value: kind,
+        }
+        ActionEnumSelect::<ActionKeyDirection> {
+            label: "Direction",
+            on_input: move |direction| {
+                on_input(ActionSequenceStep { direction, ..value });
+            },
+            disabled,
+            value: direction,
+        }
+        ActionMillisInput {
+            label: "Delay after",
+            disabled,
+            on_input: move |delay_millis| {
+                on_input(ActionSequenceStep {
+                    delay_millis,
+                    ..value
+                });
+            },
+            value: delay_millis,
+        }
+        ActionMillisInput {
+            label: "Delay random range",
+            disabled,
+            on_input: move |delay_millis_random_range| {
+                on_input(ActionSequenceStep {
+                    delay_millis_random_range,
+                    ..value
+                });
+            },
+            value: delay_millis_random_range,
         }
     }
 }
@@ -1162,6 +2126,36 @@ fn ActionConditionInput(
                 value: millis,
             }
         }
+        if let ActionCondition::SkillOffCooldown(id) = value {
+            NumberInputU32 {
+                label: "Skill ID",
+                label_class: LABEL_CLASS,
+                div_class: DIV_CLASS,
+                input_class: "{INPUT_CLASS} p-1",
+                disabled,
+                minimum_value: 0,
+                on_input: move |id| {
+                    on_input(ActionCondition::SkillOffCooldown(id));
+                },
+                value: id,
+            }
+        }
+    }
+}
+
+#[component]
+fn ActionWeightInput(disabled: bool, on_input: EventHandler<u32>, value: u32) -> Element {
+    rsx! {
+        NumberInputU32 {
+            label: "Weight",
+            label_class: LABEL_CLASS,
+            div_class: DIV_CLASS,
+            input_class: "{INPUT_CLASS} p-1",
+            disabled,
+            minimum_value: 0,
+            on_input,
+            value,
+        }
     }
 }
 
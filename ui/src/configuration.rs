@@ -1,8 +1,11 @@
 use std::{fmt::Display, str::FromStr};
 
 use backend::{
-    ActionConfiguration, Class, Configuration as ConfigurationData, IntoEnumIterator,
-    KeyBindingConfiguration, PotionMode,
+    ActionConfiguration, Bound, BuffDuration, BuffKind, Class, Configuration as ConfigurationData,
+    CustomBuff, CustomSkill, CustomSkillDetection, IntoEnumIterator, KeyBindingConfiguration,
+    MovementProfile, PlaceableSkill, PotionMode, PotionRule, buffs_region_frame,
+    capture_custom_buff_template, capture_custom_skill_anchor, capture_custom_skill_template,
+    game_frame,
 };
 use dioxus::prelude::*;
 use rand::distr::{Alphanumeric, SampleString};
@@ -10,7 +13,10 @@ use rand::distr::{Alphanumeric, SampleString};
 use crate::{
     AppMessage,
     icons::{CheckMarkIcon, XIcon},
-    input::{Checkbox, KeyBindingInput, MillisInput, PercentageInput, use_auto_numeric},
+    input::{
+        Checkbox, KeyBindingInput, MillisInput, NumberInputI32, NumberInputU32, PercentageInput,
+        use_auto_numeric,
+    },
     key::KeyBindingConfigurationInput,
     select::{EnumSelect, TextSelect},
     tab::Tab,
@@ -27,6 +33,8 @@ const INTERACT: &str = "Interact";
 const CASH_SHOP: &str = "Cash Shop";
 const FEED_PET: &str = "Feed Pet";
 const POTION: &str = "Potion";
+const MANA_POTION: &str = "Mana Potion";
+const INVENTORY: &str = "Inventory";
 const SAYRAM_ELIXIR: &str = "Sayram's Elixir";
 const AURELIA_ELIXIR: &str = "Aurelia's Elixir";
 const EXP_X3: &str = "3x EXP Coupon";
@@ -39,9 +47,36 @@ const EXTREME_RED_POTION: &str = "Extreme Red Potion";
 const EXTREME_BLUE_POTION: &str = "Extreme Blue Potion";
 const EXTREME_GREEN_POTION: &str = "Extreme Green Potion";
 const EXTREME_GOLD_POTION: &str = "Extreme Gold Potion";
+const BUFF_DURATION_KINDS: [(BuffKind, &str); 8] = [
+    (BuffKind::SayramElixir, SAYRAM_ELIXIR),
+    (BuffKind::AureliaElixir, AURELIA_ELIXIR),
+    (BuffKind::ExpCouponX3, EXP_X3),
+    (BuffKind::BonusExpCoupon, BONUS_EXP),
+    (BuffKind::LegionWealth, LEGION_WEALTH),
+    (BuffKind::LegionLuck, LEGION_LUCK),
+    (BuffKind::WealthAcquisitionPotion, WEALTH_ACQUISITION_POTION),
+    (BuffKind::ExpAccumulationPotion, EXP_ACCUMULATION_POTION),
+];
 const TAB_GAME: &str = "Game";
 const TAB_BUFFS: &str = "Buffs";
+const TAB_CUSTOM_BUFFS: &str = "Custom Buffs";
+const TAB_CUSTOM_SKILLS: &str = "Custom Skills";
+const TAB_PLACEABLE_SKILLS: &str = "Placeable Skills";
+const TAB_POTION_RULES: &str = "Potion Rules";
+const TAB_MESOS: &str = "Mesos";
 const TAB_FIXED_ACTIONS: &str = "Fixed Actions";
+const CROP_FRAME_JS: &str = r#"
+    const canvas = document.getElementById("canvas-crop-frame");
+    const canvasCtx = canvas.getContext("2d");
+    const [buffer, width, height, bound] = await dioxus.recv();
+    const data = new ImageData(new Uint8ClampedArray(buffer), width, height);
+    const bitmap = await createImageBitmap(data);
+    canvas.width = width;
+    canvas.height = height;
+    canvasCtx.drawImage(bitmap, 0, 0);
+    canvasCtx.strokeStyle = "rgb(255, 0, 0)";
+    canvasCtx.strokeRect(bound.x, bound.y, bound.width, bound.height);
+"#;
 
 #[component]
 pub fn Configuration(
@@ -69,7 +104,16 @@ pub fn Configuration(
 
     rsx! {
         Tab {
-            tabs: vec![TAB_GAME.to_string(), TAB_BUFFS.to_string(), TAB_FIXED_ACTIONS.to_string()],
+            tabs: vec![
+                TAB_GAME.to_string(),
+                TAB_BUFFS.to_string(),
+                TAB_CUSTOM_BUFFS.to_string(),
+                TAB_CUSTOM_SKILLS.to_string(),
+                TAB_PLACEABLE_SKILLS.to_string(),
+                TAB_POTION_RULES.to_string(),
+                TAB_MESOS.to_string(),
+                TAB_FIXED_ACTIONS.to_string(),
+            ],
             div_class: "px-2 pt-2 pb-1",
             class: "text-xs px-2 pb-2 focus:outline-none",
             selected_class: "text-gray-800 border-b",
@@ -117,6 +161,26 @@ pub fn Configuration(
                             on_config,
                         }
                     },
+                    TAB_CUSTOM_BUFFS => rsx! {
+                        ConfigCustomBuffs { is_disabled, config_view, on_config }
+                    },
+                    TAB_CUSTOM_SKILLS => rsx! {
+                        ConfigCustomSkills { is_disabled, config_view, on_config }
+                    },
+                    TAB_PLACEABLE_SKILLS => rsx! {
+                        ConfigPlaceableSkills { is_disabled, config_view, on_config }
+                    },
+                    TAB_POTION_RULES => rsx! {
+                        ConfigPotionRules { is_disabled, config_view, on_config }
+                    },
+                    TAB_MESOS => rsx! {
+                        ConfigMesos {
+                            active,
+                            is_disabled,
+                            config_view,
+                            on_config,
+                        }
+                    },
                     TAB_FIXED_ACTIONS => rsx! {
                         ConfigFixedActions {
                             active,
@@ -306,9 +370,75 @@ fn ConfigGameKeyBindings(
                     },
                 }
             }
+            KeyBindingConfigurationInput {
+                label: MANA_POTION,
+                label_active: active,
+                is_disabled: is_disabled(),
+                is_toggleable: true,
+                on_input: move |key: Option<KeyBindingConfiguration>| {
+                    on_config(ConfigurationData {
+                        mana_potion_key: key.unwrap(),
+                        ..config_view.peek().clone()
+                    });
+                },
+                value: Some(config_view().mana_potion_key),
+                ConfigEnumSelect::<PotionMode> {
+                    label: "Mana Potion Mode",
+                    on_select: move |mode| {
+                        on_config(ConfigurationData {
+                            mana_potion_mode: mode,
+                            ..config_view.peek().clone()
+                        });
+                    },
+                    disabled: is_disabled(),
+                    selected: config_view().mana_potion_mode,
+                }
+                match config_view().mana_potion_mode {
+                    PotionMode::EveryMillis(value) => rsx! {
+                        ConfigMillisInput {
+                            label: "Every Milliseconds",
+                            disabled: is_disabled(),
+                            on_input: move |value| {
+                                on_config(ConfigurationData {
+                                    mana_potion_mode: PotionMode::EveryMillis(value),
+                                    ..config_view.peek().clone()
+                                });
+                            },
+                            value,
+                        }
+                    },
+                    PotionMode::Percentage(value) => rsx! {
+                        PercentageInput {
+                            label: "Below Mana Percentage",
+                            div_class: DIV_CLASS,
+                            label_class: LABEL_CLASS,
+                            input_class: INPUT_CLASS,
+                            disabled: is_disabled(),
+                            on_input: move |value| {
+                                on_config(ConfigurationData {
+                                    mana_potion_mode: PotionMode::Percentage(value),
+                                    ..config_view.peek().clone()
+                                });
+                            },
+                            value,
+                        }
+                        ConfigMillisInput {
+                            label: "Mana Update Milliseconds",
+                            disabled: is_disabled(),
+                            on_input: move |value| {
+                                on_config(ConfigurationData {
+                                    mana_update_millis: value,
+                                    ..config_view.peek().clone()
+                                });
+                            },
+                            value: config_view().mana_update_millis,
+                        }
+                    },
+                }
+            }
             div { class: "space-y-2",
                 p { class: "font-normal italic text-xs text-gray-400 mb-1",
-                    "Class affects only link key timing except Blaster"
+                    "Class selects the movement profile preset unless a custom profile is used"
                 }
                 ConfigEnumSelect::<Class> {
                     label: "Class",
@@ -321,6 +451,178 @@ fn ConfigGameKeyBindings(
                     disabled: is_disabled(),
                     selected: config_view().class,
                 }
+                ConfigMovementProfile { is_disabled, config_view, on_config }
+            }
+        }
+    }
+}
+
+#[component]
+fn ConfigMovementProfile(
+    is_disabled: Memo<bool>,
+    config_view: Memo<ConfigurationData>,
+    on_config: EventHandler<ConfigurationData>,
+) -> Element {
+    let on_profile = move |profile: MovementProfile| {
+        on_config(ConfigurationData {
+            custom_movement_profile: Some(profile),
+            ..config_view.peek().clone()
+        });
+    };
+    let peek_profile = move || config_view.peek().custom_movement_profile.unwrap();
+
+    rsx! {
+        Checkbox {
+            label: "Custom Movement Profile",
+            div_class: DIV_CLASS,
+            label_class: LABEL_CLASS,
+            input_class: "w-44",
+            disabled: is_disabled(),
+            on_input: move |custom: bool| {
+                let config = config_view.peek().clone();
+                on_config(ConfigurationData {
+                    custom_movement_profile: custom.then(|| config.class.movement_profile()),
+                    ..config
+                });
+            },
+            value: config_view().custom_movement_profile.is_some(),
+        }
+        if let Some(profile) = config_view().custom_movement_profile {
+            ConfigThresholdInput {
+                label: "Double Jump Threshold",
+                disabled: is_disabled(),
+                on_input: move |double_jump_threshold| {
+                    on_profile(MovementProfile {
+                        double_jump_threshold,
+                        ..peek_profile()
+                    });
+                },
+                value: profile.double_jump_threshold,
+            }
+            ConfigThresholdInput {
+                label: "Jump Threshold",
+                disabled: is_disabled(),
+                on_input: move |jump_threshold| {
+                    on_profile(MovementProfile {
+                        jump_threshold,
+                        ..peek_profile()
+                    });
+                },
+                value: profile.jump_threshold,
+            }
+            ConfigThresholdInput {
+                label: "Up Jump Threshold",
+                disabled: is_disabled(),
+                on_input: move |up_jump_threshold| {
+                    on_profile(MovementProfile {
+                        up_jump_threshold,
+                        ..peek_profile()
+                    });
+                },
+                value: profile.up_jump_threshold,
+            }
+            ConfigThresholdInput {
+                label: "Grappling Threshold",
+                disabled: is_disabled(),
+                on_input: move |grappling_threshold| {
+                    on_profile(MovementProfile {
+                        grappling_threshold,
+                        ..peek_profile()
+                    });
+                },
+                value: profile.grappling_threshold,
+            }
+            ConfigThresholdInput {
+                label: "Grappling Max Threshold",
+                disabled: is_disabled(),
+                on_input: move |grappling_max_threshold| {
+                    on_profile(MovementProfile {
+                        grappling_max_threshold,
+                        ..peek_profile()
+                    });
+                },
+                value: profile.grappling_max_threshold,
+            }
+            ConfigThresholdInput {
+                label: "Teleport Fall Threshold",
+                disabled: is_disabled(),
+                on_input: move |teleport_fall_threshold| {
+                    on_profile(MovementProfile {
+                        teleport_fall_threshold,
+                        ..peek_profile()
+                    });
+                },
+                value: profile.teleport_fall_threshold,
+            }
+            ConfigThresholdInput {
+                label: "Teleport Up Jump Threshold",
+                disabled: is_disabled(),
+                on_input: move |teleport_up_jump_threshold| {
+                    on_profile(MovementProfile {
+                        teleport_up_jump_threshold,
+                        ..peek_profile()
+                    });
+                },
+                value: profile.teleport_up_jump_threshold,
+            }
+            NumberInputU32 {
+                label: "Move Timeout Ticks",
+                div_class: DIV_CLASS,
+                label_class: LABEL_CLASS,
+                input_class: INPUT_CLASS,
+                disabled: is_disabled(),
+                minimum_value: 1,
+                on_input: move |move_timeout| {
+                    on_profile(MovementProfile {
+                        move_timeout,
+                        ..peek_profile()
+                    });
+                },
+                value: profile.move_timeout,
+            }
+            NumberInputU32 {
+                label: "Velocity Samples",
+                div_class: DIV_CLASS,
+                label_class: LABEL_CLASS,
+                input_class: INPUT_CLASS,
+                disabled: is_disabled(),
+                minimum_value: 2,
+                on_input: move |velocity_samples| {
+                    on_profile(MovementProfile {
+                        velocity_samples,
+                        ..peek_profile()
+                    });
+                },
+                value: profile.velocity_samples,
+            }
+            NumberInputU32 {
+                label: "Link Key Timeout Ticks",
+                div_class: DIV_CLASS,
+                label_class: LABEL_CLASS,
+                input_class: INPUT_CLASS,
+                disabled: is_disabled(),
+                minimum_value: 1,
+                on_input: move |link_key_timeout| {
+                    on_profile(MovementProfile {
+                        link_key_timeout,
+                        ..peek_profile()
+                    });
+                },
+                value: profile.link_key_timeout,
+            }
+            Checkbox {
+                label: "Jump After Link Key",
+                div_class: DIV_CLASS,
+                label_class: LABEL_CLASS,
+                input_class: "w-44",
+                disabled: is_disabled(),
+                on_input: move |jump_after_link_key| {
+                    on_profile(MovementProfile {
+                        jump_after_link_key,
+                        ..peek_profile()
+                    });
+                },
+                value: profile.jump_after_link_key,
             }
         }
     }
@@ -490,6 +792,909 @@ fn ConfigBuffKeyBindings(
             },
             value: Some(config_view().extreme_gold_potion_key),
         }
+        div { class: "space-y-2 mt-2",
+            p { class: "font-normal italic text-xs text-gray-400 mb-1",
+                "Buff with known duration is used again before it expires, 0 if unknown"
+            }
+            for (kind , label) in BUFF_DURATION_KINDS {
+                ConfigMillisInput {
+                    label: format!("{label} Duration"),
+                    disabled: is_disabled(),
+                    on_input: move |millis| {
+                        let mut config = config_view.peek().clone();
+                        config.buff_durations.retain(|duration| duration.kind != kind);
+                        if millis > 0 {
+                            config.buff_durations.push(BuffDuration { kind, millis });
+                        }
+                        on_config(config);
+                    },
+                    value: config_view()
+                        .buff_durations
+                        .into_iter()
+                        .find(|duration| duration.kind == kind)
+                        .map(|duration| duration.millis)
+                        .unwrap_or_default(),
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ConfigCustomBuffs(
+    is_disabled: Memo<bool>,
+    config_view: Memo<ConfigurationData>,
+    on_config: EventHandler<ConfigurationData>,
+) -> Element {
+    let mut selected = use_signal(|| None::<u32>);
+    let mut frame = use_signal(|| None::<(Vec<u8>, usize, usize)>);
+    let mut crop = use_signal(|| Bound {
+        x: 0,
+        y: 0,
+        width: 32,
+        height: 32,
+    });
+    let custom_buffs = use_memo(move || config_view().custom_buffs);
+    let selected_buff = use_memo(move || {
+        selected().and_then(|id| custom_buffs().into_iter().find(|buff| buff.id == id))
+    });
+    let on_buff = move |buff: CustomBuff| {
+        let mut config = config_view.peek().clone();
+        if let Some(inner) = config
+            .custom_buffs
+            .iter_mut()
+            .find(|inner| inner.id == buff.id)
+        {
+            *inner = buff;
+            on_config(config);
+        }
+    };
+
+    use_effect(move || {
+        let bound = crop();
+        if let Some((buffer, width, height)) = frame() {
+            spawn(async move {
+                document::eval(CROP_FRAME_JS)
+                    .send((buffer, width, height, bound))
+                    .unwrap();
+            });
+        }
+    });
+
+    rsx! {
+        div { class: "flex flex-col space-y-2",
+            ul { class: "list-disc text-xs text-gray-700 pl-4",
+                li { "Capture the buffs region while the buff is active" }
+                li { "Adjust the red rectangle to cover only the buff icon before creating" }
+            }
+            div { class: "flex space-x-2",
+                TextSelect {
+                    create_text: "+ Create new custom buff",
+                    on_create: move |name: String| {
+                        spawn(async move {
+                            let Some(template) = capture_custom_buff_template(*crop.peek()).await
+                            else {
+                                return;
+                            };
+                            let mut config = config_view.peek().clone();
+                            let id = config
+                                .custom_buffs
+                                .iter()
+                                .map(|buff| buff.id + 1)
+                                .max()
+                                .unwrap_or_default();
+                            config.custom_buffs.push(CustomBuff {
+                                id,
+                                name,
+                                template,
+                                ..CustomBuff::default()
+                            });
+                            selected.set(Some(id));
+                            on_config(config);
+                        });
+                    },
+                    disabled: is_disabled() || frame().is_none(),
+                    on_select: move |(i, _): (usize, String)| {
+                        selected.set(custom_buffs.peek().get(i).map(|buff| buff.id));
+                    },
+                    options: custom_buffs().into_iter().map(|buff| buff.name).collect::<Vec<_>>(),
+                    selected: selected_buff().map(|buff| buff.name),
+                }
+                if let Some(buff) = selected_buff() {
+                    button {
+                        class: "button-danger h-7 w-18",
+                        onclick: move |_| {
+                            let mut config = config_view.peek().clone();
+                            config.custom_buffs.retain(|inner| inner.id != buff.id);
+                            selected.set(None);
+                            on_config(config);
+                        },
+                        "Delete"
+                    }
+                }
+            }
+            if let Some(buff) = selected_buff() {
+                KeyBindingInput {
+                    label: "Key",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    disabled: is_disabled(),
+                    on_input: move |key| {
+                        let buff = selected_buff.peek().clone().unwrap();
+                        on_buff(CustomBuff {
+                            key: KeyBindingConfiguration { key, ..buff.key },
+                            ..buff
+                        });
+                    },
+                    value: buff.key.key,
+                }
+                Checkbox {
+                    label: "Enabled",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: "w-44",
+                    disabled: is_disabled(),
+                    on_input: move |enabled| {
+                        let buff = selected_buff.peek().clone().unwrap();
+                        on_buff(CustomBuff {
+                            key: KeyBindingConfiguration {
+                                enabled,
+                                ..buff.key
+                            },
+                            ..buff
+                        });
+                    },
+                    value: buff.key.enabled,
+                }
+                PercentageInput {
+                    label: "Detection Threshold",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    disabled: is_disabled(),
+                    on_input: move |percent: f32| {
+                        on_buff(CustomBuff {
+                            threshold: percent as f64 / 100.0,
+                            ..selected_buff.peek().clone().unwrap()
+                        });
+                    },
+                    value: (buff.threshold * 100.0) as f32,
+                }
+                ConfigMillisInput {
+                    label: "Duration",
+                    disabled: is_disabled(),
+                    on_input: move |millis| {
+                        on_buff(CustomBuff {
+                            duration_millis: (millis > 0).then_some(millis),
+                            ..selected_buff.peek().clone().unwrap()
+                        });
+                    },
+                    value: buff.duration_millis.unwrap_or_default(),
+                }
+                button {
+                    class: "button-secondary h-6",
+                    disabled: is_disabled() || frame().is_none(),
+                    onclick: move |_| async move {
+                        if let Some(template) = capture_custom_buff_template(*crop.peek()).await {
+                            on_buff(CustomBuff {
+                                template,
+                                ..selected_buff.peek().clone().unwrap()
+                            });
+                        }
+                    },
+                    "Replace template with rectangle"
+                }
+            }
+            button {
+                class: "button-primary h-6",
+                disabled: is_disabled(),
+                onclick: move |_| async move {
+                    frame.set(buffs_region_frame().await);
+                },
+                "Capture buffs region"
+            }
+            if frame().is_some() {
+                canvas { class: "w-full", id: "canvas-crop-frame" }
+                NumberInputI32 {
+                    label: "Rectangle X",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |x| {
+                        crop.set(Bound { x, ..*crop.peek() });
+                    },
+                    value: crop().x,
+                }
+                NumberInputI32 {
+                    label: "Rectangle Y",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |y| {
+                        crop.set(Bound { y, ..*crop.peek() });
+                    },
+                    value: crop().y,
+                }
+                NumberInputI32 {
+                    label: "Rectangle Width",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |width| {
+                        crop.set(Bound { width, ..*crop.peek() });
+                    },
+                    value: crop().width,
+                }
+                NumberInputI32 {
+                    label: "Rectangle Height",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |height| {
+                        crop.set(Bound { height, ..*crop.peek() });
+                    },
+                    value: crop().height,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ConfigCustomSkills(
+    is_disabled: Memo<bool>,
+    config_view: Memo<ConfigurationData>,
+    on_config: EventHandler<ConfigurationData>,
+) -> Element {
+    let mut selected = use_signal(|| None::<u32>);
+    let mut frame = use_signal(|| None::<(Vec<u8>, usize, usize)>);
+    let mut crop = use_signal(|| Bound {
+        x: 0,
+        y: 0,
+        width: 32,
+        height: 32,
+    });
+    let custom_skills = use_memo(move || config_view().custom_skills);
+    let selected_skill = use_memo(move || {
+        selected().and_then(|id| custom_skills().into_iter().find(|skill| skill.id == id))
+    });
+    let on_skill = move |skill: CustomSkill| {
+        let mut config = config_view.peek().clone();
+        if let Some(inner) = config
+            .custom_skills
+            .iter_mut()
+            .find(|inner| inner.id == skill.id)
+        {
+            *inner = skill;
+            on_config(config);
+        }
+    };
+
+    use_effect(move || {
+        let bound = crop();
+        if let Some((buffer, width, height)) = frame() {
+            spawn(async move {
+                document::eval(CROP_FRAME_JS)
+                    .send((buffer, width, height, bound))
+                    .unwrap();
+            });
+        }
+    });
+
+    rsx! {
+        div { class: "flex flex-col space-y-2",
+            ul { class: "list-disc text-xs text-gray-700 pl-4",
+                li { "Capture the game frame while the skill is off cooldown" }
+                li { "Adjust the red rectangle to cover only the skill icon before creating" }
+                li { "A template is matched anywhere while a region only checks its center pixel" }
+                li { "Use the skill ID with the SkillOffCooldown action condition" }
+            }
+            div { class: "flex space-x-2",
+                TextSelect {
+                    create_text: "+ Create new custom skill",
+                    on_create: move |name: String| {
+                        spawn(async move {
+                            let Some(template) = capture_custom_skill_template(*crop.peek()).await
+                            else {
+                                return;
+                            };
+                            let mut config = config_view.peek().clone();
+                            let id = config
+                                .custom_skills
+                                .iter()
+                                .map(|skill| skill.id + 1)
+                                .max()
+                                .unwrap_or_default();
+                            config.custom_skills.push(CustomSkill {
+                                id,
+                                name,
+                                detection: CustomSkillDetection::Template(template),
+                                ..CustomSkill::default()
+                            });
+                            selected.set(Some(id));
+                            on_config(config);
+                        });
+                    },
+                    disabled: is_disabled() || frame().is_none(),
+                    on_select: move |(i, _): (usize, String)| {
+                        selected.set(custom_skills.peek().get(i).map(|skill| skill.id));
+                    },
+                    options: custom_skills()
+                        .into_iter()
+                        .map(|skill| skill.name)
+                        .collect::<Vec<_>>(),
+                    selected: selected_skill().map(|skill| skill.name),
+                }
+                if let Some(skill) = selected_skill() {
+                    button {
+                        class: "button-danger h-7 w-18",
+                        onclick: move |_| {
+                            let mut config = config_view.peek().clone();
+                            config.custom_skills.retain(|inner| inner.id != skill.id);
+                            selected.set(None);
+                            on_config(config);
+                        },
+                        "Delete"
+                    }
+                }
+            }
+            if let Some(skill) = selected_skill() {
+                p { class: "text-xs text-gray-700",
+                    "ID: {skill.id} - Detection: {skill.detection}"
+                }
+                if matches!(skill.detection, CustomSkillDetection::Template(_)) {
+                    PercentageInput {
+                        label: "Detection Threshold",
+                        div_class: DIV_CLASS,
+                        label_class: LABEL_CLASS,
+                        input_class: INPUT_CLASS,
+                        disabled: is_disabled(),
+                        on_input: move |percent: f32| {
+                            on_skill(CustomSkill {
+                                threshold: percent as f64 / 100.0,
+                                ..selected_skill.peek().clone().unwrap()
+                            });
+                        },
+                        value: (skill.threshold * 100.0) as f32,
+                    }
+                }
+                div { class: "flex space-x-2",
+                    button {
+                        class: "button-secondary h-6 flex-1",
+                        disabled: is_disabled() || frame().is_none(),
+                        onclick: move |_| async move {
+                            let bound = *crop.peek();
+                            if let Some(template) = capture_custom_skill_template(bound).await {
+                                on_skill(CustomSkill {
+                                    detection: CustomSkillDetection::Template(template),
+                                    ..selected_skill.peek().clone().unwrap()
+                                });
+                            }
+                        },
+                        "Use rectangle as template"
+                    }
+                    button {
+                        class: "button-secondary h-6 flex-1",
+                        disabled: is_disabled() || frame().is_none(),
+                        onclick: move |_| async move {
+                            let bound = *crop.peek();
+                            if let Some(anchor) = capture_custom_skill_anchor(bound).await {
+                                on_skill(CustomSkill {
+                                    detection: CustomSkillDetection::Region(bound, anchor),
+                                    ..selected_skill.peek().clone().unwrap()
+                                });
+                            }
+                        },
+                        "Use rectangle as region"
+                    }
+                }
+            }
+            button {
+                class: "button-primary h-6",
+                disabled: is_disabled(),
+                onclick: move |_| async move {
+                    frame.set(game_frame().await);
+                },
+                "Capture game frame"
+            }
+            if frame().is_some() {
+                canvas { class: "w-full", id: "canvas-crop-frame" }
+                NumberInputI32 {
+                    label: "Rectangle X",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |x| {
+                        crop.set(Bound { x, ..*crop.peek() });
+                    },
+                    value: crop().x,
+                }
+                NumberInputI32 {
+                    label: "Rectangle Y",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |y| {
+                        crop.set(Bound { y, ..*crop.peek() });
+                    },
+                    value: crop().y,
+                }
+                NumberInputI32 {
+                    label: "Rectangle Width",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |width| {
+                        crop.set(Bound { width, ..*crop.peek() });
+                    },
+                    value: crop().width,
+                }
+                NumberInputI32 {
+                    label: "Rectangle Height",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |height| {
+                        crop.set(Bound { height, ..*crop.peek() });
+                    },
+                    value: crop().height,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ConfigPlaceableSkills(
+    is_disabled: Memo<bool>,
+    config_view: Memo<ConfigurationData>,
+    on_config: EventHandler<ConfigurationData>,
+) -> Element {
+    let mut selected = use_signal(|| None::<u32>);
+    let placeable_skills = use_memo(move || config_view().placeable_skills);
+    let selected_skill = use_memo(move || {
+        selected().and_then(|id| placeable_skills().into_iter().find(|skill| skill.id == id))
+    });
+    let on_skill = move |skill: PlaceableSkill| {
+        let mut config = config_view.peek().clone();
+        if let Some(inner) = config
+            .placeable_skills
+            .iter_mut()
+            .find(|inner| inner.id == skill.id)
+        {
+            *inner = skill;
+            on_config(config);
+        }
+    };
+
+    rsx! {
+        div { class: "flex flex-col space-y-2",
+            ul { class: "list-disc text-xs text-gray-700 pl-4",
+                li { "A placeable skill is used again when expired or the player moved too far" }
+                li { "Distance is measured in minimap pixels from where it was last placed" }
+            }
+            div { class: "flex space-x-2",
+                TextSelect {
+                    create_text: "+ Create new placeable skill",
+                    on_create: move |name: String| {
+                        let mut config = config_view.peek().clone();
+                        let id = config
+                            .placeable_skills
+                            .iter()
+                            .map(|skill| skill.id + 1)
+                            .max()
+                            .unwrap_or_default();
+                        config.placeable_skills.push(PlaceableSkill {
+                            id,
+                            name,
+                            ..PlaceableSkill::default()
+                        });
+                        selected.set(Some(id));
+                        on_config(config);
+                    },
+                    disabled: is_disabled(),
+                    on_select: move |(i, _): (usize, String)| {
+                        selected.set(placeable_skills.peek().get(i).map(|skill| skill.id));
+                    },
+                    options: placeable_skills()
+                        .into_iter()
+                        .map(|skill| skill.name)
+                        .collect::<Vec<_>>(),
+                    selected: selected_skill().map(|skill| skill.name),
+                }
+                if let Some(skill) = selected_skill() {
+                    button {
+                        class: "button-danger h-7 w-18",
+                        onclick: move |_| {
+                            let mut config = config_view.peek().clone();
+                            config.placeable_skills.retain(|inner| inner.id != skill.id);
+                            selected.set(None);
+                            on_config(config);
+                        },
+                        "Delete"
+                    }
+                }
+            }
+            if let Some(skill) = selected_skill() {
+                KeyBindingInput {
+                    label: "Key",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    disabled: is_disabled(),
+                    on_input: move |key| {
+                        let skill = selected_skill.peek().clone().unwrap();
+                        on_skill(PlaceableSkill {
+                            key: KeyBindingConfiguration { key, ..skill.key },
+                            ..skill
+                        });
+                    },
+                    value: skill.key.key,
+                }
+                Checkbox {
+                    label: "Enabled",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: "w-44",
+                    disabled: is_disabled(),
+                    on_input: move |enabled| {
+                        let skill = selected_skill.peek().clone().unwrap();
+                        on_skill(PlaceableSkill {
+                            key: KeyBindingConfiguration {
+                                enabled,
+                                ..skill.key
+                            },
+                            ..skill
+                        });
+                    },
+                    value: skill.key.enabled,
+                }
+                ConfigMillisInput {
+                    label: "Duration",
+                    disabled: is_disabled(),
+                    on_input: move |duration_millis| {
+                        on_skill(PlaceableSkill {
+                            duration_millis,
+                            ..selected_skill.peek().clone().unwrap()
+                        });
+                    },
+                    value: skill.duration_millis,
+                }
+                NumberInputU32 {
+                    label: "Max Distance",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    disabled: is_disabled(),
+                    minimum_value: 0,
+                    on_input: move |max_distance| {
+                        on_skill(PlaceableSkill {
+                            max_distance,
+                            ..selected_skill.peek().clone().unwrap()
+                        });
+                    },
+                    value: skill.max_distance,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ConfigPotionRules(
+    is_disabled: Memo<bool>,
+    config_view: Memo<ConfigurationData>,
+    on_config: EventHandler<ConfigurationData>,
+) -> Element {
+    let mut selected = use_signal(|| None::<u32>);
+    let potion_rules = use_memo(move || config_view().potion_rules);
+    let selected_rule = use_memo(move || {
+        selected().and_then(|id| potion_rules().into_iter().find(|rule| rule.id == id))
+    });
+    let on_rule = move |rule: PotionRule| {
+        let mut config = config_view.peek().clone();
+        if let Some(inner) = config
+            .potion_rules
+            .iter_mut()
+            .find(|inner| inner.id == rule.id)
+        {
+            *inner = rule;
+            on_config(config);
+        }
+    };
+
+    rsx! {
+        div { class: "flex flex-col space-y-2",
+            ul { class: "list-disc text-xs text-gray-700 pl-4",
+                li { "Only the rule with the lowest reached percentage is used" }
+                li { "A rule can halt and notify when health stays at or below for too long" }
+            }
+            div { class: "flex space-x-2",
+                TextSelect {
+                    create_text: "+ Create new potion rule",
+                    on_create: move |name: String| {
+                        let mut config = config_view.peek().clone();
+                        let id = config
+                            .potion_rules
+                            .iter()
+                            .map(|rule| rule.id + 1)
+                            .max()
+                            .unwrap_or_default();
+                        config.potion_rules.push(PotionRule {
+                            id,
+                            name,
+                            ..PotionRule::default()
+                        });
+                        selected.set(Some(id));
+                        on_config(config);
+                    },
+                    disabled: is_disabled(),
+                    on_select: move |(i, _): (usize, String)| {
+                        selected.set(potion_rules.peek().get(i).map(|rule| rule.id));
+                    },
+                    options: potion_rules()
+                        .into_iter()
+                        .map(|rule| rule.name)
+                        .collect::<Vec<_>>(),
+                    selected: selected_rule().map(|rule| rule.name),
+                }
+                if let Some(rule) = selected_rule() {
+                    button {
+                        class: "button-danger h-7 w-18",
+                        onclick: move |_| {
+                            let mut config = config_view.peek().clone();
+                            config.potion_rules.retain(|inner| inner.id != rule.id);
+                            selected.set(None);
+                            on_config(config);
+                        },
+                        "Delete"
+                    }
+                }
+            }
+            if let Some(rule) = selected_rule() {
+                KeyBindingInput {
+                    label: "Key",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    disabled: is_disabled(),
+                    on_input: move |key| {
+                        let rule = selected_rule.peek().clone().unwrap();
+                        on_rule(PotionRule {
+                            key: KeyBindingConfiguration { key, ..rule.key },
+                            ..rule
+                        });
+                    },
+                    value: rule.key.key,
+                }
+                Checkbox {
+                    label: "Enabled",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: "w-44",
+                    disabled: is_disabled(),
+                    on_input: move |enabled| {
+                        let rule = selected_rule.peek().clone().unwrap();
+                        on_rule(PotionRule {
+                            key: KeyBindingConfiguration {
+                                enabled,
+                                ..rule.key
+                            },
+                            ..rule
+                        });
+                    },
+                    value: rule.key.enabled,
+                }
+                PercentageInput {
+                    label: "Below Health Percentage",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    disabled: is_disabled(),
+                    on_input: move |below_percent| {
+                        on_rule(PotionRule {
+                            below_percent,
+                            ..selected_rule.peek().clone().unwrap()
+                        });
+                    },
+                    value: rule.below_percent,
+                }
+                ConfigMillisInput {
+                    label: "Cooldown",
+                    disabled: is_disabled(),
+                    on_input: move |cooldown_millis| {
+                        on_rule(PotionRule {
+                            cooldown_millis,
+                            ..selected_rule.peek().clone().unwrap()
+                        });
+                    },
+                    value: rule.cooldown_millis,
+                }
+                Checkbox {
+                    label: "Halt When Critical",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: "w-44",
+                    disabled: is_disabled(),
+                    on_input: move |halt| {
+                        on_rule(PotionRule {
+                            halt_after_millis: halt.then_some(10_000),
+                            ..selected_rule.peek().clone().unwrap()
+                        });
+                    },
+                    value: rule.halt_after_millis.is_some(),
+                }
+                Checkbox {
+                    label: "Stop Attacking When Below",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: "w-44",
+                    disabled: is_disabled(),
+                    on_input: move |stop_attacking| {
+                        on_rule(PotionRule {
+                            stop_attacking,
+                            ..selected_rule.peek().clone().unwrap()
+                        });
+                    },
+                    value: rule.stop_attacking,
+                }
+                if let Some(halt_after_millis) = rule.halt_after_millis {
+                    ConfigMillisInput {
+                        label: "Critical Milliseconds",
+                        disabled: is_disabled(),
+                        on_input: move |halt_after_millis| {
+                            on_rule(PotionRule {
+                                halt_after_millis: Some(halt_after_millis),
+                                ..selected_rule.peek().clone().unwrap()
+                            });
+                        },
+                        value: halt_after_millis,
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ConfigMesos(
+    active: Signal<Option<&'static str>>,
+    is_disabled: Memo<bool>,
+    config_view: Memo<ConfigurationData>,
+    on_config: EventHandler<ConfigurationData>,
+) -> Element {
+    let mut frame = use_signal(|| None::<(Vec<u8>, usize, usize)>);
+    let mut crop = use_signal(move || {
+        config_view.peek().mesos_region.unwrap_or(Bound {
+            x: 0,
+            y: 0,
+            width: 120,
+            height: 16,
+        })
+    });
+
+    use_effect(move || {
+        let bound = crop();
+        if let Some((buffer, width, height)) = frame() {
+            spawn(async move {
+                document::eval(CROP_FRAME_JS)
+                    .send((buffer, width, height, bound))
+                    .unwrap();
+            });
+        }
+    });
+
+    rsx! {
+        div { class: "flex flex-col space-y-2",
+            ul { class: "list-disc text-xs text-gray-700 pl-4",
+                li { "Capture the game frame while the inventory is opened" }
+                li { "Adjust the red rectangle to cover only the mesos amount" }
+                li { "The inventory is opened and closed again when the player is idle" }
+            }
+            KeyBindingConfigurationInput {
+                label: INVENTORY,
+                label_active: active,
+                is_disabled: is_disabled(),
+                is_toggleable: true,
+                on_input: move |key: Option<KeyBindingConfiguration>| {
+                    on_config(ConfigurationData {
+                        inventory_key: key.unwrap(),
+                        ..config_view.peek().clone()
+                    });
+                },
+                value: Some(config_view().inventory_key),
+                ConfigMillisInput {
+                    label: "Every Milliseconds",
+                    disabled: is_disabled(),
+                    on_input: move |value| {
+                        on_config(ConfigurationData {
+                            mesos_update_millis: value,
+                            ..config_view.peek().clone()
+                        });
+                    },
+                    value: config_view().mesos_update_millis,
+                }
+            }
+            p { class: "text-xs text-gray-700",
+                {
+                    config_view()
+                        .mesos_region
+                        .map(|bound| {
+                            format!(
+                                "Region: {}, {}, {} x {}",
+                                bound.x,
+                                bound.y,
+                                bound.width,
+                                bound.height,
+                            )
+                        })
+                        .unwrap_or("Region: Not set".to_string())
+                }
+            }
+            button {
+                class: "button-primary h-6",
+                disabled: is_disabled(),
+                onclick: move |_| async move {
+                    frame.set(game_frame().await);
+                },
+                "Capture game frame"
+            }
+            if frame().is_some() {
+                canvas { class: "w-full", id: "canvas-crop-frame" }
+                NumberInputI32 {
+                    label: "Rectangle X",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |x| {
+                        crop.set(Bound { x, ..*crop.peek() });
+                    },
+                    value: crop().x,
+                }
+                NumberInputI32 {
+                    label: "Rectangle Y",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |y| {
+                        crop.set(Bound { y, ..*crop.peek() });
+                    },
+                    value: crop().y,
+                }
+                NumberInputI32 {
+                    label: "Rectangle Width",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |width| {
+                        crop.set(Bound { width, ..*crop.peek() });
+                    },
+                    value: crop().width,
+                }
+                NumberInputI32 {
+                    label: "Rectangle Height",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    on_input: move |height| {
+                        crop.set(Bound { height, ..*crop.peek() });
+                    },
+                    value: crop().height,
+                }
+                button {
+                    class: "button-secondary h-6",
+                    disabled: is_disabled(),
+                    onclick: move |_| {
+                        on_config(ConfigurationData {
+                            mesos_region: Some(*crop.peek()),
+                            ..config_view.peek().clone()
+                        });
+                    },
+                    "Use rectangle as mesos region"
+                }
+            }
+        }
     }
 }
 
@@ -799,6 +2004,26 @@ fn ConfigMillisInput(
     }
 }
 
+#[component]
+fn ConfigThresholdInput(
+    label: String,
+    disabled: bool,
+    on_input: EventHandler<i32>,
+    value: i32,
+) -> Element {
+    rsx! {
+        NumberInputI32 {
+            label,
+            div_class: DIV_CLASS,
+            label_class: LABEL_CLASS,
+            input_class: INPUT_CLASS,
+            disabled,
+            on_input,
+            value,
+        }
+    }
+}
+
 #[component]
 fn ConfigHeader(
     text: String,
@@ -42,9 +42,12 @@ mod key;
 mod minimap;
 mod notification;
 mod platform;
+mod playlist;
 mod rotation;
 mod select;
+mod session;
 mod settings;
+mod simulation;
 mod tab;
 
 const TAILWIND_CSS: Asset = asset!("public/tailwind.css");
@@ -201,6 +204,7 @@ fn App() -> Element {
                         Actions {
                             app_coroutine: coroutine,
                             minimap,
+                            config,
                             settings,
                             preset,
                             copy_position,
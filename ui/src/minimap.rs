@@ -1,9 +1,10 @@
 use std::sync::Arc;
 
 use backend::{
-    Action, ActionKey, ActionMove, GameState, Minimap as MinimapData, RotationMode, create_minimap,
-    delete_map, minimap_frame, minimap_platforms_bound, player_state, query_maps, redetect_minimap,
-    rotate_actions, rotate_actions_halting, update_minimap, upsert_map,
+    Action, ActionKey, ActionMove, ActionSequence, GameState, Minimap as MinimapData, RotationMode,
+    change_channel, create_minimap, delete_map, minimap_frame, minimap_platforms_bound,
+    player_state, query_maps, redetect_minimap, reset_timers, rotate_actions,
+    rotate_actions_halting, update_minimap, upsert_map,
 };
 use dioxus::{document::EvalError, prelude::*};
 use futures_util::StreamExt;
@@ -59,7 +60,8 @@ const MINIMAP_ACTIONS_JS: &str = r#"
     const [width, height, actions, boundEnabled, bound, platforms] = await dioxus.recv();
     canvasCtx.clearRect(0, 0, canvas.width, canvas.height);
     const anyActions = actions.filter((action) => action.condition === "Any");
-    const erdaActions = actions.filter((action) => action.condition === "ErdaShowerOffCooldown");
+    const skillActions = actions.filter((action) =>
+        action.condition === "ErdaShowerOffCooldown" || action.condition === "SkillOffCooldown");
     const millisActions = actions.filter((action) => action.condition === "EveryMillis");
 
     canvasCtx.fillStyle = "rgb(255, 153, 128)";
@@ -88,7 +90,7 @@ const MINIMAP_ACTIONS_JS: &str = r#"
 
     canvasCtx.fillStyle = "rgb(179, 198, 255)";
     canvasCtx.strokeStyle = "rgb(179, 198, 255)";
-    drawActions(canvas, canvasCtx, erdaActions, true);
+    drawActions(canvas, canvasCtx, skillActions, true);
 
     canvasCtx.fillStyle = "rgb(128, 255, 204)";
     canvasCtx.strokeStyle = "rgb(128, 255, 204)";
@@ -136,6 +138,8 @@ struct ActionView {
 #[derive(Clone, Debug)]
 pub enum MinimapMessage {
     ToggleHalting,
+    ResetTimers,
+    ChangeChannel,
     RedetectMinimap,
     CreateMinimap(String),
     UpdateMinimap(MinimapData, bool),
@@ -181,6 +185,12 @@ pub fn Minimap(
                     MinimapMessage::ToggleHalting => {
                         rotate_actions(!halting()).await;
                     }
+                    MinimapMessage::ResetTimers => {
+                        reset_timers().await;
+                    }
+                    MinimapMessage::ChangeChannel => {
+                        change_channel().await;
+                    }
                     MinimapMessage::RedetectMinimap => {
                         redetect_minimap().await;
                     }
@@ -254,12 +264,20 @@ pub fn Minimap(
                     position: Some(position),
                     condition,
                     ..
+                })
+                | Action::Sequence(ActionSequence {
+                    position: Some(position),
+                    condition,
+                    ..
                 }) => Some(ActionView {
                     x: position.x,
                     y: position.y,
                     condition: condition.to_string(),
                 }),
-                Action::Key(ActionKey { position: None, .. }) => None,
+                Action::Key(ActionKey { position: None, .. })
+                | Action::Sequence(ActionSequence { position: None, .. })
+                | Action::Macro(_)
+                | Action::Branch(_) => None,
             })
             .collect::<Vec<ActionView>>();
 
@@ -274,7 +292,12 @@ pub fn Minimap(
                     }
                 }
                 RotationMode::PingPong(ping_pong) => Some(ping_pong.bound),
-                RotationMode::StartToEnd | RotationMode::StartToEndThenReverse => None,
+                RotationMode::StartToEnd
+                | RotationMode::StartToEndThenReverse
+                | RotationMode::Random
+                | RotationMode::Weighted
+                | RotationMode::ShuffleEachCycle
+                | RotationMode::Patrol => None,
             };
 
             spawn(async move {
@@ -390,6 +413,58 @@ pub fn Minimap(
                                 .unwrap_or("Health: Unknown".to_string())
                         }
                     }
+                    p {
+                        {
+                            state()
+                                .and_then(|state| state.mana)
+                                .map(|(current_mana, max_mana)| {
+                                    format!("Mana: {current_mana} / {max_mana}")
+                                })
+                                .unwrap_or("Mana: Unknown".to_string())
+                        }
+                    }
+                    p {
+                        {
+                            state()
+                                .and_then(|state| state.exp)
+                                .map(|exp| format!("EXP: {exp:.2}%"))
+                                .unwrap_or("EXP: Unknown".to_string())
+                        }
+                    }
+                    if let Some(session) = state().and_then(|state| state.exp_session) {
+                        p {
+                            {
+                                format!(
+                                    "EXP Gained: {:.2}% ({:.2}% / hour, {} level(s))",
+                                    session.exp_gained_percent,
+                                    session.exp_per_hour_percent(),
+                                    session.levels_gained,
+                                )
+                            }
+                        }
+                    }
+                    p {
+                        {
+                            state()
+                                .and_then(|state| state.mesos)
+                                .map(|mesos| format!("Mesos: {mesos}"))
+                                .unwrap_or("Mesos: Unknown".to_string())
+                        }
+                    }
+                    if let Some(session) = state()
+                        .filter(|state| state.mesos.is_some())
+                        .and_then(|state| state.exp_session)
+                    {
+                        p {
+                            {
+                                format!(
+                                    "Mesos Gained: {} ({} / hour)",
+                                    session.mesos_gained,
+                                    session.mesos_per_hour(),
+                                )
+                            }
+                        }
+                    }
                     p {
                         {
                             state()
@@ -421,6 +496,28 @@ pub fn Minimap(
                                 .unwrap_or("Erda Shower: Unknown".to_string())
                         }
                     }
+                    for (name , skill_state) in state()
+                        .map(|state| state.custom_skill_states)
+                        .unwrap_or_default()
+                    {
+                        p { "{name}: {skill_state}" }
+                    }
+                    p {
+                        {
+                            state()
+                                .and_then(|state| state.playlist)
+                                .map(|(i, preset)| format!("Playlist: {}. {preset}", i + 1))
+                                .unwrap_or("Playlist: None".to_string())
+                        }
+                    }
+                    if let Some(mismatches) = state()
+                        .map(|state| state.buff_mismatches)
+                        .filter(|mismatches| !mismatches.is_empty())
+                    {
+                        p { class: "text-red-500",
+                            {format!("Buff Mismatch: {}", mismatches.join(", "))}
+                        }
+                    }
                 }
             }
             div { class: "flex w-full space-x-6 items-center justify-center items-stretch h-7",
@@ -444,6 +541,22 @@ pub fn Minimap(
                     },
                     "Re-detect map"
                 }
+                button {
+                    class: "button-secondary",
+                    disabled: minimap().is_none(),
+                    onclick: move |_| async move {
+                        coroutine.send(MinimapMessage::ResetTimers);
+                    },
+                    "Reset timers"
+                }
+                button {
+                    class: "button-secondary",
+                    disabled: minimap().is_none() || halting(),
+                    onclick: move |_| async move {
+                        coroutine.send(MinimapMessage::ChangeChannel);
+                    },
+                    "Change channel"
+                }
                 button {
                     class: "button-danger",
                     disabled: minimap().is_none(),
@@ -3,6 +3,7 @@ use dioxus::prelude::*;
 
 use crate::{
     AppMessage,
+    input::MillisInput,
     settings::{SettingsCheckbox, SettingsTextInput},
 };
 
@@ -112,6 +113,33 @@ pub fn Notifications(
                 },
                 value: notifications_view().notify_on_player_friend_appear,
             }
+            SettingsCheckbox {
+                label: "Report EXP Periodically",
+                on_input: move |report| {
+                    on_notifications(NotificationsData {
+                        exp_report_millis: report.then_some(3_600_000),
+                        ..notifications_view.peek().clone()
+                    });
+                },
+                value: notifications_view().exp_report_millis.is_some(),
+            }
+            if let Some(exp_report_millis) = notifications_view().exp_report_millis {
+                MillisInput {
+                    label: "Report EXP Every",
+                    label_class: "text-xs text-gray-700 flex-1 inline-block data-[disabled]:text-gray-400",
+                    div_class: "flex items-center space-x-4 mt-2",
+                    input_class: "w-24 text-gray-700 text-xs p-1 border rounded border-gray-300",
+                    disabled: false,
+                    on_input: move |exp_report_millis: u64| {
+                        on_notifications(NotificationsData {
+                            // Avoids spamming the webhook
+                            exp_report_millis: Some(exp_report_millis.max(60_000)),
+                            ..notifications_view.peek().clone()
+                        });
+                    },
+                    value: exp_report_millis,
+                }
+            }
         }
     }
 }
@@ -1,6 +1,4 @@
-use backend::{
-    KeyBindingConfiguration, MAX_PLATFORMS_COUNT, Minimap, Platform, Settings, key_receiver,
-};
+use backend::{KeyBindingConfiguration, Minimap, Platform, Portal, Rope, Settings, key_receiver};
 use dioxus::prelude::*;
 
 use crate::{
@@ -18,10 +16,8 @@ pub fn Platforms(
     copy_position: ReadOnlySignal<Option<(i32, i32)>>,
 ) -> Element {
     let mut editing = use_signal(Platform::default);
-    let add_platform_disabled = use_memo(move || {
-        let minimap = minimap();
-        minimap.is_none() || minimap.unwrap().platforms.len() >= MAX_PLATFORMS_COUNT
-    });
+    let mut editing_rope = use_signal(Rope::default);
+    let mut editing_portal = use_signal(Portal::default);
 
     use_future(move || async move {
         let mut key_receiver = key_receiver().await;
@@ -159,7 +155,7 @@ pub fn Platforms(
                 copy_position,
                 label: "Add",
                 delete: false,
-                disabled: add_platform_disabled(),
+                disabled: minimap().is_none(),
                 on_click: move |_| {
                     if let Some(mut minimap) = minimap.peek().clone() {
                         minimap.platforms.push(*editing.peek());
@@ -171,6 +167,97 @@ pub fn Platforms(
                 },
                 value: editing(),
             }
+            div { class: "flex items-center justify-between text-xs text-gray-700 border-b border-gray-300 mt-3 mb-2 data-[disabled]:text-gray-400",
+                p { class: "w-26", "Rope X" }
+                p { class: "w-26", "Y Bottom" }
+                p { class: "w-26", "Y Top" }
+                div { class: "w-18" }
+            }
+            if let Some(Minimap { ropes, .. }) = minimap() {
+                for (i , rope) in ropes.into_iter().enumerate() {
+                    RopeInput {
+                        copy_position,
+                        label: "Delete",
+                        delete: true,
+                        disabled: minimap().is_none(),
+                        on_click: move |_| {
+                            if let Some(mut minimap) = minimap.peek().clone() {
+                                minimap.ropes.remove(i);
+                                on_save(minimap);
+                            }
+                        },
+                        on_input: move |value| {
+                            if let Some(mut minimap) = minimap.peek().clone() {
+                                *minimap.ropes.get_mut(i).unwrap() = value;
+                                on_save(minimap);
+                            }
+                        },
+                        value: rope,
+                    }
+                }
+            }
+            RopeInput {
+                copy_position,
+                label: "Add",
+                delete: false,
+                disabled: minimap().is_none(),
+                on_click: move |_| {
+                    if let Some(mut minimap) = minimap.peek().clone() {
+                        minimap.ropes.push(*editing_rope.peek());
+                        on_save(minimap);
+                    }
+                },
+                on_input: move |value| {
+                    editing_rope.set(value);
+                },
+                value: editing_rope(),
+            }
+            div { class: "flex items-center justify-between text-xs text-gray-700 border-b border-gray-300 mt-3 mb-2 data-[disabled]:text-gray-400",
+                p { class: "w-19", "Portal X" }
+                p { class: "w-19", "Portal Y" }
+                p { class: "w-19", "To X" }
+                p { class: "w-19", "To Y" }
+                div { class: "w-18" }
+            }
+            if let Some(Minimap { portals, .. }) = minimap() {
+                for (i , portal) in portals.into_iter().enumerate() {
+                    PortalInput {
+                        copy_position,
+                        label: "Delete",
+                        delete: true,
+                        disabled: minimap().is_none(),
+                        on_click: move |_| {
+                            if let Some(mut minimap) = minimap.peek().clone() {
+                                minimap.portals.remove(i);
+                                on_save(minimap);
+                            }
+                        },
+                        on_input: move |value| {
+                            if let Some(mut minimap) = minimap.peek().clone() {
+                                *minimap.portals.get_mut(i).unwrap() = value;
+                                on_save(minimap);
+                            }
+                        },
+                        value: portal,
+                    }
+                }
+            }
+            PortalInput {
+                copy_position,
+                label: "Add",
+                delete: false,
+                disabled: minimap().is_none(),
+                on_click: move |_| {
+                    if let Some(mut minimap) = minimap.peek().clone() {
+                        minimap.portals.push(*editing_portal.peek());
+                        on_save(minimap);
+                    }
+                },
+                on_input: move |value| {
+                    editing_portal.set(value);
+                },
+                value: editing_portal(),
+            }
         }
     }
 }
@@ -264,14 +351,163 @@ fn PlatformInput(
     }
 }
 
+#[component]
+fn RopeInput(
+    copy_position: ReadOnlySignal<Option<(i32, i32)>>,
+    label: String,
+    delete: bool,
+    disabled: bool,
+    on_click: EventHandler,
+    on_input: EventHandler<Rope>,
+    value: Rope,
+) -> Element {
+    let Rope { x, y_bottom, y_top } = value;
+
+    rsx! {
+        div { class: "flex items-center justify-between text-xs text-gray-700",
+            PlatformNumberInput {
+                disabled,
+                on_icon_click: move |_| {
+                    if let Some((x, _)) = *copy_position.peek() {
+                        on_input(Rope { x, ..value });
+                    }
+                },
+                on_input: move |x| {
+                    on_input(Rope { x, ..value });
+                },
+                value: x,
+            }
+            PlatformNumberInput {
+                disabled,
+                on_icon_click: move |_| {
+                    if let Some((_, y_bottom)) = *copy_position.peek() {
+                        on_input(Rope { y_bottom, ..value });
+                    }
+                },
+                on_input: move |y_bottom| {
+                    on_input(Rope { y_bottom, ..value });
+                },
+                value: y_bottom,
+            }
+            PlatformNumberInput {
+                disabled,
+                on_icon_click: move |_| {
+                    if let Some((_, y_top)) = *copy_position.peek() {
+                        on_input(Rope { y_top, ..value });
+                    }
+                },
+                on_input: move |y_top| {
+                    on_input(Rope { y_top, ..value });
+                },
+                value: y_top,
+            }
+            button {
+                class: {
+                    let class = if delete { "button-danger" } else { "button-primary" };
+                    format!("{class} h-6 w-18")
+                },
+                disabled,
+                onclick: move |_| {
+                    on_click(());
+                },
+                {label}
+            }
+        }
+    }
+}
+
+#[component]
+fn PortalInput(
+    copy_position: ReadOnlySignal<Option<(i32, i32)>>,
+    label: String,
+    delete: bool,
+    disabled: bool,
+    on_click: EventHandler,
+    on_input: EventHandler<Portal>,
+    value: Portal,
+) -> Element {
+    const WIDTH_CLASS: &str = "w-19";
+
+    let Portal { x, y, to_x, to_y } = value;
+
+    rsx! {
+        div { class: "flex items-center justify-between text-xs text-gray-700",
+            PlatformNumberInput {
+                width_class: WIDTH_CLASS,
+                disabled,
+                on_icon_click: move |_| {
+                    if let Some((x, y)) = *copy_position.peek() {
+                        on_input(Portal { x, y, ..value });
+                    }
+                },
+                on_input: move |x| {
+                    on_input(Portal { x, ..value });
+                },
+                value: x,
+            }
+            PlatformNumberInput {
+                width_class: WIDTH_CLASS,
+                disabled,
+                on_icon_click: move |_| {
+                    if let Some((x, y)) = *copy_position.peek() {
+                        on_input(Portal { x, y, ..value });
+                    }
+                },
+                on_input: move |y| {
+                    on_input(Portal { y, ..value });
+                },
+                value: y,
+            }
+            PlatformNumberInput {
+                width_class: WIDTH_CLASS,
+                disabled,
+                on_icon_click: move |_| {
+                    if let Some((to_x, to_y)) = *copy_position.peek() {
+                        on_input(Portal { to_x, to_y, ..value });
+                    }
+                },
+                on_input: move |to_x| {
+                    on_input(Portal { to_x, ..value });
+                },
+                value: to_x,
+            }
+            PlatformNumberInput {
+                width_class: WIDTH_CLASS,
+                disabled,
+                on_icon_click: move |_| {
+                    if let Some((to_x, to_y)) = *copy_position.peek() {
+                        on_input(Portal { to_x, to_y, ..value });
+                    }
+                },
+                on_input: move |to_y| {
+                    on_input(Portal { to_y, ..value });
+                },
+                value: to_y,
+            }
+            button {
+                class: {
+                    let class = if delete { "button-danger" } else { "button-primary" };
+                    format!("{class} h-6 w-18")
+                },
+                disabled,
+                onclick: move |_| {
+                    on_click(());
+                },
+                {label}
+            }
+        }
+    }
+}
+
 #[component]
 fn PlatformNumberInput(
+    #[props(default = String::from("w-26"))] width_class: String,
     disabled: bool,
     on_icon_click: EventHandler,
     on_input: EventHandler<i32>,
     value: i32,
 ) -> Element {
-    const INPUT_CLASS: &str = "w-26 h-6 px-1.5 border border-gray-300 rounded text-xs text-ellipsis outline-none disabled:text-gray-400 disabled:cursor-not-allowed";
+    const INPUT_CLASS: &str = "h-6 px-1.5 border border-gray-300 rounded text-xs text-ellipsis outline-none disabled:text-gray-400 disabled:cursor-not-allowed";
 
     let mut is_hovering = use_signal(|| false);
 
@@ -287,7 +523,7 @@ fn PlatformNumberInput(
             NumberInputI32 {
                 label: "",
                 label_class: "hidden",
-                input_class: INPUT_CLASS,
+                input_class: format!("{width_class} {INPUT_CLASS}"),
                 disabled,
                 on_input: move |value| {
                     on_input(value);
@@ -0,0 +1,183 @@
+use backend::{Minimap, PlaylistEntry, PlaylistLimit};
+use dioxus::prelude::*;
+
+use crate::{
+    input::{Checkbox, MillisInput, NumberInputU32},
+    select::{EnumSelect, Select},
+};
+
+const DIV_CLASS: &str = "flex py-2 border-b border-gray-100 space-x-2";
+const LABEL_CLASS: &str = "flex-1 text-xs text-gray-700 inline-block data-[disabled]:text-gray-400";
+const INPUT_CLASS: &str = "w-36 px-1.5 h-6 border border-gray-300 rounded text-xs text-ellipsis outline-none disabled:text-gray-400 disabled:cursor-not-allowed";
+
+#[component]
+pub fn Playlist(
+    minimap: ReadOnlySignal<Option<Minimap>>,
+    on_save: EventHandler<Minimap>,
+) -> Element {
+    let disabled = use_memo(move || minimap().is_none());
+    let presets = use_memo::<Vec<String>>(move || {
+        minimap()
+            .map(|minimap| {
+                let mut presets = minimap.actions.keys().cloned().collect::<Vec<_>>();
+                presets.sort();
+                presets
+            })
+            .unwrap_or_default()
+    });
+    let entries = use_memo(move || {
+        minimap()
+            .map(|minimap| minimap.playlist)
+            .unwrap_or_default()
+    });
+
+    rsx! {
+        div { class: "flex flex-col space-y-2",
+            ul { class: "list-disc text-xs text-gray-700 pl-4",
+                li { "When enabled, the playlist presets are run in order instead of the selected preset" }
+                li { "Switching preset only happens when there is no linked action executing" }
+                li { "Cycles counts the number of times all Any condition actions are rotated" }
+                li {
+                    "In auto mobbing and ping pong, each mob or side is a cycle. In patrol, visiting all zones is a cycle"
+                }
+            }
+            div { class: "h-2 border-b border-gray-300 mb-2" }
+            Checkbox {
+                label: "Playlist Enabled",
+                label_class: LABEL_CLASS,
+                div_class: DIV_CLASS,
+                input_class: "w-36 text-xs text-gray-700 text-ellipsis rounded outline-none disabled:cursor-not-allowed disabled:text-gray-400",
+                disabled: disabled(),
+                on_input: move |enabled| {
+                    if let Some(mut minimap) = minimap.peek().clone() {
+                        minimap.playlist_enabled = enabled;
+                        on_save(minimap);
+                    }
+                },
+                value: minimap().map(|minimap| minimap.playlist_enabled).unwrap_or_default(),
+            }
+            for (i , entry) in entries().into_iter().enumerate() {
+                div { class: "flex items-center justify-between py-2 border-b border-gray-300",
+                    p { class: "text-xs text-gray-700", {format!("Entry {}", i + 1)} }
+                    button {
+                        class: "button-danger h-6 w-18",
+                        disabled: disabled(),
+                        onclick: move |_| {
+                            if let Some(mut minimap) = minimap.peek().clone() {
+                                minimap.playlist.remove(i);
+                                on_save(minimap);
+                            }
+                        },
+                        "Delete"
+                    }
+                }
+                PlaylistEntryInput {
+                    disabled: disabled(),
+                    presets: presets(),
+                    on_input: move |entry| {
+                        if let Some(mut minimap) = minimap.peek().clone() {
+                            *minimap.playlist.get_mut(i).unwrap() = entry;
+                            on_save(minimap);
+                        }
+                    },
+                    value: entry,
+                }
+            }
+            button {
+                class: "button-primary h-6 w-full mt-2",
+                disabled: disabled() || presets().is_empty(),
+                onclick: move |_| {
+                    if let Some(mut minimap) = minimap.peek().clone() {
+                        minimap.playlist.push(PlaylistEntry {
+                            preset: presets.peek().first().cloned().unwrap_or_default(),
+                            ..PlaylistEntry::default()
+                        });
+                        on_save(minimap);
+                    }
+                },
+                "Add Entry"
+            }
+        }
+    }
+}
+
+#[component]
+fn PlaylistEntryInput(
+    disabled: bool,
+    presets: Vec<String>,
+    on_input: EventHandler<PlaylistEntry>,
+    value: PlaylistEntry,
+) -> Element {
+    let value = use_memo(use_reactive!(|value| value));
+    let options = presets
+        .into_iter()
+        .map(|preset| (preset.clone(), preset))
+        .collect::<Vec<_>>();
+
+    rsx! {
+        Select {
+            label: "Preset",
+            div_class: DIV_CLASS,
+            label_class: LABEL_CLASS,
+            select_class: INPUT_CLASS,
+            disabled,
+            options,
+            on_select: move |(_, preset): (usize, String)| {
+                on_input(PlaylistEntry {
+                    preset,
+                    ..value.peek().clone()
+                });
+            },
+            selected: value().preset,
+        }
+        EnumSelect {
+            label: "Switch After",
+            div_class: DIV_CLASS,
+            label_class: LABEL_CLASS,
+            select_class: INPUT_CLASS,
+            disabled,
+            on_select: move |limit: PlaylistLimit| {
+                on_input(PlaylistEntry {
+                    limit: limit.non_zero(),
+                    ..value.peek().clone()
+                });
+            },
+            selected: value().limit,
+        }
+        match value().limit {
+            PlaylistLimit::Millis(millis) => rsx! {
+                MillisInput {
+                    label: "Duration",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    disabled,
+                    on_input: move |millis| {
+                        on_input(PlaylistEntry {
+                            limit: PlaylistLimit::Millis(millis),
+                            ..value.peek().clone()
+                        });
+                    },
+                    value: millis,
+                }
+            },
+            PlaylistLimit::Cycles(cycles) => rsx! {
+                NumberInputU32 {
+                    label: "Cycles",
+                    div_class: DIV_CLASS,
+                    label_class: LABEL_CLASS,
+                    input_class: INPUT_CLASS,
+                    disabled,
+                    minimum_value: 1,
+                    on_input: move |cycles| {
+                        on_input(PlaylistEntry {
+                            limit: PlaylistLimit::Cycles(cycles),
+                            ..value.peek().clone()
+                        });
+                    },
+                    value: cycles,
+                }
+            },
+        }
+    }
+}
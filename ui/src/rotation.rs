@@ -1,4 +1,6 @@
-use backend::{AutoMobbing, Bound, PingPong, RotationMode};
+use backend::{
+    ActionKeyDirection, AutoMobbing, Bound, PatrolBehavior, PatrolZone, PingPong, RotationMode,
+};
 use dioxus::prelude::*;
 
 use crate::{
@@ -15,20 +17,29 @@ pub fn Rotations(
     disabled: bool,
     on_rotation_mode: EventHandler<RotationMode>,
     on_reset_on_erda: EventHandler<bool>,
+    on_patrol_zones: EventHandler<Vec<PatrolZone>>,
     rotation_mode: RotationMode,
     reset_on_erda: bool,
+    patrol_zones: Vec<PatrolZone>,
 ) -> Element {
     rsx! {
         div { class: "flex flex-col space-y-2",
             ul { class: "list-disc text-xs text-gray-700 pl-4",
                 li { "Other rotation modes apply only to Any condition action" }
                 li { "Action in preset with Any condition is ignored when auto mobbing enabled" }
+                li { "Random mode never picks the same Any condition action twice in a row" }
+                li { "Weighted mode picks Any condition action based on its weight" }
+                li { "Shuffle each cycle mode runs all Any condition actions in a new random order each cycle" }
                 li {
                     "When reset rotation on Erda condotion is ticked, all Any condition actions will restart from the beginning"
                 }
                 li { "Mob detected outside of bound is ignored" }
                 li { "Auto mobbing X,Y origin is top-left of minimap" }
                 li { "Overrides the below bound if auto mobbing bound by platforms enabled" }
+                li {
+                    "Patrol mode visits each zone in order and stays in a zone for its dwell time after entering"
+                }
+                li { "Patrol mode moves between zones without attacking" }
             }
             div { class: "h-2 border-b border-gray-300 mb-2" }
             EnumSelect {
@@ -50,7 +61,9 @@ pub fn Rotations(
                 disabled: disabled
                     | matches!(
                         rotation_mode,
-                        RotationMode::PingPong(_) | RotationMode::AutoMobbing(_)
+                        RotationMode::PingPong(_)
+                        | RotationMode::AutoMobbing(_)
+                        | RotationMode::Patrol
                     ),
                 on_input: move |checked| {
                     on_reset_on_erda(checked);
@@ -73,11 +86,138 @@ pub fn Rotations(
                     },
                     value: ping_pong,
                 }
+            } else if let RotationMode::Patrol = rotation_mode {
+                PatrolZonesInput {
+                    disabled,
+                    on_input: move |zones| {
+                        on_patrol_zones(zones);
+                    },
+                    value: patrol_zones,
+                }
             }
         }
     }
 }
 
+#[component]
+fn PatrolZonesInput(
+    disabled: bool,
+    on_input: EventHandler<Vec<PatrolZone>>,
+    value: Vec<PatrolZone>,
+) -> Element {
+    let zones = use_memo(use_reactive!(|value| value));
+
+    rsx! {
+        for (i , zone) in zones().into_iter().enumerate() {
+            div { class: "flex items-center justify-between py-2 border-b border-gray-300",
+                p { class: "text-xs text-gray-700", {format!("Zone {}", i + 1)} }
+                button {
+                    class: "button-danger h-6 w-18",
+                    disabled,
+                    onclick: move |_| {
+                        let mut zones = zones.peek().clone();
+                        zones.remove(i);
+                        on_input(zones);
+                    },
+                    "Delete"
+                }
+            }
+            PatrolZoneInput {
+                disabled,
+                on_input: move |zone| {
+                    let mut zones = zones.peek().clone();
+                    *zones.get_mut(i).unwrap() = zone;
+                    on_input(zones);
+                },
+                value: zone,
+            }
+        }
+        button {
+            class: "button-primary h-6 w-full mt-2",
+            disabled,
+            onclick: move |_| {
+                let mut zones = zones.peek().clone();
+                zones.push(PatrolZone::default());
+                on_input(zones);
+            },
+            "Add Zone"
+        }
+    }
+}
+
+#[component]
+fn PatrolZoneInput(
+    disabled: bool,
+    on_input: EventHandler<PatrolZone>,
+    value: PatrolZone,
+) -> Element {
+    let PatrolZone {
+        bound,
+        behavior,
+        direction,
+        dwell_millis,
+        ..
+    } = value;
+    let ping_pong = PingPong {
+        bound,
+        key: value.key,
+        key_count: value.key_count,
+        key_wait_before_millis: value.key_wait_before_millis,
+        key_wait_after_millis: value.key_wait_after_millis,
+    };
+
+    rsx! {
+        EnumSelect {
+            label: "Behavior",
+            div_class: DIV_CLASS,
+            label_class: LABEL_CLASS,
+            select_class: INPUT_CLASS,
+            disabled,
+            on_select: move |behavior: PatrolBehavior| {
+                on_input(PatrolZone { behavior, ..value });
+            },
+            selected: behavior,
+        }
+        EnumSelect {
+            label: "Initial Direction",
+            div_class: DIV_CLASS,
+            label_class: LABEL_CLASS,
+            select_class: INPUT_CLASS,
+            disabled: disabled || matches!(behavior, PatrolBehavior::AutoMobbing),
+            on_select: move |direction: ActionKeyDirection| {
+                on_input(PatrolZone { direction, ..value });
+            },
+            selected: direction,
+        }
+        MillisInput {
+            label: "Dwell",
+            div_class: DIV_CLASS,
+            label_class: LABEL_CLASS,
+            input_class: INPUT_CLASS,
+            disabled,
+            on_input: move |dwell_millis| {
+                on_input(PatrolZone { dwell_millis, ..value });
+            },
+            value: dwell_millis,
+        }
+        // Zone shares the same fields with ping pong
+        PingPongInput {
+            disabled,
+            on_input: move |ping_pong: PingPong| {
+                on_input(PatrolZone {
+                    bound: ping_pong.bound,
+                    key: ping_pong.key,
+                    key_count: ping_pong.key_count,
+                    key_wait_before_millis: ping_pong.key_wait_before_millis,
+                    key_wait_after_millis: ping_pong.key_wait_after_millis,
+                    ..value
+                });
+            },
+            value: ping_pong,
+        }
+    }
+}
+
 #[component]
 fn AutoMobbingInput(
     disabled: bool,
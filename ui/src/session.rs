@@ -0,0 +1,82 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use backend::{Minimap, SessionRecord, query_sessions};
+use dioxus::prelude::*;
+use tokio::task::spawn_blocking;
+
+#[component]
+pub fn Sessions(minimap: ReadOnlySignal<Option<Minimap>>) -> Element {
+    let mut sessions = use_resource(move || async move {
+        let Some(map) = minimap().map(|minimap| minimap.name) else {
+            return vec![];
+        };
+        let mut sessions = spawn_blocking(|| query_sessions().unwrap_or_default())
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|session| session.map == map)
+            .collect::<Vec<_>>();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.started_at_secs));
+        sessions
+    });
+    let sessions_view = use_memo(move || sessions().unwrap_or_default());
+
+    rsx! {
+        div { class: "flex flex-col space-y-2",
+            ul { class: "list-disc text-xs text-gray-700 pl-4",
+                li { "Lists the past sessions of the current map from newest to oldest" }
+                li { "A session is saved when halting or on each EXP report" }
+            }
+            div { class: "h-2 border-b border-gray-300 mb-2" }
+            for session in sessions_view() {
+                SessionItem { session }
+            }
+            if sessions_view().is_empty() {
+                p { class: "text-xs text-gray-700", "No session" }
+            }
+            button {
+                class: "button-primary h-6 w-full mt-2",
+                disabled: minimap().is_none(),
+                onclick: move |_| {
+                    sessions.restart();
+                },
+                "Refresh"
+            }
+        }
+    }
+}
+
+#[component]
+fn SessionItem(session: SessionRecord) -> Element {
+    let now_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let ago_hours = now_secs.saturating_sub(session.started_at_secs) as f32 / 3600.0;
+    let elapsed_minutes = session.elapsed_millis / 60_000;
+
+    rsx! {
+        div { class: "flex flex-col py-2 border-b border-gray-100 text-xs text-gray-700",
+            p { {format!("Started {ago_hours:.1} hour(s) ago, ran {elapsed_minutes} minute(s)")} }
+            p {
+                {
+                    format!(
+                        "EXP: {:.2}% ({:.2}% / hour, {} level(s))",
+                        session.exp_gained_percent,
+                        session.exp_per_hour_percent(),
+                        session.levels_gained,
+                    )
+                }
+            }
+            p {
+                {
+                    format!(
+                        "Mesos: {} ({} / hour)",
+                        session.mesos_gained,
+                        session.mesos_per_hour(),
+                    )
+                }
+            }
+        }
+    }
+}
@@ -10,7 +10,7 @@ use dioxus::prelude::*;
 
 use crate::{
     AppMessage,
-    input::{Checkbox, LabeledInput},
+    input::{Checkbox, LabeledInput, MillisInput, NumberInputU32},
     key::KeyBindingConfigurationInput,
     select::{EnumSelect, Select},
 };
@@ -78,6 +78,87 @@ pub fn Settings(
                     },
                     value: settings_view().stop_on_fail_or_change_map,
                 }
+                SettingsCheckbox {
+                    label: "Revive On Death",
+                    on_input: move |revive_on_death| {
+                        on_settings(SettingsData {
+                            revive_on_death,
+                            ..settings_view.peek().clone()
+                        });
+                    },
+                    value: settings_view().revive_on_death,
+                }
+                SettingsCheckbox {
+                    label: "Stop Actions If Dies Too Often",
+                    on_input: move |enabled: bool| {
+                        on_settings(SettingsData {
+                            max_deaths_per_hour: enabled.then_some(3),
+                            ..settings_view.peek().clone()
+                        });
+                    },
+                    value: settings_view().max_deaths_per_hour.is_some(),
+                }
+                if let Some(max_deaths_per_hour) = settings_view().max_deaths_per_hour {
+                    NumberInputU32 {
+                        label: "Max Deaths Per Hour",
+                        label_class: SELECT_LABEL_CLASS,
+                        div_class: SELECT_DIV_CLASS,
+                        input_class: "w-44 h-7 text-xs text-gray-700 border border-gray-300 rounded outline-none p-1",
+                        minimum_value: 1,
+                        on_input: move |max_deaths_per_hour| {
+                            on_settings(SettingsData {
+                                max_deaths_per_hour: Some(max_deaths_per_hour),
+                                ..settings_view.peek().clone()
+                            });
+                        },
+                        value: max_deaths_per_hour,
+                    }
+                }
+                SettingsCheckbox {
+                    label: "Change Channel If Stranger Appears",
+                    on_input: move |change_channel_on_stranger| {
+                        on_settings(SettingsData {
+                            change_channel_on_stranger,
+                            ..settings_view.peek().clone()
+                        });
+                    },
+                    value: settings_view().change_channel_on_stranger,
+                }
+                SettingsCheckbox {
+                    label: "Change Channel Periodically",
+                    on_input: move |enabled: bool| {
+                        on_settings(SettingsData {
+                            change_channel_millis: enabled.then_some(1800000),
+                            ..settings_view.peek().clone()
+                        });
+                    },
+                    value: settings_view().change_channel_millis.is_some(),
+                }
+                if let Some(change_channel_millis) = settings_view().change_channel_millis {
+                    MillisInput {
+                        label: "Change Channel Every Milliseconds",
+                        label_class: SELECT_LABEL_CLASS,
+                        div_class: SELECT_DIV_CLASS,
+                        input_class: "w-44 h-7 text-xs text-gray-700 border border-gray-300 rounded outline-none p-1",
+                        on_input: move |millis| {
+                            on_settings(SettingsData {
+                                change_channel_millis: Some(millis),
+                                ..settings_view.peek().clone()
+                            });
+                        },
+                        value: change_channel_millis,
+                    }
+                }
+                SettingsCheckbox {
+                    label: "Change To Random Channel",
+                    on_input: move |change_channel_random| {
+                        on_settings(SettingsData {
+                            change_channel_random,
+                            ..settings_view.peek().clone()
+                        });
+                    },
+                    value: settings_view().change_channel_random,
+                }
                 SettingsEnumSelect::<CaptureMode> {
                     label: "Capture Mode",
                     on_select: move |capture_mode| {
@@ -0,0 +1,195 @@
+use backend::{
+    MAX_SIMULATION_DURATION_MILLIS, SimulatedAction, SimulatedActionKind, SimulationOptions,
+    SimulationReport, SimulationWarning, simulate_rotation,
+};
+use dioxus::prelude::*;
+
+use crate::input::MillisInput;
+
+const DIV_CLASS: &str = "flex py-2 border-b border-gray-100 space-x-2";
+const LABEL_CLASS: &str = "flex-1 text-xs text-gray-700 inline-block data-[disabled]:text-gray-400";
+const INPUT_CLASS: &str = "w-36 px-1.5 h-6 border border-gray-300 rounded text-xs text-ellipsis outline-none disabled:text-gray-400 disabled:cursor-not-allowed";
+
+#[component]
+pub fn Simulation(disabled: bool) -> Element {
+    let mut options = use_signal(SimulationOptions::default);
+    let mut report = use_signal::<Option<SimulationReport>>(|| None);
+    let mut simulating = use_signal(|| false);
+
+    rsx! {
+        div { class: "flex flex-col space-y-2",
+            ul { class: "list-disc text-xs text-gray-700 pl-4",
+                li { "Simulates the running preset offline without sending any key" }
+                li { "Buff, rune, elite boss and skill off cooldown actions are not simulated" }
+                li { "Auto mobbing, ping pong and patrol normal actions are not simulated" }
+                li { "Action number is the position of the action in the preset" }
+                li {
+                    {
+                        format!(
+                            "Duration is capped at {} hour(s)",
+                            MAX_SIMULATION_DURATION_MILLIS / 3_600_000,
+                        )
+                    }
+                }
+            }
+            div { class: "h-2 border-b border-gray-300 mb-2" }
+            MillisInput {
+                label: "Duration",
+                div_class: DIV_CLASS,
+                label_class: LABEL_CLASS,
+                input_class: INPUT_CLASS,
+                disabled,
+                on_input: move |millis| {
+                    options.write().duration_millis = millis;
+                },
+                value: options().duration_millis,
+            }
+            MillisInput {
+                label: "Move Duration",
+                div_class: DIV_CLASS,
+                label_class: LABEL_CLASS,
+                input_class: INPUT_CLASS,
+                disabled,
+                on_input: move |millis| {
+                    options.write().move_millis = millis;
+                },
+                value: options().move_millis,
+            }
+            MillisInput {
+                label: "Key Duration",
+                div_class: DIV_CLASS,
+                label_class: LABEL_CLASS,
+                input_class: INPUT_CLASS,
+                disabled,
+                on_input: move |millis| {
+                    options.write().key_millis = millis;
+                },
+                value: options().key_millis,
+            }
+            MillisInput {
+                label: "Erda Shower Cooldown",
+                div_class: DIV_CLASS,
+                label_class: LABEL_CLASS,
+                input_class: INPUT_CLASS,
+                disabled,
+                on_input: move |millis| {
+                    options.write().erda_shower_cooldown_millis = millis;
+                },
+                value: options().erda_shower_cooldown_millis,
+            }
+            button {
+                class: "button-primary h-6 w-full mt-2",
+                disabled: disabled || simulating(),
+                onclick: move |_| {
+                    simulating.set(true);
+                    spawn(async move {
+                        report.set(simulate_rotation(*options.peek()).await);
+                        simulating.set(false);
+                    });
+                },
+                "Simulate"
+            }
+            if let Some(report) = report() {
+                SimulationReportView { report }
+            }
+        }
+    }
+}
+
+#[component]
+fn SimulationReportView(report: SimulationReport) -> Element {
+    let normal_count = report
+        .timeline
+        .iter()
+        .filter(|action| action.kind == SimulatedActionKind::Normal)
+        .count();
+    let priority_count = report.timeline.len() - normal_count;
+    let overridden_count = report
+        .timeline
+        .iter()
+        .filter(|action| action.overridden)
+        .count();
+    let warnings = report
+        .warnings
+        .iter()
+        .map(|warning| match warning {
+            SimulationWarning::NeverFired(index) => {
+                format!("Action #{} never executed", index + 1)
+            }
+            SimulationWarning::EveryMillisBelowExecutionTime(index) => format!(
+                "Action #{} interval is not longer than its execution time and can starve other actions",
+                index + 1
+            ),
+        })
+        .collect::<Vec<_>>();
+
+    rsx! {
+        div { class: "flex flex-col pt-2 text-xs text-gray-700 space-y-1",
+            p { {format!("Normal actions executed: {normal_count}")} }
+            p { {format!("Priority actions executed: {priority_count}")} }
+            p { {format!("Actions overridden: {overridden_count}")} }
+            p {
+                {
+                    format!(
+                        "Longest normal actions starvation: {}ms",
+                        report.longest_normal_starvation_millis,
+                    )
+                }
+            }
+            if warnings.is_empty() {
+                p { "No warning" }
+            }
+            for warning in warnings {
+                p { class: "text-red-500", {warning} }
+            }
+            div { class: "h-2 border-b border-gray-300 mb-2" }
+            p { class: "font-normal italic text-xs text-gray-400",
+                "Timeline of the executed actions"
+            }
+            div { class: "flex flex-col max-h-80 overflow-y-auto scrollbar",
+                for action in report.timeline {
+                    SimulatedActionItem { action }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SimulatedActionItem(action: SimulatedAction) -> Element {
+    let kind = match action.kind {
+        SimulatedActionKind::Normal => "Normal",
+        SimulatedActionKind::Priority => "Priority",
+    };
+    let end = action
+        .end_millis
+        .map(format_millis)
+        .unwrap_or("end".to_string());
+    let outcome = if action.overridden {
+        " (overridden)"
+    } else {
+        ""
+    };
+
+    rsx! {
+        p { class: "py-1 border-b border-gray-100",
+            {
+                format!(
+                    "{} - {end}: {kind} action #{}{outcome}",
+                    format_millis(action.start_millis),
+                    action.index + 1,
+                )
+            }
+        }
+    }
+}
+
+/// Formats `millis` as `minutes:seconds.millis`
+fn format_millis(millis: u64) -> String {
+    format!(
+        "{:02}:{:02}.{:03}",
+        millis / 60_000,
+        (millis / 1000) % 60,
+        millis % 1000
+    )
+}